@@ -1,10 +1,10 @@
-use std::{collections::BTreeMap, fmt, ops};
+use std::{fmt, ops};
 
-use anyhow::{bail, Error, Result};
+use anyhow::{Error, Result};
 use num_format::{Locale, ToFormattedString};
 use serde::{
     de::{self, DeserializeOwned, Visitor},
-    Deserialize, Deserializer, Serialize,
+    Deserialize, Deserializer, Serialize, Serializer,
 };
 use serde_with::{base64::Base64, serde_as};
 
@@ -15,6 +15,84 @@ pub struct Plan {
     pub args: PlanArguments,
 }
 
+/// Prints the plan back in the pipeline syntax, e.g. `filesrc path='lib.rs'`.
+impl fmt::Display for Plan {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self { kind, args } = self;
+        fmt_plan(f, kind, args)
+    }
+}
+
+impl Plan {
+    /// Prints the plans back in the pipeline syntax, joining them with `!`.
+    pub fn display_seq(plans: &[Self]) -> impl '_ + fmt::Display {
+        PlanSeq(plans)
+    }
+}
+
+struct PlanSeq<'a>(&'a [Plan]);
+
+impl fmt::Display for PlanSeq<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, plan) in self.0.iter().enumerate() {
+            if index > 0 {
+                write!(f, " ! ")?;
+            }
+            write!(f, "{plan}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes the kind and the arguments in the pipeline syntax.
+///
+/// The strings are quoted unless they would be parsed back as the same bare names.
+/// The values beyond the grammar, e.g. arrays, are written as quoted strings.
+/// The quotes and backslashes in the quoted strings are escaped with a backslash.
+pub fn fmt_plan(f: &mut fmt::Formatter<'_>, kind: &PlanKind, args: &PlanArguments) -> fmt::Result {
+    write!(f, "{kind}")?;
+    for (key, value) in args.iter() {
+        write!(f, " {key}=")?;
+        match value {
+            Value::Bool(v) => write!(f, "{v}")?,
+            Value::Number(v) => write!(f, "{v}")?,
+            Value::String(v) => fmt_plan_string(f, v)?,
+            value => fmt_plan_string(f, &value.to_string())?,
+        }
+    }
+    Ok(())
+}
+
+fn fmt_plan_string(f: &mut fmt::Formatter<'_>, value: &str) -> fmt::Result {
+    const RESERVED: &[&str] = &["false", "true"];
+    const SUFFIXES: &[&str] = &["batch", "sink", "src", "store", "stream"];
+
+    let mut chars = value.chars();
+    let is_bare = chars.next().is_some_and(|c| c.is_ascii_lowercase())
+        && chars.all(|c| c == '_' || c.is_ascii_lowercase() || c.is_ascii_digit())
+        && !RESERVED.contains(&value)
+        && !SUFFIXES.iter().any(|suffix| value.ends_with(suffix));
+    if is_bare {
+        return write!(f, "{value}");
+    }
+
+    // Prefer the quote absent in the value, e.g. `"it's"`
+    let quote = if value.contains('\'') && !value.contains('"') {
+        '"'
+    } else {
+        '\''
+    };
+    write!(f, "{quote}")?;
+    for c in value.chars() {
+        if c == quote || c == '\\' {
+            write!(f, "\\")?;
+        }
+        write!(f, "{c}")?;
+    }
+    write!(f, "{quote}")
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub enum PlanKind {
     Batch { name: String },
@@ -105,12 +183,115 @@ pub struct PlanArgument {
     pub value: Value,
 }
 
-#[derive(Clone, Default, Serialize, Deserialize)]
+/// A machine-readable description of an argument accepted by a factory.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ArgSpec {
+    pub name: String,
+    pub required: bool,
+    pub default: Option<String>,
+    pub help: String,
+}
+
+impl fmt::Display for ArgSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self {
+            name,
+            required,
+            default,
+            help,
+        } = self;
+
+        write!(f, "{name}")?;
+        if let Some(default) = default {
+            write!(f, "={default}")?;
+        } else if *required {
+            write!(f, " (required)")?;
+        }
+        if !help.is_empty() {
+            write!(f, ": {help}")?;
+        }
+        Ok(())
+    }
+}
+
+impl ArgSpec {
+    pub fn required(name: impl Into<String>, help: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            required: true,
+            default: None,
+            help: help.into(),
+        }
+    }
+
+    pub fn optional(name: impl Into<String>, help: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            required: false,
+            default: None,
+            help: help.into(),
+        }
+    }
+
+    pub fn with_default(mut self, default: impl ToString) -> Self {
+        self.default = Some(default.to_string());
+        self
+    }
+
+    /// Returns the default as if it were written bare in a pipeline, e.g. `10` or `true`.
+    pub fn default_value(&self) -> Option<Value> {
+        let default = self.default.as_deref()?;
+        Some(match default {
+            "true" => Value::Bool(true),
+            "false" => Value::Bool(false),
+            _ if default.parse::<::serde_json::Number>().is_ok() => {
+                Value::Number(Number::Dynamic(default.into()))
+            }
+            _ => Value::String(default.into()),
+        })
+    }
+}
+
+impl PlanArguments {
+    /// Fills the missing arguments with the defaults of the given specs.
+    ///
+    /// The explicit arguments always take precedence over the defaults.
+    pub fn with_defaults(&self, specs: &[ArgSpec]) -> Self {
+        let mut args = self.clone();
+        for spec in specs {
+            if args.contains_key(&spec.name) {
+                continue;
+            }
+            if let Some(value) = spec.default_value() {
+                args.insert(spec.name.clone(), value);
+            }
+        }
+        args
+    }
+}
+
+/// The backing map of [`Object`].
+///
+/// Keys are sorted by default.
+/// With the `preserve-order` feature, keys keep their insertion order instead,
+/// so that e.g. CSV columns are emitted in the file order.
+#[cfg(not(feature = "preserve-order"))]
+pub type ObjectMap = ::std::collections::BTreeMap<String, Value>;
+
+/// The backing map of [`Object`].
+///
+/// Keys are sorted by default.
+/// With the `preserve-order` feature, keys keep their insertion order instead,
+/// so that e.g. CSV columns are emitted in the file order.
+#[cfg(feature = "preserve-order")]
+pub type ObjectMap = ::indexmap::IndexMap<String, Value>;
+
+#[derive(Clone, Default, PartialEq, Serialize, Deserialize)]
 #[serde(transparent)]
-pub struct Object(BTreeMap<String, Value>);
+pub struct Object(ObjectMap);
 
 impl ops::Deref for Object {
-    type Target = BTreeMap<String, Value>;
+    type Target = ObjectMap;
 
     #[inline]
     fn deref(&self) -> &Self::Target {
@@ -125,6 +306,16 @@ impl ops::DerefMut for Object {
     }
 }
 
+impl FromIterator<(String, Value)> for Object {
+    #[inline]
+    fn from_iter<T>(iter: T) -> Self
+    where
+        T: IntoIterator<Item = (String, Value)>,
+    {
+        Self(iter.into_iter().collect())
+    }
+}
+
 impl fmt::Debug for Object {
     #[inline]
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -133,6 +324,55 @@ impl fmt::Debug for Object {
 }
 
 impl Object {
+    /// Moves all entries from `other` into `self`, leaving `other` empty.
+    #[inline]
+    pub fn append(&mut self, other: &mut Self) {
+        self.0.append(&mut other.0)
+    }
+
+    /// Removes a key, preserving the order of the remaining entries.
+    #[inline]
+    pub fn remove(&mut self, key: &str) -> Option<Value> {
+        #[cfg(not(feature = "preserve-order"))]
+        {
+            self.0.remove(key)
+        }
+        #[cfg(feature = "preserve-order")]
+        {
+            self.0.shift_remove(key)
+        }
+    }
+
+    /// Returns the boolean value, or `None` if missing or of another type.
+    #[inline]
+    pub fn get_bool(&self, key: &str) -> Option<bool> {
+        self.0.get(key).and_then(Value::as_bool)
+    }
+
+    /// Returns the binary value, or `None` if missing or of another type.
+    #[inline]
+    pub fn get_bytes(&self, key: &str) -> Option<&[u8]> {
+        self.0.get(key).and_then(Value::as_bytes)
+    }
+
+    /// Returns the number as `f64`, or `None` if missing or of another type.
+    #[inline]
+    pub fn get_f64(&self, key: &str) -> Option<f64> {
+        self.0.get(key).and_then(Value::as_f64)
+    }
+
+    /// Returns the number as `i64`, or `None` if missing, fractional or of another type.
+    #[inline]
+    pub fn get_i64(&self, key: &str) -> Option<i64> {
+        self.0.get(key).and_then(Value::as_i64)
+    }
+
+    /// Returns the string value, or `None` if missing or of another type.
+    #[inline]
+    pub fn get_str(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(Value::as_str)
+    }
+
     pub fn from_json(json: ::serde_json::Value) -> Result<Self> {
         ::serde_json::from_value(json).map_err(Into::into)
     }
@@ -179,6 +419,26 @@ pub enum Value {
     Number(Number),
     Binary(Binary),
     String(String),
+    Array(Vec<Value>),
+    Object(Object),
+}
+
+/// Compares the values structurally, with the [`Number`] semantics.
+///
+/// The values of different variants are never equal, e.g. `1` and `"1"`.
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Null, Self::Null) => true,
+            (Self::Bool(a), Self::Bool(b)) => a == b,
+            (Self::Number(a), Self::Number(b)) => a == b,
+            (Self::Binary(a), Self::Binary(b)) => a == b,
+            (Self::String(a), Self::String(b)) => a == b,
+            (Self::Array(a), Self::Array(b)) => a == b,
+            (Self::Object(a), Self::Object(b)) => a == b,
+            _ => false,
+        }
+    }
 }
 
 impl fmt::Debug for Value {
@@ -189,6 +449,8 @@ impl fmt::Debug for Value {
             Self::Number(v) => v.fmt(f),
             Self::Binary(v) => v.fmt(f),
             Self::String(v) => v.fmt(f),
+            Self::Array(v) => v.fmt(f),
+            Self::Object(v) => v.fmt(f),
         }
     }
 }
@@ -201,6 +463,78 @@ impl fmt::Display for Value {
             Self::Number(v) => v.fmt(f),
             Self::Binary(v) => v.fmt(f),
             Self::String(v) => fmt::Debug::fmt(v, f),
+            Self::Array(v) => {
+                write!(f, "[")?;
+                for (index, item) in v.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{item}")?;
+                }
+                write!(f, "]")
+            }
+            Self::Object(v) => {
+                write!(f, "{{")?;
+                for (index, (key, value)) in v.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{key:?}: {value}")?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+impl Value {
+    #[inline]
+    pub const fn as_bool(&self) -> Option<bool> {
+        match self {
+            Self::Bool(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Self::Binary(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Self::Number(v) => v.as_f64(),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Self::Number(v) => v.as_i64(),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Canonicalizes the nested numbers, see [`Number::canonicalize`].
+    pub fn canonicalize(&mut self) {
+        match self {
+            Self::Number(v) => v.canonicalize(),
+            Self::Array(values) => values.iter_mut().for_each(Self::canonicalize),
+            Self::Object(object) => object.values_mut().for_each(Self::canonicalize),
+            _ => (),
         }
     }
 }
@@ -220,6 +554,8 @@ impl_atomic_value!(bool => Bool);
 impl_atomic_value!(Number => Number);
 impl_atomic_value!(Binary => Binary);
 impl_atomic_value!(String => String);
+impl_atomic_value!(Vec<Value> => Array);
+impl_atomic_value!(Object => Object);
 
 macro_rules! impl_atomic_integer_value {
     ( $( $ty:ty ),* ) => {
@@ -274,8 +610,16 @@ impl TryFrom<::serde_json::Value> for Value {
             ::serde_json::Value::Bool(value) => Ok(Self::Bool(value)),
             ::serde_json::Value::Number(value) => Ok(Self::Number(Number::Fixed(value))),
             ::serde_json::Value::String(value) => Ok(Self::String(value)),
-            ::serde_json::Value::Array(_) => bail!("Array type is not supported yet"),
-            ::serde_json::Value::Object(_) => bail!("Nested object type is not supported yet"),
+            ::serde_json::Value::Array(values) => values
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<Result<_>>()
+                .map(Self::Array),
+            ::serde_json::Value::Object(map) => map
+                .into_iter()
+                .map(|(key, value)| value.try_into().map(|value| (key, value)))
+                .collect::<Result<_>>()
+                .map(Self::Object),
         }
     }
 }
@@ -293,11 +637,11 @@ macro_rules! impl_atomic_integer_deserialize {
     };
 }
 
-impl Visitor<'_> for ValueVisitor {
+impl<'de> Visitor<'de> for ValueVisitor {
     type Value = Value;
 
     fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-        formatter.write_str("a map object")
+        formatter.write_str("a value")
     }
 
     fn visit_none<E>(self) -> std::result::Result<Self::Value, E>
@@ -324,6 +668,22 @@ impl Visitor<'_> for ValueVisitor {
     impl_atomic_integer_deserialize!(u32 => visit_u32);
     impl_atomic_integer_deserialize!(u64 => visit_u64);
 
+    fn visit_unit<E>(self) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Value::Null)
+    }
+
+    fn visit_f64<E>(self, v: f64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        ::serde_json::Number::from_f64(v)
+            .map(|v| Value::Number(Number::Fixed(v)))
+            .ok_or_else(|| E::custom(format!("non-finite number is not supported: {v}")))
+    }
+
     fn visit_bytes<E>(self, v: &[u8]) -> Result<Self::Value, E>
     where
         E: de::Error,
@@ -351,8 +711,41 @@ impl Visitor<'_> for ValueVisitor {
     {
         Ok(Value::String(v))
     }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: de::SeqAccess<'de>,
+    {
+        let mut values = Vec::with_capacity(seq.size_hint().unwrap_or_default());
+        while let Some(value) = seq.next_element()? {
+            values.push(value);
+        }
+        Ok(Value::Array(values))
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: de::MapAccess<'de>,
+    {
+        let mut object = Object::default();
+        while let Some(key) = map.next_key::<String>()? {
+            // serde_json represents arbitrary precision numbers as a magic map
+            if object.is_empty() && key == SERDE_JSON_NUMBER_TOKEN {
+                let value: String = map.next_value()?;
+                return value
+                    .parse()
+                    .map(|v| Value::Number(Number::Fixed(v)))
+                    .map_err(de::Error::custom);
+            }
+            let value = map.next_value()?;
+            object.insert(key, value);
+        }
+        Ok(Value::Object(object))
+    }
 }
 
+const SERDE_JSON_NUMBER_TOKEN: &str = "$serde_json::private::Number";
+
 impl<'de> Deserialize<'de> for Value {
     #[inline]
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
@@ -364,10 +757,19 @@ impl<'de> Deserialize<'de> for Value {
 }
 
 #[serde_as]
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(transparent)]
 pub struct Binary(#[serde_as(as = "Base64")] pub Vec<u8>);
 
+impl Binary {
+    /// Decodes a base64 string, the serialized form of [`Binary`].
+    ///
+    /// Note that this differs from taking the UTF-8 bytes of the string.
+    pub fn from_base64(text: &str) -> Result<Self> {
+        ::serde_json::from_value(::serde_json::Value::String(text.into())).map_err(Into::into)
+    }
+}
+
 impl From<Vec<u8>> for Binary {
     #[inline]
     fn from(value: Vec<u8>) -> Self {
@@ -398,8 +800,13 @@ impl fmt::Display for Binary {
     }
 }
 
-#[derive(Clone, Serialize)]
-#[serde(untagged)]
+/// A JSON number, or the text of a number given in a pipeline, e.g. `count=10`.
+///
+/// Both are serialized as JSON numbers, e.g. `Dynamic("42")` as `42`, so that they deserialize
+/// into numeric fields. Only the dynamic texts beyond JSON are serialized as strings,
+/// e.g. `Dynamic("NaN")` as `"NaN"`, which are deserialized back as [`Value::String`].
+/// The deserialized numbers are always fixed.
+#[derive(Clone)]
 pub enum Number {
     Fixed(::serde_json::Number),
     Dynamic(String),
@@ -421,6 +828,80 @@ impl fmt::Display for Number {
     }
 }
 
+impl Number {
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Self::Fixed(v) => v.as_f64(),
+            Self::Dynamic(v) => v.parse().ok(),
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Self::Fixed(v) => v.as_i64(),
+            Self::Dynamic(v) => v.parse().ok(),
+        }
+    }
+
+    /// Converts a dynamic number into a fixed one if it is a valid JSON number, e.g. `"42"` into `42`.
+    ///
+    /// The other dynamic numbers are kept as they are, e.g. `"NaN"`.
+    pub fn canonicalize(&mut self) {
+        if let Self::Dynamic(text) = self {
+            if let Ok(v) = text.parse() {
+                *self = Self::Fixed(v);
+            }
+        }
+    }
+
+    fn as_i128(&self) -> Option<i128> {
+        match self {
+            Self::Fixed(v) => v
+                .as_i64()
+                .map(Into::into)
+                .or_else(|| v.as_u64().map(Into::into)),
+            Self::Dynamic(v) => v.parse().ok(),
+        }
+    }
+}
+
+/// Compares the numbers regardless of their representations.
+///
+/// 1. If both are integers, they are compared exactly, e.g. `42 == "42"`.
+/// 2. Otherwise, if both parse as non-NaN `f64`, they are compared as `f64`,
+///    e.g. `1 == "1.0"` and `0.5 == "5e-1"`, subject to the `f64` precision.
+/// 3. Otherwise, their textual forms are compared, e.g. `"NaN" == "NaN"`.
+///
+/// `Eq` is not implemented, as mixing the exact and `f64` comparisons is not transitive
+/// for the integers beyond `2^53`.
+impl PartialEq for Number {
+    fn eq(&self, other: &Self) -> bool {
+        if let (Some(a), Some(b)) = (self.as_i128(), other.as_i128()) {
+            return a == b;
+        }
+        match (self.as_f64(), other.as_f64()) {
+            (Some(a), Some(b)) if !a.is_nan() && !b.is_nan() => a == b,
+            _ => self.to_string() == other.to_string(),
+        }
+    }
+}
+
+impl Serialize for Number {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            Self::Fixed(v) => v.serialize(serializer),
+            // Keep numeric arguments (e.g. `count=10`) deserializable as numbers
+            Self::Dynamic(v) => match v.parse::<::serde_json::Number>() {
+                Ok(v) => v.serialize(serializer),
+                Err(_) => v.serialize(serializer),
+            },
+        }
+    }
+}
+
 impl<'de> Deserialize<'de> for Number {
     #[inline]
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
@@ -430,3 +911,219 @@ impl<'de> Deserialize<'de> for Number {
         ::serde_json::Number::deserialize(deserializer).map(Self::Fixed)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_number_dynamic_to_json() {
+        let mut args = PlanArguments::default();
+        args.insert("count".into(), Value::Number(Number::Dynamic("42".into())));
+        args.insert("ratio".into(), Value::Number(Number::Dynamic("0.5".into())));
+
+        let json = args.to_json().unwrap();
+        assert_eq!(json["count"].as_u64(), Some(42));
+        assert_eq!(json["ratio"].as_f64(), Some(0.5));
+    }
+
+    #[test]
+    fn test_value_nested_round_trip() {
+        let json = r#"{"a":[1,2.5,"x"],"b":{"c":null,"d":[{"e":true}]}}"#;
+        let object = Object::from_slice(json.as_bytes()).unwrap();
+        assert!(matches!(object.get("a"), Some(Value::Array(values)) if values.len() == 3));
+        assert!(matches!(object.get("b"), Some(Value::Object(_))));
+        assert_eq!(object.to_string().unwrap(), json);
+    }
+
+    #[test]
+    fn test_number_eq_cross_representation() {
+        let fixed = |json: &str| Number::Fixed(json.parse().unwrap());
+        let dynamic = |text: &str| Number::Dynamic(text.into());
+
+        assert_eq!(fixed("42"), dynamic("42"));
+        assert_eq!(fixed("1"), dynamic("1.0"));
+        assert_eq!(fixed("0.5"), dynamic("5e-1"));
+        assert_eq!(
+            fixed("18446744073709551615"),
+            dynamic("18446744073709551615")
+        );
+        assert_ne!(fixed("42"), dynamic("43"));
+        assert_ne!(fixed("-1"), fixed("18446744073709551615"));
+
+        // Not numeric, compared textually
+        assert_eq!(dynamic("NaN"), dynamic("NaN"));
+        assert_ne!(dynamic("1x"), dynamic("1"));
+    }
+
+    #[test]
+    fn test_number_serde_round_trip() {
+        let mut object = Object::default();
+        object.insert("int".into(), Value::Number(Number::Dynamic("42".into())));
+        object.insert("float".into(), Value::Number(Number::Dynamic("0.5".into())));
+        object.insert("nan".into(), Value::Number(Number::Dynamic("NaN".into())));
+        object.insert("fixed".into(), Value::from(7));
+
+        let json = ::serde_json::to_string(&object).unwrap();
+        let expected: ::serde_json::Value =
+            ::serde_json::from_str(r#"{"int":42,"float":0.5,"nan":"NaN","fixed":7}"#).unwrap();
+        assert_eq!(
+            ::serde_json::from_str::<::serde_json::Value>(&json).unwrap(),
+            expected,
+        );
+
+        let parsed = Object::from_slice(json.as_bytes()).unwrap();
+        for key in ["int", "float", "fixed"] {
+            assert!(
+                matches!(parsed.get(key), Some(Value::Number(Number::Fixed(_)))),
+                "{key}",
+            );
+            assert_eq!(parsed.get(key), object.get(key), "{key}");
+        }
+        assert_eq!(parsed.get("nan"), Some(&Value::String("NaN".into())));
+    }
+
+    #[test]
+    fn test_number_canonicalize() {
+        let canonicalize = |text: &str| {
+            let mut number = Number::Dynamic(text.into());
+            number.canonicalize();
+            number
+        };
+
+        // Integers
+        assert!(matches!(canonicalize("42"), Number::Fixed(v) if v.as_i64() == Some(42)));
+        assert!(matches!(canonicalize("-7"), Number::Fixed(v) if v.as_i64() == Some(-7)));
+        assert!(matches!(
+            canonicalize("18446744073709551615"),
+            Number::Fixed(v) if v.as_u64() == Some(u64::MAX)
+        ));
+        // Beyond 64 bits, kept exactly
+        assert!(matches!(
+            canonicalize("18446744073709551616"),
+            Number::Fixed(v) if v.to_string() == "18446744073709551616"
+        ));
+
+        // Floats
+        assert!(matches!(canonicalize("0.5"), Number::Fixed(v) if v.as_f64() == Some(0.5)));
+        assert!(matches!(canonicalize("5e-1"), Number::Fixed(v) if v.as_f64() == Some(0.5)));
+
+        // Not valid JSON numbers
+        for text in ["NaN", "1x", "", "+1", "0x10"] {
+            assert!(matches!(canonicalize(text), Number::Dynamic(v) if v == text));
+        }
+
+        // Nested values
+        let mut value = Value::Array(vec![
+            Value::Number(Number::Dynamic("1".into())),
+            Value::Object(
+                [("a".into(), Value::Number(Number::Dynamic("2.5".into())))]
+                    .into_iter()
+                    .collect(),
+            ),
+        ]);
+        value.canonicalize();
+        let Value::Array(values) = &value else {
+            unreachable!()
+        };
+        assert!(matches!(&values[0], Value::Number(Number::Fixed(_))));
+        assert!(matches!(
+            &values[1],
+            Value::Object(object) if matches!(object.get("a"), Some(Value::Number(Number::Fixed(_))))
+        ));
+    }
+
+    #[test]
+    fn test_value_eq() {
+        assert_eq!(Value::Number(Number::Dynamic("7".into())), Value::from(7),);
+        assert_ne!(Value::from(1), Value::from("1"));
+        assert_ne!(Value::Null, Value::from(false));
+
+        assert_eq!(Value::from(b"foo"), Value::from(b"foo"));
+        assert_ne!(Value::from(b"foo"), Value::from(b"bar"));
+        assert_ne!(Value::from(b"foo"), Value::from("foo"));
+
+        let array = |items: &[i32]| Value::Array(items.iter().map(|&v| v.into()).collect());
+        assert_eq!(array(&[1, 2]), array(&[1, 2]));
+        assert_ne!(array(&[1, 2]), array(&[2, 1]));
+    }
+
+    #[test]
+    fn test_arguments_with_defaults() {
+        let specs = [
+            ArgSpec::optional("count", "").with_default(10),
+            ArgSpec::optional("mode", "").with_default("mask"),
+            ArgSpec::optional("pretty", "").with_default(true),
+            ArgSpec::optional("unset", ""),
+        ];
+
+        let mut args = PlanArguments::default();
+        args.insert("mode".into(), "hash".into());
+        let args = args.with_defaults(&specs);
+
+        assert_eq!(args.get_i64("count"), Some(10));
+        assert_eq!(args.get_str("mode"), Some("hash"));
+        assert_eq!(args.get_bool("pretty"), Some(true));
+        assert!(!args.contains_key("unset"));
+    }
+
+    fn typed_object() -> Object {
+        let mut object = Object::default();
+        object.insert("bool".into(), true.into());
+        object.insert("bytes".into(), b"foo".into());
+        object.insert("float".into(), Value::Number(Number::Dynamic("0.5".into())));
+        object.insert("int".into(), 42.into());
+        object.insert("str".into(), "foo".into());
+        object
+    }
+
+    #[test]
+    fn test_object_get_bool() {
+        let object = typed_object();
+        assert_eq!(object.get_bool("bool"), Some(true));
+        assert_eq!(object.get_bool("str"), None);
+        assert_eq!(object.get_bool("missing"), None);
+    }
+
+    #[test]
+    fn test_object_get_bytes() {
+        let object = typed_object();
+        assert_eq!(object.get_bytes("bytes"), Some(b"foo".as_slice()));
+        assert_eq!(object.get_bytes("str"), None);
+    }
+
+    #[test]
+    fn test_object_get_f64() {
+        let object = typed_object();
+        assert_eq!(object.get_f64("float"), Some(0.5));
+        assert_eq!(object.get_f64("int"), Some(42.0));
+        assert_eq!(object.get_f64("str"), None);
+    }
+
+    #[test]
+    fn test_object_get_i64() {
+        let object = typed_object();
+        assert_eq!(object.get_i64("int"), Some(42));
+        assert_eq!(object.get_i64("float"), None);
+        assert_eq!(object.get_i64("bool"), None);
+    }
+
+    #[test]
+    fn test_object_get_str() {
+        let object = typed_object();
+        assert_eq!(object.get_str("str"), Some("foo"));
+        assert_eq!(object.get_str("int"), None);
+    }
+
+    #[cfg(feature = "preserve-order")]
+    #[test]
+    fn test_object_preserve_order() {
+        let json = r#"{"name":"foo","id":1,"age":42}"#;
+        let mut object = Object::from_slice(json.as_bytes()).unwrap();
+        assert_eq!(object.keys().collect::<Vec<_>>(), ["name", "id", "age"]);
+        assert_eq!(object.to_string().unwrap(), json);
+
+        object.remove("id");
+        assert_eq!(object.keys().collect::<Vec<_>>(), ["name", "age"]);
+    }
+}
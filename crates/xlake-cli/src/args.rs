@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+
 use clap::Parser;
 
 #[derive(Debug, Parser)]
@@ -5,6 +7,56 @@ use clap::Parser;
 pub struct Args {
     pub command: Vec<String>,
 
+    /// Directory caching the hashed objects of every run, as `localstore` before the sink;
+    /// ignored if the command has its own store
+    #[arg(global = true, long, env = "XLAKE_CACHE")]
+    pub cache: Option<PathBuf>,
+
+    /// JSON file keeping the offsets of the resumable srcs per their arguments, e.g. `ndjsonsrc`;
+    /// loaded before the run and updated once it succeeds
+    #[arg(global = true, long, env = "XLAKE_CHECKPOINT")]
+    pub checkpoint: Option<PathBuf>,
+
+    /// Maximum number of the IO-heavy works in flight across all the nodes, e.g. processes;
+    /// the stores also look up and write as many objects at once
+    #[arg(global = true, long, env = "XLAKE_CONCURRENCY_LIMIT")]
+    pub concurrency_limit: Option<usize>,
+
+    /// TOML file defining the default arguments per factory, e.g. `[filesrc]`;
+    /// explicit arguments in the command take precedence
+    #[arg(global = true, long, env = "XLAKE_CONFIG")]
+    pub config: Option<PathBuf>,
+
     #[arg(global = true, long)]
     pub debug: bool,
+
+    /// Log the models provided by the objects of each stage, as the pipeline runs
+    #[arg(global = true, long, env = "XLAKE_EXPLAIN")]
+    pub explain: bool,
+
+    /// TOML file declaring the pipeline as `[[nodes]]` with `kind` and `args`,
+    /// rather than the command
+    #[arg(global = true, long, env = "XLAKE_FILE")]
+    pub file: Option<PathBuf>,
+
+    /// List the available factories and their arguments
+    #[arg(long)]
+    pub list: bool,
+
+    /// Directory of the plugins registering their factories, e.g. `libmy_func.so`;
+    /// they take precedence over the builtins of the same kinds
+    #[arg(global = true, long, env = "XLAKE_PLUGINS")]
+    pub plugins: Option<PathBuf>,
+
+    /// Skip the funcs whose input models are absent upstream, rather than failing
+    #[arg(global = true, long, env = "XLAKE_SKIP_MISSING_MODELS")]
+    pub skip_missing_models: bool,
+
+    /// Print what the sink has written after the run, e.g. the number of the objects
+    #[arg(global = true, long, env = "XLAKE_SUMMARY")]
+    pub summary: bool,
+
+    /// Reload the plugins whenever they change and run the command again, until interrupted
+    #[arg(global = true, long, env = "XLAKE_WATCH_PLUGINS", requires = "plugins")]
+    pub watch_plugins: bool,
 }
@@ -1,11 +1,13 @@
 mod args;
 
-use std::process::exit;
+use std::{path::Path, process::exit, sync::Arc};
 
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
 use clap::{CommandFactory, Parser};
-use tracing::error;
-use xlake::PipeSession;
+use tokio::fs;
+use tracing::{error, info, warn};
+use xlake::{pipeline::PipelineFile, plugins::PluginRegistry, PipeSession};
+use xlake_ast::{Plan, PlanArguments, PlanKind};
 
 #[global_allocator]
 static ALLOC: ::snmalloc_rs::SnMalloc = ::snmalloc_rs::SnMalloc;
@@ -24,14 +26,149 @@ async fn main() {
 }
 
 async fn try_main(args: self::args::Args) -> Result<()> {
-    let self::args::Args { command, debug: _ } = args;
-    let input = command.join(" ");
-    if input.trim().is_empty() {
-        <self::args::Args as CommandFactory>::command().print_help()?;
+    let self::args::Args {
+        command,
+        cache,
+        checkpoint,
+        concurrency_limit,
+        config,
+        debug: _,
+        explain,
+        file,
+        list,
+        plugins,
+        skip_missing_models,
+        summary,
+        watch_plugins,
+    } = args;
+
+    let mut session = PipeSession::default();
+    session.set_explain(explain);
+    session.set_skip_missing_models(skip_missing_models);
+    if let Some(path) = cache {
+        let args = PlanArguments::from_json(::serde_json::json!({ "path": path }))?;
+        let kind = PlanKind::Store {
+            name: "local".into(),
+        };
+        session.set_cache(Plan { kind, args });
+    }
+    if let Some(limit) = concurrency_limit {
+        session.set_concurrency_limit(limit);
+    }
+    if let Some(path) = config {
+        let config = fs::read_to_string(&path)
+            .await
+            .with_context(|| format!("Failed to read config: {}", path.display()))?;
+        let defaults = ::toml::from_str(&config)
+            .with_context(|| format!("Invalid config: {}", path.display()))?;
+        session.set_defaults(defaults);
+    }
+    let plugins = match plugins {
+        Some(dir) => {
+            let plugins = PluginRegistry::load(&dir)
+                .with_context(|| format!("Failed to load plugins: {}", dir.display()))?;
+            let plugins = Arc::new(plugins);
+            session.set_plugins(plugins.clone());
+            Some(plugins)
+        }
+        None => None,
+    };
+    if let Some(path) = &checkpoint {
+        if fs::try_exists(path).await? {
+            let checkpoint = fs::read_to_string(path)
+                .await
+                .with_context(|| format!("Failed to read checkpoint: {}", path.display()))?;
+            let checkpoint = ::serde_json::from_str(&checkpoint)
+                .with_context(|| format!("Invalid checkpoint: {}", path.display()))?;
+            session.set_checkpoint(checkpoint);
+        }
+    }
+    if list {
+        print_factories(&session);
         return Ok(());
     }
 
-    let session = PipeSession::default();
-    session.call(&input).await?;
+    let input = command.join(" ");
+    match &file {
+        Some(_) if !input.trim().is_empty() => {
+            bail!("Cannot give both the command and the pipeline file")
+        }
+        None if input.trim().is_empty() => {
+            <self::args::Args as CommandFactory>::command().print_help()?;
+            return Ok(());
+        }
+        _ => (),
+    }
+
+    let call = || {
+        run(
+            &session,
+            &input,
+            file.as_deref(),
+            summary,
+            checkpoint.as_deref(),
+        )
+    };
+    let plugins = match plugins {
+        Some(plugins) if watch_plugins => plugins,
+        _ => return call().await,
+    };
+
+    // Run again with the reloaded plugins, reporting the failures without exiting
+    let _watcher = plugins.watch()?;
+    let mut generation = plugins.subscribe();
+    loop {
+        if let Err(error) = call().await {
+            error!("{error}");
+        }
+        info!(
+            "Waiting for the plugins to change: {}",
+            plugins.dir().display()
+        );
+        generation.changed().await?;
+    }
+}
+
+async fn run(
+    session: &PipeSession,
+    input: &str,
+    file: Option<&Path>,
+    summary: bool,
+    checkpoint: Option<&Path>,
+) -> Result<()> {
+    match file {
+        Some(path) => {
+            let file = fs::read_to_string(path)
+                .await
+                .with_context(|| format!("Failed to read pipeline file: {}", path.display()))?;
+            let file: PipelineFile = ::toml::from_str(&file)
+                .with_context(|| format!("Invalid pipeline file: {}", path.display()))?;
+            session.call_with(file.into_plans()?).await?;
+        }
+        None => session.call(input).await?,
+    }
+
+    if summary {
+        match session.summary() {
+            Some(summary) => info!("{summary}"),
+            None => warn!("The sink has no summary"),
+        }
+    }
+
+    if let Some(path) = checkpoint {
+        let checkpoint = ::serde_json::to_string_pretty(&session.checkpoint())?;
+        fs::write(path, checkpoint)
+            .await
+            .with_context(|| format!("Failed to write checkpoint: {}", path.display()))?;
+    }
     Ok(())
 }
+
+fn print_factories(session: &PipeSession) {
+    for factory in session.factories() {
+        println!("{}", factory.kind());
+        for arg in factory.arguments() {
+            println!("    {arg}");
+        }
+    }
+}
@@ -0,0 +1,29 @@
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::ArgSpec;
+
+/// The arguments of a factory, deserialized from the [`PlanArguments`](xlake_ast::PlanArguments).
+///
+/// Derive it with `#[derive(PipeArgs)]` to describe the fields as they are deserialized:
+/// the fields are required unless `Option` or `#[serde(default)]`,
+/// and the doc comments become the help.
+pub trait PipeArgs {
+    fn arg_specs() -> Vec<ArgSpec>;
+}
+
+/// Formats a default value as written in a pipeline, e.g. `desc` or `10`.
+///
+/// Returns `None` for the values which cannot be written bare, e.g. `null` or arrays.
+#[doc(hidden)]
+pub fn __default_value<T>(value: &T) -> Option<String>
+where
+    T: ?Sized + Serialize,
+{
+    match ::serde_json::to_value(value).ok()? {
+        Value::Bool(value) => Some(value.to_string()),
+        Value::Number(value) => Some(value.to_string()),
+        Value::String(value) if !value.is_empty() => Some(value),
+        _ => None,
+    }
+}
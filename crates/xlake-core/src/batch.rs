@@ -1,20 +1,26 @@
-use std::{fmt, ops, pin::Pin};
+use std::{collections::HashSet, fmt, ops, path::PathBuf, pin::Pin, sync::Arc};
 
 use anyhow::{bail, Result};
 use arrow_json::JsonSerializable;
 use async_trait::async_trait;
 use datafusion::{
     arrow::{
-        array::{cast, ArrayRef, ArrowPrimitiveType, AsArray, RecordBatch},
-        datatypes::{self, DataType},
+        array::{
+            cast, ArrayRef, ArrowPrimitiveType, AsArray, BooleanBuilder, Float64Builder,
+            Int64Builder, RecordBatch, StringBuilder,
+        },
+        datatypes::{self, DataType, Field, Schema},
     },
-    prelude::SessionContext,
+    execution::runtime_env::{RuntimeEnv, RuntimeEnvBuilder},
+    prelude::{SessionConfig, SessionContext},
 };
 use futures::{stream, Stream, StreamExt, TryStreamExt};
 use serde::{Deserialize, Serialize};
 use xlake_ast::{Object, PlanArguments, PlanKind, Value};
 
-use crate::{object::ObjectLayer, stream::DefaultStream, PipeEdge, PipeNodeFactory, PipeNodeImpl};
+use crate::{
+    object::ObjectLayer, stream::DefaultStream, ArgSpec, PipeEdge, PipeNodeFactory, PipeNodeImpl,
+};
 
 pub type DefaultBatchFactory = DataFusionBatchFactory;
 pub type DefaultBatch = DataFusionBatch;
@@ -48,6 +54,29 @@ impl PipeNodeFactory for DataFusionBatchFactory {
         NAME.into()
     }
 
+    fn arguments(&self) -> Vec<ArgSpec> {
+        vec![
+            ArgSpec::optional("batch_size", "Maximum number of rows per record batch")
+                .with_default(DEFAULT_BATCH_SIZE),
+            ArgSpec::optional(
+                "memory_limit",
+                "Maximum bytes of memory for the queries, spilling or failing beyond it",
+            ),
+            ArgSpec::optional(
+                "ordered",
+                "Preserve the input order, processing in a single partition",
+            )
+            .with_default(false),
+            ArgSpec::optional("spill_dir", "Directory of the spilled files"),
+            ArgSpec::optional(
+                "target_partitions",
+                "Number of partitions to be processed in parallel, defaults to the CPU count",
+            ),
+            ArgSpec::optional("table", "Name of the registered table to be read")
+                .with_default(DEFAULT_TABLE_REF),
+        ]
+    }
+
     fn input(&self) -> PipeEdge {
         PipeEdge {
             model: Some(vec!["batch".into()]),
@@ -65,13 +94,114 @@ impl PipeNodeFactory for DataFusionBatchFactory {
 
     async fn build(&self, args: &PlanArguments) -> Result<PipeNodeImpl> {
         let args: BatchFormatArgs = args.to()?;
-        let imp = DataFusionBatch::new(args);
+        let imp = DataFusionBatch::new(args)?;
         Ok(PipeNodeImpl::Batch(Box::new(imp)))
     }
 }
 
-#[derive(Clone, Debug, Default, Serialize, Deserialize)]
-pub struct BatchFormatArgs {}
+/// The default batch size of DataFusion, which balances memory usage and vectorization.
+pub const DEFAULT_BATCH_SIZE: usize = 8192;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BatchFormatArgs {
+    /// Maximum number of rows per record batch, defaults to [`DEFAULT_BATCH_SIZE`]
+    #[serde(default)]
+    pub batch_size: Option<usize>,
+    /// Maximum bytes of memory for the queries, unbounded by default
+    ///
+    /// The memory-hungry operators, e.g. sorts and joins, spill into [`Self::spill_dir`]
+    /// beyond it, or fail if they cannot.
+    #[serde(default)]
+    pub memory_limit: Option<usize>,
+    /// Whether to emit the rows in the input order, e.g. of the lines of a CSV file.
+    ///
+    /// DataFusion may interleave the rows of the parallel partitions, so this forces a single
+    /// partition, trading the parallelism of the scans and the aggregations for the order.
+    #[serde(default)]
+    pub ordered: bool,
+    /// Directory of the spilled files, defaults to the OS temporary directory
+    #[serde(default)]
+    pub spill_dir: Option<PathBuf>,
+    /// Name of the registered table to be read, defaults to [`DEFAULT_TABLE_REF`]
+    #[serde(default = "BatchFormatArgs::default_table")]
+    pub table: String,
+    /// Number of partitions to be processed in parallel, defaults to the CPU count
+    #[serde(default)]
+    pub target_partitions: Option<usize>,
+}
+
+impl Default for BatchFormatArgs {
+    fn default() -> Self {
+        Self {
+            batch_size: None,
+            memory_limit: None,
+            ordered: false,
+            spill_dir: None,
+            table: Self::default_table(),
+            target_partitions: None,
+        }
+    }
+}
+
+impl BatchFormatArgs {
+    fn default_table() -> String {
+        DEFAULT_TABLE_REF.into()
+    }
+
+    fn to_config(&self) -> Result<SessionConfig> {
+        let &Self {
+            batch_size,
+            memory_limit: _,
+            ordered,
+            spill_dir: _,
+            table: _,
+            target_partitions,
+        } = self;
+
+        let mut config = SessionConfig::new();
+        if let Some(batch_size) = batch_size {
+            if batch_size == 0 {
+                bail!("batch_size should be positive")
+            }
+            config = config.with_batch_size(batch_size);
+        }
+        if let Some(target_partitions) = target_partitions {
+            if target_partitions == 0 {
+                bail!("target_partitions should be positive")
+            }
+            config = config.with_target_partitions(target_partitions);
+        }
+        if ordered {
+            if target_partitions.is_some_and(|target_partitions| target_partitions > 1) {
+                bail!("target_partitions should be 1 if ordered")
+            }
+            config = config
+                .with_target_partitions(1)
+                .with_repartition_file_scans(false);
+        }
+        Ok(config)
+    }
+
+    fn to_runtime(&self) -> Result<Arc<RuntimeEnv>> {
+        let Self {
+            memory_limit,
+            spill_dir,
+            ..
+        } = self;
+
+        let mut builder = RuntimeEnvBuilder::new();
+        if let Some(memory_limit) = *memory_limit {
+            if memory_limit == 0 {
+                bail!("memory_limit should be positive")
+            }
+            builder = builder.with_memory_limit(memory_limit, 1.0);
+        }
+        if let Some(spill_dir) = spill_dir {
+            builder = builder.with_temp_file_path(spill_dir);
+        }
+        Ok(Arc::new(builder.build()?))
+    }
+}
 
 #[derive(Default)]
 pub struct DataFusionBatch {
@@ -80,11 +210,53 @@ pub struct DataFusionBatch {
 }
 
 impl DataFusionBatch {
-    fn new(args: BatchFormatArgs) -> Self {
-        Self {
-            args,
-            ctx: Default::default(),
+    pub fn new(args: BatchFormatArgs) -> Result<Self> {
+        let ctx = SessionContext::new_with_config_rt(args.to_config()?, args.to_runtime()?);
+        Ok(Self { args, ctx })
+    }
+
+    /// Returns the name of the table to be read into the stream.
+    #[inline]
+    pub fn table_name(&self) -> &str {
+        &self.args.table
+    }
+
+    /// Returns whether the table to be read is registered, e.g. by an upstream node.
+    #[inline]
+    pub fn has_table(&self) -> Result<bool> {
+        self.ctx.table_exist(self.table_name()).map_err(Into::into)
+    }
+
+    /// Shares the session, reading the current table by another name as well.
+    pub async fn with_table(&self, table: &str) -> Result<Self> {
+        if table != self.table_name() {
+            let view = self.ctx.table(self.table_name()).await?.into_view();
+            self.ctx.deregister_table(table)?;
+            self.ctx.register_table(table, view)?;
         }
+        Ok(Self {
+            args: BatchFormatArgs {
+                table: table.into(),
+                ..self.args.clone()
+            },
+            ctx: self.ctx.clone(),
+        })
+    }
+
+    /// Runs the query over the registered tables, replacing the table to be read with its result.
+    pub async fn query(&self, query: &str) -> Result<()> {
+        let df = self.ctx.sql(query).await?;
+        let view = df.into_view();
+        self.ctx.deregister_table(self.args.table.as_str())?;
+        self.ctx.register_table(self.args.table.as_str(), view)?;
+        Ok(())
+    }
+
+    /// Registers the objects as the table to be read, inferring its schema.
+    pub fn register_rows(&self, rows: &[Object]) -> Result<()> {
+        let batch = rows_to_record_batch(rows)?;
+        self.ctx.register_batch(self.table_name(), batch)?;
+        Ok(())
     }
 }
 
@@ -122,7 +294,7 @@ impl PipeBatch for DataFusionBatch {
     }
 
     async fn to_stream(&mut self) -> Result<DefaultStream> {
-        let df = self.ctx.table(DEFAULT_TABLE_REF).await?;
+        let df = self.ctx.table(self.args.table.as_str()).await?;
         let stream = df.execute_stream().await?;
         let stream = stream
             .map_err(Into::into)
@@ -135,6 +307,91 @@ impl PipeBatch for DataFusionBatch {
     }
 }
 
+/// Builds a record batch from the objects; the inverse of [`record_batches_to_rows`].
+///
+/// Each column is inferred as `Boolean`, `Int64` or `Float64` if all of its non-null values
+/// are so, or `Utf8` otherwise, taking the non-string values as JSON.
+/// The missing fields are nulls.
+pub fn rows_to_record_batch(rows: &[Object]) -> Result<RecordBatch> {
+    // Keep the columns in the order of appearance
+    let mut seen = HashSet::new();
+    let names: Vec<&String> = rows
+        .iter()
+        .flat_map(|row| row.keys())
+        .filter(|&name| seen.insert(name))
+        .collect();
+    if names.is_empty() {
+        return Ok(RecordBatch::new_empty(Arc::new(Schema::empty())));
+    }
+
+    let mut fields = Vec::with_capacity(names.len());
+    let mut columns = Vec::with_capacity(names.len());
+    for name in names {
+        let values = || {
+            rows.iter()
+                .map(|row| row.get(name).filter(|value| !matches!(value, Value::Null)))
+        };
+        let data_type = infer_data_type(values().flatten());
+
+        let column: ArrayRef = match data_type {
+            DataType::Boolean => {
+                let mut builder = BooleanBuilder::with_capacity(rows.len());
+                values().for_each(|value| builder.append_option(value.and_then(Value::as_bool)));
+                Arc::new(builder.finish())
+            }
+            DataType::Int64 => {
+                let mut builder = Int64Builder::with_capacity(rows.len());
+                values().for_each(|value| builder.append_option(value.and_then(Value::as_i64)));
+                Arc::new(builder.finish())
+            }
+            DataType::Float64 => {
+                let mut builder = Float64Builder::with_capacity(rows.len());
+                values().for_each(|value| builder.append_option(value.and_then(Value::as_f64)));
+                Arc::new(builder.finish())
+            }
+            _ => {
+                let mut builder = StringBuilder::new();
+                values().for_each(|value| match value {
+                    Some(Value::String(value)) => builder.append_value(value),
+                    Some(value) => builder.append_value(value.to_string()),
+                    None => builder.append_null(),
+                });
+                Arc::new(builder.finish())
+            }
+        };
+        fields.push(Field::new(name, data_type, true));
+        columns.push(column);
+    }
+    RecordBatch::try_new(Arc::new(Schema::new(fields)), columns).map_err(Into::into)
+}
+
+fn infer_data_type<'a>(mut values: impl Iterator<Item = &'a Value>) -> DataType {
+    let Some(first) = values.next() else {
+        return DataType::Utf8;
+    };
+    let mut data_type = match first {
+        Value::Bool(_) => DataType::Boolean,
+        Value::Number(number) if number.as_i64().is_some() => DataType::Int64,
+        Value::Number(number) if number.as_f64().is_some() => DataType::Float64,
+        _ => return DataType::Utf8,
+    };
+    for value in values {
+        data_type = match (data_type, value) {
+            (DataType::Boolean, Value::Bool(_)) => DataType::Boolean,
+            (DataType::Int64, Value::Number(number)) if number.as_i64().is_some() => {
+                DataType::Int64
+            }
+            (DataType::Int64 | DataType::Float64, Value::Number(number))
+                if number.as_f64().is_some() =>
+            {
+                DataType::Float64
+            }
+            _ => return DataType::Utf8,
+        };
+    }
+    data_type
+}
+
 fn record_batches_to_async_rows(
     batch: Result<RecordBatch>,
 ) -> Pin<Box<dyn Send + Stream<Item = Result<Object>>>> {
@@ -144,7 +401,8 @@ fn record_batches_to_async_rows(
     }
 }
 
-fn record_batches_to_rows(batch: &RecordBatch) -> Result<Vec<Object>> {
+/// Converts each row of the record batch into an object, skipping the null fields.
+pub fn record_batches_to_rows(batch: &RecordBatch) -> Result<Vec<Object>> {
     let mut rows = vec![Object::default(); batch.num_rows()];
 
     let schema = batch.schema();
@@ -310,3 +568,209 @@ fn set_column_by_primitive_type<T>(
             }
         });
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use datafusion::{
+        arrow::{
+            array::{Int64Array, RecordBatch},
+            datatypes::{DataType, Field, Schema},
+        },
+        prelude::CsvReadOptions,
+    };
+    use futures::TryStreamExt;
+    use xlake_ast::{Number, Object, Value};
+
+    use crate::{object::LazyObject, PipeChannel};
+
+    use super::{
+        record_batches_to_rows, rows_to_record_batch, BatchFormatArgs, DataFusionBatch, PipeBatch,
+    };
+
+    #[test]
+    fn test_batch_config_applied() {
+        let args = BatchFormatArgs {
+            batch_size: Some(128),
+            target_partitions: Some(3),
+            ..Default::default()
+        };
+        let batch = DataFusionBatch::new(args).unwrap();
+
+        let config = batch.copied_config();
+        assert_eq!(config.batch_size(), 128);
+        assert_eq!(config.target_partitions(), 3);
+    }
+
+    #[test]
+    fn test_batch_config_ordered() {
+        let args = BatchFormatArgs {
+            ordered: true,
+            ..Default::default()
+        };
+        let batch = DataFusionBatch::new(args).unwrap();
+        assert_eq!(batch.copied_config().target_partitions(), 1);
+
+        let args = BatchFormatArgs {
+            ordered: true,
+            target_partitions: Some(4),
+            ..Default::default()
+        };
+        assert!(DataFusionBatch::new(args).is_err());
+    }
+
+    #[::tokio::test]
+    async fn test_batch_ordered_csv() {
+        let path =
+            ::std::env::temp_dir().join(format!("xlake-ordered-{}.csv", ::std::process::id()));
+        let lines: Vec<_> = (0..64).rev().map(|id| id.to_string()).collect();
+        ::std::fs::write(&path, format!("id\n{}\n", lines.join("\n"))).unwrap();
+
+        let args = BatchFormatArgs {
+            batch_size: Some(4),
+            ordered: true,
+            ..Default::default()
+        };
+        let mut batch = DataFusionBatch::new(args).unwrap();
+        let options = CsvReadOptions::default();
+        batch
+            .register_csv(batch.table_name(), path.to_string_lossy(), options)
+            .await
+            .unwrap();
+
+        let stream = batch.to_stream().await.unwrap();
+        let ids: Vec<String> = PipeChannel::from_stream(stream)
+            .into_stream::<LazyObject>()
+            .await
+            .unwrap()
+            .map_ok(|item| item.get_raw("id").unwrap().to_string())
+            .try_collect()
+            .await
+            .unwrap();
+        ::std::fs::remove_file(&path).unwrap();
+        assert_eq!(ids, lines);
+    }
+
+    #[::tokio::test]
+    async fn test_batch_memory_limit() {
+        let spill_dir =
+            ::std::env::temp_dir().join(format!("xlake-spill-{}", ::std::process::id()));
+        ::std::fs::create_dir_all(&spill_dir).unwrap();
+
+        let args = BatchFormatArgs {
+            memory_limit: Some(256 << 10),
+            spill_dir: Some(spill_dir.clone()),
+            ..Default::default()
+        };
+        let mut batch = DataFusionBatch::new(args).unwrap();
+
+        // Sorting the table needs far more memory than the limit
+        let schema = Schema::new(vec![Field::new("id", DataType::Int64, false)]);
+        let ids = Int64Array::from_iter_values(0..(1 << 18));
+        let record = RecordBatch::try_new(Arc::new(schema), vec![Arc::new(ids)]).unwrap();
+        batch.register_batch("source", record).unwrap();
+        batch
+            .query("SELECT id FROM source ORDER BY id DESC")
+            .await
+            .unwrap();
+
+        let result: ::anyhow::Result<Vec<i64>> = async {
+            let stream = batch.to_stream().await?;
+            PipeChannel::from_stream(stream)
+                .into_stream::<LazyObject>()
+                .await?
+                .map_ok(|item| item.get_i64("id").unwrap())
+                .try_collect()
+                .await
+        }
+        .await;
+        ::std::fs::remove_dir_all(&spill_dir).unwrap();
+
+        // Either spilled to complete, or failed without exhausting the memory
+        match result {
+            Ok(ids) => {
+                assert_eq!(ids.len(), 1 << 18);
+                assert!(ids.windows(2).all(|pair| pair[0] > pair[1]));
+            }
+            Err(error) => assert!(error.to_string().contains("Resources exhausted")),
+        }
+    }
+
+    #[test]
+    fn test_batch_config_invalid() {
+        let args = BatchFormatArgs {
+            batch_size: Some(0),
+            ..Default::default()
+        };
+        assert!(DataFusionBatch::new(args).is_err());
+    }
+
+    #[::tokio::test]
+    async fn test_batch_custom_table() {
+        let args = BatchFormatArgs {
+            table: "custom".into(),
+            ..Default::default()
+        };
+        let mut batch = DataFusionBatch::new(args).unwrap();
+
+        let schema = Schema::new(vec![Field::new("id", DataType::Int64, false)]);
+        let ids = Int64Array::from(vec![1, 2, 3]);
+        let record = RecordBatch::try_new(Arc::new(schema), vec![Arc::new(ids)]).unwrap();
+        batch.register_batch(batch.table_name(), record).unwrap();
+
+        let stream = batch.to_stream().await.unwrap();
+        let ids: Vec<String> = PipeChannel::from_stream(stream)
+            .into_stream::<LazyObject>()
+            .await
+            .unwrap()
+            .map_ok(|item| item.get_raw("id").unwrap().to_string())
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(ids, ["1", "2", "3"]);
+    }
+
+    #[test]
+    fn test_rows_to_record_batch_round_trip() {
+        let rows: Vec<Object> = [
+            vec![
+                ("id", Value::from(1)),
+                ("name", "foo".into()),
+                ("ok", true.into()),
+            ],
+            vec![
+                ("id", 2.into()),
+                ("score", Number::Dynamic("0.5".into()).into()),
+            ],
+        ]
+        .into_iter()
+        .map(|row| row.into_iter().map(|(k, v)| (k.into(), v)).collect())
+        .collect();
+
+        let batch = rows_to_record_batch(&rows).unwrap();
+        let schema = batch.schema();
+        assert_eq!(
+            schema.field_with_name("id").unwrap().data_type(),
+            &DataType::Int64
+        );
+        assert_eq!(
+            schema.field_with_name("name").unwrap().data_type(),
+            &DataType::Utf8
+        );
+        assert_eq!(
+            schema.field_with_name("ok").unwrap().data_type(),
+            &DataType::Boolean
+        );
+        assert_eq!(
+            schema.field_with_name("score").unwrap().data_type(),
+            &DataType::Float64
+        );
+
+        // The missing fields are read back as missing
+        let rows = record_batches_to_rows(&batch).unwrap();
+        assert_eq!(rows[0].get("name").unwrap().to_string(), r#""foo""#);
+        assert!(rows[1].get("name").is_none());
+        assert_eq!(rows[1].get("score").unwrap().to_string(), "0.5");
+    }
+}
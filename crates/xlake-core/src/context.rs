@@ -0,0 +1,64 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// The resources shared by all the nodes of a session, passed into each call.
+#[derive(Clone, Debug, Default)]
+pub struct PipeContext {
+    limiter: Option<Arc<Semaphore>>,
+}
+
+impl PipeContext {
+    /// Caps the number of the IO-heavy works in flight across all the nodes.
+    pub fn with_concurrency_limit(limit: usize) -> Self {
+        Self {
+            limiter: Some(Arc::new(Semaphore::new(limit.max(1)))),
+        }
+    }
+
+    /// Waits for a slot of an IO-heavy work, e.g. spawning a process.
+    ///
+    /// The slot is held until the returned permit is dropped; `None` if unlimited.
+    pub async fn acquire(&self) -> Result<Option<OwnedSemaphorePermit>> {
+        match &self.limiter {
+            Some(limiter) => Ok(Some(limiter.clone().acquire_owned().await?)),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use futures::future;
+
+    use super::PipeContext;
+
+    #[::tokio::test]
+    async fn test_context_concurrency_limit() {
+        let ctx = PipeContext::with_concurrency_limit(2);
+        let in_flight = AtomicUsize::default();
+        let max_in_flight = AtomicUsize::default();
+
+        let tasks = (0..8).map(|_| async {
+            let _permit = ctx.acquire().await.unwrap();
+            let count = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            max_in_flight.fetch_max(count, Ordering::SeqCst);
+            for _ in 0..4 {
+                ::tokio::task::yield_now().await;
+            }
+            in_flight.fetch_sub(1, Ordering::SeqCst);
+        });
+        future::join_all(tasks).await;
+
+        assert_eq!(max_in_flight.load(Ordering::SeqCst), 2);
+    }
+
+    #[::tokio::test]
+    async fn test_context_unlimited() {
+        let ctx = PipeContext::default();
+        assert!(ctx.acquire().await.unwrap().is_none());
+    }
+}
@@ -1,4 +1,6 @@
+pub mod args;
 pub mod batch;
+pub mod context;
 pub mod models;
 pub mod object;
 pub mod stream;
@@ -7,10 +9,12 @@ use std::{
     borrow::Borrow,
     collections::BTreeSet,
     fmt,
+    future::Future,
     marker::PhantomData,
     pin::Pin,
-    sync::Arc,
+    sync::{Arc, Mutex},
     task::{Context, Poll},
+    time::Duration,
 };
 
 use anyhow::Result;
@@ -18,13 +22,78 @@ use async_trait::async_trait;
 use futures::{Stream, StreamExt, TryStreamExt};
 use serde::Serialize;
 use tracing::debug;
-use xlake_ast::{Object, PlanArguments, PlanKind, PlanType};
+use xlake_ast::{fmt_plan, Object, PlanArguments, PlanKind, PlanType, Value};
 
+pub use xlake_ast::ArgSpec;
+
+/// A node transforming the items of a channel.
+///
+/// Prefer returning a lazy channel, e.g. with [`PipeChannel::and_then`],
+/// so that the items flow through the following nodes one by one
+/// instead of being buffered at each func boundary.
 #[async_trait]
-pub trait PipeFunc: fmt::Debug {
+pub trait PipeFunc: Send + Sync + fmt::Debug {
     async fn call(&self, channel: PipeChannel) -> Result<PipeChannel>;
+
+    /// Calls with the resources shared by the session, e.g. the concurrency limit.
+    ///
+    /// Override it to acquire the resources; it falls back to [`Self::call`] by default.
+    async fn call_with_context(
+        &self,
+        ctx: &self::context::PipeContext,
+        channel: PipeChannel,
+    ) -> Result<PipeChannel> {
+        let _ = ctx;
+        self.call(channel).await
+    }
+
+    /// Returns the per-object timeout and retries, applied by the session.
+    ///
+    /// If given, the func is called once per object, so it should not aggregate the objects.
+    fn retry_options(&self) -> Option<RetryOptions> {
+        None
+    }
+}
+
+/// Options of the per-object timeout and retries of a [`PipeFunc`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct RetryOptions {
+    /// Delay before the first retry, doubled after each failure.
+    pub backoff: Duration,
+    /// Maximum number of the retries after the first attempt.
+    pub max_retries: usize,
+    /// Maximum duration of each attempt; the timed out attempts are retried.
+    pub timeout: Option<Duration>,
+    /// Whether to retry every failure, rather than the [`TransientError`]s only.
+    pub all_errors: bool,
+}
+
+impl Default for RetryOptions {
+    fn default() -> Self {
+        Self {
+            backoff: Duration::from_millis(100),
+            max_retries: 3,
+            timeout: None,
+            all_errors: false,
+        }
+    }
+}
+
+/// Marks an error as transient, so that it is retried under [`RetryOptions`].
+///
+/// The other errors are propagated immediately, unless [`RetryOptions::all_errors`] is set.
+#[derive(Debug)]
+pub struct TransientError(pub anyhow::Error);
+
+impl fmt::Display for TransientError {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
 }
 
+impl std::error::Error for TransientError {}
+
 pub trait PipeModelConverter: fmt::Debug {}
 
 #[async_trait]
@@ -101,11 +170,65 @@ pub trait PipeModelView {
 #[async_trait]
 pub trait PipeSink: fmt::Debug {
     async fn call(&self, channel: PipeChannel) -> Result<()>;
+
+    /// Returns what the last call has written, if the sink keeps track of it.
+    fn summary(&self) -> Option<SinkSummary> {
+        None
+    }
+}
+
+/// What a [`PipeSink`] has written in a call, e.g. to be printed after a run.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct SinkSummary {
+    /// Number of the written objects.
+    pub items: usize,
+    /// Number of the written bytes, including the separators.
+    pub bytes: u64,
+    /// Duration of the call.
+    pub duration: Duration,
+}
+
+impl fmt::Display for SinkSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self {
+            items,
+            bytes,
+            duration,
+        } = self;
+        write!(f, "Wrote {items} objects ({bytes} bytes) in {duration:?}")
+    }
+}
+
+/// Keeps the [`SinkSummary`] of the last call, shared by the clones of a sink.
+#[derive(Clone, Debug, Default)]
+pub struct SinkSummaryCell(Arc<Mutex<Option<SinkSummary>>>);
+
+impl SinkSummaryCell {
+    pub fn get(&self) -> Option<SinkSummary> {
+        *self.0.lock().unwrap()
+    }
+
+    pub fn set(&self, summary: SinkSummary) {
+        self.0.lock().unwrap().replace(summary);
+    }
 }
 
 #[async_trait]
 pub trait PipeSrc: fmt::Debug {
     async fn call(&self) -> Result<PipeChannel>;
+
+    /// Returns the resumable view of the src, if it supports the `offset` argument.
+    fn as_resumable(&self) -> Option<&dyn Resumable> {
+        None
+    }
+}
+
+/// A src which can skip the items processed by a previous run.
+///
+/// The offset is opaque to the session, which passes it back as the `offset` argument.
+pub trait Resumable: fmt::Debug {
+    /// Returns the offset right after the last emitted item, or `None` if unknown.
+    fn offset(&self) -> Option<Value>;
 }
 
 #[async_trait]
@@ -115,11 +238,70 @@ pub trait PipeStore: Send + Sync + fmt::Debug {
     async fn read_item(&self, hash: &self::models::hash::Hash) -> Result<Object>;
 
     async fn write_item(&self, hash: &self::models::hash::Hash, object: &Object) -> Result<()>;
+
+    /// Returns a checksum of the stored item, e.g. as an ETag, or `None` if missing.
+    ///
+    /// The default implementation digests the whole item; override it if cheaper,
+    /// along with [`Self::read_item_with_checksum`].
+    async fn checksum(&self, hash: &self::models::hash::Hash) -> Result<Option<String>> {
+        if !self.contains(hash).await? {
+            return Ok(None);
+        }
+        let object = self.read_item(hash).await?;
+        let checksum = self::models::hash::Hash::digest(object.to_vec()?);
+        Ok(Some(checksum.to_string()))
+    }
+
+    /// Reads the item along with the same checksum as [`Self::checksum`].
+    ///
+    /// The default implementation digests the item read, rather than reading it twice.
+    async fn read_item_with_checksum(
+        &self,
+        hash: &self::models::hash::Hash,
+    ) -> Result<(Object, String)> {
+        let object = self.read_item(hash).await?;
+        let checksum = self::models::hash::Hash::digest(object.to_vec()?);
+        Ok((object, checksum.to_string()))
+    }
+}
+
+/// Options of [`PipeStoreExt::save_with`] and [`PipeStoreExt::tee_with`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct SaveOptions {
+    /// Maximum number of the objects being looked up or written at once.
+    ///
+    /// [`PipeStoreExt::save_with`] may emit the objects out of order if greater than `1`,
+    /// while [`PipeStoreExt::tee_with`] keeps their order.
+    pub concurrency: usize,
+}
+
+impl Default for SaveOptions {
+    fn default() -> Self {
+        Self { concurrency: 1 }
+    }
 }
 
 #[async_trait]
 pub trait PipeStoreExt {
-    async fn save(&self, channel: PipeChannel) -> Result<PipeChannel>;
+    /// Writes all the objects into the store before passing them through.
+    async fn save(&self, channel: PipeChannel) -> Result<PipeChannel> {
+        self.save_with(channel, SaveOptions::default()).await
+    }
+
+    /// Writes all the objects into the store, overlapping the independent ones.
+    ///
+    /// Fails on the first error, dropping the pending lookups and writes.
+    async fn save_with(&self, channel: PipeChannel, options: SaveOptions) -> Result<PipeChannel>;
+
+    /// Lazily writes each object into the store while passing it through.
+    ///
+    /// The objects without a hash are passed through as they are.
+    async fn tee(&self, channel: PipeChannel) -> Result<PipeChannel> {
+        self.tee_with(channel, SaveOptions::default()).await
+    }
+
+    /// Lazily writes each object into the store, overlapping the independent ones in order.
+    async fn tee_with(&self, channel: PipeChannel, options: SaveOptions) -> Result<PipeChannel>;
 }
 
 #[async_trait]
@@ -127,41 +309,67 @@ impl<T> PipeStoreExt for Arc<T>
 where
     T: 'static + ?Sized + PipeStore,
 {
-    async fn save(&self, channel: PipeChannel) -> Result<PipeChannel> {
+    async fn save_with(&self, channel: PipeChannel, options: SaveOptions) -> Result<PipeChannel> {
+        let SaveOptions { concurrency } = options;
+        let store = self.clone();
         channel
             .into_stream::<self::object::LazyObject>()
             .await?
-            .and_then(|item| async {
-                let mut item = match self::models::hash::HashModelView::__cast(item) {
-                    Ok(item) => item,
-                    Err(item) => return Ok(item),
-                };
-                let hash = item.hash();
-                if self.contains(&hash).await? {
-                    // Hit
-                    if item.is_ready() {
-                        // Drop the future and get it from the store
-                        debug!("Hit cache: {hash}");
-                        let future = Box::pin({
-                            let store = self.clone();
-                            async move { store.read_item(&hash).await }
-                        });
-                        Ok(item.into_any().replace_with(future))
-                    } else {
-                        Ok(item.into_any())
-                    }
-                } else {
-                    // Miss
-                    debug!("Miss cache: {hash}");
-                    let content = item.into_any().flatten().await?;
-                    self.write_item(&hash, content.as_content_unpolled())
-                        .await?;
-                    Ok(content)
-                }
-            })
+            .map_ok(move |item| tee_item(store.clone(), item))
+            .try_buffer_unordered(concurrency.max(1))
             .try_collect()
             .await
     }
+
+    async fn tee_with(&self, channel: PipeChannel, options: SaveOptions) -> Result<PipeChannel> {
+        let SaveOptions { concurrency } = options;
+        let store = self.clone();
+        let stream = channel
+            .into_stream::<self::object::LazyObject>()
+            .await?
+            .map_ok(move |item| tee_item(store.clone(), item))
+            .try_buffered(concurrency.max(1))
+            .boxed();
+        let stream = self::stream::DefaultStream::from_stream(stream);
+        Ok(PipeChannel::from_stream(stream))
+    }
+}
+
+async fn tee_item<T>(
+    store: Arc<T>,
+    item: self::object::LazyObject,
+) -> Result<self::object::LazyObject>
+where
+    T: 'static + ?Sized + PipeStore,
+{
+    let mut item = match self::models::hash::HashModelView::__cast(item) {
+        Ok(item) => item,
+        Err(item) => return Ok(item),
+    };
+    let hash = item.hash();
+    if store.contains(&hash).await? {
+        // Hit
+        if item.is_ready() {
+            // Drop the future and get it from the store
+            debug!("Hit cache: {hash}");
+            let future = Box::pin(async move {
+                let (mut object, etag) = store.read_item_with_checksum(&hash).await?;
+                object.insert(self::models::hash::ETAG.into(), Value::String(etag));
+                Ok(object)
+            });
+            Ok(item.into_any().replace_with(future))
+        } else {
+            Ok(item.into_any())
+        }
+    } else {
+        // Miss
+        debug!("Miss cache: {hash}");
+        let content = item.into_any().flatten().await?;
+        store
+            .write_item(&hash, content.as_content_unpolled())
+            .await?;
+        Ok(content)
+    }
 }
 
 #[derive(Debug)]
@@ -205,17 +413,7 @@ impl fmt::Debug for PipeNode {
 impl fmt::Display for PipeNode {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let Self { kind, args, imp: _ } = self;
-        write!(f, "{kind}")?;
-
-        for (index, (key, value)) in args.iter().enumerate() {
-            if index > 0 {
-                write!(f, ", ")?;
-            } else {
-                write!(f, " ")?;
-            }
-            write!(f, "{key}={value:?}")?;
-        }
-        Ok(())
+        fmt_plan(f, kind, args)
     }
 }
 
@@ -242,6 +440,10 @@ pub trait PipeNodeFactory: fmt::Debug {
 
     fn name(&self) -> String;
 
+    fn arguments(&self) -> Vec<ArgSpec> {
+        Vec::default()
+    }
+
     fn input(&self) -> PipeEdge {
         PipeEdge::default()
     }
@@ -250,12 +452,33 @@ pub trait PipeNodeFactory: fmt::Debug {
         PipeEdge::default()
     }
 
+    /// Returns the output edge of the node built with the arguments, e.g. the models per format.
+    ///
+    /// It falls back to [`Self::output`] by default.
+    fn output_with(&self, args: &PlanArguments) -> PipeEdge {
+        let _ = args;
+        self.output()
+    }
+
+    /// Returns whether the func maps each object independently of the others.
+    ///
+    /// Only such funcs can be called once per object by the session, e.g. for `dead_letter`,
+    /// so override it unless the func aggregates or numbers the objects.
+    fn is_per_object(&self) -> bool {
+        false
+    }
+
+    /// Builds the node.
+    ///
+    /// The session fills the missing arguments with the defaults declared in
+    /// [`arguments`](Self::arguments) beforehand.
     async fn build(&self, args: &PlanArguments) -> Result<PipeNodeImpl>;
 }
 
 #[derive(Debug)]
 pub struct PipeChannel {
     batch: Box<dyn self::batch::PipeBatch>,
+    models: BTreeSet<String>,
     stream: Box<dyn self::stream::PipeStream>,
 }
 
@@ -263,6 +486,7 @@ impl Default for PipeChannel {
     fn default() -> Self {
         Self {
             batch: Box::new(self::batch::DefaultBatch::default()),
+            models: BTreeSet::default(),
             stream: Box::new(self::stream::DefaultStream::default()),
         }
     }
@@ -295,6 +519,18 @@ impl PipeChannel {
         }
     }
 
+    /// Wraps the batch, also exposing its table to the stream consumers.
+    pub async fn from_batch_streamed(
+        mut batch: impl 'static + self::batch::PipeBatch,
+    ) -> Result<Self> {
+        let stream = batch.to_stream().await?;
+        Ok(Self {
+            batch: Box::new(batch),
+            models: BTreeSet::default(),
+            stream: Box::new(stream),
+        })
+    }
+
     #[inline]
     pub fn from_stream(stream: impl 'static + self::stream::PipeStream) -> Self {
         Self {
@@ -314,12 +550,53 @@ impl PipeChannel {
         Self::from_stream(stream)
     }
 
+    /// Returns the batch of the channel, e.g. to query the table registered upstream.
+    #[inline]
+    pub async fn to_batch(&mut self) -> Result<self::batch::DefaultBatch> {
+        self.batch.to_default().await
+    }
+
+    /// Declares the models the objects provide, e.g. from the static edges.
+    #[inline]
+    pub fn with_models(mut self, models: impl IntoIterator<Item = String>) -> Self {
+        self.models.extend(models);
+        self
+    }
+
+    /// Returns the models the objects provide, best-effort.
+    ///
+    /// This is the union of the declared models and the ones of the buffered objects.
+    pub fn models(&self) -> BTreeSet<String> {
+        let mut models = self.stream.models();
+        models.extend(self.models.iter().cloned());
+        models
+    }
+
+    /// Lazily applies `f` to each item as the channel is polled.
+    pub async fn and_then<F, Fut>(self, f: F) -> Result<Self>
+    where
+        F: 'static + Send + FnMut(self::object::LazyObject) -> Fut,
+        Fut: 'static + Send + Future<Output = Result<self::object::LazyObject>>,
+    {
+        let stream = self
+            .into_stream::<self::object::LazyObject>()
+            .await?
+            .and_then(f)
+            .boxed();
+        let stream = self::stream::DefaultStream::from_stream(stream);
+        Ok(Self::from_stream(stream))
+    }
+
     #[inline]
     pub async fn into_stream<T>(self) -> Result<PipeChannelStream<T>>
     where
         T: Unpin + PipeModelOwned<self::object::LazyObject>,
     {
-        let Self { batch, mut stream } = self;
+        let Self {
+            batch,
+            models: _,
+            mut stream,
+        } = self;
 
         Ok(PipeChannelStream {
             _view: PhantomData,
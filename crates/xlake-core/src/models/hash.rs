@@ -16,6 +16,11 @@ use crate::{
     PipeModelObject, PipeModelOwned, PipeModelView,
 };
 
+/// The field of the objects loaded from a store, keeping the checksum of the stored item.
+///
+/// It is not a part of the contents, so that it is excluded from the hash.
+pub const ETAG: &str = "etag";
+
 pub trait Hashable {
     fn as_bytes(&self) -> &[u8];
 }
@@ -94,6 +99,14 @@ impl<T> HashableExt for T where T: Hashable {}
 #[serde(transparent)]
 pub struct Hash<T = String>(pub(crate) T);
 
+impl Hash {
+    /// Digests the given bytes, as used by the [`HashModelView`]s.
+    #[inline]
+    pub fn digest(hashable: impl Hashable) -> Self {
+        Self(hashable.digest_string())
+    }
+}
+
 impl<T> ops::Deref for Hash<T> {
     type Target = T;
 
@@ -265,6 +278,14 @@ impl<T> HashModelView<T>
 where
     T: BorrowMut<LazyObject>,
 {
+    pub fn from_item(mut item: T, hashable: impl Hashable) -> Self {
+        item.borrow_mut().insert(
+            self::__keys::hash.into(),
+            Value::String(hashable.digest_string()),
+        );
+        Self { item }
+    }
+
     pub fn hash(&mut self) -> Hash {
         Hash(
             self.item
@@ -1,4 +1,11 @@
-use std::{collections::BTreeSet, fmt, future::Future, ops, pin::Pin};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+    future::Future,
+    ops,
+    pin::Pin,
+    sync::Arc,
+};
 
 use anyhow::Result;
 use futures::{stream::FuturesOrdered, FutureExt, TryFutureExt, TryStreamExt};
@@ -59,6 +66,7 @@ impl LazyObject {
                     content: Default::default(),
                     future: Some(future),
                     models: <T as crate::PipeModelObject>::__provides(),
+                    peeks: Default::default(),
                 };
                 self.layers.push(layer)
             }
@@ -73,6 +81,36 @@ impl LazyObject {
         self.layers.push(layer)
     }
 
+    /// Returns the models provided by all the layers, including the pending ones.
+    pub fn models(&self) -> BTreeSet<String> {
+        self.layers
+            .iter()
+            .flat_map(|layer| layer.models.iter().cloned())
+            .collect()
+    }
+
+    /// Returns whether any layer provides the model, without casting into its view.
+    pub fn has_model(&self, name: &str) -> bool {
+        self.layers.iter().any(|layer| layer.has_model(name))
+    }
+
+    /// Reads up to `len` leading bytes of the binary field, without resolving the pending futures.
+    ///
+    /// The ready contents are preferred over the peeks registered by [`ObjectLayer::set_peek`].
+    /// Returns `None` if the field is neither ready nor peekable, e.g. to fall back to [`Self::flatten`].
+    pub fn peek(&self, key: &str, len: usize) -> MaybeObject<Option<Vec<u8>>> {
+        for layer in self.layers.iter().rev() {
+            if let Some(bytes) = layer.get_bytes(key) {
+                let bytes = bytes[..len.min(bytes.len())].to_vec();
+                return async move { Ok(Some(bytes)) }.boxed();
+            }
+            if let Some(peek) = layer.peeks.get(key) {
+                return peek(len).map_ok(Some).boxed();
+            }
+        }
+        async { Ok(None) }.boxed()
+    }
+
     pub async fn flatten(mut self) -> Result<Self> {
         let () = self
             .layers
@@ -92,6 +130,7 @@ impl LazyObject {
             content: Default::default(),
             future: None,
             models: Default::default(),
+            peeks: Default::default(),
         };
         for mut layer in layers {
             object.merge_without_future(&mut layer)
@@ -112,6 +151,9 @@ impl LazyObject {
 
 type MaybeObject<T = Object> = Pin<Box<dyn Send + Future<Output = Result<T>>>>;
 
+/// Reads up to the given number of the leading bytes of a pending binary field.
+pub type PeekFn = Arc<dyn Send + Sync + Fn(usize) -> MaybeObject<Vec<u8>>>;
+
 #[derive(Serialize, Deserialize)]
 pub struct ObjectLayer {
     #[serde(flatten)]
@@ -120,6 +162,9 @@ pub struct ObjectLayer {
     future: Option<MaybeObject>,
     #[serde(rename = "__models")]
     models: BTreeSet<String>,
+    /// The readers of the leading bytes of the pending binary fields
+    #[serde(skip)]
+    peeks: BTreeMap<String, PeekFn>,
 }
 
 impl fmt::Debug for ObjectLayer {
@@ -142,6 +187,7 @@ impl ObjectLayer {
             content,
             future: None,
             models,
+            peeks: Default::default(),
         }
     }
 
@@ -151,6 +197,7 @@ impl ObjectLayer {
             content,
             future: None,
             models: Default::default(),
+            peeks: Default::default(),
         }
     }
 
@@ -163,6 +210,7 @@ impl ObjectLayer {
             content: Object::from_value(object)?,
             future: None,
             models,
+            peeks: Default::default(),
         })
     }
 
@@ -171,6 +219,28 @@ impl ObjectLayer {
         &self.content
     }
 
+    #[inline]
+    pub const fn content(&self) -> &Object {
+        &self.content
+    }
+
+    /// Returns the models provided by this layer only.
+    #[inline]
+    pub const fn models(&self) -> &BTreeSet<String> {
+        &self.models
+    }
+
+    /// Returns whether this layer provides the model.
+    #[inline]
+    pub fn has_model(&self, name: &str) -> bool {
+        self.models.contains(name)
+    }
+
+    #[inline]
+    pub fn content_mut(&mut self) -> &mut Object {
+        &mut self.content
+    }
+
     #[inline]
     pub fn get<T>(&mut self, key: &str) -> Option<&mut T::Target>
     where
@@ -179,6 +249,31 @@ impl ObjectLayer {
         <T as ValueExt>::get(self, key)
     }
 
+    #[inline]
+    pub fn get_bool(&self, key: &str) -> Option<bool> {
+        self.content.get_bool(key)
+    }
+
+    #[inline]
+    pub fn get_bytes(&self, key: &str) -> Option<&[u8]> {
+        self.content.get_bytes(key)
+    }
+
+    #[inline]
+    pub fn get_f64(&self, key: &str) -> Option<f64> {
+        self.content.get_f64(key)
+    }
+
+    #[inline]
+    pub fn get_i64(&self, key: &str) -> Option<i64> {
+        self.content.get_i64(key)
+    }
+
+    #[inline]
+    pub fn get_str(&self, key: &str) -> Option<&str> {
+        self.content.get_str(key)
+    }
+
     #[inline]
     pub fn get_raw(&self, key: &str) -> Option<&Value> {
         self.content.get(key)
@@ -189,28 +284,57 @@ impl ObjectLayer {
         self.content.get_mut(key)
     }
 
+    /// Inserts the field, canonicalizing its numbers so that e.g. `"42"` and `42` behave the same.
     #[inline]
-    pub fn insert(&mut self, key: String, value: Value) -> Option<Value> {
+    pub fn insert(&mut self, key: String, mut value: Value) -> Option<Value> {
+        value.canonicalize();
         self.content.insert(key, value)
     }
 
+    /// Registers a cheap reader of the leading bytes of the binary field `key`,
+    /// which is filled by the pending future of this layer.
+    #[inline]
+    pub fn set_peek(&mut self, key: impl Into<String>, peek: PeekFn) {
+        self.peeks.insert(key.into(), peek);
+    }
+
     #[inline]
     pub(crate) const fn is_ready(&self) -> bool {
         self.future.is_none()
     }
 
+    /// Clones the content and the models, returning `None` if the layer
+    /// still has a pending future.
+    pub fn try_clone(&self) -> Option<Self> {
+        if !self.is_ready() {
+            return None;
+        }
+        Some(Self {
+            content: self.content.clone(),
+            future: None,
+            models: self.models.clone(),
+            peeks: self.peeks.clone(),
+        })
+    }
+
     fn merge_without_future(&mut self, other: &mut Self) {
         self.content.append(&mut other.content);
         self.models.append(&mut other.models);
+        self.peeks.append(&mut other.peeks);
     }
 
     async fn take_future(&mut self) -> Result<()> {
         if let Some(future) = self.future.take() {
-            self.content.append(&mut *future.await?)
+            self.content.append(&mut future.await?)
         }
         Ok(())
     }
 
+    /// Serializes the content into a single-line JSON.
+    pub fn to_string_compact(&self) -> Result<String> {
+        self.content.to_string()
+    }
+
     pub fn to_string_pretty(&self) -> Result<String> {
         self.content.to_string_pretty()
     }
@@ -240,7 +364,12 @@ macro_rules! impl_model_entity {
 impl_model_entity!(bool as Bool => bool);
 impl_model_entity!(Number as Number => Number);
 impl_model_entity!(String as String => String);
+impl_model_entity!(Vec<Value> as Array => Vec<Value>);
+impl_model_entity!(Object as Object => Object);
 
+/// Reads the bytes, decoding a string as base64, e.g. of a `Binary` loaded from JSON.
+///
+/// A string is never reinterpreted as its UTF-8 bytes; a non-base64 one is not binary.
 impl ValueExt for Binary {
     type Target = Vec<u8>;
 
@@ -248,8 +377,7 @@ impl ValueExt for Binary {
     fn get<'a>(layer: &'a mut ObjectLayer, key: &str) -> Option<&'a mut Self::Target> {
         let value = layer.content.get_mut(key)?;
         if let Value::String(v) = value {
-            let v = Binary(v.as_bytes().to_vec());
-            *value = Value::Binary(v);
+            *value = Value::Binary(Binary::from_base64(v).ok()?);
         }
         match value {
             Value::Binary(v) => Some(v),
@@ -266,3 +394,116 @@ impl ValueExt for Value {
         layer.content.get_mut(key)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::BTreeSet,
+        sync::{
+            atomic::{AtomicBool, AtomicUsize, Ordering},
+            Arc,
+        },
+    };
+
+    use futures::FutureExt;
+    use xlake_ast::{Binary, Object, Value};
+
+    use super::{LazyObject, ObjectLayer};
+
+    #[test]
+    fn test_binary_json_round_trip() {
+        let bytes = vec![0, 159, 146, 150, 255];
+        let mut object = Object::default();
+        object.insert("content".into(), Binary(bytes.clone()).into());
+
+        // Binary is stored as a base64 string in JSON
+        let object = Object::from_slice(&object.to_vec().unwrap()).unwrap();
+        assert!(matches!(object.get("content"), Some(Value::String(_))));
+
+        let mut layer = ObjectLayer::from_object_dyn(object);
+        assert_eq!(layer.get::<Binary>("content"), Some(&mut bytes.clone()));
+    }
+
+    #[test]
+    fn test_binary_not_base64() {
+        let mut object = Object::default();
+        object.insert("content".into(), "not base64!".into());
+
+        let mut layer = ObjectLayer::from_object_dyn(object);
+        assert_eq!(layer.get::<Binary>("content"), None);
+    }
+
+    #[test]
+    fn test_to_string_compact() {
+        let mut object = Object::default();
+        object.insert("name".into(), "foo".into());
+        object.insert("tags".into(), Value::Array(vec!["a".into(), "b".into()]));
+        let layer = ObjectLayer::from_object_dyn(object);
+
+        let compact = layer.to_string_compact().unwrap();
+        assert!(!compact.contains('\n'));
+        assert!(layer.to_string_pretty().unwrap().contains('\n'));
+    }
+
+    #[test]
+    fn test_models() {
+        let models = BTreeSet::from(["binary".to_string(), "file".to_string()]);
+        let layer = ObjectLayer::from_object(Object::default(), models.clone());
+        assert_eq!(layer.models(), &models);
+        assert!(layer.has_model("binary"));
+        assert!(!layer.has_model("doc"));
+
+        // The models of the other layers are kept apart until flattened
+        let mut item = LazyObject::from(layer);
+        item.append_layer(ObjectLayer::empty(BTreeSet::from(["doc".into()])));
+        assert!(item.has_model("binary"));
+        assert!(item.has_model("doc"));
+        assert!(!item.has_model("hash"));
+        assert_eq!(item.models().len(), 3);
+        assert_eq!(item.layers[0].models(), &models);
+    }
+
+    #[::tokio::test]
+    async fn test_peek_deferred_binary() {
+        const LEN: usize = 64 * 1024 * 1024;
+        let resolved = Arc::new(AtomicBool::new(false));
+        let read = Arc::new(AtomicUsize::new(0));
+
+        let mut layer = ObjectLayer::empty(BTreeSet::from(["binary".into()]));
+        layer.future = Some({
+            let resolved = resolved.clone();
+            async move {
+                resolved.store(true, Ordering::SeqCst);
+                let mut object = Object::default();
+                object.insert("content".into(), Binary(vec![7; LEN]).into());
+                Ok(object)
+            }
+            .boxed()
+        });
+        layer.set_peek(
+            "content",
+            Arc::new({
+                let read = read.clone();
+                move |len| {
+                    let len = len.min(LEN);
+                    read.fetch_add(len, Ordering::SeqCst);
+                    async move { Ok::<_, anyhow::Error>(vec![7; len]) }.boxed()
+                }
+            }),
+        );
+        let item = LazyObject::from(layer);
+
+        // Only the leading bytes are read, leaving the future pending
+        let head = item.peek("content", 16).await.unwrap();
+        assert_eq!(head, Some(vec![7; 16]));
+        assert_eq!(read.load(Ordering::SeqCst), 16);
+        assert!(!resolved.load(Ordering::SeqCst));
+        assert!(item.peek("missing", 16).await.unwrap().is_none());
+
+        // The resolved content is preferred over the peek
+        let item = item.flatten().await.unwrap();
+        assert!(resolved.load(Ordering::SeqCst));
+        assert_eq!(item.peek("content", 4).await.unwrap(), Some(vec![7; 4]));
+        assert_eq!(read.load(Ordering::SeqCst), 16);
+    }
+}
@@ -1,5 +1,5 @@
 use std::{
-    collections::VecDeque,
+    collections::{BTreeSet, VecDeque},
     fmt, mem,
     pin::Pin,
     task::{Context, Poll},
@@ -7,7 +7,7 @@ use std::{
 
 use anyhow::Result;
 use async_trait::async_trait;
-use futures::{Stream, StreamExt};
+use futures::{Stream, StreamExt, TryStreamExt};
 use xlake_ast::{PlanArguments, PlanKind};
 
 use crate::{object::LazyObject, PipeEdge, PipeNodeFactory, PipeNodeImpl};
@@ -21,6 +21,11 @@ pub const NAME: &str = "memory";
 pub trait PipeStream: Send + fmt::Debug {
     fn extend_one(&mut self, item: LazyObject);
 
+    /// Returns the models of the items known so far, e.g. the buffered ones.
+    fn models(&self) -> BTreeSet<String> {
+        BTreeSet::default()
+    }
+
     async fn to_default(&mut self) -> Result<DefaultStream>;
 }
 
@@ -63,6 +68,10 @@ impl PipeNodeFactory for MemoryStreamFactory {
     }
 }
 
+/// A stream of the inner stream followed by the buffered items.
+///
+/// The inner stream is drained fully first, then the items given by [`PipeStream::extend_one`],
+/// including the ones pushed after the inner stream is exhausted.
 #[derive(Default)]
 pub struct MemoryStream {
     stream: Option<Pin<Box<dyn Send + Stream<Item = Result<LazyObject>>>>>,
@@ -71,7 +80,7 @@ pub struct MemoryStream {
 
 impl fmt::Debug for MemoryStream {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.debug_struct("StreamFormat")
+        f.debug_struct("MemoryStream")
             .field("stream", &"...")
             .field("new", &self.new)
             .finish()
@@ -120,6 +129,10 @@ impl PipeStream for MemoryStream {
         self.new.push_back(item)
     }
 
+    fn models(&self) -> BTreeSet<String> {
+        self.new.iter().flat_map(|item| item.models()).collect()
+    }
+
     async fn to_default(&mut self) -> Result<Self> {
         let Self { stream, new } = self;
         Ok(Self {
@@ -142,9 +155,164 @@ impl Stream for MemoryStream {
             ref mut new,
         } = self.get_mut();
 
-        match stream.as_mut().map(|stream| stream.poll_next_unpin(cx)) {
-            Some(Poll::Ready(None)) | None => Poll::Ready(new.pop_front().map(Ok)),
-            Some(polled) => polled,
+        if let Some(inner) = stream.as_mut() {
+            match inner.poll_next_unpin(cx) {
+                // Drop the exhausted stream, which may not be polled again
+                Poll::Ready(None) => *stream = None,
+                polled => return polled,
+            }
+        }
+        Poll::Ready(new.pop_front().map(Ok))
+    }
+}
+
+/// A lookahead of one item over a stream of objects, e.g. in the windowing or merge funcs
+/// detecting the boundaries by the next item without consuming it.
+///
+/// The inner stream is not polled again once exhausted.
+#[derive(Debug)]
+pub struct PeekableStream<S> {
+    done: bool,
+    peeked: Option<LazyObject>,
+    stream: S,
+}
+
+impl<S> PeekableStream<S>
+where
+    S: Unpin + Stream<Item = Result<LazyObject>>,
+{
+    pub const fn new(stream: S) -> Self {
+        Self {
+            done: false,
+            peeked: None,
+            stream,
         }
     }
+
+    /// Returns the next item without consuming it, or `None` at the end of stream.
+    ///
+    /// The errors of the inner stream are returned here, rather than being buffered.
+    pub async fn peek(&mut self) -> Result<Option<&LazyObject>> {
+        if self.peeked.is_none() && !self.done {
+            match self.stream.try_next().await? {
+                Some(item) => self.peeked = Some(item),
+                None => self.done = true,
+            }
+        }
+        Ok(self.peeked.as_ref())
+    }
+
+    /// Consumes the next item only if it satisfies the predicate.
+    pub async fn next_if(
+        &mut self,
+        predicate: impl FnOnce(&LazyObject) -> bool,
+    ) -> Result<Option<LazyObject>> {
+        let matched = matches!(self.peek().await?, Some(item) if predicate(item));
+        Ok(if matched { self.peeked.take() } else { None })
+    }
+
+    /// Returns the inner stream, along with the peeked item if any.
+    pub fn into_inner(self) -> (Option<LazyObject>, S) {
+        (self.peeked, self.stream)
+    }
+}
+
+impl<S> Stream for PeekableStream<S>
+where
+    S: Unpin + Stream<Item = Result<LazyObject>>,
+{
+    type Item = Result<LazyObject>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if let Some(item) = this.peeked.take() {
+            return Poll::Ready(Some(Ok(item)));
+        }
+        if this.done {
+            return Poll::Ready(None);
+        }
+        match this.stream.poll_next_unpin(cx) {
+            Poll::Ready(None) => {
+                this.done = true;
+                Poll::Ready(None)
+            }
+            polled => polled,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::{stream, StreamExt, TryStreamExt};
+    use xlake_ast::Object;
+
+    use crate::object::{LazyObject, ObjectLayer};
+
+    use super::{MemoryStream, PeekableStream, PipeStream};
+
+    fn item(name: &str) -> LazyObject {
+        let mut object = Object::default();
+        object.insert("name".into(), name.into());
+        ObjectLayer::from_object_dyn(object).into()
+    }
+
+    async fn next_names(stream: &mut MemoryStream) -> Vec<String> {
+        let mut names = Vec::default();
+        while let Some(item) = stream.try_next().await.unwrap() {
+            names.push(item.get_str("name").unwrap().to_string());
+        }
+        names
+    }
+
+    #[::tokio::test]
+    async fn test_memory_stream_order() {
+        // `unfold` panics if polled after the end of stream
+        let inner = stream::unfold(0, |index| async move {
+            (index < 2).then(|| (Ok(item(&format!("inner{index}"))), index + 1))
+        });
+        let mut stream = MemoryStream::from_stream(inner.boxed());
+        PipeStream::extend_one(&mut stream, item("before"));
+        assert_eq!(
+            next_names(&mut stream).await,
+            ["inner0", "inner1", "before"]
+        );
+
+        // The items pushed after the end of stream are still emitted
+        PipeStream::extend_one(&mut stream, item("after"));
+        assert_eq!(next_names(&mut stream).await, ["after"]);
+        assert!(next_names(&mut stream).await.is_empty());
+    }
+
+    #[::tokio::test]
+    async fn test_peekable_stream() {
+        // `unfold` panics if polled after the end of stream
+        let inner = stream::unfold(0, |index| async move {
+            (index < 3).then(|| (Ok(item(&format!("item{index}"))), index + 1))
+        });
+        let mut stream = PeekableStream::new(inner.boxed());
+        fn name(item: Option<&LazyObject>) -> Option<&str> {
+            item.and_then(|item| item.get_str("name"))
+        }
+
+        // Peeking twice does not consume the item
+        assert_eq!(name(stream.peek().await.unwrap()), Some("item0"));
+        assert_eq!(name(stream.peek().await.unwrap()), Some("item0"));
+        let next = stream.try_next().await.unwrap();
+        assert_eq!(name(next.as_ref()), Some("item0"));
+
+        // The predicate decides whether to consume the peeked item
+        let is_item2 = |item: &LazyObject| item.get_str("name") == Some("item2");
+        assert!(stream.next_if(is_item2).await.unwrap().is_none());
+        assert_eq!(name(stream.peek().await.unwrap()), Some("item1"));
+        let next = stream.try_next().await.unwrap();
+        assert_eq!(name(next.as_ref()), Some("item1"));
+        let next = stream.next_if(is_item2).await.unwrap();
+        assert_eq!(name(next.as_ref()), Some("item2"));
+
+        // The end of stream is kept without polling the inner stream again
+        assert!(stream.peek().await.unwrap().is_none());
+        assert!(stream.peek().await.unwrap().is_none());
+        assert!(stream.try_next().await.unwrap().is_none());
+        assert!(stream.try_next().await.unwrap().is_none());
+    }
 }
@@ -2,8 +2,9 @@ use cruet::Inflector;
 use proc_macro::TokenStream;
 use quote::quote;
 use syn::{
-    parse_macro_input, parse_quote, Data, DataStruct, DeriveInput, Fields, FieldsNamed,
-    GenericParam, Generics, Ident, Type, Visibility,
+    parse_macro_input, parse_quote, token, Data, DataStruct, DeriveInput, Expr, ExprLit, Fields,
+    FieldsNamed, GenericParam, Generics, Ident, Lit, LitStr, Meta, MetaNameValue, Path, Token,
+    Type, TypePath, Visibility,
 };
 
 #[proc_macro_derive(PipeModelObject)]
@@ -346,6 +347,129 @@ pub fn derive_pipe_model(input: TokenStream) -> TokenStream {
     TokenStream::from(expanded)
 }
 
+#[proc_macro_derive(PipeArgs, attributes(serde))]
+pub fn derive_pipe_args(input: TokenStream) -> TokenStream {
+    let DeriveInput {
+        attrs: _,
+        vis: _,
+        ident,
+        generics,
+        data,
+    } = parse_macro_input!(input as DeriveInput);
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let fields = match data {
+        Data::Struct(DataStruct {
+            fields: Fields::Named(FieldsNamed { named, .. }),
+            ..
+        }) => named,
+        Data::Struct(_) => panic!("Only named structs are supported"),
+        Data::Enum(_) => panic!("Enum types are not supported"),
+        Data::Union(_) => panic!("Union types are not supported"),
+    };
+
+    let mut specs = Vec::with_capacity(fields.len());
+    for field in &fields {
+        let ident = field.ident.as_ref().expect("a named struct");
+        let mut name = ident.to_string().trim_start_matches("r#").to_string();
+        // `Some(None)` for `#[serde(default)]`, `Some(Some(path))` for `#[serde(default = "path")]`
+        let mut default: Option<Option<Path>> = None;
+        let mut help = Vec::default();
+        let mut skip = false;
+
+        for attr in &field.attrs {
+            // Collect the doc comments as the help
+            if attr.path().is_ident("doc") {
+                if let Meta::NameValue(MetaNameValue {
+                    value:
+                        Expr::Lit(ExprLit {
+                            lit: Lit::Str(line),
+                            ..
+                        }),
+                    ..
+                }) = &attr.meta
+                {
+                    help.push(line.value().trim().to_string());
+                }
+                continue;
+            }
+            if !attr.path().is_ident("serde") {
+                continue;
+            }
+
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("default") {
+                    default = Some(if meta.input.peek(Token![=]) {
+                        let path: LitStr = meta.value()?.parse()?;
+                        Some(path.parse()?)
+                    } else {
+                        None
+                    });
+                } else if meta.path.is_ident("rename") && meta.input.peek(Token![=]) {
+                    let rename: LitStr = meta.value()?.parse()?;
+                    name = rename.value();
+                } else if meta.path.is_ident("skip") || meta.path.is_ident("skip_deserializing") {
+                    skip = true;
+                } else if meta.input.peek(Token![=]) {
+                    // Ignore the other attributes, e.g. `deserialize_with`
+                    meta.value()?.parse::<Expr>()?;
+                } else if meta.input.peek(token::Paren) {
+                    meta.parse_nested_meta(|meta| {
+                        if meta.input.peek(Token![=]) {
+                            meta.value()?.parse::<Expr>()?;
+                        }
+                        Ok(())
+                    })?;
+                }
+                Ok(())
+            })
+            .expect("valid serde attributes");
+        }
+        if skip {
+            continue;
+        }
+
+        let help = help.join(" ");
+        let ty = &field.ty;
+        let is_option = matches!(
+            ty,
+            Type::Path(TypePath { qself: None, path })
+                if path.segments.last().is_some_and(|segment| segment.ident == "Option")
+        );
+
+        let constructor = if is_option || default.is_some() {
+            quote! { optional }
+        } else {
+            quote! { required }
+        };
+        let default = match default {
+            Some(Some(path)) => quote! { ::xlake_core::args::__default_value(&#path()) },
+            Some(None) if !is_option => quote! {
+                ::xlake_core::args::__default_value(
+                    &<#ty as ::core::default::Default>::default(),
+                )
+            },
+            Some(None) | None => quote! { None },
+        };
+        specs.push(quote! {
+            {
+                let mut spec = ::xlake_core::ArgSpec::#constructor(#name, #help);
+                spec.default = #default;
+                spec
+            }
+        });
+    }
+
+    let expanded = quote! {
+        impl #impl_generics ::xlake_core::args::PipeArgs for #ident #ty_generics #where_clause {
+            fn arg_specs() -> ::std::vec::Vec<::xlake_core::ArgSpec> {
+                vec![#(#specs),*]
+            }
+        }
+    };
+    TokenStream::from(expanded)
+}
+
 // Add a bound `T: PipeModelObject` to every type parameter T.
 fn add_trait_bounds(mut generics: Generics) -> Generics {
     for param in &mut generics.params {
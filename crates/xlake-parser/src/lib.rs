@@ -8,8 +8,32 @@ impl ::core::fmt::Debug for SeqParser {
     }
 }
 
+/// Unescapes the quotes and backslashes of a quoted string.
+///
+/// The other escapes are kept as they are, e.g. `\d` of a regex.
+fn unescape(value: &str) -> String {
+    let mut output = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(next @ ('\\' | '\'' | '"')) => output.push(next),
+                Some(next) => {
+                    output.push(c);
+                    output.push(next);
+                }
+                None => output.push(c),
+            },
+            c => output.push(c),
+        }
+    }
+    output
+}
+
 #[cfg(test)]
 mod tests {
+    use xlake_ast::{Plan, PlanArguments, PlanKind, Value};
+
     use super::SeqParser;
 
     #[test]
@@ -54,10 +78,97 @@ mod tests {
         let _ = parser.parse(input).unwrap();
     }
 
+    #[test]
+    fn test_grammar_argument_bool() {
+        let parser = SeqParser::new();
+        let input = "filesrc path='true' watch=true ! stdoutsink pretty=false";
+        let plans = parser.parse(input).unwrap();
+        assert_eq!(plans[0].args.get("path").unwrap().to_string(), r#""true""#);
+        assert_eq!(plans[0].args.get("watch").unwrap().to_string(), "true");
+        assert_eq!(plans[1].args.get("pretty").unwrap().to_string(), "false");
+    }
+
     #[test]
     fn test_grammar_argument_sep_space() {
         let parser = SeqParser::new();
         let input = "filesrc cache=content path='lib.rs' ! stdoutsink";
         let _ = parser.parse(input).unwrap();
     }
+
+    #[test]
+    fn test_grammar_display_round_trip() {
+        let parser = SeqParser::new();
+        let inputs = [
+            "stdinsrc ! stdoutsink",
+            "filesrc path='lib.rs' ! stdoutsink",
+            "filesrc cache=content,path='lib.rs' ! stdoutsink",
+            "filesrc cache=content, path='lib.rs' ! stdoutsink",
+            "filesrc path='true' watch=true ! stdoutsink pretty=false",
+            "filesrc cache=content path='lib.rs' ! stdoutsink",
+            r#"repeatsrc count=2 json='{"name":"foo"}' ! doc:parse_json ! stdoutsink"#,
+            r#"filesrc path="it's.txt" ! hash:object ! stdoutsink"#,
+        ];
+        for input in inputs {
+            let plans = parser.parse(input).unwrap();
+            let output = Plan::display_seq(&plans).to_string();
+            let parsed = parser.parse(&output).unwrap();
+            assert_eq!(plans.len(), parsed.len(), "{output}");
+            for (plan, parsed) in plans.iter().zip(&parsed) {
+                assert_eq!(plan.kind, parsed.kind, "{output}");
+                assert_eq!(plan.args, parsed.args, "{output}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_grammar_display_round_trip_escape() {
+        let parser = SeqParser::new();
+        let values = [
+            r#"it's "quoted""#,
+            r#"say "hi""#,
+            r"C:\my\file.txt",
+            r"it's a \ backslash",
+            r"\d+\",
+        ];
+        for value in values {
+            let mut args = PlanArguments::default();
+            args.insert("path".into(), Value::String(value.into()));
+            let kind = PlanKind::Src {
+                name: "file".into(),
+            };
+            let output = Plan { kind, args }.to_string();
+            let plans = parser.parse(&output).unwrap();
+            assert_eq!(
+                plans[0].args.get("path"),
+                Some(&Value::String(value.into())),
+                "{output}",
+            );
+        }
+    }
+
+    #[test]
+    fn test_grammar_argument_escape() {
+        let parser = SeqParser::new();
+        let input = r#"filesrc path='it\'s' pattern='\d+\\' ! stdoutsink"#;
+        let plans = parser.parse(input).unwrap();
+        assert_eq!(
+            plans[0].args.get("path"),
+            Some(&Value::String("it's".into()))
+        );
+        assert_eq!(
+            plans[0].args.get("pattern"),
+            Some(&Value::String(r"\d+\".into())),
+        );
+    }
+
+    #[test]
+    fn test_grammar_display() {
+        let parser = SeqParser::new();
+        let input = "filesrc cache=content, path='true' ! stdoutsink pretty=false";
+        let plans = parser.parse(input).unwrap();
+        assert_eq!(
+            Plan::display_seq(&plans).to_string(),
+            "filesrc cache=content path='true' ! stdoutsink pretty=false",
+        );
+    }
 }
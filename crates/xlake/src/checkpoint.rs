@@ -0,0 +1,99 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use xlake_ast::{Plan, PlanArguments, PlanKind, Value};
+
+/// The argument of the resumable srcs to skip the processed items.
+const OFFSET: &str = "offset";
+
+/// Offsets of the resumable srcs per plan, e.g. `ndjsonsrc path='a.ndjson'`.
+///
+/// The explicit `offset` arguments in a pipeline always take precedence over the checkpoint.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Checkpoint(BTreeMap<String, Value>);
+
+impl Checkpoint {
+    /// Returns the key of the src, i.e. its kind and arguments except `offset`.
+    ///
+    /// The arguments are sorted, so that the same src is matched regardless of their order.
+    pub fn key(plan: &Plan) -> String {
+        let Plan { kind, args } = plan;
+        let args: BTreeMap<_, _> = args.iter().filter(|(key, _)| *key != OFFSET).collect();
+        let args: PlanArguments = args
+            .into_iter()
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        Plan {
+            kind: kind.clone(),
+            args,
+        }
+        .to_string()
+    }
+
+    pub fn apply(&self, plans: &mut [Plan]) {
+        for plan in plans {
+            if !matches!(&plan.kind, PlanKind::Src { .. }) || plan.args.contains_key(OFFSET) {
+                continue;
+            }
+            if let Some(offset) = self.get(plan) {
+                plan.args.insert(OFFSET.into(), offset.clone());
+            }
+        }
+    }
+
+    #[inline]
+    pub fn get(&self, plan: &Plan) -> Option<&Value> {
+        self.0.get(&Self::key(plan))
+    }
+
+    #[inline]
+    pub fn insert(&mut self, plan: &Plan, offset: Value) -> Option<Value> {
+        self.0.insert(Self::key(plan), offset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use xlake_parser::SeqParser;
+
+    use super::Checkpoint;
+
+    #[test]
+    fn test_checkpoint_key() {
+        let parse = |input: &str| SeqParser::new().parse(input).unwrap();
+
+        let mut checkpoint = Checkpoint::default();
+        let plan = parse("ndjsonsrc path='a.ndjson' id=uuid offset=7")
+            .pop()
+            .unwrap();
+        checkpoint.insert(&plan, 3.into());
+        assert_eq!(
+            ::serde_json::to_string(&checkpoint).unwrap(),
+            r#"{"ndjsonsrc id=uuid path='a.ndjson'":3}"#,
+        );
+
+        let offset = |input: &str| {
+            let mut plans = parse(input);
+            checkpoint.apply(&mut plans);
+            plans[0].args.get_i64("offset")
+        };
+
+        // The same src is resumed regardless of the argument order
+        assert_eq!(
+            offset("ndjsonsrc id=uuid path='a.ndjson' ! stdoutsink"),
+            Some(3)
+        );
+        // The srcs of the other arguments do not share the offset
+        assert_eq!(
+            offset("ndjsonsrc id=uuid path='b.ndjson' ! stdoutsink"),
+            None
+        );
+        assert_eq!(offset("ndjsonsrc path='a.ndjson' ! stdoutsink"), None);
+        // The explicit offset takes precedence
+        assert_eq!(
+            offset("ndjsonsrc path='a.ndjson' id=uuid offset=1 ! stdoutsink"),
+            Some(1)
+        );
+    }
+}
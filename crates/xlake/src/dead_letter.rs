@@ -0,0 +1,99 @@
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use futures::{stream, StreamExt};
+use tokio::sync::mpsc;
+use tracing::warn;
+use xlake_core::{
+    context::PipeContext, object::LazyObject, stream::DefaultStream, PipeChannel, PipeFunc,
+    PipeSink,
+};
+
+use crate::per_object::{self, PerObject};
+
+/// The argument accepted by every per-object func to route its failures into a sink.
+pub const ARG: &str = "dead_letter";
+
+/// The field of the dead-lettered objects holding the error message.
+pub const ERROR: &str = "error";
+
+/// Calls the inner func once per object, sending the failed inputs into the dead-letter channel.
+///
+/// The failed inputs are sent in the order of their failures, along with the [`ERROR`] field,
+/// while the outputs of the others continue as they are.
+/// The upstream failures are not caught, but fail the pipeline as usual.
+#[derive(Clone, Debug)]
+pub struct DeadLetterFunc {
+    inner: Arc<dyn PipeFunc>,
+    tx: mpsc::UnboundedSender<LazyObject>,
+}
+
+impl DeadLetterFunc {
+    /// Returns the func and its dead-letter sink, whose input is closed along with the func.
+    pub fn new(inner: Arc<dyn PipeFunc>, sink: Box<dyn PipeSink>) -> (Self, DeadLetterSink) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let func = Self { inner, tx };
+
+        let stream = stream::unfold(rx, |mut rx| async move {
+            rx.recv().await.map(|item| (Ok(item), rx))
+        })
+        .boxed();
+        let channel = PipeChannel::from_stream(DefaultStream::from_stream(stream));
+        (func, DeadLetterSink { channel, sink })
+    }
+}
+
+#[async_trait]
+impl PerObject for DeadLetterFunc {
+    async fn call_item(&self, ctx: &PipeContext, item: LazyObject) -> Result<Vec<LazyObject>> {
+        // Download the whole object, to be kept for the failure
+        let mut item = item.flatten().await?;
+        let input = item.try_clone().context("Unflattened object")?;
+
+        // Resolve the outputs here, so that their failures are dead-lettered too
+        match per_object::call_once(&*self.inner, ctx, input.into()).await {
+            Ok(rows) => Ok(rows),
+            Err(error) => {
+                warn!("Dead-lettered an object: {error:#}");
+                item.insert(ERROR.into(), format!("{error:#}").into());
+                // The dead-letter sink may stop reading early, e.g. after a failure of its own
+                let _ = self.tx.send(item);
+                Ok(Vec::default())
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl PipeFunc for DeadLetterFunc {
+    async fn call(&self, channel: PipeChannel) -> Result<PipeChannel> {
+        self.call_with_context(&PipeContext::default(), channel)
+            .await
+    }
+
+    async fn call_with_context(
+        &self,
+        ctx: &PipeContext,
+        channel: PipeChannel,
+    ) -> Result<PipeChannel> {
+        per_object::call(self, ctx, channel).await
+    }
+}
+
+/// A sink receiving the failed objects of a [`DeadLetterFunc`].
+#[derive(Debug)]
+pub struct DeadLetterSink {
+    channel: PipeChannel,
+    sink: Box<dyn PipeSink>,
+}
+
+impl DeadLetterSink {
+    /// Writes the failed objects until the func and its outputs are dropped.
+    ///
+    /// It should run along with the main sink, which drives the func.
+    pub async fn call(self) -> Result<()> {
+        let Self { channel, sink } = self;
+        sink.call(channel).await
+    }
+}
@@ -0,0 +1,115 @@
+use std::{
+    fmt,
+    future::Future,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    task::Poll,
+    time::Duration,
+};
+
+use futures::{stream, stream::BoxStream, StreamExt};
+use tokio::time::{self, Instant};
+
+/// The time for the objects in flight to reach the sink after the deadline, e.g. the store writes.
+pub const GRACE_PERIOD: Duration = Duration::from_secs(5);
+
+/// The error of a pipeline stopped at its deadline.
+#[derive(Debug)]
+pub struct DeadlineExceeded(pub Duration);
+
+impl fmt::Display for DeadlineExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Deadline exceeded after {:?}", self.0)
+    }
+}
+
+impl std::error::Error for DeadlineExceeded {}
+
+/// Stops the src at the deadline, so that the objects in flight are completed rather than dropped.
+#[derive(Debug)]
+pub(crate) struct Deadline {
+    at: Instant,
+    /// Whether the src has been stopped by the deadline
+    stopped: Arc<AtomicBool>,
+}
+
+impl Deadline {
+    pub(crate) fn new(duration: Duration) -> Self {
+        Self {
+            at: Instant::now() + duration,
+            stopped: Default::default(),
+        }
+    }
+
+    pub(crate) fn is_stopped(&self) -> bool {
+        self.stopped.load(Ordering::SeqCst)
+    }
+
+    /// Ends the stream of the src once the deadline has passed.
+    ///
+    /// The src is polled once more past the deadline, so that the srcs already ended are not
+    /// reported as stopped. An object ready by then is still emitted, as the src has counted it.
+    pub(crate) fn wrap<T>(&self, mut stream: BoxStream<'static, T>) -> BoxStream<'static, T>
+    where
+        T: 'static + Send,
+    {
+        let at = self.at;
+        let stopped = self.stopped.clone();
+        let mut timer = Box::pin(time::sleep_until(at));
+        let mut exceeded = false;
+        stream::poll_fn(move |cx| {
+            if exceeded {
+                // The src had more objects past the deadline
+                stopped.store(true, Ordering::SeqCst);
+                return Poll::Ready(None);
+            }
+
+            // Check the clock as well, as a busy pipeline may never yield to the timer
+            if !(timer.as_mut().poll(cx).is_ready() || Instant::now() >= at) {
+                return stream.poll_next_unpin(cx);
+            }
+            match stream.poll_next_unpin(cx) {
+                Poll::Ready(Some(item)) => {
+                    exceeded = true;
+                    Poll::Ready(Some(item))
+                }
+                Poll::Ready(None) => Poll::Ready(None),
+                Poll::Pending => {
+                    stopped.store(true, Ordering::SeqCst);
+                    Poll::Ready(None)
+                }
+            }
+        })
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use futures::{stream, StreamExt};
+
+    use super::Deadline;
+
+    async fn collect(stream: stream::BoxStream<'static, i32>) -> (Vec<i32>, bool) {
+        let deadline = Deadline::new(Duration::ZERO);
+        let items = deadline.wrap(stream).collect().await;
+        (items, deadline.is_stopped())
+    }
+
+    #[::tokio::test]
+    async fn test_deadline_stopped() {
+        // The object ready past the deadline is emitted rather than dropped
+        assert_eq!(collect(stream::iter(0..3).boxed()).await, (vec![0], true));
+        assert_eq!(collect(stream::pending().boxed()).await, (vec![], true));
+    }
+
+    #[::tokio::test]
+    async fn test_deadline_terminated() {
+        // The src already ended is not reported as stopped
+        assert_eq!(collect(stream::empty().boxed()).await, (vec![], false));
+    }
+}
@@ -0,0 +1,61 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use xlake_ast::{Plan, PlanArguments};
+
+/// Default argument values per factory, e.g. `filesrc` or `stream:sleep`.
+///
+/// The explicit arguments in a pipeline always take precedence over the defaults.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct PlanDefaults(BTreeMap<String, PlanArguments>);
+
+impl PlanDefaults {
+    pub fn apply(&self, plans: &mut [Plan]) {
+        for Plan { kind, args } in plans {
+            let Some(defaults) = self.0.get(&kind.to_string()) else {
+                continue;
+            };
+            for (key, value) in defaults.iter() {
+                if !args.contains_key(key) {
+                    args.insert(key.clone(), value.clone());
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use xlake_parser::SeqParser;
+
+    use super::PlanDefaults;
+
+    #[test]
+    fn test_defaults_explicit_wins() {
+        let defaults: PlanDefaults = ::toml::from_str(
+            r#"
+            [filesrc]
+            cache = "content"
+            max_bytes = 1024
+
+            [stdoutsink]
+            pretty = true
+            "#,
+        )
+        .unwrap();
+
+        let mut plans = SeqParser::new()
+            .parse("filesrc path='my_file.pdf' cache='path' ! stdoutsink")
+            .unwrap();
+        defaults.apply(&mut plans);
+
+        let args = &plans[0].args;
+        assert_eq!(args.get("cache").unwrap().to_string(), r#""path""#);
+        assert_eq!(args.get("max_bytes").unwrap().to_string(), "1024");
+        assert_eq!(args.get("path").unwrap().to_string(), r#""my_file.pdf""#);
+
+        let args = &plans[1].args;
+        assert_eq!(args.get("pretty").unwrap().to_string(), "true");
+    }
+}
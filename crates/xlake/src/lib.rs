@@ -1,59 +1,244 @@
+pub mod checkpoint;
+pub mod dead_letter;
+pub mod deadline;
+pub mod defaults;
+pub mod memoize;
 pub mod models;
+mod per_object;
+pub mod pipeline;
+#[cfg(feature = "plugins")]
+pub mod plugins;
+pub mod retry;
 pub mod sinks;
 pub mod srcs;
 pub mod stores;
+#[cfg(test)]
+mod testing;
 
 use std::{
     collections::{BTreeMap, BTreeSet},
     fmt, iter,
+    sync::{Arc, Mutex},
+    time::Duration,
 };
 
 use anyhow::{anyhow, bail, Context, Result};
-use tracing::debug;
-use xlake_ast::{Plan, PlanKind};
-use xlake_core::{PipeEdge, PipeNode, PipeNodeFactory, PipeNodeImpl, PipeStoreExt};
+use futures::StreamExt;
+use tracing::{debug, info, warn};
+use xlake_ast::{Plan, PlanArguments, PlanKind, PlanType, Value};
+use xlake_core::{
+    context::PipeContext, object::LazyObject, stream::DefaultStream, PipeChannel, PipeEdge,
+    PipeFunc, PipeNode, PipeNodeFactory, PipeNodeImpl, PipeSink, PipeStoreExt, SaveOptions,
+    SinkSummary,
+};
 use xlake_parser::SeqParser;
 
+use self::dead_letter::{DeadLetterFunc, DeadLetterSink};
+use self::deadline::{Deadline, DeadlineExceeded};
+use self::memoize::MemoizeFunc;
+use self::models::builtins::route::{RouteBranch, RouteFactory, RouteFunc};
+#[cfg(feature = "fs")]
+use self::sinks::local::checkpoint::{CheckpointSink, CheckpointSinkArgs, CheckpointSinkFactory};
+use self::stores::tiered::{TieredStore, TieredStoreArgs, TieredStoreFactory};
+
 #[derive(Debug)]
 pub struct PipeSession {
-    factories: BTreeMap<PlanKind, Box<dyn PipeNodeFactory>>,
+    cache: Option<Plan>,
+    checkpoint: Mutex<self::checkpoint::Checkpoint>,
+    ctx: PipeContext,
+    defaults: self::defaults::PlanDefaults,
+    explain: bool,
+    factories: BTreeMap<PlanKind, Arc<dyn PipeNodeFactory>>,
     parser: SeqParser,
+    #[cfg(feature = "plugins")]
+    plugins: Option<Arc<self::plugins::PluginRegistry>>,
+    save_options: SaveOptions,
+    skip_missing_models: bool,
+    summary: Mutex<Option<SinkSummary>>,
 }
 
 impl Default for PipeSession {
+    #[inline]
     fn default() -> Self {
-        let mut session = Self::empty();
-        session.add_builtin_factories();
-        session
+        Self::with_builtins(&BuiltinFlags::default())
     }
 }
 
 impl PipeSession {
     pub fn empty() -> Self {
         Self {
+            cache: None,
+            checkpoint: Default::default(),
+            ctx: Default::default(),
+            defaults: Default::default(),
+            explain: false,
             factories: Default::default(),
             parser: Default::default(),
+            #[cfg(feature = "plugins")]
+            plugins: None,
+            save_options: Default::default(),
+            skip_missing_models: false,
+            summary: Default::default(),
         }
     }
 
-    fn add_builtin_factories(&mut self) {
+    pub fn with_builtins(flags: &BuiltinFlags) -> Self {
+        let mut session = Self::empty();
+        session.add_builtin_factories(flags);
+        session
+    }
+
+    fn add_builtin_factories(&mut self, flags: &BuiltinFlags) {
         // Defaults
         self.insert_factory(Box::new(::xlake_core::batch::DefaultBatchFactory::default()));
         self.insert_factory(Box::new(
             ::xlake_core::stream::DefaultStreamFactory::default(),
         ));
 
+        self.insert_factory(Box::new(
+            self::models::builtins::approx::ApproxDistinctFactory,
+        ));
+        self.insert_factory(Box::new(self::models::builtins::barrier::BarrierFactory));
+        self.insert_factory(Box::new(self::models::builtins::batch::sql::SqlFactory));
+        self.insert_factory(Box::new(
+            self::models::builtins::batch_group::BatchGroupFactory,
+        ));
+        #[cfg(feature = "compression")]
+        self.insert_factory(Box::new(
+            self::models::builtins::binary::compress::CompressFactory,
+        ));
+        #[cfg(feature = "compression")]
+        self.insert_factory(Box::new(
+            self::models::builtins::binary::compress::DecompressFactory,
+        ));
+        #[cfg(feature = "crypto")]
+        self.insert_factory(Box::new(
+            self::models::builtins::binary::crypto::DecryptFactory,
+        ));
+        #[cfg(feature = "crypto")]
+        self.insert_factory(Box::new(
+            self::models::builtins::binary::crypto::EncryptFactory,
+        ));
+        #[cfg(feature = "delta")]
+        self.insert_factory(Box::new(
+            self::models::builtins::binary::delta::ApplyDeltaFactory,
+        ));
+        #[cfg(feature = "delta")]
+        self.insert_factory(Box::new(
+            self::models::builtins::binary::delta::DeltaFactory,
+        ));
+        #[cfg(feature = "mime-detect")]
+        self.insert_factory(Box::new(
+            self::models::builtins::binary::detect::DetectTypeFactory,
+        ));
         #[cfg(feature = "libreoffice")]
         self.insert_factory(Box::new(self::models::builtins::binary::pdf::PdfFactory));
+        #[cfg(feature = "encoding")]
+        self.insert_factory(Box::new(
+            self::models::builtins::binary::to_doc::BinaryToDocFactory,
+        ));
+        self.insert_factory(Box::new(self::models::builtins::cast::CastFactory));
+        self.insert_factory(Box::new(self::models::builtins::collect::CollectFactory));
+        self.insert_factory(Box::new(self::models::builtins::diff::DiffFactory));
+        #[cfg(feature = "clean-text")]
+        self.insert_factory(Box::new(
+            self::models::builtins::doc::clean::CleanTextFactory,
+        ));
+        #[cfg(feature = "lang-detect")]
+        self.insert_factory(Box::new(
+            self::models::builtins::doc::lang::LangDetectFactory,
+        ));
+        self.insert_factory(Box::new(
+            self::models::builtins::doc::parse_json::ParseJsonFactory,
+        ));
+        self.insert_factory(Box::new(
+            self::models::builtins::doc::split_delim::SplitDelimFactory,
+        ));
+        self.insert_factory(Box::new(self::models::builtins::explode::ExplodeFactory));
+        self.insert_factory(Box::new(
+            self::models::builtins::format_number::FormatNumberFactory,
+        ));
+        self.insert_factory(Box::new(self::models::builtins::hash::CacheKeyFactory));
+        self.insert_factory(Box::new(self::models::builtins::hash::HashObjectFactory));
+        self.insert_factory(Box::new(self::models::builtins::infer::InferTypesFactory));
+        #[cfg(feature = "jq")]
+        self.insert_factory(Box::new(self::models::builtins::jq::JqFactory));
+        #[cfg(feature = "json-schema")]
+        self.insert_factory(Box::new(
+            self::models::builtins::jsonschema::JsonSchemaFactory,
+        ));
+        self.insert_factory(Box::new(
+            self::models::builtins::micro_batch::MicroBatchFactory,
+        ));
+        self.insert_factory(Box::new(self::models::builtins::pick::PickFactory));
+        self.insert_factory(Box::new(self::models::builtins::presence::PresenceFactory));
+        self.insert_factory(Box::new(self::models::builtins::profile::ProfileFactory));
+        self.insert_factory(Box::new(self::models::builtins::redact::RedactFactory));
+        self.insert_factory(Box::new(self::models::builtins::route::RouteFactory));
+        self.insert_factory(Box::new(self::models::builtins::seqid::SeqIdFactory));
+        self.insert_factory(Box::new(self::models::builtins::size::SizeFactory));
+        self.insert_factory(Box::new(self::models::builtins::sleep::SleepFactory));
+        self.insert_factory(Box::new(self::models::builtins::sort::SortFactory));
+        self.insert_factory(Box::new(self::models::builtins::to_batch::ToBatchFactory));
+        self.insert_factory(Box::new(self::models::builtins::topn::TopNPerKeyFactory));
+        self.insert_factory(Box::new(self::models::builtins::unwrap::UnwrapFactory));
+        self.insert_factory(Box::new(
+            self::models::builtins::window_agg::WindowAggFactory,
+        ));
+        #[cfg(feature = "mongodb")]
+        self.insert_factory(Box::new(self::sinks::db::mongo::MongoSinkFactory));
+        self.insert_factory(Box::new(self::sinks::local::assert::AssertSinkFactory));
+        #[cfg(feature = "fs")]
+        self.insert_factory(Box::new(
+            self::sinks::local::checkpoint::CheckpointSinkFactory,
+        ));
+        #[cfg(feature = "csv")]
+        self.insert_factory(Box::new(self::sinks::local::csv::CsvSinkFactory));
+        #[cfg(feature = "io-std")]
+        self.insert_factory(Box::new(self::sinks::local::kv::KvSinkFactory));
+        #[cfg(feature = "io-std")]
+        self.insert_factory(Box::new(
+            self::sinks::local::model_histogram::ModelHistogramSinkFactory,
+        ));
+        #[cfg(feature = "fs")]
+        self.insert_factory(Box::new(self::sinks::local::rotate::RotateSinkFactory));
         #[cfg(feature = "io-std")]
         self.insert_factory(Box::new(self::sinks::local::stdout::StdoutSinkFactory));
+        #[cfg(feature = "template")]
+        self.insert_factory(Box::new(self::sinks::local::template::TemplateSinkFactory));
+        #[cfg(feature = "otel")]
+        self.insert_factory(Box::new(self::sinks::monitoring::otel::OtelSinkFactory));
+        #[cfg(feature = "kafka")]
+        self.insert_factory(Box::new(self::sinks::mq::kafka::KafkaSinkFactory));
+        #[cfg(feature = "grpc")]
+        self.insert_factory(Box::new(self::sinks::remote::grpc::GrpcSinkFactory));
+        #[cfg(feature = "mongodb")]
+        self.insert_factory(Box::new(self::srcs::db::mongo::MongoSrcFactory));
         self.insert_factory(Box::new(self::srcs::local::csv::CsvSrcFactory));
         #[cfg(feature = "fs")]
         self.insert_factory(Box::new(self::srcs::local::file::FileSrcFactory));
+        self.insert_factory(Box::new(self::srcs::local::inline::InlineSrcFactory));
+        #[cfg(feature = "json5")]
+        self.insert_factory(Box::new(self::srcs::local::json5::Json5SrcFactory));
+        #[cfg(feature = "fs")]
+        self.insert_factory(Box::new(self::srcs::local::ndjson::NdjsonSrcFactory));
+        self.insert_factory(Box::new(self::srcs::local::repeat::RepeatSrcFactory));
         #[cfg(feature = "io-std")]
         self.insert_factory(Box::new(self::srcs::local::stdin::StdinSrcFactory));
+        #[cfg(feature = "kafka")]
+        self.insert_factory(Box::new(self::srcs::mq::kafka::KafkaSrcFactory));
+        #[cfg(feature = "flight")]
+        self.insert_factory(Box::new(self::srcs::remote::flight::FlightSrcFactory));
+        #[cfg(feature = "grpc")]
+        self.insert_factory(Box::new(self::srcs::remote::grpc::GrpcSrcFactory));
+        #[cfg(feature = "http")]
+        self.insert_factory(Box::new(self::srcs::remote::prom::PromSrcFactory));
         #[cfg(feature = "fs")]
         self.insert_factory(Box::new(self::stores::local::LocalStoreFactory));
+        self.insert_factory(Box::new(self::stores::tiered::TieredStoreFactory));
+
+        // Drop the disabled builtins
+        self.factories.retain(|kind, _| flags.contains(kind));
     }
 
     pub async fn call(&self, input: &str) -> Result<()> {
@@ -65,19 +250,68 @@ impl PipeSession {
     }
 
     pub async fn call_with(&self, plans: Vec<Plan>) -> Result<()> {
+        self.call_with_until(plans, None).await
+    }
+
+    /// Calls the pipeline, failing with [`DeadlineExceeded`] if it takes longer than `duration`.
+    ///
+    /// At the deadline, the src is stopped so that the objects in flight reach the sink,
+    /// e.g. completing the store writes. They are dropped after the [`GRACE_PERIOD`].
+    ///
+    /// [`DeadlineExceeded`]: self::deadline::DeadlineExceeded
+    /// [`GRACE_PERIOD`]: self::deadline::GRACE_PERIOD
+    pub async fn call_with_deadline(&self, input: &str, duration: Duration) -> Result<()> {
+        let plans = self
+            .parser
+            .parse(input)
+            .map_err(|error| anyhow!("Failed to parse command: {error}"))?;
+
+        let deadline = Deadline::new(duration);
+        let call = self.call_with_until(plans, Some(&deadline));
+        match ::tokio::time::timeout(duration + self::deadline::GRACE_PERIOD, call).await {
+            Ok(result) => result?,
+            Err(_) => {
+                warn!("Dropped the objects in flight after the deadline");
+                return Err(DeadlineExceeded(duration).into());
+            }
+        }
+        if deadline.is_stopped() {
+            return Err(DeadlineExceeded(duration).into());
+        }
+        Ok(())
+    }
+
+    async fn call_with_until(
+        &self,
+        mut plans: Vec<Plan>,
+        deadline: Option<&Deadline>,
+    ) -> Result<()> {
+        self.apply_cache(&mut plans);
+
+        // Key the offset on the src as given, before filling its arguments
+        let src_plan = plans
+            .iter()
+            .find(|plan| matches!(&plan.kind, PlanKind::Src { .. }))
+            .cloned();
+
+        // Fill the missing arguments before building the nodes
+        self.checkpoint.lock().unwrap().apply(&mut plans);
+        self.defaults.apply(&mut plans);
+
         let mut input_batch = ::xlake_core::batch::NAME.to_string();
         let mut input_model = BTreeSet::default();
         let mut input_stream = ::xlake_core::stream::NAME.to_string();
+        let mut dead_letters = Vec::default();
         let mut nodes = Vec::default();
         let mut term_input = None;
         let mut term_output = None;
 
         debug!("Begin initializing {} plans", plans.len());
-        for (index, Plan { kind, args }) in plans.into_iter().enumerate() {
+        for (index, Plan { kind, mut args }) in plans.into_iter().enumerate() {
             debug!("Initialize index {index} @ plan {kind}");
             let type_name = kind.type_name();
 
-            let factory = match self.factories.get(&kind) {
+            let factory = match self.find_factory(&kind) {
                 Some(factory) => factory,
                 None => bail!("No such {type_name}: '{kind}'"),
             };
@@ -88,6 +322,19 @@ impl PipeSession {
                 stream: output_stream,
             } = factory.input();
 
+            // Drop the optional funcs, keeping the edges of the previous node
+            if self.skip_missing_models && matches!(&kind, PlanKind::Func { .. }) {
+                let missing: Vec<_> = output_model
+                    .iter()
+                    .flatten()
+                    .filter(|&name| !input_model.contains(name))
+                    .collect();
+                if !missing.is_empty() {
+                    warn!("Skipping {kind}: missing models {missing:?}");
+                    continue;
+                }
+            }
+
             debug!("sequence.{index}.{kind}.pre: '{args:?}'");
             {
                 debug!("sequence.{index}.{kind}.pre.batch: '{input_batch:?}'");
@@ -118,7 +365,26 @@ impl PipeSession {
                 batch: output_batch,
                 model: output_model,
                 stream: output_stream,
-            } = factory.output();
+            } = factory.output_with(&args);
+
+            // Stamp the ids at source time, e.g. `repeatsrc id=uuid`
+            let id = match &kind {
+                PlanKind::Src { .. } => self::srcs::id::IdMode::take_from(&mut args)?,
+                _ => Default::default(),
+            };
+            if id != self::srcs::id::IdMode::None {
+                input_model.insert(self::models::builtins::id::consts::NAME.into());
+            }
+
+            // Route the failures of the func into a sink, e.g. `dead_letter='stdoutsink'`
+            let dead_letter = take_per_object_arg(&*factory, &mut args, self::dead_letter::ARG)?;
+
+            // Skip the inputs already processed by the func, e.g. `memoize='localstore'`
+            let memoize = take_per_object_arg(&*factory, &mut args, self::memoize::ARG)?;
+
+            // Retry each failed object, e.g. `retries=3 timeout=1000`
+            let retries = take_per_object_arg(&*factory, &mut args, self::retry::RETRIES)?;
+            let timeout = take_per_object_arg(&*factory, &mut args, self::retry::TIMEOUT)?;
 
             {
                 debug!("sequence.{index}.{kind}.post.batch: {output_batch:?}");
@@ -133,7 +399,44 @@ impl PipeSession {
                 input_stream = output_stream;
             }
 
-            let imp = factory.build(&args).await?;
+            // Keep the declared defaults in sync with the runtime behavior
+            let args = args.with_defaults(&factory.arguments());
+            let imp = match &kind {
+                kind if *kind == RouteFactory.kind() => self.build_route(&args).await?,
+                #[cfg(feature = "fs")]
+                kind if *kind == CheckpointSinkFactory.kind() => {
+                    self.build_checkpoint(&args).await?
+                }
+                kind if *kind == TieredStoreFactory.kind() => self.build_tiered(&args).await?,
+                _ => factory.build(&args).await?,
+            };
+            let imp = match imp {
+                PipeNodeImpl::Src(imp) if id != self::srcs::id::IdMode::None => {
+                    PipeNodeImpl::Src(Box::new(self::srcs::id::IdSrc::new(imp, id)))
+                }
+                PipeNodeImpl::Func(imp) => {
+                    let imp = self::retry::RetryArgsFunc::wrap(imp, retries, timeout)?;
+                    let imp = match memoize {
+                        Some(store) => {
+                            let key = Plan {
+                                kind: kind.clone(),
+                                args: args.clone(),
+                            };
+                            self.build_memoize(imp, key.to_string(), &store).await?
+                        }
+                        None => imp,
+                    };
+                    match dead_letter {
+                        Some(sink) => {
+                            let (imp, dead_letter) = self.build_dead_letter(imp, &sink).await?;
+                            dead_letters.push(dead_letter);
+                            PipeNodeImpl::Func(Box::new(imp))
+                        }
+                        None => PipeNodeImpl::Func(imp),
+                    }
+                }
+                imp => imp,
+            };
             let imp_type_name = imp.type_name();
             if imp_type_name != type_name {
                 bail!("Unexpected node: expected {type_name:?}, but given {imp_type_name:?}")
@@ -156,8 +459,9 @@ impl PipeSession {
                 bail!("Cannot link after sink: '{kind}'")
             }
 
+            // Keep the models declared so far to surface them on the channel
             let node = PipeNode { kind, args, imp };
-            nodes.push(node)
+            nodes.push((node, input_model.clone()))
         }
 
         if term_input.is_none() {
@@ -177,33 +481,271 @@ impl PipeSession {
 
         debug!("Begin executing {} plans", nodes.len());
         let mut channel = None;
-        for (index, node) in nodes.into_iter().enumerate() {
+        let mut src = None;
+        for (index, (node, models)) in nodes.into_iter().enumerate() {
             debug!("Execute index {index} @ plan {}", &node.kind);
             let next_channel = match node.imp {
                 // TODO: to be implemented
                 PipeNodeImpl::Batch(imp) => todo!(),
                 // TODO: to be implemented
-                PipeNodeImpl::Func(imp) => imp.call(channel.unwrap()).await?,
+                PipeNodeImpl::Func(imp) => match imp.retry_options() {
+                    Some(options) => {
+                        let imp = self::retry::RetryFunc::new(Arc::from(imp), options);
+                        imp.call_with_context(&self.ctx, channel.unwrap()).await?
+                    }
+                    None => imp.call_with_context(&self.ctx, channel.unwrap()).await?,
+                },
                 // TODO: to be implemented
                 PipeNodeImpl::Sink(imp) => {
-                    imp.call(channel.unwrap()).await?;
+                    // Drain the dead letters along with the main sink, which closes their inputs
+                    let dead_letters = dead_letters.drain(..).map(DeadLetterSink::call);
+                    ::futures::try_join!(
+                        imp.call(channel.unwrap()),
+                        ::futures::future::try_join_all(dead_letters),
+                    )?;
+                    *self.summary.lock().unwrap() = imp.summary();
                     break;
                 }
-                PipeNodeImpl::Src(imp) => imp.call().await?,
+                PipeNodeImpl::Src(imp) => {
+                    let mut next_channel = imp.call().await?;
+                    if let Some(deadline) = deadline {
+                        let stream = next_channel.into_stream::<LazyObject>().await?.boxed();
+                        let stream = DefaultStream::from_stream(deadline.wrap(stream));
+                        next_channel = PipeChannel::from_stream(stream);
+                    }
+                    src = Some(imp);
+                    next_channel
+                }
+                // Pass through the objects, caching them into the store
                 PipeNodeImpl::Store(imp) => match channel.take() {
-                    Some(channel) => imp.save(channel).await?,
+                    Some(channel) => imp.tee_with(channel, self.save_options).await?,
                     // TODO: to be implemented (load)
-                    None => todo!(),
+                    None => bail!("Cannot load from store: '{}'", &node.kind),
                 },
                 // TODO: to be implemented
                 PipeNodeImpl::Stream(imp) => todo!(),
             };
+            let next_channel = next_channel.with_models(models);
+            if self.explain {
+                let models: Vec<_> = next_channel.models().into_iter().collect();
+                info!("{index}: {} -> [{}]", &node.kind, models.join(", "));
+            } else {
+                debug!("sequence.{index}.models: {:?}", next_channel.models());
+            }
             channel = Some(next_channel);
         }
         debug!("Finalizing plans");
+
+        // Record the progress only if the whole pipeline succeeded
+        if let (Some(plan), Some(imp)) = (src_plan, src) {
+            if let Some(offset) = imp.as_resumable().and_then(|imp| imp.offset()) {
+                debug!("Checkpoint {plan}: {offset}");
+                self.checkpoint.lock().unwrap().insert(&plan, offset);
+            }
+        }
         Ok(())
     }
 
+    /// Inserts the session-level store right before the sink, unless any store is given.
+    fn apply_cache(&self, plans: &mut Vec<Plan>) {
+        let cache = match &self.cache {
+            Some(cache) => cache,
+            None => return,
+        };
+        if plans
+            .iter()
+            .any(|plan| matches!(&plan.kind, PlanKind::Store { .. }))
+        {
+            debug!("Skipping cache: explicit store is given");
+            return;
+        }
+        let index = plans
+            .iter()
+            .position(|plan| matches!(&plan.kind, PlanKind::Sink { .. }))
+            .unwrap_or(plans.len());
+        plans.insert(index, cache.clone());
+    }
+
+    /// Builds a node given as an argument of another one, e.g. the branches of `stream:route`.
+    async fn build_nested(&self, plan: Plan) -> Result<PipeNodeImpl> {
+        let Plan { kind, args } = plan;
+        if self.is_nesting(&kind) {
+            bail!("Nested {kind} is not supported")
+        }
+        let factory = match self.find_factory(&kind) {
+            Some(factory) => factory,
+            None => bail!("No such {}: '{kind}'", kind.type_name()),
+        };
+        let args = args.with_defaults(&factory.arguments());
+        factory.build(&args).await
+    }
+
+    /// Returns whether the node is built with the nested nodes by the session.
+    fn is_nesting(&self, kind: &PlanKind) -> bool {
+        #[cfg(feature = "fs")]
+        if *kind == CheckpointSinkFactory.kind() {
+            return true;
+        }
+        *kind == RouteFactory.kind() || *kind == TieredStoreFactory.kind()
+    }
+
+    /// Builds the branches of `stream:route`, each of which is a sequence of funcs.
+    async fn build_route(&self, args: &PlanArguments) -> Result<PipeNodeImpl> {
+        let mut branches = Vec::default();
+        for (model, pipeline) in args.iter() {
+            let pipeline = match pipeline {
+                Value::String(pipeline) => pipeline,
+                _ => bail!("Expected funcs for the branch '{model}', but given {pipeline}"),
+            };
+            let plans = self
+                .parser
+                .parse(pipeline)
+                .map_err(|error| anyhow!("Failed to parse the branch '{model}': {error}"))?;
+            debug!("Route {model} @ {}", Plan::display_seq(&plans));
+
+            let mut funcs = Vec::with_capacity(plans.len());
+            for plan in plans {
+                if !matches!(&plan.kind, PlanKind::Func { .. }) {
+                    bail!(
+                        "Only funcs are allowed in the branch '{model}': '{}'",
+                        &plan.kind
+                    )
+                }
+                let imp: Arc<dyn PipeFunc> = match self.build_nested(plan).await? {
+                    PipeNodeImpl::Func(imp) => Arc::from(imp),
+                    imp => bail!(
+                        "Unexpected node: expected function, but given {:?}",
+                        imp.type_name(),
+                    ),
+                };
+                funcs.push(match imp.retry_options() {
+                    Some(options) => Arc::new(self::retry::RetryFunc::new(imp, options)),
+                    None => imp,
+                });
+            }
+            branches.push(RouteBranch {
+                model: model.clone(),
+                funcs,
+            });
+        }
+        let imp = RouteFunc::new(branches)?;
+        Ok(PipeNodeImpl::Func(Box::new(imp)))
+    }
+
+    /// Builds `checkpointsink` wrapping the inner sink.
+    #[cfg(feature = "fs")]
+    async fn build_checkpoint(&self, args: &PlanArguments) -> Result<PipeNodeImpl> {
+        let CheckpointSinkArgs { every, sink, state } = args.to()?;
+        let inner = self.build_nested_sink(&sink).await?;
+        let imp = CheckpointSink::new(inner, every, state)?;
+        Ok(PipeNodeImpl::Sink(Box::new(imp)))
+    }
+
+    /// Builds `tieredstore` composing the inner stores.
+    async fn build_tiered(&self, args: &PlanArguments) -> Result<PipeNodeImpl> {
+        let TieredStoreArgs { tiers, write } = args.to()?;
+        let plans = self
+            .parser
+            .parse(&tiers)
+            .map_err(|error| anyhow!("Failed to parse the tiers: {error}"))?;
+
+        let mut stores = Vec::with_capacity(plans.len());
+        for plan in plans {
+            if !matches!(&plan.kind, PlanKind::Store { .. }) {
+                bail!("Only stores are allowed in the tiers: '{}'", &plan.kind)
+            }
+            match self.build_nested(plan).await? {
+                PipeNodeImpl::Store(imp) => stores.push(imp),
+                imp => bail!(
+                    "Unexpected node: expected store, but given {:?}",
+                    imp.type_name(),
+                ),
+            }
+        }
+        let imp = TieredStore::new(stores, write.as_deref())?;
+        Ok(PipeNodeImpl::Store(Arc::new(imp)))
+    }
+
+    /// Wraps the func to route its failures into the given sink.
+    async fn build_dead_letter(
+        &self,
+        imp: Box<dyn PipeFunc>,
+        sink: &Value,
+    ) -> Result<(DeadLetterFunc, DeadLetterSink)> {
+        let sink = match sink {
+            Value::String(sink) => self.build_nested_sink(sink).await?,
+            _ => bail!("Expected a sink for the dead letters, but given {sink}"),
+        };
+
+        // Retry first, dead-lettering only the objects failed after all
+        let imp: Arc<dyn PipeFunc> = Arc::from(imp);
+        let imp = match imp.retry_options() {
+            Some(options) => Arc::new(self::retry::RetryFunc::new(imp, options)),
+            None => imp,
+        };
+        Ok(DeadLetterFunc::new(imp, sink))
+    }
+
+    /// Wraps the func to memoize its outputs into the given store, keyed with the func.
+    async fn build_memoize(
+        &self,
+        imp: Box<dyn PipeFunc>,
+        key: String,
+        store: &Value,
+    ) -> Result<Box<dyn PipeFunc>> {
+        let store = match store {
+            Value::String(store) => store,
+            _ => bail!("Expected a store for the memos, but given {store}"),
+        };
+        let mut plans = self
+            .parser
+            .parse(store)
+            .map_err(|error| anyhow!("Failed to parse the memo store: {error}"))?;
+        let plan = match plans.pop() {
+            Some(plan) if plans.is_empty() && matches!(&plan.kind, PlanKind::Store { .. }) => plan,
+            _ => bail!("Expected a single store, but given '{store}'"),
+        };
+        let store = match self.build_nested(plan).await? {
+            PipeNodeImpl::Store(imp) => imp,
+            imp => bail!(
+                "Unexpected node: expected store, but given {:?}",
+                imp.type_name(),
+            ),
+        };
+
+        // Retry first, memoizing only the succeeded outputs
+        let imp: Arc<dyn PipeFunc> = Arc::from(imp);
+        let imp = match imp.retry_options() {
+            Some(options) => Arc::new(self::retry::RetryFunc::new(imp, options)),
+            None => imp,
+        };
+        Ok(Box::new(MemoizeFunc::new(imp, key, store)))
+    }
+
+    /// Builds a sink given as an argument of another node, e.g. `sink='stdoutsink'`.
+    async fn build_nested_sink(&self, sink: &str) -> Result<Box<dyn PipeSink>> {
+        let mut plans = self
+            .parser
+            .parse(sink)
+            .map_err(|error| anyhow!("Failed to parse the inner sink: {error}"))?;
+        let plan = match plans.pop() {
+            Some(plan) if plans.is_empty() && matches!(&plan.kind, PlanKind::Sink { .. }) => plan,
+            _ => bail!("Expected a single sink, but given '{sink}'"),
+        };
+        match self.build_nested(plan).await? {
+            PipeNodeImpl::Sink(imp) => Ok(imp),
+            imp => bail!(
+                "Unexpected node: expected sink, but given {:?}",
+                imp.type_name(),
+            ),
+        }
+    }
+
+    /// Returns the offsets of the resumable srcs after the last calls.
+    pub fn checkpoint(&self) -> self::checkpoint::Checkpoint {
+        self.checkpoint.lock().unwrap().clone()
+    }
+
     fn collect_factories<'a>(
         &self,
         iter: impl Iterator<Item = &'a String>,
@@ -224,11 +766,70 @@ impl PipeSession {
             .collect()
     }
 
+    /// Returns the factory of the kind, preferring the plugins to the builtins.
+    fn find_factory(&self, kind: &PlanKind) -> Option<Arc<dyn PipeNodeFactory>> {
+        #[cfg(feature = "plugins")]
+        if let Some(factory) = self.plugins.as_ref().and_then(|plugins| plugins.get(kind)) {
+            return Some(factory);
+        }
+        self.factories.get(kind).cloned()
+    }
+
+    pub fn factories(&self) -> impl Iterator<Item = &dyn PipeNodeFactory> {
+        self.factories.values().map(|factory| &**factory)
+    }
+
+    /// Caches the objects of every call into the given store, e.g. `localstore`.
+    ///
+    /// Ignored in the calls having their own stores.
+    pub fn set_cache(&mut self, plan: Plan) {
+        self.cache = Some(plan);
+    }
+
+    pub fn set_checkpoint(&mut self, checkpoint: self::checkpoint::Checkpoint) {
+        self.checkpoint = Mutex::new(checkpoint);
+    }
+
+    /// Caps the number of the IO-heavy works in flight across all the nodes, e.g. processes.
+    ///
+    /// The stores also look up and write up to `limit` objects at once, keeping their order.
+    pub fn set_concurrency_limit(&mut self, limit: usize) {
+        self.ctx = PipeContext::with_concurrency_limit(limit);
+        self.save_options = SaveOptions { concurrency: limit };
+    }
+
+    pub fn set_defaults(&mut self, defaults: self::defaults::PlanDefaults) {
+        self.defaults = defaults;
+    }
+
+    /// Logs the models provided by the objects of each stage, as the pipeline runs.
+    pub fn set_explain(&mut self, explain: bool) {
+        self.explain = explain;
+    }
+
+    /// Registers the factories of the plugins, looked up on every call.
+    ///
+    /// The plugins reloaded in the meantime are used from the next call.
+    #[cfg(feature = "plugins")]
+    pub fn set_plugins(&mut self, plugins: Arc<self::plugins::PluginRegistry>) {
+        self.plugins = Some(plugins);
+    }
+
+    /// Skips the funcs whose input models are absent upstream, rather than failing.
+    pub fn set_skip_missing_models(&mut self, skip: bool) {
+        self.skip_missing_models = skip;
+    }
+
+    /// Returns what the sink has written in the last call, if it keeps track of it.
+    pub fn summary(&self) -> Option<SinkSummary> {
+        *self.summary.lock().unwrap()
+    }
+
     pub fn insert_factory(
         &mut self,
         factory: Box<dyn PipeNodeFactory>,
-    ) -> Option<Box<dyn PipeNodeFactory>> {
-        self.factories.insert(factory.kind(), factory)
+    ) -> Option<Arc<dyn PipeNodeFactory>> {
+        self.factories.insert(factory.kind(), factory.into())
     }
 
     fn validate_types<'a>(
@@ -244,6 +845,88 @@ impl PipeSession {
     }
 }
 
+/// Takes the argument of a func to be called once per object by the session, e.g. `dead_letter`.
+///
+/// It is rejected for the funcs over the whole stream, e.g. `stream:sort`,
+/// which would otherwise be applied to each object alone.
+/// The arguments declared by the func itself, e.g. `timeout` of `stream:micro_batch`, are kept.
+fn take_per_object_arg(
+    factory: &dyn PipeNodeFactory,
+    args: &mut PlanArguments,
+    name: &str,
+) -> Result<Option<Value>> {
+    let kind = factory.kind();
+    if !matches!(&kind, PlanKind::Func { .. })
+        || factory.arguments().iter().any(|spec| spec.name == name)
+    {
+        return Ok(None);
+    }
+    match args.remove(name) {
+        Some(_) if !factory.is_per_object() => {
+            bail!("Unsupported argument '{name}': {kind} is not a per-object func")
+        }
+        value => Ok(value),
+    }
+}
+
+/// Selects the builtin factories to be registered into a [`PipeSession`].
+///
+/// The default batch and stream factories are always registered.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BuiltinFlags {
+    pub funcs: bool,
+    pub models: bool,
+    pub sinks: bool,
+    pub srcs: bool,
+    pub stores: bool,
+    /// Factories to be skipped by its kind, e.g. `binary:pdf` or `stdoutsink`
+    pub exclude: BTreeSet<String>,
+}
+
+impl Default for BuiltinFlags {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            funcs: true,
+            models: true,
+            sinks: true,
+            srcs: true,
+            stores: true,
+            exclude: Default::default(),
+        }
+    }
+}
+
+impl BuiltinFlags {
+    pub fn none() -> Self {
+        Self {
+            funcs: false,
+            models: false,
+            sinks: false,
+            srcs: false,
+            stores: false,
+            exclude: Default::default(),
+        }
+    }
+
+    pub fn exclude(mut self, kind: impl Into<String>) -> Self {
+        self.exclude.insert(kind.into());
+        self
+    }
+
+    fn contains(&self, kind: &PlanKind) -> bool {
+        let enabled = match kind.type_name() {
+            PlanType::Batch | PlanType::Stream => true,
+            PlanType::Func => self.funcs,
+            PlanType::Model => self.models,
+            PlanType::Sink => self.sinks,
+            PlanType::Src => self.srcs,
+            PlanType::Store => self.stores,
+        };
+        enabled && !self.exclude.contains(&kind.to_string())
+    }
+}
+
 #[derive(Copy, Clone)]
 enum ValidatableTypeName {
     Batch,
@@ -260,3 +943,798 @@ impl fmt::Display for ValidatableTypeName {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::BTreeSet,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc, Mutex,
+        },
+        time::Duration,
+    };
+
+    use anyhow::{bail, Result};
+    use async_trait::async_trait;
+    use futures::TryStreamExt;
+    use serde::Deserialize;
+    use xlake_ast::{Binary, Object, Plan, PlanType};
+    use xlake_core::{
+        models::hash::Hash,
+        object::{LazyObject, ObjectLayer},
+        ArgSpec, PipeChannel, PipeFunc, PipeNodeImpl, PipeSink, PipeSrc, PipeStore, PipeStoreExt,
+        SaveOptions, SinkSummary, SinkSummaryCell,
+    };
+
+    use super::{
+        models::builtins::{
+            barrier::BarrierFunc, binary::BinaryModelObject, doc::DocModelObject,
+            hash::HashObjectFunc,
+        },
+        testing::{
+            channel, object, CollectSinkFactory, MemoryStore, MemoryStoreFactory, NodeFactory,
+        },
+        BuiltinFlags, PipeSession,
+    };
+
+    /// Records the order in which the items pass through.
+    #[derive(Debug)]
+    struct TraceFunc {
+        name: &'static str,
+        log: Arc<Mutex<Vec<String>>>,
+    }
+
+    #[async_trait]
+    impl PipeFunc for TraceFunc {
+        async fn call(&self, channel: PipeChannel) -> Result<PipeChannel> {
+            let name = self.name;
+            let log = self.log.clone();
+            channel
+                .and_then(move |item| {
+                    let index = item.get_raw("index").unwrap().to_string();
+                    log.lock().unwrap().push(format!("{name}{index}"));
+                    async move { Ok(item) }
+                })
+                .await
+        }
+    }
+
+    #[::tokio::test]
+    async fn test_func_streaming() {
+        let log = Arc::new(Mutex::new(Vec::default()));
+        let trace = |name| TraceFunc {
+            name,
+            log: log.clone(),
+        };
+        let funcs: Vec<Box<dyn PipeFunc>> = vec![
+            Box::new(trace("a")),
+            Box::new(HashObjectFunc::default()),
+            Box::new(trace("b")),
+        ];
+
+        let mut channel = channel((0..3).map(|index| object([("index", index)])));
+        for func in &funcs {
+            channel = func.call(channel).await.unwrap();
+        }
+        assert!(log.lock().unwrap().is_empty());
+
+        let items: Vec<LazyObject> = channel
+            .into_stream()
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(items.len(), 3);
+        assert_eq!(*log.lock().unwrap(), ["a0", "b0", "a1", "b1", "a2", "b2"],);
+    }
+
+    #[::tokio::test]
+    async fn test_func_barrier() {
+        let log = Arc::new(Mutex::new(Vec::default()));
+        let trace = |name| TraceFunc {
+            name,
+            log: log.clone(),
+        };
+        let funcs: Vec<Box<dyn PipeFunc>> = vec![
+            Box::new(trace("a")),
+            Box::new(BarrierFunc::default()),
+            Box::new(trace("b")),
+        ];
+
+        let mut channel = channel((0..3).map(|index| object([("index", index)])));
+        for func in &funcs {
+            channel = func.call(channel).await.unwrap();
+        }
+        // The upstream is already drained by the barrier
+        assert_eq!(*log.lock().unwrap(), ["a0", "a1", "a2"]);
+
+        let items: Vec<LazyObject> = channel
+            .into_stream()
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(items.len(), 3);
+        assert_eq!(*log.lock().unwrap(), ["a0", "a1", "a2", "b0", "b1", "b2"]);
+    }
+
+    #[::tokio::test]
+    async fn test_builtins_exclude() {
+        let flags = BuiltinFlags::default().exclude("stdoutsink");
+        let session = PipeSession::with_builtins(&flags);
+
+        let error = session.call("stdoutsink").await.unwrap_err();
+        assert_eq!(error.to_string(), "No such sink: 'stdoutsink'");
+    }
+
+    #[::tokio::test]
+    async fn test_builtins_category() {
+        let flags = BuiltinFlags {
+            sinks: false,
+            ..Default::default()
+        };
+        let session = PipeSession::with_builtins(&flags);
+
+        let error = session.call("stdoutsink").await.unwrap_err();
+        assert_eq!(error.to_string(), "No such sink: 'stdoutsink'");
+    }
+
+    #[::tokio::test]
+    async fn test_skip_missing_models() {
+        let input = r#"repeatsrc count=2 json='{"name":"foo"}'
+            ! doc:parse_json
+            ! collectsink"#;
+
+        let items = Arc::new(Mutex::new(Vec::default()));
+        let mut session = PipeSession::default();
+        session.insert_factory(Box::new(CollectSinkFactory(items.clone())));
+        assert!(session.call(input).await.is_err());
+        assert!(items.lock().unwrap().is_empty());
+
+        // The `doc` model is absent, so the func is dropped
+        session.set_skip_missing_models(true);
+        session.call(input).await.unwrap();
+
+        let items = items.lock().unwrap();
+        assert_eq!(items.len(), 2);
+        assert!(items
+            .iter()
+            .all(|item| item.get_raw("name").unwrap().to_string() == r#""foo""#));
+    }
+
+    #[::tokio::test]
+    async fn test_store_tee() {
+        let store = Arc::new(MemoryStore::default());
+        let items = Arc::new(Mutex::new(Vec::default()));
+
+        let mut session = PipeSession::default();
+        session.insert_factory(Box::new(MemoryStoreFactory(store.clone())));
+        session.insert_factory(Box::new(CollectSinkFactory(items.clone())));
+        session
+            .call(
+                r#"repeatsrc count=3 json='{"name":"foo"}'
+                ! hash:object
+                ! memorystore
+                ! collectsink"#,
+            )
+            .await
+            .unwrap();
+
+        // The same objects share a single hash
+        assert_eq!(store.items.lock().unwrap().len(), 1);
+
+        let items = items.lock().unwrap();
+        assert_eq!(items.len(), 3);
+        assert!(items
+            .iter()
+            .all(|item| item.get_raw("name").unwrap().to_string() == r#""foo""#));
+
+        // The objects loaded from the store carry their checksums, read once per object
+        let loaded = items.iter().filter(|item| item.get_raw("etag").is_some());
+        assert_eq!(loaded.count(), 2);
+        assert_eq!(store.reads.load(Ordering::SeqCst), 2);
+    }
+
+    #[::tokio::test]
+    async fn test_store_cache_key() {
+        let store = Arc::new(MemoryStore::default());
+        let items = Arc::new(Mutex::new(Vec::default()));
+
+        let mut session = PipeSession::default();
+        session.insert_factory(Box::new(MemoryStoreFactory(store.clone())));
+        session.insert_factory(Box::new(CollectSinkFactory(items.clone())));
+        session
+            .call(
+                r#"repeatsrc count=2 json='{"name":"foo"}'
+                ! stream:seqid
+                ! hash:cache_key keys=name
+                ! memorystore
+                ! collectsink"#,
+            )
+            .await
+            .unwrap();
+
+        // The objects differing only in the ignored field share a single entry
+        assert_eq!(store.items.lock().unwrap().len(), 1);
+
+        let items = items.lock().unwrap();
+        assert_eq!(items.len(), 2);
+        let loaded = items.iter().filter(|item| item.get_raw("etag").is_some());
+        assert_eq!(loaded.count(), 1);
+    }
+
+    #[::tokio::test]
+    async fn test_session_cache() {
+        let store = Arc::new(MemoryStore::default());
+        let items = Arc::new(Mutex::new(Vec::default()));
+
+        let mut session = PipeSession::default();
+        session.insert_factory(Box::new(MemoryStoreFactory(store.clone())));
+        session.insert_factory(Box::new(CollectSinkFactory(items.clone())));
+        let mut cache = session.parser.parse("memorystore").unwrap();
+        session.set_cache(cache.pop().unwrap());
+
+        let input = r#"repeatsrc count=2 json='{"name":"foo"}'
+            ! stream:seqid
+            ! hash:object
+            ! collectsink"#;
+
+        // The cache is inserted right before the sink
+        let mut plans = session.parser.parse(input).unwrap();
+        session.apply_cache(&mut plans);
+        assert_eq!(
+            Plan::display_seq(&plans).to_string(),
+            "repeatsrc count=2 json='{\"name\":\"foo\"}' ! stream:seqid ! hash:object ! memorystore ! collectsink",
+        );
+
+        // The explicit store takes the place of the cache
+        session.apply_cache(&mut plans);
+        assert_eq!(plans.len(), 5);
+
+        session.call(input).await.unwrap();
+        assert_eq!(store.items.lock().unwrap().len(), 2);
+        assert!(items
+            .lock()
+            .unwrap()
+            .drain(..)
+            .all(|item| item.get_raw("etag").is_none()));
+
+        // The second run hits the cache
+        session.call(input).await.unwrap();
+        assert_eq!(store.items.lock().unwrap().len(), 2);
+        assert!(items
+            .lock()
+            .unwrap()
+            .iter()
+            .all(|item| item.get_raw("etag").is_some()));
+    }
+
+    /// Tracks the number of the lookups in flight.
+    #[derive(Debug, Default)]
+    struct SlowStore {
+        in_flight: AtomicUsize,
+        max_in_flight: AtomicUsize,
+        inner: MemoryStore,
+    }
+
+    #[async_trait]
+    impl PipeStore for SlowStore {
+        async fn contains(&self, hash: &Hash) -> Result<bool> {
+            let in_flight = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_in_flight.fetch_max(in_flight, Ordering::SeqCst);
+            ::tokio::time::sleep(Duration::from_millis(10)).await;
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+            self.inner.contains(hash).await
+        }
+
+        async fn read_item(&self, hash: &Hash) -> Result<Object> {
+            self.inner.read_item(hash).await
+        }
+
+        async fn write_item(&self, hash: &Hash, object: &Object) -> Result<()> {
+            self.inner.write_item(hash, object).await
+        }
+    }
+
+    async fn save_slow(concurrency: usize) -> (Arc<SlowStore>, usize) {
+        let channel = channel((0..4).map(|index| object([("index", index)])));
+        let channel = HashObjectFunc::default().call(channel).await.unwrap();
+
+        let store = Arc::new(SlowStore::default());
+        let options = SaveOptions { concurrency };
+        let items: Vec<LazyObject> = store
+            .save_with(channel, options)
+            .await
+            .unwrap()
+            .into_stream()
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        (store, items.len())
+    }
+
+    #[::tokio::test]
+    async fn test_store_save_concurrency() {
+        let (store, len) = save_slow(4).await;
+        assert_eq!(len, 4);
+        assert_eq!(store.inner.items.lock().unwrap().len(), 4);
+        assert!(store.max_in_flight.load(Ordering::SeqCst) > 1);
+
+        let (store, len) = save_slow(1).await;
+        assert_eq!(len, 4);
+        assert_eq!(store.max_in_flight.load(Ordering::SeqCst), 1);
+    }
+
+    #[::tokio::test]
+    async fn test_session_store_concurrency() {
+        let store = Arc::new(SlowStore::default());
+        let items = Arc::new(Mutex::new(Vec::default()));
+
+        let mut session = PipeSession::default();
+        session.insert_factory(Box::new(NodeFactory::new(PlanType::Store, "slow", {
+            let store = store.clone();
+            move |_| Ok(PipeNodeImpl::Store(store.clone()))
+        })));
+        session.insert_factory(Box::new(CollectSinkFactory(items.clone())));
+        session.set_concurrency_limit(4);
+        session
+            .call("repeatsrc count=4 json='{}' ! stream:seqid ! hash:object ! slowstore ! collectsink")
+            .await
+            .unwrap();
+
+        // The lookups overlap, while the objects keep their order
+        assert!(store.max_in_flight.load(Ordering::SeqCst) > 1);
+        let ids: Vec<_> = items
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|item| item.get_i64("id").unwrap())
+            .collect();
+        assert_eq!(ids, [0, 1, 2, 3]);
+    }
+
+    #[::tokio::test]
+    async fn test_sql_join_side_input() {
+        let path = ::std::env::temp_dir().join(format!("xlake-side-{}.csv", ::std::process::id()));
+        ::std::fs::write(&path, "name,score\nfoo,10\nbar,20\n").unwrap();
+
+        let items = Arc::new(Mutex::new(Vec::default()));
+        let mut session = PipeSession::default();
+        session.insert_factory(Box::new(CollectSinkFactory(items.clone())));
+        let result = session
+            .call(&format!(
+                r#"repeatsrc count=2 json='{{"name":"foo"}}'
+                ! batch:sql tables='b={}'
+                    query='SELECT a.name, b.score FROM a JOIN b ON a.name = b.name' table=a
+                ! collectsink"#,
+                path.display(),
+            ))
+            .await;
+        ::std::fs::remove_file(&path).unwrap();
+        result.unwrap();
+
+        let items = items.lock().unwrap();
+        assert_eq!(items.len(), 2);
+        assert!(items.iter().all(|item| item.get_i64("score") == Some(10)));
+    }
+
+    /// Records the models of the given channel.
+    #[derive(Debug)]
+    struct ModelsSink(Arc<Mutex<BTreeSet<String>>>);
+
+    #[async_trait]
+    impl PipeSink for ModelsSink {
+        async fn call(&self, channel: PipeChannel) -> Result<()> {
+            *self.0.lock().unwrap() = channel.models();
+            Ok(())
+        }
+    }
+
+    #[::tokio::test]
+    async fn test_channel_models() {
+        let path =
+            ::std::env::temp_dir().join(format!("xlake-models-{}.txt", ::std::process::id()));
+        ::std::fs::write(&path, "hello").unwrap();
+
+        let models = Arc::new(Mutex::new(BTreeSet::default()));
+        let mut session = PipeSession::default();
+        session.insert_factory(Box::new(NodeFactory::new(PlanType::Sink, "models", {
+            let models = models.clone();
+            move |_| Ok(PipeNodeImpl::Sink(Box::new(ModelsSink(models.clone()))))
+        })));
+        let result = session
+            .call(&format!("filesrc path='{}' ! modelssink", path.display()))
+            .await;
+        ::std::fs::remove_file(&path).unwrap();
+        result.unwrap();
+
+        let models = models.lock().unwrap();
+        assert_eq!(
+            *models,
+            BTreeSet::from(["binary", "file", "hash", "stream"].map(Into::into))
+        );
+    }
+
+    /// Emits a single greeting, declaring its default only in the arguments.
+    #[derive(Debug, Deserialize)]
+    struct GreetSrc {
+        name: String,
+    }
+
+    #[async_trait]
+    impl PipeSrc for GreetSrc {
+        async fn call(&self) -> Result<PipeChannel> {
+            let greeting = format!("hello {}", self.name);
+            Ok(channel([object([("greeting", greeting)])]))
+        }
+    }
+
+    #[::tokio::test]
+    async fn test_factory_declared_defaults() {
+        let items = Arc::new(Mutex::new(Vec::default()));
+
+        let mut session = PipeSession::default();
+        let factory = NodeFactory::new(PlanType::Src, "greet", |args| {
+            let imp: GreetSrc = args.to()?;
+            Ok(PipeNodeImpl::Src(Box::new(imp)))
+        });
+        session.insert_factory(Box::new(factory.with_arguments(vec![
+            ArgSpec::optional("name", "Whom to greet").with_default("world"),
+        ])));
+        session.insert_factory(Box::new(CollectSinkFactory(items.clone())));
+        session.call("greetsrc ! collectsink").await.unwrap();
+        session
+            .call("greetsrc name='xlake' ! collectsink")
+            .await
+            .unwrap();
+
+        let greetings: Vec<_> = items
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|item| item.get_raw("greeting").unwrap().to_string())
+            .collect();
+        assert_eq!(greetings, [r#""hello world""#, r#""hello xlake""#]);
+    }
+
+    /// Emits a doc, a binary and an object of no model.
+    #[derive(Debug)]
+    struct MixedSrc;
+
+    #[async_trait]
+    impl PipeSrc for MixedSrc {
+        async fn call(&self) -> Result<PipeChannel> {
+            let items: [LazyObject; 3] = [
+                DocModelObject {
+                    document: "a,b".into(),
+                }
+                .into(),
+                BinaryModelObject {
+                    content: Binary(b"xyz".to_vec()),
+                }
+                .into(),
+                ObjectLayer::from_object_dyn(object([("name", "foo")])).into(),
+            ];
+            Ok(items.into_iter().collect())
+        }
+    }
+
+    #[::tokio::test]
+    async fn test_route() {
+        let items = Arc::new(Mutex::new(Vec::default()));
+        let mut session = PipeSession::default();
+        session.insert_factory(Box::new(NodeFactory::new(PlanType::Src, "mixed", |_| {
+            Ok(PipeNodeImpl::Src(Box::new(MixedSrc)))
+        })));
+        session.insert_factory(Box::new(CollectSinkFactory(items.clone())));
+        session
+            .call(
+                r#"mixedsrc
+                ! stream:route binary='stream:size content=content' doc="doc:split_delim delimiter=','"
+                ! collectsink"#,
+            )
+            .await
+            .unwrap();
+
+        let mut items: Vec<_> = items
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|item| {
+                match (
+                    item.get_str("document"),
+                    item.get_i64("content_size_bytes"),
+                    item.get_str("name"),
+                ) {
+                    (Some(document), None, None) => format!("doc {document}"),
+                    (None, Some(size), None) => format!("binary {size}"),
+                    (None, None, Some(name)) => format!("other {name}"),
+                    _ => panic!("Unexpected object: {item:?}"),
+                }
+            })
+            .collect();
+        items.sort();
+        assert_eq!(items, ["binary 3", "doc a", "doc b", "other foo"]);
+
+        // The branches accept the funcs only
+        let error = session
+            .call("mixedsrc ! stream:route doc='stdoutsink' ! collectsink")
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("Only funcs are allowed"));
+    }
+
+    /// Fails on the objects of the odd `id`s.
+    #[derive(Debug)]
+    struct FailOddFunc;
+
+    #[async_trait]
+    impl PipeFunc for FailOddFunc {
+        async fn call(&self, channel: PipeChannel) -> Result<PipeChannel> {
+            channel
+                .and_then(|item| async move {
+                    match item.get_i64("id") {
+                        Some(id) if id % 2 == 1 => bail!("Odd id: {id}"),
+                        _ => Ok(item),
+                    }
+                })
+                .await
+        }
+    }
+
+    #[::tokio::test]
+    async fn test_dead_letter() {
+        let items = Arc::new(Mutex::new(Vec::default()));
+        let mut session = PipeSession::default();
+        session.insert_factory(Box::new(
+            NodeFactory::new(PlanType::Func, "fail_odd", |_| {
+                Ok(PipeNodeImpl::Func(Box::new(FailOddFunc)))
+            })
+            .per_object(),
+        ));
+        session.insert_factory(Box::new(CollectSinkFactory(items.clone())));
+
+        // The pipeline succeeds, as the failures are dead-lettered
+        session
+            .call(
+                r#"repeatsrc count=5 json='{"name":"foo"}'
+                ! stream:seqid
+                ! stream:fail_odd dead_letter='collectsink'
+                ! collectsink"#,
+            )
+            .await
+            .unwrap();
+
+        // Both sinks share the buffer, where the dead letters carry their errors
+        let items = items.lock().unwrap();
+        let ids = |dead: bool| -> Vec<_> {
+            items
+                .iter()
+                .filter(|item| item.get_raw("error").is_some() == dead)
+                .map(|item| item.get_i64("id").unwrap())
+                .collect()
+        };
+        assert_eq!(ids(false), [0, 2, 4]);
+        assert_eq!(ids(true), [1, 3]);
+        assert!(items
+            .iter()
+            .filter_map(|item| item.get_str("error"))
+            .all(|error| error.contains("Odd id")));
+        assert!(items.iter().all(|item| item.get_str("name") == Some("foo")));
+        drop(items);
+
+        // The funcs over the whole stream cannot be called once per object
+        let error = session
+            .call(
+                "repeatsrc count=5 json='{}'
+                ! stream:sort by='id' dead_letter='collectsink'
+                ! collectsink",
+            )
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("not a per-object func"));
+
+        // Without the dead letters, the first failure aborts the pipeline
+        let error = session
+            .call("repeatsrc count=5 json='{}' ! stream:seqid ! stream:fail_odd ! collectsink")
+            .await
+            .unwrap_err();
+        assert!(format!("{error:#}").contains("Odd id: 1"));
+    }
+
+    #[derive(Debug)]
+    struct ExpensiveFunc(Arc<AtomicUsize>);
+
+    #[async_trait]
+    impl PipeFunc for ExpensiveFunc {
+        async fn call(&self, channel: PipeChannel) -> Result<PipeChannel> {
+            let calls = self.0.clone();
+            channel
+                .and_then(move |mut item| {
+                    let calls = calls.fetch_add(1, Ordering::SeqCst);
+                    async move {
+                        item.insert("calls".into(), (calls as i64).into());
+                        Ok(item)
+                    }
+                })
+                .await
+        }
+    }
+
+    #[::tokio::test]
+    async fn test_memoize() {
+        let calls = Arc::new(AtomicUsize::default());
+        let items = Arc::new(Mutex::new(Vec::default()));
+        let mut session = PipeSession::default();
+        let factory = NodeFactory::new(PlanType::Func, "expensive", {
+            let calls = calls.clone();
+            move |_| Ok(PipeNodeImpl::Func(Box::new(ExpensiveFunc(calls.clone()))))
+        });
+        session.insert_factory(Box::new(factory.per_object()));
+        session.insert_factory(Box::new(CollectSinkFactory(items.clone())));
+
+        let path = ::std::env::temp_dir().join(format!("xlake-memoize-{}", ::std::process::id()));
+        let input = format!(
+            r#"repeatsrc count=3 json='{{"name":"foo"}}'
+            ! stream:seqid
+            ! hash:object
+            ! stream:expensive memoize='localstore path="{}"'
+            ! collectsink"#,
+            path.display(),
+        );
+
+        // The second run shares the store, skipping the func
+        for _ in 0..2 {
+            session.call(&input).await.unwrap();
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+        // The funcs over the whole stream are not memoized per object
+        let error = session
+            .call("repeatsrc count=3 json='{}' ! stream:collect memoize='localstore' ! collectsink")
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("not a per-object func"));
+
+        let items = items.lock().unwrap();
+        let outputs: Vec<_> = items
+            .iter()
+            .map(|item| (item.get_i64("id").unwrap(), item.get_i64("calls").unwrap()))
+            .collect();
+        assert_eq!(outputs, [(0, 0), (1, 1), (2, 2), (0, 0), (1, 1), (2, 2)]);
+        assert!(items.iter().all(|item| item.has_model("hash")));
+
+        ::std::fs::remove_dir_all(&path).unwrap();
+    }
+
+    /// Fails the first attempt of each object, given one at a time.
+    #[derive(Debug)]
+    struct FailOnceFunc(Arc<AtomicUsize>);
+
+    #[async_trait]
+    impl PipeFunc for FailOnceFunc {
+        async fn call(&self, channel: PipeChannel) -> Result<PipeChannel> {
+            if self.0.fetch_add(1, Ordering::SeqCst) % 2 == 0 {
+                bail!("First attempt")
+            }
+            Ok(channel)
+        }
+    }
+
+    #[::tokio::test]
+    async fn test_retries() {
+        let calls = Arc::new(AtomicUsize::default());
+        let items = Arc::new(Mutex::new(Vec::default()));
+        let mut session = PipeSession::default();
+        let factory = NodeFactory::new(PlanType::Func, "fail_once", {
+            let calls = calls.clone();
+            move |_| Ok(PipeNodeImpl::Func(Box::new(FailOnceFunc(calls.clone()))))
+        });
+        session.insert_factory(Box::new(factory.per_object()));
+        session.insert_factory(Box::new(CollectSinkFactory(items.clone())));
+
+        // Each object is retried on its own
+        session
+            .call("repeatsrc count=3 json='{}' ! stream:fail_once retries=1 timeout=1000 ! collectsink")
+            .await
+            .unwrap();
+        assert_eq!(items.lock().unwrap().len(), 3);
+        assert_eq!(calls.load(Ordering::SeqCst), 6);
+
+        // Without the retries, the first failure aborts the pipeline
+        let error = session
+            .call("repeatsrc count=3 json='{}' ! stream:fail_once ! collectsink")
+            .await
+            .unwrap_err();
+        assert!(format!("{error:#}").contains("First attempt"));
+
+        // The funcs over the whole stream cannot be retried per object
+        let error = session
+            .call("repeatsrc count=3 json='{}' ! stream:sort by='id' retries=1 ! collectsink")
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("not a per-object func"));
+    }
+
+    /// Counts the objects, reporting them in the summary.
+    #[derive(Debug, Default)]
+    struct SummarySink(SinkSummaryCell);
+
+    #[async_trait]
+    impl PipeSink for SummarySink {
+        async fn call(&self, channel: PipeChannel) -> Result<()> {
+            let items = channel
+                .into_stream::<LazyObject>()
+                .await?
+                .try_fold(0, |items, _| async move { Ok(items + 1) })
+                .await?;
+            self.0.set(SinkSummary {
+                items,
+                ..Default::default()
+            });
+            Ok(())
+        }
+
+        fn summary(&self) -> Option<SinkSummary> {
+            self.0.get()
+        }
+    }
+
+    #[::tokio::test]
+    async fn test_sink_summary() {
+        let mut session = PipeSession::default();
+        session.insert_factory(Box::new(NodeFactory::new(
+            PlanType::Sink,
+            "summary",
+            |_| Ok(PipeNodeImpl::Sink(Box::<SummarySink>::default())),
+        )));
+        assert!(session.summary().is_none());
+
+        session
+            .call("repeatsrc count=5 json='{}' ! summarysink")
+            .await
+            .unwrap();
+        assert_eq!(session.summary().unwrap().items, 5);
+
+        // The sinks not keeping track of the writes have no summary
+        session
+            .call("repeatsrc count=5 json='{}' ! assertsink min_rows=5")
+            .await
+            .unwrap();
+        assert!(session.summary().is_none());
+    }
+
+    #[::tokio::test]
+    async fn test_call_with_deadline() {
+        let items = Arc::new(Mutex::new(Vec::default()));
+        let mut session = PipeSession::default();
+        session.insert_factory(Box::new(CollectSinkFactory(items.clone())));
+
+        // The infinite src is stopped at the deadline, while the objects so far are written
+        let started = ::std::time::Instant::now();
+        let error = session
+            .call_with_deadline(
+                "repeatsrc infinite=true json='{}' ! stream:seqid ! collectsink",
+                Duration::from_millis(100),
+            )
+            .await
+            .unwrap_err();
+        assert!(started.elapsed() < Duration::from_secs(2));
+        assert!(error.is::<super::deadline::DeadlineExceeded>());
+        assert!(!items.lock().unwrap().is_empty());
+
+        // The pipelines finished in time succeed
+        session
+            .call_with_deadline(
+                "repeatsrc count=3 json='{}' ! collectsink",
+                Duration::from_secs(10),
+            )
+            .await
+            .unwrap();
+    }
+}
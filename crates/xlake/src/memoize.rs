@@ -0,0 +1,130 @@
+use std::{collections::BTreeSet, sync::Arc};
+
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use tracing::debug;
+use xlake_ast::{Object, Value};
+use xlake_core::{
+    context::PipeContext,
+    models::hash::{Hash, HashModelView},
+    object::{LazyObject, ObjectLayer},
+    PipeChannel, PipeFunc, PipeModelOwned, PipeModelOwnedExt, PipeStore,
+};
+
+use crate::per_object::{self, PerObject};
+
+/// The argument accepted by every per-object func to memoize its outputs into a store.
+pub const ARG: &str = "memoize";
+
+/// The fields of the memoized entries.
+const CONTENT: &str = "content";
+const MODELS: &str = "models";
+const ROWS: &str = "rows";
+
+/// Calls the inner func once per object, skipping the inputs already processed.
+///
+/// The outputs are keyed on the input `hash` along with the func itself, so that
+/// an expensive func, e.g. an embedding, runs once per input across the pipeline runs.
+/// The inputs without a hash are passed to the inner func as they are.
+#[derive(Clone, Debug)]
+pub struct MemoizeFunc {
+    inner: Arc<dyn PipeFunc>,
+    /// The identity of the inner func, e.g. its plan
+    key: String,
+    store: Arc<dyn PipeStore>,
+}
+
+impl MemoizeFunc {
+    pub fn new(inner: Arc<dyn PipeFunc>, key: String, store: Arc<dyn PipeStore>) -> Self {
+        Self { inner, key, store }
+    }
+}
+
+#[async_trait]
+impl PerObject for MemoizeFunc {
+    async fn call_item(&self, ctx: &PipeContext, item: LazyObject) -> Result<Vec<LazyObject>> {
+        let item = item.flatten().await?;
+        let mut item: HashModelView = match item.view() {
+            Ok(item) => item,
+            Err(item) => return per_object::call_once(&*self.inner, ctx, item).await,
+        };
+
+        let hash = Hash::digest(format!("{}\n{}", &self.key, item.hash()));
+        if self.store.contains(&hash).await? {
+            debug!("Hit memo: {hash}");
+            let entry = self.store.read_item(&hash).await?;
+            return decode(entry);
+        }
+
+        debug!("Miss memo: {hash}");
+        // Resolve the outputs here, so that they can be stored
+        let rows = per_object::call_once(&*self.inner, ctx, item.__into_inner()).await?;
+        self.store.write_item(&hash, &encode(&rows)).await?;
+        Ok(rows)
+    }
+}
+
+#[async_trait]
+impl PipeFunc for MemoizeFunc {
+    async fn call(&self, channel: PipeChannel) -> Result<PipeChannel> {
+        self.call_with_context(&PipeContext::default(), channel)
+            .await
+    }
+
+    async fn call_with_context(
+        &self,
+        ctx: &PipeContext,
+        channel: PipeChannel,
+    ) -> Result<PipeChannel> {
+        per_object::call(self, ctx, channel).await
+    }
+}
+
+/// Packs the flattened outputs into a single entry, keeping their models.
+fn encode(rows: &[LazyObject]) -> Object {
+    let rows = rows
+        .iter()
+        .map(|row| {
+            let models = row.models().into_iter().map(Value::String).collect();
+            let mut entry = Object::default();
+            entry.insert(CONTENT.into(), Value::Object(row.content().clone()));
+            entry.insert(MODELS.into(), Value::Array(models));
+            Value::Object(entry)
+        })
+        .collect();
+
+    let mut entry = Object::default();
+    entry.insert(ROWS.into(), Value::Array(rows));
+    entry
+}
+
+/// Unpacks the outputs packed by [`encode`].
+fn decode(mut entry: Object) -> Result<Vec<LazyObject>> {
+    let rows = match entry.remove(ROWS) {
+        Some(Value::Array(rows)) => rows,
+        _ => bail!("Invalid memo: missing {ROWS}"),
+    };
+    rows.into_iter()
+        .map(|row| {
+            let mut row = match row {
+                Value::Object(row) => row,
+                _ => bail!("Invalid memo: expected an object, but given {row}"),
+            };
+            let content = match row.remove(CONTENT) {
+                Some(Value::Object(content)) => content,
+                _ => bail!("Invalid memo: missing {CONTENT}"),
+            };
+            let models = match row.remove(MODELS) {
+                Some(Value::Array(models)) => models
+                    .into_iter()
+                    .map(|model| match model {
+                        Value::String(model) => Ok(model),
+                        _ => bail!("Invalid memo: expected a model, but given {model}"),
+                    })
+                    .collect::<Result<BTreeSet<_>>>()?,
+                _ => bail!("Invalid memo: missing {MODELS}"),
+            };
+            Ok(ObjectLayer::from_object(content, models).into())
+        })
+        .collect()
+}
@@ -0,0 +1,227 @@
+use std::{
+    fmt,
+    hash::{DefaultHasher, Hash, Hasher},
+    iter,
+};
+
+use anyhow::{bail, Error, Result};
+use async_trait::async_trait;
+use futures::TryStreamExt;
+use serde::{Deserialize, Serialize};
+use xlake_ast::{Number, Object, PlanArguments, PlanKind, Value};
+use xlake_core::{
+    object::{LazyObject, ObjectLayer},
+    ArgSpec, PipeChannel, PipeFunc, PipeNodeFactory, PipeNodeImpl,
+};
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct ApproxDistinctFactory;
+
+impl fmt::Display for ApproxDistinctFactory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.kind().fmt(f)
+    }
+}
+
+#[async_trait]
+impl PipeNodeFactory for ApproxDistinctFactory {
+    fn kind(&self) -> PlanKind {
+        PlanKind::Func {
+            model_name: super::stream::consts::NAME.into(),
+            func: self.name(),
+        }
+    }
+
+    fn name(&self) -> String {
+        "approx_distinct".into()
+    }
+
+    fn arguments(&self) -> Vec<ArgSpec> {
+        vec![
+            ArgSpec::required("key", "Field to count the distinct values"),
+            ArgSpec::optional(
+                "precision",
+                "Bits of the registers in [4, 18], trading memory for accuracy",
+            )
+            .with_default(ApproxDistinctFuncArgs::default_precision()),
+        ]
+    }
+
+    async fn build(&self, args: &PlanArguments) -> Result<PipeNodeImpl> {
+        let args: ApproxDistinctFuncArgs = args.to()?;
+        let imp = ApproxDistinctFunc::try_from(args)?;
+        Ok(PipeNodeImpl::Func(Box::new(imp)))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ApproxDistinctFuncArgs {
+    key: String,
+    #[serde(default = "ApproxDistinctFuncArgs::default_precision")]
+    precision: u8,
+}
+
+impl ApproxDistinctFuncArgs {
+    const fn default_precision() -> u8 {
+        14
+    }
+}
+
+/// Estimates the number of the distinct values with a fixed memory of `2^precision` bytes.
+#[derive(Clone, Debug)]
+pub struct ApproxDistinctFunc {
+    key: String,
+    precision: u8,
+}
+
+impl TryFrom<ApproxDistinctFuncArgs> for ApproxDistinctFunc {
+    type Error = Error;
+
+    fn try_from(args: ApproxDistinctFuncArgs) -> Result<Self, Self::Error> {
+        let ApproxDistinctFuncArgs { key, precision } = args;
+        if !(4..=18).contains(&precision) {
+            bail!("precision should be in [4, 18]: {precision}")
+        }
+        Ok(Self { key, precision })
+    }
+}
+
+#[async_trait]
+impl PipeFunc for ApproxDistinctFunc {
+    async fn call(&self, channel: PipeChannel) -> Result<PipeChannel> {
+        let mut count = 0usize;
+        let mut sketch = HyperLogLog::new(self.precision);
+
+        // Drain the source, emitting the summary at the end of stream
+        let mut iter = channel.into_stream::<LazyObject>().await?;
+        while let Some(item) = iter.try_next().await? {
+            let item = item.flatten().await?;
+            match item.get_raw(&self.key) {
+                Some(Value::Null) | None => continue,
+                Some(value) => sketch.insert(&value.to_string()),
+            }
+            count += 1;
+        }
+
+        let float = |value: f64| {
+            ::serde_json::Number::from_f64(value)
+                .map(|value| Value::Number(Number::Fixed(value)))
+                .unwrap_or(Value::Null)
+        };
+        let object: Object = [
+            ("count", count.into()),
+            ("estimate", (sketch.estimate().round() as u64).into()),
+            ("key", Value::String(self.key.clone())),
+            ("standard_error", float(sketch.standard_error())),
+        ]
+        .into_iter()
+        .map(|(key, value)| (key.into(), value))
+        .collect();
+        let item = ObjectLayer::from_object_dyn(object).into();
+        Ok(iter::once::<LazyObject>(item).collect())
+    }
+}
+
+/// A HyperLogLog sketch of `2^precision` registers.
+#[derive(Clone, Debug)]
+struct HyperLogLog {
+    precision: u8,
+    registers: Vec<u8>,
+}
+
+impl HyperLogLog {
+    fn new(precision: u8) -> Self {
+        Self {
+            precision,
+            registers: vec![0; 1 << precision],
+        }
+    }
+
+    fn insert(&mut self, value: &str) {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        let hash = hasher.finish();
+
+        // The first bits select the register, the rest give the rank
+        let index = (hash >> (64 - self.precision)) as usize;
+        let rest = hash << self.precision;
+        let max_rank = 64 - self.precision + 1;
+        let rank = (rest.leading_zeros() as u8 + 1).min(max_rank);
+        if self.registers[index] < rank {
+            self.registers[index] = rank;
+        }
+    }
+
+    fn estimate(&self) -> f64 {
+        let m = self.registers.len() as f64;
+        let alpha = match self.registers.len() {
+            16 => 0.673,
+            32 => 0.697,
+            64 => 0.709,
+            _ => 0.7213 / (1.0 + 1.079 / m),
+        };
+        let sum: f64 = self
+            .registers
+            .iter()
+            .map(|&rank| 2f64.powi(-i32::from(rank)))
+            .sum();
+        let raw = alpha * m * m / sum;
+
+        // Fall back to the linear counting on the small cardinalities
+        let zeros = self.registers.iter().filter(|&&rank| rank == 0).count();
+        if raw <= 2.5 * m && zeros > 0 {
+            m * (m / zeros as f64).ln()
+        } else {
+            raw
+        }
+    }
+
+    fn standard_error(&self) -> f64 {
+        1.04 / (self.registers.len() as f64).sqrt()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::TryStreamExt;
+    use xlake_core::{object::LazyObject, PipeFunc};
+
+    use crate::testing::{channel, object};
+
+    use super::{ApproxDistinctFunc, ApproxDistinctFuncArgs};
+
+    #[::tokio::test]
+    async fn test_approx_distinct() {
+        let distinct = 20_000;
+
+        // Each value appears three times
+        let channel = channel(
+            (0..3 * distinct).map(|index| object([("user", format!("user-{}", index % distinct))])),
+        );
+
+        let func = ApproxDistinctFunc::try_from(ApproxDistinctFuncArgs {
+            key: "user".into(),
+            precision: 14,
+        })
+        .unwrap();
+        let items: Vec<LazyObject> = func
+            .call(channel)
+            .await
+            .unwrap()
+            .into_stream()
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(items.len(), 1);
+
+        let get = |key| items[0].get_raw(key).unwrap().to_string();
+        assert_eq!(get("count"), (3 * distinct).to_string());
+
+        // Allow 4 standard errors, i.e. about 3.2%
+        let estimate: f64 = get("estimate").parse().unwrap();
+        let error = (estimate - distinct as f64).abs() / distinct as f64;
+        assert!(error < 0.04, "estimate: {estimate}");
+    }
+}
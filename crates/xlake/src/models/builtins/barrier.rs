@@ -0,0 +1,58 @@
+use std::fmt;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use futures::TryStreamExt;
+use serde::{Deserialize, Serialize};
+use xlake_ast::{PlanArguments, PlanKind};
+use xlake_core::{object::LazyObject, PipeChannel, PipeFunc, PipeNodeFactory, PipeNodeImpl};
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct BarrierFactory;
+
+impl fmt::Display for BarrierFactory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.kind().fmt(f)
+    }
+}
+
+#[async_trait]
+impl PipeNodeFactory for BarrierFactory {
+    fn kind(&self) -> PlanKind {
+        PlanKind::Func {
+            model_name: super::stream::consts::NAME.into(),
+            func: self.name(),
+        }
+    }
+
+    fn name(&self) -> String {
+        "barrier".into()
+    }
+
+    async fn build(&self, args: &PlanArguments) -> Result<PipeNodeImpl> {
+        let imp: BarrierFunc = args.to()?;
+        Ok(PipeNodeImpl::Func(Box::new(imp)))
+    }
+}
+
+/// Awaits the whole upstream before passing any object to the downstream.
+///
+/// The pipelines stream the objects one by one otherwise, so this is the explicit
+/// materialization point, e.g. to read a store only after all the writes are complete.
+/// Note that all the objects are buffered in memory until the end of stream.
+#[derive(Copy, Clone, Debug, Default, Serialize, Deserialize)]
+pub struct BarrierFunc {}
+
+#[async_trait]
+impl PipeFunc for BarrierFunc {
+    async fn call(&self, channel: PipeChannel) -> Result<PipeChannel> {
+        // Drain the upstream, completing the pending writes of each object
+        let items: Vec<LazyObject> = channel
+            .into_stream::<LazyObject>()
+            .await?
+            .and_then(|item| item.flatten())
+            .try_collect()
+            .await?;
+        Ok(items.into_iter().collect())
+    }
+}
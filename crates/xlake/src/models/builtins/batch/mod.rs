@@ -1,3 +1,8 @@
 pub mod python;
+pub mod sql;
 
 // BatchModel is virtual and auto-driven by the format.
+
+pub(crate) mod consts {
+    pub(crate) const NAME: &str = "batch";
+}
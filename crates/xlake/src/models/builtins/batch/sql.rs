@@ -0,0 +1,238 @@
+use std::{collections::BTreeMap, fmt, path::PathBuf};
+
+use anyhow::{anyhow, bail, Error, Result};
+use async_trait::async_trait;
+use datafusion::prelude::{CsvReadOptions, NdJsonReadOptions};
+use futures::TryStreamExt;
+use serde::{Deserialize, Serialize};
+use xlake_ast::{PlanArguments, PlanKind};
+use xlake_core::{
+    batch::{BatchFormatArgs, DataFusionBatch, DEFAULT_TABLE_REF, NAME},
+    object::LazyObject,
+    ArgSpec, PipeChannel, PipeEdge, PipeFunc, PipeNodeFactory, PipeNodeImpl,
+};
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct SqlFactory;
+
+impl fmt::Display for SqlFactory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.kind().fmt(f)
+    }
+}
+
+#[async_trait]
+impl PipeNodeFactory for SqlFactory {
+    fn kind(&self) -> PlanKind {
+        PlanKind::Func {
+            model_name: super::consts::NAME.into(),
+            func: self.name(),
+        }
+    }
+
+    fn name(&self) -> String {
+        "sql".into()
+    }
+
+    fn arguments(&self) -> Vec<ArgSpec> {
+        vec![
+            ArgSpec::required("query", "SQL query over the registered tables"),
+            ArgSpec::optional("table", "Name of the table of the input objects")
+                .with_default(DEFAULT_TABLE_REF),
+            ArgSpec::optional(
+                "tables",
+                "Comma-separated side inputs to join, e.g. `b=b.csv,c=c.ndjson`",
+            ),
+        ]
+    }
+
+    fn output(&self) -> PipeEdge {
+        PipeEdge {
+            batch: NAME.into(),
+            model: Some(vec![super::consts::NAME.into()]),
+            ..Default::default()
+        }
+    }
+
+    async fn build(&self, args: &PlanArguments) -> Result<PipeNodeImpl> {
+        let args: SqlFuncArgs = args.to()?;
+        let imp = SqlFunc::try_from(args)?;
+        Ok(PipeNodeImpl::Func(Box::new(imp)))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SqlFuncArgs {
+    query: String,
+    #[serde(default = "SqlFuncArgs::default_table")]
+    table: String,
+    #[serde(default)]
+    tables: Option<String>,
+}
+
+impl SqlFuncArgs {
+    fn default_table() -> String {
+        DEFAULT_TABLE_REF.into()
+    }
+}
+
+/// Runs a SQL query over the input objects and the side inputs, e.g. to join them.
+///
+/// The side inputs are registered into the same session before the input objects,
+/// which are materialized into a table.
+/// If the channel already carries a table, e.g. from `to_batch`, its session is reused instead,
+/// reading the table by its own name as well as by `table`.
+/// Note that `default` is a reserved word, so the default table should be quoted in the query.
+#[derive(Clone, Debug)]
+pub struct SqlFunc {
+    query: String,
+    table: String,
+    tables: BTreeMap<String, PathBuf>,
+}
+
+impl TryFrom<SqlFuncArgs> for SqlFunc {
+    type Error = Error;
+
+    fn try_from(args: SqlFuncArgs) -> Result<Self, Self::Error> {
+        let SqlFuncArgs {
+            query,
+            table,
+            tables,
+        } = args;
+
+        let tables = tables
+            .iter()
+            .flat_map(|tables| tables.split(','))
+            .map(str::trim)
+            .filter(|table| !table.is_empty())
+            .map(|table| match table.split_once('=') {
+                Some((name, path)) => Ok((name.trim().into(), path.trim().into())),
+                None => Err(anyhow!("Side input should be `name=path`: '{table}'")),
+            })
+            .collect::<Result<BTreeMap<String, PathBuf>>>()?;
+        if tables.contains_key(&table) {
+            bail!("Side input conflicts with the input table: '{table}'")
+        }
+        Ok(Self {
+            query,
+            table,
+            tables,
+        })
+    }
+}
+
+impl SqlFunc {
+    async fn register_side_inputs(&self, batch: &DataFusionBatch) -> Result<()> {
+        for (name, path) in &self.tables {
+            let extension = path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .unwrap_or_default();
+            let path = path.to_string_lossy();
+            match extension {
+                "csv" => {
+                    let options = CsvReadOptions::default();
+                    batch.register_csv(name.as_str(), path, options).await?
+                }
+                "json" | "ndjson" => {
+                    let extension = format!(".{extension}");
+                    let options = NdJsonReadOptions::default().file_extension(&extension);
+                    batch.register_json(name.as_str(), path, options).await?
+                }
+                _ => bail!("Unsupported side input: {path}"),
+            }
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl PipeFunc for SqlFunc {
+    async fn call(&self, mut channel: PipeChannel) -> Result<PipeChannel> {
+        let upstream = channel.to_batch().await?;
+        let reuse = upstream.has_table()?;
+        let batch = if reuse {
+            upstream.with_table(&self.table).await?
+        } else {
+            DataFusionBatch::new(BatchFormatArgs {
+                table: self.table.clone(),
+                ..Default::default()
+            })?
+        };
+
+        // Load the side inputs before the main stream
+        self.register_side_inputs(&batch).await?;
+
+        if !reuse {
+            let rows: Vec<_> = channel
+                .into_stream::<LazyObject>()
+                .await?
+                .and_then(|item| item.flatten())
+                .map_ok(|item| item.content().clone())
+                .try_collect()
+                .await?;
+            batch.register_rows(&rows)?;
+        }
+
+        batch.query(&self.query).await?;
+        PipeChannel::from_batch_streamed(batch).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::TryStreamExt;
+    use xlake_ast::Value;
+    use xlake_core::{object::LazyObject, PipeFunc};
+
+    use crate::testing::{channel, object};
+
+    use super::{SqlFunc, SqlFuncArgs};
+
+    #[::tokio::test]
+    async fn test_sql_join() {
+        let path = ::std::env::temp_dir().join(format!("xlake-join-{}.csv", ::std::process::id()));
+        ::std::fs::write(&path, "id,score\n1,10\n2,20\n3,30\n").unwrap();
+
+        let channel = channel(
+            [(2, "bar"), (1, "foo"), (4, "qux")]
+                .map(|(id, name)| object([("id", Value::from(id)), ("name", name.into())])),
+        );
+
+        let func = SqlFunc::try_from(SqlFuncArgs {
+            query: "SELECT a.name, b.score FROM a JOIN b ON a.id = b.id ORDER BY a.name".into(),
+            table: "a".into(),
+            tables: Some(format!("b={}", path.display())),
+        })
+        .unwrap();
+        let rows: Vec<_> = func
+            .call(channel)
+            .await
+            .unwrap()
+            .into_stream::<LazyObject>()
+            .await
+            .unwrap()
+            .map_ok(|item| {
+                let name = item.get_str("name").unwrap().to_string();
+                (name, item.get_i64("score").unwrap())
+            })
+            .try_collect()
+            .await
+            .unwrap();
+        ::std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(rows, [("bar".into(), 20), ("foo".into(), 10)]);
+    }
+
+    #[test]
+    fn test_sql_side_inputs_invalid() {
+        let args = |tables: &str| SqlFuncArgs {
+            query: "SELECT 1".into(),
+            table: "a".into(),
+            tables: Some(tables.into()),
+        };
+        assert!(SqlFunc::try_from(args("b")).is_err());
+        assert!(SqlFunc::try_from(args("a=a.csv")).is_err());
+        assert!(SqlFunc::try_from(args("b=b.csv, c=c.ndjson")).is_ok());
+    }
+}
@@ -0,0 +1,197 @@
+use std::fmt;
+
+use anyhow::{bail, Error, Result};
+use async_trait::async_trait;
+use futures::{stream, StreamExt, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use xlake_ast::{Object, PlanArguments, PlanKind, Value};
+use xlake_core::{
+    object::{LazyObject, ObjectLayer},
+    stream::DefaultStream,
+    ArgSpec, PipeChannel, PipeFunc, PipeNodeFactory, PipeNodeImpl,
+};
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct BatchGroupFactory;
+
+impl fmt::Display for BatchGroupFactory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.kind().fmt(f)
+    }
+}
+
+#[async_trait]
+impl PipeNodeFactory for BatchGroupFactory {
+    fn kind(&self) -> PlanKind {
+        PlanKind::Func {
+            model_name: super::stream::consts::NAME.into(),
+            func: self.name(),
+        }
+    }
+
+    fn name(&self) -> String {
+        "batch_group".into()
+    }
+
+    fn arguments(&self) -> Vec<ArgSpec> {
+        vec![
+            ArgSpec::required("size", "Number of the consecutive objects per group"),
+            ArgSpec::optional("target", "Array field to store the grouped objects")
+                .with_default(BatchGroupFuncArgs::default_target()),
+        ]
+    }
+
+    async fn build(&self, args: &PlanArguments) -> Result<PipeNodeImpl> {
+        let args: BatchGroupFuncArgs = args.to()?;
+        let imp = BatchGroupFunc::try_from(args)?;
+        Ok(PipeNodeImpl::Func(Box::new(imp)))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BatchGroupFuncArgs {
+    size: usize,
+    #[serde(default = "BatchGroupFuncArgs::default_target")]
+    target: String,
+}
+
+impl BatchGroupFuncArgs {
+    fn default_target() -> String {
+        "items".into()
+    }
+}
+
+/// Groups every `size` consecutive objects into a single object, e.g. for the bulk APIs.
+///
+/// Each group has the objects in the `target` array, along with their `count`.
+/// The last group may have fewer objects, being flushed at the end of stream.
+#[derive(Clone, Debug)]
+pub struct BatchGroupFunc {
+    size: usize,
+    target: String,
+}
+
+impl TryFrom<BatchGroupFuncArgs> for BatchGroupFunc {
+    type Error = Error;
+
+    fn try_from(args: BatchGroupFuncArgs) -> Result<Self, Self::Error> {
+        let BatchGroupFuncArgs { size, target } = args;
+
+        if size == 0 {
+            bail!("size should be positive")
+        }
+        if target == "count" {
+            bail!("target should not be 'count'")
+        }
+        Ok(Self { size, target })
+    }
+}
+
+impl BatchGroupFunc {
+    fn group(&self, values: Vec<Value>) -> LazyObject {
+        let mut object = Object::default();
+        object.insert("count".into(), values.len().into());
+        object.insert(self.target.clone(), Value::Array(values));
+        ObjectLayer::from_object_dyn(object).into()
+    }
+}
+
+#[async_trait]
+impl PipeFunc for BatchGroupFunc {
+    async fn call(&self, channel: PipeChannel) -> Result<PipeChannel> {
+        let func = self.clone();
+        let input = channel.into_stream::<LazyObject>().await?;
+        let stream = stream::try_unfold(Some(input), move |input| {
+            let func = func.clone();
+            async move {
+                let Some(mut input) = input else {
+                    return Ok(None);
+                };
+
+                let mut values = Vec::with_capacity(func.size);
+                while values.len() < func.size {
+                    match input.try_next().await? {
+                        Some(item) => {
+                            let item = item.flatten().await?;
+                            values.push(Value::Object(item.content().clone()));
+                        }
+                        // Flush the partial group, if any
+                        None if values.is_empty() => return Ok(None),
+                        None => return Ok(Some((func.group(values), None))),
+                    }
+                }
+                Ok(Some((func.group(values), Some(input))))
+            }
+        })
+        .boxed();
+        Ok(PipeChannel::from_stream(DefaultStream::from_stream(stream)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::TryStreamExt;
+    use xlake_ast::Value;
+    use xlake_core::{object::LazyObject, PipeFunc};
+
+    use crate::testing::{channel, object};
+
+    use super::{BatchGroupFunc, BatchGroupFuncArgs};
+
+    async fn batch_group(size: usize, count: i64) -> Vec<(i64, Vec<i64>)> {
+        let channel = channel((0..count).map(|id| object([("id", id)])));
+
+        let func = BatchGroupFunc::try_from(BatchGroupFuncArgs {
+            size,
+            target: BatchGroupFuncArgs::default_target(),
+        })
+        .unwrap();
+        func.call(channel)
+            .await
+            .unwrap()
+            .into_stream::<LazyObject>()
+            .await
+            .unwrap()
+            .map_ok(|item| {
+                let ids = match item.get_raw("items") {
+                    Some(Value::Array(values)) => values
+                        .iter()
+                        .map(|value| match value {
+                            Value::Object(object) => object.get_i64("id").unwrap(),
+                            _ => unreachable!(),
+                        })
+                        .collect(),
+                    _ => unreachable!(),
+                };
+                (item.get_i64("count").unwrap(), ids)
+            })
+            .try_collect()
+            .await
+            .unwrap()
+    }
+
+    #[::tokio::test]
+    async fn test_batch_group_partial() {
+        assert_eq!(
+            batch_group(3, 7).await,
+            [(3, vec![0, 1, 2]), (3, vec![3, 4, 5]), (1, vec![6])],
+        );
+    }
+
+    #[::tokio::test]
+    async fn test_batch_group_exact() {
+        // No empty group is flushed at the end of stream
+        assert_eq!(batch_group(2, 4).await, [(2, vec![0, 1]), (2, vec![2, 3])]);
+        assert!(batch_group(2, 0).await.is_empty());
+    }
+
+    #[test]
+    fn test_batch_group_invalid() {
+        let args = |size, target: &str| BatchGroupFuncArgs {
+            size,
+            target: target.into(),
+        };
+        assert!(BatchGroupFunc::try_from(args(0, "items")).is_err());
+        assert!(BatchGroupFunc::try_from(args(2, "count")).is_err());
+    }
+}
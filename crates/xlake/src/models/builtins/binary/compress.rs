@@ -0,0 +1,281 @@
+use std::fmt;
+
+use anyhow::Result;
+use async_compression::{
+    tokio::bufread::{GzipDecoder, GzipEncoder, ZstdDecoder, ZstdEncoder},
+    Level,
+};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncRead, AsyncReadExt};
+use xlake_ast::{PlanArguments, PlanKind};
+use xlake_core::{
+    object::LazyObject, ArgSpec, PipeChannel, PipeEdge, PipeFunc, PipeModelOwned,
+    PipeModelOwnedExt, PipeNodeFactory, PipeNodeImpl,
+};
+
+use crate::models::builtins::file::FileModelView;
+
+use super::BinaryModelView;
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct CompressFactory;
+
+impl fmt::Display for CompressFactory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.kind().fmt(f)
+    }
+}
+
+#[async_trait]
+impl PipeNodeFactory for CompressFactory {
+    fn kind(&self) -> PlanKind {
+        PlanKind::Func {
+            model_name: super::consts::NAME.into(),
+            func: self.name(),
+        }
+    }
+
+    fn name(&self) -> String {
+        "compress".into()
+    }
+
+    fn arguments(&self) -> Vec<ArgSpec> {
+        vec![
+            ArgSpec::optional("algo", "`gzip` or `zstd`").with_default("gzip"),
+            ArgSpec::optional("level", "Compression level of the algorithm"),
+        ]
+    }
+
+    fn input(&self) -> PipeEdge {
+        PipeEdge {
+            model: Some(vec![super::consts::NAME.into()]),
+            ..Default::default()
+        }
+    }
+
+    fn output(&self) -> PipeEdge {
+        PipeEdge {
+            model: Some(vec![super::consts::NAME.into()]),
+            ..Default::default()
+        }
+    }
+
+    fn is_per_object(&self) -> bool {
+        true
+    }
+
+    async fn build(&self, args: &PlanArguments) -> Result<PipeNodeImpl> {
+        let imp: CompressFunc = args.to()?;
+        Ok(PipeNodeImpl::Func(Box::new(imp)))
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, Serialize, Deserialize)]
+pub struct CompressFunc {
+    #[serde(default)]
+    algo: CompressAlgo,
+    #[serde(default)]
+    level: Option<i32>,
+}
+
+impl CompressFunc {
+    async fn compress(&self, item: LazyObject) -> Result<LazyObject> {
+        let &Self { algo, level } = self;
+
+        // Download the file contents
+        let item = item.flatten().await?;
+        let mut item: BinaryModelView = match item.view() {
+            Ok(item) => item,
+            Err(item) => return Ok(item),
+        };
+
+        let content = item.content();
+        *content = algo.compress(content, level).await?;
+
+        // Append the extension, e.g. `pdf` -> `pdf.gz`
+        let item = item.__into_inner();
+        let item = match item.view::<FileModelView>() {
+            Ok(mut item) => {
+                let extension = item.extension();
+                *extension = format!("{extension}.{}", algo.extension());
+                item.__into_inner()
+            }
+            Err(item) => item,
+        };
+        Ok(item)
+    }
+}
+
+#[async_trait]
+impl PipeFunc for CompressFunc {
+    async fn call(&self, channel: PipeChannel) -> Result<PipeChannel> {
+        let func = *self;
+        channel
+            .and_then(move |item| async move { func.compress(item).await })
+            .await
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct DecompressFactory;
+
+impl fmt::Display for DecompressFactory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.kind().fmt(f)
+    }
+}
+
+#[async_trait]
+impl PipeNodeFactory for DecompressFactory {
+    fn kind(&self) -> PlanKind {
+        PlanKind::Func {
+            model_name: super::consts::NAME.into(),
+            func: self.name(),
+        }
+    }
+
+    fn name(&self) -> String {
+        "decompress".into()
+    }
+
+    fn arguments(&self) -> Vec<ArgSpec> {
+        vec![ArgSpec::optional("algo", "`gzip` or `zstd`").with_default("gzip")]
+    }
+
+    fn input(&self) -> PipeEdge {
+        PipeEdge {
+            model: Some(vec![super::consts::NAME.into()]),
+            ..Default::default()
+        }
+    }
+
+    fn output(&self) -> PipeEdge {
+        PipeEdge {
+            model: Some(vec![super::consts::NAME.into()]),
+            ..Default::default()
+        }
+    }
+
+    fn is_per_object(&self) -> bool {
+        true
+    }
+
+    async fn build(&self, args: &PlanArguments) -> Result<PipeNodeImpl> {
+        let imp: DecompressFunc = args.to()?;
+        Ok(PipeNodeImpl::Func(Box::new(imp)))
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, Serialize, Deserialize)]
+pub struct DecompressFunc {
+    #[serde(default)]
+    algo: CompressAlgo,
+}
+
+impl DecompressFunc {
+    async fn decompress(&self, item: LazyObject) -> Result<LazyObject> {
+        let &Self { algo } = self;
+
+        // Download the file contents
+        let item = item.flatten().await?;
+        let mut item: BinaryModelView = match item.view() {
+            Ok(item) => item,
+            Err(item) => return Ok(item),
+        };
+
+        let content = item.content();
+        *content = algo.decompress(content).await?;
+
+        // Strip the extension, e.g. `pdf.gz` -> `pdf`
+        let item = item.__into_inner();
+        let item = match item.view::<FileModelView>() {
+            Ok(mut item) => {
+                let extension = item.extension();
+                let suffix = format!(".{}", algo.extension());
+                if let Some(stripped) = extension.strip_suffix(&suffix) {
+                    *extension = stripped.into();
+                }
+                item.__into_inner()
+            }
+            Err(item) => item,
+        };
+        Ok(item)
+    }
+}
+
+#[async_trait]
+impl PipeFunc for DecompressFunc {
+    async fn call(&self, channel: PipeChannel) -> Result<PipeChannel> {
+        let func = *self;
+        channel
+            .and_then(move |item| async move { func.decompress(item).await })
+            .await
+    }
+}
+
+#[derive(
+    Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum CompressAlgo {
+    #[default]
+    Gzip,
+    Zstd,
+}
+
+impl CompressAlgo {
+    const fn extension(&self) -> &'static str {
+        match self {
+            Self::Gzip => "gz",
+            Self::Zstd => "zst",
+        }
+    }
+
+    async fn compress(&self, content: &[u8], level: Option<i32>) -> Result<Vec<u8>> {
+        let level = level.map(Level::Precise).unwrap_or(Level::Default);
+        match self {
+            Self::Gzip => read_to_end(GzipEncoder::with_quality(content, level)).await,
+            Self::Zstd => read_to_end(ZstdEncoder::with_quality(content, level)).await,
+        }
+    }
+
+    async fn decompress(&self, content: &[u8]) -> Result<Vec<u8>> {
+        match self {
+            Self::Gzip => read_to_end(GzipDecoder::new(content)).await,
+            Self::Zstd => read_to_end(ZstdDecoder::new(content)).await,
+        }
+    }
+}
+
+async fn read_to_end(mut reader: impl Unpin + AsyncRead) -> Result<Vec<u8>> {
+    let mut buf = Vec::default();
+    reader.read_to_end(&mut buf).await?;
+    Ok(buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CompressAlgo;
+
+    async fn round_trip(algo: CompressAlgo, level: Option<i32>) {
+        let content = b"hello world! ".repeat(64);
+        let compressed = algo.compress(&content, level).await.unwrap();
+        assert_ne!(compressed, content);
+
+        let decompressed = algo.decompress(&compressed).await.unwrap();
+        assert_eq!(decompressed, content);
+    }
+
+    #[::tokio::test]
+    async fn test_compress_gzip() {
+        round_trip(CompressAlgo::Gzip, None).await;
+        round_trip(CompressAlgo::Gzip, Some(9)).await;
+    }
+
+    #[::tokio::test]
+    async fn test_compress_zstd() {
+        round_trip(CompressAlgo::Zstd, None).await;
+        round_trip(CompressAlgo::Zstd, Some(19)).await;
+    }
+}
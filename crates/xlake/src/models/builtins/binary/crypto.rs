@@ -0,0 +1,327 @@
+use std::{env, fmt, sync::Arc};
+
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+    Aes256Gcm, Nonce,
+};
+use anyhow::{anyhow, bail, Context, Error, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use xlake_ast::{Binary, PlanArguments, PlanKind};
+use xlake_core::{
+    object::LazyObject, ArgSpec, PipeChannel, PipeEdge, PipeFunc, PipeModelOwned,
+    PipeModelOwnedExt, PipeNodeFactory, PipeNodeImpl,
+};
+
+use super::BinaryModelView;
+
+/// The length of the nonce prepended to each ciphertext.
+const NONCE_LEN: usize = 12;
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct EncryptFactory;
+
+impl fmt::Display for EncryptFactory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.kind().fmt(f)
+    }
+}
+
+#[async_trait]
+impl PipeNodeFactory for EncryptFactory {
+    fn kind(&self) -> PlanKind {
+        PlanKind::Func {
+            model_name: super::consts::NAME.into(),
+            func: self.name(),
+        }
+    }
+
+    fn name(&self) -> String {
+        "encrypt".into()
+    }
+
+    fn arguments(&self) -> Vec<ArgSpec> {
+        CryptoFuncArgs::arguments()
+    }
+
+    fn input(&self) -> PipeEdge {
+        PipeEdge {
+            model: Some(vec![super::consts::NAME.into()]),
+            ..Default::default()
+        }
+    }
+
+    fn output(&self) -> PipeEdge {
+        PipeEdge {
+            model: Some(vec![super::consts::NAME.into()]),
+            ..Default::default()
+        }
+    }
+
+    fn is_per_object(&self) -> bool {
+        true
+    }
+
+    async fn build(&self, args: &PlanArguments) -> Result<PipeNodeImpl> {
+        let args: CryptoFuncArgs = args.to()?;
+        let imp = EncryptFunc {
+            key: args.try_into()?,
+        };
+        Ok(PipeNodeImpl::Func(Box::new(imp)))
+    }
+}
+
+/// Encrypts the content with AES-256-GCM, prepending the random nonce.
+#[derive(Clone, Debug)]
+pub struct EncryptFunc {
+    key: CryptoKey,
+}
+
+impl EncryptFunc {
+    async fn encrypt(&self, item: LazyObject) -> Result<LazyObject> {
+        // Download the file contents
+        let item = item.flatten().await?;
+        let mut item: BinaryModelView = match item.view() {
+            Ok(item) => item,
+            Err(item) => return Ok(item),
+        };
+
+        let content = item.content();
+        *content = self.key.encrypt(content)?;
+        Ok(item.__into_inner())
+    }
+}
+
+#[async_trait]
+impl PipeFunc for EncryptFunc {
+    async fn call(&self, channel: PipeChannel) -> Result<PipeChannel> {
+        let func = self.clone();
+        channel
+            .and_then(move |item| {
+                let func = func.clone();
+                async move { func.encrypt(item).await }
+            })
+            .await
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct DecryptFactory;
+
+impl fmt::Display for DecryptFactory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.kind().fmt(f)
+    }
+}
+
+#[async_trait]
+impl PipeNodeFactory for DecryptFactory {
+    fn kind(&self) -> PlanKind {
+        PlanKind::Func {
+            model_name: super::consts::NAME.into(),
+            func: self.name(),
+        }
+    }
+
+    fn name(&self) -> String {
+        "decrypt".into()
+    }
+
+    fn arguments(&self) -> Vec<ArgSpec> {
+        CryptoFuncArgs::arguments()
+    }
+
+    fn input(&self) -> PipeEdge {
+        PipeEdge {
+            model: Some(vec![super::consts::NAME.into()]),
+            ..Default::default()
+        }
+    }
+
+    fn output(&self) -> PipeEdge {
+        PipeEdge {
+            model: Some(vec![super::consts::NAME.into()]),
+            ..Default::default()
+        }
+    }
+
+    fn is_per_object(&self) -> bool {
+        true
+    }
+
+    async fn build(&self, args: &PlanArguments) -> Result<PipeNodeImpl> {
+        let args: CryptoFuncArgs = args.to()?;
+        let imp = DecryptFunc {
+            key: args.try_into()?,
+        };
+        Ok(PipeNodeImpl::Func(Box::new(imp)))
+    }
+}
+
+/// Decrypts the content encrypted by [`EncryptFunc`], failing on the tampered ones.
+#[derive(Clone, Debug)]
+pub struct DecryptFunc {
+    key: CryptoKey,
+}
+
+impl DecryptFunc {
+    async fn decrypt(&self, item: LazyObject) -> Result<LazyObject> {
+        // Download the file contents
+        let item = item.flatten().await?;
+        let mut item: BinaryModelView = match item.view() {
+            Ok(item) => item,
+            Err(item) => return Ok(item),
+        };
+
+        let content = item.content();
+        *content = self.key.decrypt(content)?;
+        Ok(item.__into_inner())
+    }
+}
+
+#[async_trait]
+impl PipeFunc for DecryptFunc {
+    async fn call(&self, channel: PipeChannel) -> Result<PipeChannel> {
+        let func = self.clone();
+        channel
+            .and_then(move |item| {
+                let func = func.clone();
+                async move { func.decrypt(item).await }
+            })
+            .await
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CryptoFuncArgs {
+    #[serde(default)]
+    key: Option<String>,
+    #[serde(default)]
+    key_env: Option<String>,
+}
+
+impl CryptoFuncArgs {
+    fn arguments() -> Vec<ArgSpec> {
+        vec![
+            ArgSpec::optional("key", "256-bit key in hex or base64"),
+            ArgSpec::optional(
+                "key_env",
+                "Environment variable having the key, rather than `key`",
+            ),
+        ]
+    }
+}
+
+/// An AES-256-GCM key, hidden from the logs.
+#[derive(Clone)]
+struct CryptoKey(Arc<Aes256Gcm>);
+
+impl fmt::Debug for CryptoKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("CryptoKey(..)")
+    }
+}
+
+impl TryFrom<CryptoFuncArgs> for CryptoKey {
+    type Error = Error;
+
+    fn try_from(args: CryptoFuncArgs) -> Result<Self, Self::Error> {
+        let CryptoFuncArgs { key, key_env } = args;
+
+        let key = match (key, key_env) {
+            (Some(key), None) => key,
+            (None, Some(key_env)) => env::var(&key_env)
+                .with_context(|| format!("Missing key in the environment: {key_env}"))?,
+            (Some(_), Some(_)) => bail!("key and key_env cannot be given together"),
+            (None, None) => bail!("Either key or key_env should be given"),
+        };
+        Self::parse(key.trim())
+    }
+}
+
+impl CryptoKey {
+    /// Parses a 256-bit key, either in hex or base64.
+    fn parse(key: &str) -> Result<Self> {
+        let bytes = match decode_hex(key) {
+            Some(bytes) => bytes,
+            None => {
+                Binary::from_base64(key)
+                    .map_err(|_| anyhow!("Key should be in hex or base64"))?
+                    .0
+            }
+        };
+        let cipher = Aes256Gcm::new_from_slice(&bytes)
+            .map_err(|_| anyhow!("Key should be 32 bytes, but given {}", bytes.len()))?;
+        Ok(Self(Arc::new(cipher)))
+    }
+
+    fn encrypt(&self, content: &[u8]) -> Result<Vec<u8>> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .0
+            .encrypt(&nonce, content)
+            .map_err(|_| anyhow!("Failed to encrypt the content"))?;
+
+        let mut output = Vec::with_capacity(NONCE_LEN + ciphertext.len());
+        output.extend_from_slice(&nonce);
+        output.extend(ciphertext);
+        Ok(output)
+    }
+
+    fn decrypt(&self, content: &[u8]) -> Result<Vec<u8>> {
+        if content.len() < NONCE_LEN {
+            bail!("Encrypted content is shorter than the nonce")
+        }
+        let (nonce, ciphertext) = content.split_at(NONCE_LEN);
+        self.0
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| anyhow!("Failed to decrypt the content: tampered or wrong key"))
+    }
+}
+
+/// Decodes the hex digits, or returns `None` if any is not.
+fn decode_hex(text: &str) -> Option<Vec<u8>> {
+    if text.len() % 2 != 0 || !text.bytes().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&text[i..i + 2], 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CryptoKey;
+
+    const KEY: &str = "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f";
+
+    #[test]
+    fn test_crypto_round_trip() {
+        let key = CryptoKey::parse(KEY).unwrap();
+        let content = b"hello world! ".repeat(64);
+
+        let encrypted = key.encrypt(&content).unwrap();
+        assert_ne!(&encrypted[super::NONCE_LEN..], &content[..]);
+        // The nonces differ per call
+        assert_ne!(key.encrypt(&content).unwrap(), encrypted);
+        assert_eq!(key.decrypt(&encrypted).unwrap(), content);
+
+        // The same key in base64
+        let key = CryptoKey::parse("AAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8=").unwrap();
+        assert_eq!(key.decrypt(&encrypted).unwrap(), content);
+
+        assert!(CryptoKey::parse("0011").is_err());
+    }
+
+    #[test]
+    fn test_crypto_tampered() {
+        let key = CryptoKey::parse(KEY).unwrap();
+        let mut encrypted = key.encrypt(b"hello world!").unwrap();
+
+        let last = encrypted.len() - 1;
+        encrypted[last] ^= 1;
+        assert!(key.decrypt(&encrypted).is_err());
+        assert!(key.decrypt(&encrypted[..4]).is_err());
+    }
+}
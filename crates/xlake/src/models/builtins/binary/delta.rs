@@ -0,0 +1,297 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{Arc, Mutex},
+};
+
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use xlake_ast::{PlanArguments, PlanKind};
+use xlake_core::{
+    object::LazyObject, ArgSpec, PipeChannel, PipeEdge, PipeFunc, PipeModelOwned,
+    PipeModelOwnedExt, PipeNodeFactory, PipeNodeImpl,
+};
+
+use super::BinaryModelView;
+
+/// The field marking whether the content is a patch against the previous version.
+const DELTA: &str = "delta";
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct DeltaFactory;
+
+impl fmt::Display for DeltaFactory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.kind().fmt(f)
+    }
+}
+
+#[async_trait]
+impl PipeNodeFactory for DeltaFactory {
+    fn kind(&self) -> PlanKind {
+        PlanKind::Func {
+            model_name: super::consts::NAME.into(),
+            func: self.name(),
+        }
+    }
+
+    fn name(&self) -> String {
+        "delta".into()
+    }
+
+    fn arguments(&self) -> Vec<ArgSpec> {
+        vec![ArgSpec::required(
+            "key",
+            "Field identifying the versions of the same content",
+        )]
+    }
+
+    fn input(&self) -> PipeEdge {
+        PipeEdge {
+            model: Some(vec![super::consts::NAME.into()]),
+            ..Default::default()
+        }
+    }
+
+    fn output(&self) -> PipeEdge {
+        PipeEdge {
+            model: Some(vec![super::consts::NAME.into()]),
+            ..Default::default()
+        }
+    }
+
+    async fn build(&self, args: &PlanArguments) -> Result<PipeNodeImpl> {
+        let imp: DeltaFunc = args.to()?;
+        Ok(PipeNodeImpl::Func(Box::new(imp)))
+    }
+}
+
+/// Replaces the content with a [bsdiff](https://www.daemonology.net/bsdiff/) patch
+/// against the previous version of the same key, marking it with `delta=true`.
+///
+/// The first version of each key is kept as it is, as the base of the following patches.
+/// The latest version per key is kept in memory.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DeltaFunc {
+    key: String,
+    #[serde(skip)]
+    versions: Arc<Mutex<HashMap<String, Vec<u8>>>>,
+}
+
+impl DeltaFunc {
+    async fn diff(&self, item: LazyObject) -> Result<LazyObject> {
+        // Download the file contents
+        let item = item.flatten().await?;
+        let version = match item.get_raw(&self.key) {
+            Some(version) => version.to_string(),
+            None => return Ok(item),
+        };
+        let mut item: BinaryModelView = match item.view() {
+            Ok(item) => item,
+            Err(item) => return Ok(item),
+        };
+
+        let content = item.content();
+        let previous = self
+            .versions
+            .lock()
+            .unwrap()
+            .insert(version, content.clone());
+        let is_delta = match previous {
+            Some(previous) => {
+                let mut patch = Vec::default();
+                ::bsdiff::diff(&previous, content, &mut patch)?;
+                *content = patch;
+                true
+            }
+            None => false,
+        };
+
+        let mut item = item.__into_inner();
+        item.insert(DELTA.into(), is_delta.into());
+        Ok(item)
+    }
+}
+
+#[async_trait]
+impl PipeFunc for DeltaFunc {
+    async fn call(&self, channel: PipeChannel) -> Result<PipeChannel> {
+        let func = self.clone();
+        channel
+            .and_then(move |item| {
+                let func = func.clone();
+                async move { func.diff(item).await }
+            })
+            .await
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct ApplyDeltaFactory;
+
+impl fmt::Display for ApplyDeltaFactory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.kind().fmt(f)
+    }
+}
+
+#[async_trait]
+impl PipeNodeFactory for ApplyDeltaFactory {
+    fn kind(&self) -> PlanKind {
+        PlanKind::Func {
+            model_name: super::consts::NAME.into(),
+            func: self.name(),
+        }
+    }
+
+    fn name(&self) -> String {
+        "apply_delta".into()
+    }
+
+    fn arguments(&self) -> Vec<ArgSpec> {
+        vec![ArgSpec::required(
+            "key",
+            "Field identifying the versions of the same content",
+        )]
+    }
+
+    fn input(&self) -> PipeEdge {
+        PipeEdge {
+            model: Some(vec![super::consts::NAME.into()]),
+            ..Default::default()
+        }
+    }
+
+    fn output(&self) -> PipeEdge {
+        PipeEdge {
+            model: Some(vec![super::consts::NAME.into()]),
+            ..Default::default()
+        }
+    }
+
+    async fn build(&self, args: &PlanArguments) -> Result<PipeNodeImpl> {
+        let imp: ApplyDeltaFunc = args.to()?;
+        Ok(PipeNodeImpl::Func(Box::new(imp)))
+    }
+}
+
+/// Reconstructs the contents patched by `binary:delta`, in the same order of the versions.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ApplyDeltaFunc {
+    key: String,
+    #[serde(skip)]
+    versions: Arc<Mutex<HashMap<String, Vec<u8>>>>,
+}
+
+impl ApplyDeltaFunc {
+    async fn patch(&self, item: LazyObject) -> Result<LazyObject> {
+        // Download the file contents
+        let item = item.flatten().await?;
+        let version = match item.get_raw(&self.key) {
+            Some(version) => version.to_string(),
+            None => return Ok(item),
+        };
+        let is_delta = item.get_bool(DELTA).unwrap_or_default();
+        let mut item: BinaryModelView = match item.view() {
+            Ok(item) => item,
+            Err(item) => return Ok(item),
+        };
+
+        let content = item.content();
+        let mut versions = self.versions.lock().unwrap();
+        if is_delta {
+            let previous = match versions.get(&version) {
+                Some(previous) => previous,
+                None => bail!("Missing the base version of {}: {version}", &self.key),
+            };
+            let mut patched = Vec::default();
+            ::bsdiff::patch(previous, &mut content.as_slice(), &mut patched)?;
+            *content = patched;
+        }
+        versions.insert(version, content.clone());
+        drop(versions);
+
+        let mut item = item.__into_inner();
+        item.insert(DELTA.into(), false.into());
+        Ok(item)
+    }
+}
+
+#[async_trait]
+impl PipeFunc for ApplyDeltaFunc {
+    async fn call(&self, channel: PipeChannel) -> Result<PipeChannel> {
+        let func = self.clone();
+        channel
+            .and_then(move |item| {
+                let func = func.clone();
+                async move { func.patch(item).await }
+            })
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use xlake_ast::Binary;
+    use xlake_core::object::LazyObject;
+
+    use super::{super::BinaryModelObject, ApplyDeltaFunc, DeltaFunc};
+
+    fn version(name: &str, content: &[u8]) -> LazyObject {
+        let mut item = LazyObject::from(BinaryModelObject {
+            content: Binary(content.to_vec()),
+        });
+        item.insert("name".into(), name.into());
+        item
+    }
+
+    #[::tokio::test]
+    async fn test_delta_round_trip() {
+        let delta = DeltaFunc {
+            key: "name".into(),
+            versions: Default::default(),
+        };
+        let apply = ApplyDeltaFunc {
+            key: "name".into(),
+            versions: Default::default(),
+        };
+
+        let base = b"The quick brown fox jumps over the lazy dog.\n".repeat(32);
+        let mut new = base.clone();
+        new.splice(100..103, b"red".iter().copied());
+        new.extend_from_slice(b"The end.\n");
+
+        let versions = [
+            ("foo", base.clone(), false),
+            ("bar", b"unrelated".to_vec(), false),
+            ("foo", new.clone(), true),
+        ];
+        for (name, content, is_delta) in versions {
+            let item = delta.diff(version(name, &content)).await.unwrap();
+            assert_eq!(item.get_bool("delta"), Some(is_delta));
+            let patch = item.get_bytes("content").unwrap().to_vec();
+            if is_delta {
+                assert!(patch.len() < content.len());
+            } else {
+                assert_eq!(patch, content);
+            }
+
+            // The base and the patch reconstruct the new version
+            let item = apply.patch(item).await.unwrap();
+            assert_eq!(item.get_bytes("content").unwrap(), content);
+            assert_eq!(item.get_bool("delta"), Some(false));
+        }
+    }
+
+    #[::tokio::test]
+    async fn test_delta_missing_base() {
+        let apply = ApplyDeltaFunc {
+            key: "name".into(),
+            versions: Default::default(),
+        };
+        let mut item = version("foo", b"patch");
+        item.insert("delta".into(), true.into());
+        assert!(apply.patch(item).await.is_err());
+    }
+}
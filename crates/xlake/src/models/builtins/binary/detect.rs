@@ -0,0 +1,167 @@
+use std::fmt;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use xlake_ast::{PlanArguments, PlanKind};
+use xlake_core::{
+    object::LazyObject, PipeChannel, PipeEdge, PipeFunc, PipeModelOwned, PipeModelOwnedExt,
+    PipeNodeFactory, PipeNodeImpl,
+};
+
+use crate::models::builtins::file::FileModelView;
+
+use super::BinaryModelView;
+
+/// The content type of the binaries with unknown signatures.
+const DEFAULT_CONTENT_TYPE: &str = "application/octet-stream";
+
+/// The number of the leading bytes to sniff, covering the signatures at the far offsets.
+const HEAD_LEN: usize = 64 * 1024;
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct DetectTypeFactory;
+
+impl fmt::Display for DetectTypeFactory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.kind().fmt(f)
+    }
+}
+
+#[async_trait]
+impl PipeNodeFactory for DetectTypeFactory {
+    fn kind(&self) -> PlanKind {
+        PlanKind::Func {
+            model_name: super::consts::NAME.into(),
+            func: self.name(),
+        }
+    }
+
+    fn name(&self) -> String {
+        "detect".into()
+    }
+
+    fn input(&self) -> PipeEdge {
+        PipeEdge {
+            model: Some(vec![super::consts::NAME.into()]),
+            ..Default::default()
+        }
+    }
+
+    fn output(&self) -> PipeEdge {
+        PipeEdge {
+            model: Some(vec![super::consts::NAME.into()]),
+            ..Default::default()
+        }
+    }
+
+    fn is_per_object(&self) -> bool {
+        true
+    }
+
+    async fn build(&self, args: &PlanArguments) -> Result<PipeNodeImpl> {
+        let imp: DetectTypeFunc = args.to()?;
+        Ok(PipeNodeImpl::Func(Box::new(imp)))
+    }
+}
+
+/// Sniffs the MIME type of the binaries by their magic bytes.
+///
+/// Writes the `content_type`, and replaces the `extension` of the `file` model if known.
+#[derive(Copy, Clone, Debug, Default, Serialize, Deserialize)]
+pub struct DetectTypeFunc {}
+
+impl DetectTypeFunc {
+    async fn detect(&self, item: LazyObject) -> Result<LazyObject> {
+        if !item.has_model(super::consts::NAME) {
+            return Ok(item);
+        }
+
+        // Read the leading bytes only, leaving the download to the later funcs
+        let (mut item, head) = match item.peek("content", HEAD_LEN).await? {
+            Some(head) => (item, head),
+            None => {
+                let item = item.flatten().await?;
+                let mut item: BinaryModelView = match item.view() {
+                    Ok(item) => item,
+                    Err(item) => return Ok(item),
+                };
+                let content = item.content();
+                let head = content[..HEAD_LEN.min(content.len())].to_vec();
+                (item.__into_inner(), head)
+            }
+        };
+
+        let kind = ::infer::get(&head);
+        let content_type = kind.map_or(DEFAULT_CONTENT_TYPE, |kind| kind.mime_type());
+        item.insert("content_type".into(), content_type.into());
+
+        match kind {
+            Some(kind) => {
+                let extension = kind.extension().into();
+                Ok(FileModelView::new(item, extension).__into_inner())
+            }
+            None => Ok(item),
+        }
+    }
+}
+
+#[async_trait]
+impl PipeFunc for DetectTypeFunc {
+    async fn call(&self, channel: PipeChannel) -> Result<PipeChannel> {
+        let func = *self;
+        channel
+            .and_then(move |item| async move { func.detect(item).await })
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::TryStreamExt;
+    use xlake_ast::Binary;
+    use xlake_core::{object::LazyObject, PipeChannel, PipeFunc};
+
+    use super::{super::BinaryModelObject, DetectTypeFunc};
+
+    #[::tokio::test]
+    async fn test_detect_magic_bytes() {
+        let binary = |content: &[u8]| {
+            LazyObject::from(BinaryModelObject {
+                content: Binary(content.to_vec()),
+            })
+        };
+        let channel: PipeChannel = [
+            binary(b"%PDF-1.7\n%\xE2\xE3\xCF\xD3\n"),
+            binary(b"\x89PNG\r\n\x1A\n\0\0\0\rIHDR"),
+            binary(b"plain text"),
+        ]
+        .into_iter()
+        .collect();
+
+        let get = |item: &LazyObject, key| {
+            item.get_str(key)
+                .map(ToString::to_string)
+                .unwrap_or_default()
+        };
+        let types: Vec<_> = DetectTypeFunc::default()
+            .call(channel)
+            .await
+            .unwrap()
+            .into_stream::<LazyObject>()
+            .await
+            .unwrap()
+            .map_ok(|item| [get(&item, "content_type"), get(&item, "extension")])
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(
+            types,
+            [
+                ["application/pdf", "pdf"],
+                ["image/png", "png"],
+                ["application/octet-stream", ""],
+            ],
+        );
+    }
+}
@@ -1,4 +1,14 @@
+#[cfg(feature = "compression")]
+pub mod compress;
+#[cfg(feature = "crypto")]
+pub mod crypto;
+#[cfg(feature = "delta")]
+pub mod delta;
+#[cfg(feature = "mime-detect")]
+pub mod detect;
 pub mod pdf;
+#[cfg(feature = "encoding")]
+pub mod to_doc;
 
 use core::{borrow, fmt};
 
@@ -40,6 +50,6 @@ where
     }
 }
 
-mod consts {
-    pub(super) const NAME: &str = "binary";
+pub(crate) mod consts {
+    pub(crate) const NAME: &str = "binary";
 }
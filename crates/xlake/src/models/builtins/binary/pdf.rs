@@ -1,17 +1,16 @@
-use std::{fmt, path::PathBuf, process::Stdio};
+use std::{fmt, path::PathBuf, process::Stdio, sync::Arc};
 
 use anyhow::{bail, Context, Result};
 use async_tempfile::TempFile;
 use async_trait::async_trait;
-use futures::TryStreamExt;
 use serde::{Deserialize, Serialize};
 use tokio::{fs, io::AsyncWriteExt, process::Command};
 use tracing::debug;
 use which::which;
 use xlake_ast::{PlanArguments, PlanKind};
 use xlake_core::{
-    object::LazyObject, PipeChannel, PipeEdge, PipeFunc, PipeModelOwned, PipeModelOwnedExt,
-    PipeNodeFactory, PipeNodeImpl,
+    context::PipeContext, object::LazyObject, ArgSpec, PipeChannel, PipeEdge, PipeFunc,
+    PipeModelOwned, PipeModelOwnedExt, PipeNodeFactory, PipeNodeImpl,
 };
 
 use crate::models::builtins::file::FileModelView;
@@ -40,6 +39,11 @@ impl PipeNodeFactory for PdfFactory {
         "pdf".into()
     }
 
+    fn arguments(&self) -> Vec<ArgSpec> {
+        vec![ArgSpec::optional("prog", "LibreOffice executable")
+            .with_default(PdfFunc::default_prog().display())]
+    }
+
     fn input(&self) -> PipeEdge {
         PipeEdge {
             model: Some(vec![super::consts::NAME.into()]),
@@ -54,6 +58,10 @@ impl PipeNodeFactory for PdfFactory {
         }
     }
 
+    fn is_per_object(&self) -> bool {
+        true
+    }
+
     async fn build(&self, args: &PlanArguments) -> Result<PipeNodeImpl> {
         let mut imp: PdfFunc = args.to()?;
         imp.init().await?;
@@ -61,7 +69,7 @@ impl PipeNodeFactory for PdfFactory {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct PdfFunc {
     #[serde(default = "PdfFunc::default_prog")]
     pub prog: PathBuf,
@@ -88,7 +96,7 @@ impl PdfFunc {
         Ok(())
     }
 
-    async fn convert(&self, item: LazyObject) -> Result<LazyObject> {
+    async fn convert(&self, ctx: &PipeContext, item: LazyObject) -> Result<LazyObject> {
         // Download the file contents
         let item = item.flatten().await?;
         let mut item: BinaryModelView = match item.view() {
@@ -96,6 +104,9 @@ impl PdfFunc {
             Err(item) => return Ok(item),
         };
 
+        // Wait for a slot before spawning the process
+        let _permit = ctx.acquire().await?;
+
         // Save to a temporary file
         let mut src = TempFile::new().await?;
         src.write_all(item.content()).await?;
@@ -157,11 +168,23 @@ impl PdfFunc {
 #[async_trait]
 impl PipeFunc for PdfFunc {
     async fn call(&self, channel: PipeChannel) -> Result<PipeChannel> {
+        self.call_with_context(&PipeContext::default(), channel)
+            .await
+    }
+
+    async fn call_with_context(
+        &self,
+        ctx: &PipeContext,
+        channel: PipeChannel,
+    ) -> Result<PipeChannel> {
+        let func = Arc::new(self.clone());
+        let ctx = ctx.clone();
         channel
-            .into_stream()
-            .await?
-            .and_then(|item| self.convert(item))
-            .try_collect()
+            .and_then(move |item| {
+                let func = func.clone();
+                let ctx = ctx.clone();
+                async move { func.convert(&ctx, item).await }
+            })
             .await
     }
 }
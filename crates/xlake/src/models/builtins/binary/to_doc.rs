@@ -0,0 +1,228 @@
+use std::fmt;
+
+use anyhow::{anyhow, bail, Error, Result};
+use async_trait::async_trait;
+use chardetng::EncodingDetector;
+use encoding_rs::Encoding;
+use serde::{Deserialize, Serialize};
+use xlake_ast::{PlanArguments, PlanKind};
+use xlake_core::{
+    object::LazyObject, ArgSpec, PipeChannel, PipeEdge, PipeFunc, PipeModelOwned,
+    PipeModelOwnedExt, PipeNodeFactory, PipeNodeImpl,
+};
+
+use crate::models::builtins::doc;
+
+use super::BinaryModelView;
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct BinaryToDocFactory;
+
+impl fmt::Display for BinaryToDocFactory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.kind().fmt(f)
+    }
+}
+
+#[async_trait]
+impl PipeNodeFactory for BinaryToDocFactory {
+    fn kind(&self) -> PlanKind {
+        PlanKind::Func {
+            model_name: super::consts::NAME.into(),
+            func: self.name(),
+        }
+    }
+
+    fn name(&self) -> String {
+        "to_doc".into()
+    }
+
+    fn arguments(&self) -> Vec<ArgSpec> {
+        vec![
+            ArgSpec::optional(
+                "encoding",
+                "Text encoding label, e.g. `latin1`, or `auto` to detect",
+            )
+            .with_default("utf-8"),
+            ArgSpec::optional("on_invalid", "`error` or `replace` on invalid sequences")
+                .with_default("error"),
+        ]
+    }
+
+    fn input(&self) -> PipeEdge {
+        PipeEdge {
+            model: Some(vec![super::consts::NAME.into()]),
+            ..Default::default()
+        }
+    }
+
+    fn output(&self) -> PipeEdge {
+        PipeEdge {
+            model: Some(vec![doc::consts::NAME.into()]),
+            ..Default::default()
+        }
+    }
+
+    fn is_per_object(&self) -> bool {
+        true
+    }
+
+    async fn build(&self, args: &PlanArguments) -> Result<PipeNodeImpl> {
+        let args: BinaryToDocFuncArgs = args.to()?;
+        let imp = BinaryToDocFunc::try_from(args)?;
+        Ok(PipeNodeImpl::Func(Box::new(imp)))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BinaryToDocFuncArgs {
+    #[serde(default = "BinaryToDocFuncArgs::default_encoding")]
+    encoding: String,
+    #[serde(default)]
+    on_invalid: InvalidAction,
+}
+
+impl BinaryToDocFuncArgs {
+    fn default_encoding() -> String {
+        "utf-8".into()
+    }
+}
+
+/// Decodes the binary contents as text into the `document` field.
+///
+/// The other fields are kept as they are, e.g. the file metadata.
+#[derive(Copy, Clone, Debug)]
+pub struct BinaryToDocFunc {
+    /// The text encoding, or `None` to detect per object
+    encoding: Option<&'static Encoding>,
+    on_invalid: InvalidAction,
+}
+
+impl TryFrom<BinaryToDocFuncArgs> for BinaryToDocFunc {
+    type Error = Error;
+
+    fn try_from(args: BinaryToDocFuncArgs) -> Result<Self, Self::Error> {
+        let BinaryToDocFuncArgs {
+            encoding,
+            on_invalid,
+        } = args;
+
+        let encoding = match encoding.as_str() {
+            "auto" => None,
+            label => Some(
+                Encoding::for_label(label.as_bytes())
+                    .ok_or_else(|| anyhow!("No such encoding: '{label}'"))?,
+            ),
+        };
+        Ok(Self {
+            encoding,
+            on_invalid,
+        })
+    }
+}
+
+impl BinaryToDocFunc {
+    fn decode(&self, content: &[u8]) -> Result<String> {
+        let &Self {
+            encoding,
+            on_invalid,
+        } = self;
+
+        let encoding = encoding.unwrap_or_else(|| {
+            let mut detector = EncodingDetector::new();
+            detector.feed(content, true);
+            detector.guess(None, true)
+        });
+        let (text, had_errors) = encoding.decode_with_bom_removal(content);
+        match on_invalid {
+            InvalidAction::Error if had_errors => {
+                bail!("Invalid {} sequences", encoding.name())
+            }
+            InvalidAction::Error | InvalidAction::Replace => Ok(text.into_owned()),
+        }
+    }
+
+    async fn to_doc(&self, item: LazyObject) -> Result<LazyObject> {
+        // Download the file contents
+        let item = item.flatten().await?;
+        let mut item: BinaryModelView = match item.view() {
+            Ok(item) => item,
+            Err(item) => return Ok(item),
+        };
+
+        let document = self.decode(item.content())?;
+        let mut item = item.__into_inner();
+        item.insert("document".into(), document.into());
+        Ok(item)
+    }
+}
+
+#[async_trait]
+impl PipeFunc for BinaryToDocFunc {
+    async fn call(&self, channel: PipeChannel) -> Result<PipeChannel> {
+        let func = *self;
+        channel
+            .and_then(move |item| async move { func.to_doc(item).await })
+            .await
+    }
+}
+
+#[derive(
+    Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum InvalidAction {
+    #[default]
+    Error,
+    Replace,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BinaryToDocFunc, BinaryToDocFuncArgs, InvalidAction};
+
+    fn decode(encoding: &str, on_invalid: InvalidAction, content: &[u8]) -> Option<String> {
+        let args = BinaryToDocFuncArgs {
+            encoding: encoding.into(),
+            on_invalid,
+        };
+        BinaryToDocFunc::try_from(args)
+            .unwrap()
+            .decode(content)
+            .ok()
+    }
+
+    /// "Le café est déjà prêt à être servi." in Latin-1
+    const LATIN1: &[u8] = b"Le caf\xe9 est d\xe9j\xe0 pr\xeat \xe0 \xeatre servi.";
+    const TEXT: &str = "Le café est déjà prêt à être servi.";
+
+    #[test]
+    fn test_to_doc_utf8() {
+        let decoded = decode("utf-8", InvalidAction::Error, TEXT.as_bytes());
+        assert_eq!(decoded.as_deref(), Some(TEXT));
+        let decoded = decode("auto", InvalidAction::Error, TEXT.as_bytes());
+        assert_eq!(decoded.as_deref(), Some(TEXT));
+    }
+
+    #[test]
+    fn test_to_doc_latin1() {
+        let decoded = decode("latin1", InvalidAction::Error, LATIN1);
+        assert_eq!(decoded.as_deref(), Some(TEXT));
+        let decoded = decode("auto", InvalidAction::Error, LATIN1);
+        assert_eq!(decoded.as_deref(), Some(TEXT));
+    }
+
+    #[test]
+    fn test_to_doc_invalid() {
+        assert_eq!(decode("utf-8", InvalidAction::Error, LATIN1), None);
+
+        let decoded = decode("utf-8", InvalidAction::Replace, b"caf\xe9").unwrap();
+        assert_eq!(decoded, "caf\u{fffd}");
+
+        let args = BinaryToDocFuncArgs {
+            encoding: "unknown".into(),
+            on_invalid: InvalidAction::Error,
+        };
+        assert!(BinaryToDocFunc::try_from(args).is_err());
+    }
+}
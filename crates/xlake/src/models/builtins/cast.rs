@@ -0,0 +1,204 @@
+use std::{collections::BTreeMap, fmt};
+
+use anyhow::{anyhow, bail, Error, Result};
+use async_trait::async_trait;
+use futures::{StreamExt, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use xlake_ast::{PlanArguments, PlanKind};
+use xlake_core::{
+    models::hash::HashModelView, object::LazyObject, stream::DefaultStream, ArgSpec, PipeChannel,
+    PipeFunc, PipeModelOwned, PipeModelOwnedExt, PipeNodeFactory, PipeNodeImpl,
+};
+
+use super::{binary::BinaryModelView, doc::DocModelView, file::FileModelView};
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct CastFactory;
+
+impl fmt::Display for CastFactory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.kind().fmt(f)
+    }
+}
+
+#[async_trait]
+impl PipeNodeFactory for CastFactory {
+    fn kind(&self) -> PlanKind {
+        PlanKind::Func {
+            model_name: super::stream::consts::NAME.into(),
+            func: self.name(),
+        }
+    }
+
+    fn name(&self) -> String {
+        "cast".into()
+    }
+
+    fn arguments(&self) -> Vec<ArgSpec> {
+        vec![
+            ArgSpec::required("model", "Model name to cast into, e.g. `doc` or `binary`"),
+            ArgSpec::optional("on_fail", "`drop` or `error` on mismatched objects")
+                .with_default("drop"),
+        ]
+    }
+
+    fn is_per_object(&self) -> bool {
+        true
+    }
+
+    async fn build(&self, args: &PlanArguments) -> Result<PipeNodeImpl> {
+        let args: CastFuncArgs = args.to()?;
+        let imp = CastFunc::try_from(args)?;
+        Ok(PipeNodeImpl::Func(Box::new(imp)))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CastFuncArgs {
+    model: String,
+    #[serde(default)]
+    on_fail: CastFailAction,
+}
+
+type CastFn = fn(LazyObject) -> Result<LazyObject, LazyObject>;
+
+fn cast<V>(item: LazyObject) -> Result<LazyObject, LazyObject>
+where
+    V: PipeModelOwned<LazyObject>,
+{
+    item.view::<V>().map(PipeModelOwned::__into_inner)
+}
+
+/// Maps each model name into its cast function.
+fn registry() -> BTreeMap<&'static str, CastFn> {
+    [
+        (
+            super::binary::consts::NAME,
+            cast::<BinaryModelView> as CastFn,
+        ),
+        (super::doc::consts::NAME, cast::<DocModelView>),
+        (super::file::consts::NAME, cast::<FileModelView>),
+        (super::hash::consts::NAME, cast::<HashModelView>),
+    ]
+    .into_iter()
+    .collect()
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct CastFunc {
+    cast: CastFn,
+    on_fail: CastFailAction,
+}
+
+impl TryFrom<CastFuncArgs> for CastFunc {
+    type Error = Error;
+
+    fn try_from(args: CastFuncArgs) -> Result<Self, Self::Error> {
+        let CastFuncArgs { model, on_fail } = args;
+        let cast = registry()
+            .remove(model.as_str())
+            .ok_or_else(|| anyhow!("No such model: '{model}'"))?;
+        Ok(Self { cast, on_fail })
+    }
+}
+
+impl CastFunc {
+    async fn cast(&self, item: LazyObject) -> Result<Option<LazyObject>> {
+        let &Self { cast, on_fail } = self;
+
+        // Download the whole object
+        let item = item.flatten().await?;
+        match (cast(item), on_fail) {
+            (Ok(item), _) => Ok(Some(item)),
+            (Err(_), CastFailAction::Drop) => Ok(None),
+            (Err(item), CastFailAction::Error) => bail!("Mismatched model: {item:?}"),
+        }
+    }
+}
+
+#[async_trait]
+impl PipeFunc for CastFunc {
+    async fn call(&self, channel: PipeChannel) -> Result<PipeChannel> {
+        let func = *self;
+        let stream = channel
+            .into_stream::<LazyObject>()
+            .await?
+            .try_filter_map(move |item| async move { func.cast(item).await })
+            .boxed();
+        Ok(PipeChannel::from_stream(DefaultStream::from_stream(stream)))
+    }
+}
+
+#[derive(
+    Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum CastFailAction {
+    #[default]
+    Drop,
+    Error,
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::TryStreamExt;
+    use xlake_ast::Binary;
+    use xlake_core::{object::LazyObject, PipeChannel, PipeFunc};
+
+    use super::{
+        super::{binary::BinaryModelObject, doc::DocModelObject},
+        CastFailAction, CastFunc, CastFuncArgs,
+    };
+
+    fn channel() -> PipeChannel {
+        let doc = |document: &str| {
+            LazyObject::from(DocModelObject {
+                document: document.into(),
+            })
+        };
+        let binary = LazyObject::from(BinaryModelObject {
+            content: Binary(b"content".to_vec()),
+        });
+        [doc("foo"), binary, doc("bar")].into_iter().collect()
+    }
+
+    async fn cast(model: &str, on_fail: CastFailAction) -> anyhow::Result<Vec<LazyObject>> {
+        let func = CastFunc::try_from(CastFuncArgs {
+            model: model.into(),
+            on_fail,
+        })?;
+        func.call(channel())
+            .await?
+            .into_stream::<LazyObject>()
+            .await?
+            .try_collect()
+            .await
+    }
+
+    #[::tokio::test]
+    async fn test_cast_drop() {
+        let items = cast("doc", CastFailAction::Drop).await.unwrap();
+        let documents: Vec<_> = items
+            .iter()
+            .map(|item| item.get_raw("document").unwrap().to_string())
+            .collect();
+        assert_eq!(documents, [r#""foo""#, r#""bar""#]);
+
+        let items = cast("binary", CastFailAction::Drop).await.unwrap();
+        assert_eq!(items.len(), 1);
+    }
+
+    #[::tokio::test]
+    async fn test_cast_error() {
+        assert!(cast("doc", CastFailAction::Error).await.is_err());
+    }
+
+    #[test]
+    fn test_cast_unknown_model() {
+        let args = CastFuncArgs {
+            model: "unknown".into(),
+            on_fail: CastFailAction::Drop,
+        };
+        assert!(CastFunc::try_from(args).is_err());
+    }
+}
@@ -0,0 +1,137 @@
+use std::{fmt, iter};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use futures::TryStreamExt;
+use serde::{Deserialize, Serialize};
+use xlake_ast::{Object, PlanArguments, PlanKind, Value};
+use xlake_core::{
+    object::{LazyObject, ObjectLayer},
+    ArgSpec, PipeChannel, PipeFunc, PipeNodeFactory, PipeNodeImpl,
+};
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct CollectFactory;
+
+impl fmt::Display for CollectFactory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.kind().fmt(f)
+    }
+}
+
+#[async_trait]
+impl PipeNodeFactory for CollectFactory {
+    fn kind(&self) -> PlanKind {
+        PlanKind::Func {
+            model_name: super::stream::consts::NAME.into(),
+            func: self.name(),
+        }
+    }
+
+    fn name(&self) -> String {
+        "collect".into()
+    }
+
+    fn arguments(&self) -> Vec<ArgSpec> {
+        vec![
+            ArgSpec::optional("key", "Field to collect, or the whole objects if not given"),
+            ArgSpec::optional("target", "Array field to store the collected values")
+                .with_default("items"),
+        ]
+    }
+
+    async fn build(&self, args: &PlanArguments) -> Result<PipeNodeImpl> {
+        let imp: CollectFunc = args.to()?;
+        Ok(PipeNodeImpl::Func(Box::new(imp)))
+    }
+}
+
+/// Collects the whole stream into a single object of an array.
+///
+/// Note that all the objects are materialized in memory until the end of stream.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CollectFunc {
+    #[serde(default)]
+    key: Option<String>,
+    #[serde(default = "CollectFunc::default_target")]
+    target: String,
+}
+
+impl CollectFunc {
+    fn default_target() -> String {
+        "items".into()
+    }
+}
+
+#[async_trait]
+impl PipeFunc for CollectFunc {
+    async fn call(&self, channel: PipeChannel) -> Result<PipeChannel> {
+        let mut values = Vec::default();
+
+        // Drain the source, emitting the array at the end of stream
+        let mut iter = channel.into_stream::<LazyObject>().await?;
+        while let Some(item) = iter.try_next().await? {
+            let item = item.flatten().await?;
+            match &self.key {
+                // Skip the objects without the field
+                Some(key) => values.extend(item.get_raw(key).cloned()),
+                None => values.push(Value::Object(item.content().clone())),
+            }
+        }
+
+        let mut object = Object::default();
+        object.insert(self.target.clone(), Value::Array(values));
+        let item = ObjectLayer::from_object_dyn(object).into();
+        Ok(iter::once::<LazyObject>(item).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::TryStreamExt;
+    use xlake_ast::Value;
+    use xlake_core::{object::LazyObject, PipeFunc};
+
+    use crate::testing::{channel, object};
+
+    use super::CollectFunc;
+
+    async fn collect(key: Option<&str>) -> Vec<String> {
+        let channel = channel([Some(1), None, Some(3)].map(|index| {
+            let index = index.map(|index| ("index", Value::from(index)));
+            object(index.into_iter().chain([("name", "foo".into())]))
+        }));
+
+        let func = CollectFunc {
+            key: key.map(Into::into),
+            target: CollectFunc::default_target(),
+        };
+        func.call(channel)
+            .await
+            .unwrap()
+            .into_stream::<LazyObject>()
+            .await
+            .unwrap()
+            .map_ok(|item| item.get_raw("items").unwrap().to_string())
+            .try_collect()
+            .await
+            .unwrap()
+    }
+
+    #[::tokio::test]
+    async fn test_collect_objects() {
+        assert_eq!(
+            collect(None).await,
+            [concat!(
+                r#"[{"index": 1, "name": "foo"}, "#,
+                r#"{"name": "foo"}, "#,
+                r#"{"index": 3, "name": "foo"}]"#,
+            )],
+        );
+    }
+
+    #[::tokio::test]
+    async fn test_collect_key() {
+        assert_eq!(collect(Some("index")).await, ["[1, 3]"]);
+    }
+}
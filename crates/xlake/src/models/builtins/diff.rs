@@ -0,0 +1,216 @@
+use std::{collections::HashMap, fmt};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use futures::{future, StreamExt, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use xlake_ast::{Object, PlanArguments, PlanKind, Value};
+use xlake_core::{
+    object::{LazyObject, ObjectLayer},
+    stream::DefaultStream,
+    ArgSpec, PipeChannel, PipeFunc, PipeNodeFactory, PipeNodeImpl,
+};
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct DiffFactory;
+
+impl fmt::Display for DiffFactory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.kind().fmt(f)
+    }
+}
+
+#[async_trait]
+impl PipeNodeFactory for DiffFactory {
+    fn kind(&self) -> PlanKind {
+        PlanKind::Func {
+            model_name: super::stream::consts::NAME.into(),
+            func: self.name(),
+        }
+    }
+
+    fn name(&self) -> String {
+        "diff".into()
+    }
+
+    fn arguments(&self) -> Vec<ArgSpec> {
+        vec![ArgSpec::optional(
+            "key",
+            "Field to group the objects, diffing only within each group",
+        )]
+    }
+
+    async fn build(&self, args: &PlanArguments) -> Result<PipeNodeImpl> {
+        let imp: DiffFunc = args.to()?;
+        Ok(PipeNodeImpl::Func(Box::new(imp)))
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct DiffFunc {
+    #[serde(default)]
+    key: Option<String>,
+}
+
+#[async_trait]
+impl PipeFunc for DiffFunc {
+    async fn call(&self, channel: PipeChannel) -> Result<PipeChannel> {
+        let key = self.key.clone();
+
+        // Keep only the last object of each group
+        let mut previous: HashMap<String, Object> = HashMap::default();
+
+        let stream = channel
+            .into_stream::<LazyObject>()
+            .await?
+            .and_then(|item| item.flatten())
+            .try_filter_map(move |item| {
+                let current = item.content().clone();
+                let group = key.as_ref().map(|key| {
+                    let value = current.get(key).cloned().unwrap_or(Value::Null);
+                    (key.clone(), value)
+                });
+                let group_id = group
+                    .as_ref()
+                    .map(|(_, value)| value.to_string())
+                    .unwrap_or_default();
+
+                let diff = previous
+                    .insert(group_id, current)
+                    .map(|previous| diff(group, &previous, item.content()))
+                    .map(|object| ObjectLayer::from_object_dyn(object).into());
+                future::ready(Ok(diff))
+            })
+            .boxed();
+        Ok(PipeChannel::from_stream(DefaultStream::from_stream(stream)))
+    }
+}
+
+/// Builds `{added, removed, changed}` keyed by the fields.
+fn diff(group: Option<(String, Value)>, previous: &Object, current: &Object) -> Object {
+    let mut added = Object::default();
+    let mut changed = Object::default();
+    let mut removed = Object::default();
+
+    for (key, value) in current.iter() {
+        match previous.get(key) {
+            None => {
+                added.insert(key.clone(), value.clone());
+            }
+            Some(old) if old != value => {
+                let change = [("from", old.clone()), ("to", value.clone())]
+                    .into_iter()
+                    .map(|(key, value)| (key.into(), value))
+                    .collect();
+                changed.insert(key.clone(), Value::Object(change));
+            }
+            Some(_) => (),
+        }
+    }
+    for (key, value) in previous.iter() {
+        if current.get(key).is_none() {
+            removed.insert(key.clone(), value.clone());
+        }
+    }
+
+    let mut object: Object = [
+        ("added", Value::Object(added)),
+        ("changed", Value::Object(changed)),
+        ("removed", Value::Object(removed)),
+    ]
+    .into_iter()
+    .map(|(key, value)| (key.into(), value))
+    .collect();
+    if let Some((key, value)) = group {
+        object.insert(key, value);
+    }
+    object
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::TryStreamExt;
+    use xlake_ast::Value;
+    use xlake_core::{object::LazyObject, PipeFunc};
+
+    use crate::testing::{channel, object};
+
+    use super::DiffFunc;
+
+    async fn diff(func: DiffFunc, rows: Vec<Vec<(&str, Value)>>) -> Vec<LazyObject> {
+        let channel = channel(rows.into_iter().map(object));
+
+        func.call(channel)
+            .await
+            .unwrap()
+            .into_stream()
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap()
+    }
+
+    fn get(item: &LazyObject, key: &str) -> String {
+        item.get_raw(key).unwrap().to_string()
+    }
+
+    fn get_field(item: &LazyObject, key: &str, field: &str) -> Option<String> {
+        match item.get_raw(key).unwrap() {
+            Value::Object(object) => object.get(field).map(ToString::to_string),
+            value => panic!("unexpected value: {value}"),
+        }
+    }
+
+    #[::tokio::test]
+    async fn test_diff_evolving() {
+        let rows = vec![
+            vec![("name", Value::from("foo")), ("age", 30.into())],
+            vec![
+                ("name", "foo".into()),
+                ("age", 31.into()),
+                ("city", "Seoul".into()),
+            ],
+            vec![("name", "bar".into()), ("age", 31.into())],
+        ];
+        let items = diff(DiffFunc::default(), rows).await;
+        assert_eq!(items.len(), 2);
+
+        assert_eq!(get_field(&items[0], "added", "city").unwrap(), r#""Seoul""#);
+        assert_eq!(
+            get_field(&items[0], "changed", "age").unwrap(),
+            r#"{"from": 30, "to": 31}"#,
+        );
+        assert!(get_field(&items[0], "changed", "name").is_none());
+        assert_eq!(get(&items[0], "removed"), "{}");
+
+        assert_eq!(get(&items[1], "added"), "{}");
+        assert!(get_field(&items[1], "changed", "name").is_some());
+        assert!(get_field(&items[1], "changed", "age").is_none());
+        assert_eq!(
+            get_field(&items[1], "removed", "city").unwrap(),
+            r#""Seoul""#
+        );
+    }
+
+    #[::tokio::test]
+    async fn test_diff_key() {
+        let rows = vec![
+            vec![("id", Value::from(1)), ("value", "a".into())],
+            vec![("id", 2.into()), ("value", "x".into())],
+            vec![("id", 1.into()), ("value", "b".into())],
+        ];
+        let func = DiffFunc {
+            key: Some("id".into()),
+        };
+        let items = diff(func, rows).await;
+
+        // Only the second object of the group `1` has a predecessor
+        assert_eq!(items.len(), 1);
+        assert_eq!(get(&items[0], "id"), "1");
+        assert_eq!(
+            get_field(&items[0], "changed", "value").unwrap(),
+            r#"{"from": "a", "to": "b"}"#,
+        );
+    }
+}
@@ -0,0 +1,238 @@
+use std::fmt;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use unicode_normalization::UnicodeNormalization;
+use xlake_ast::{PlanArguments, PlanKind};
+use xlake_core::{
+    object::LazyObject, ArgSpec, PipeChannel, PipeEdge, PipeFunc, PipeModelOwned,
+    PipeModelOwnedExt, PipeNodeFactory, PipeNodeImpl,
+};
+
+use super::DocModelView;
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct CleanTextFactory;
+
+impl fmt::Display for CleanTextFactory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.kind().fmt(f)
+    }
+}
+
+#[async_trait]
+impl PipeNodeFactory for CleanTextFactory {
+    fn kind(&self) -> PlanKind {
+        PlanKind::Func {
+            model_name: super::consts::NAME.into(),
+            func: self.name(),
+        }
+    }
+
+    fn name(&self) -> String {
+        "clean".into()
+    }
+
+    fn arguments(&self) -> Vec<ArgSpec> {
+        vec![
+            ArgSpec::optional(
+                "collapse_whitespace",
+                "Collapse the runs of whitespaces, keeping a line break if any",
+            )
+            .with_default(true),
+            ArgSpec::optional(
+                "form",
+                "Unicode normalization form: `nfc`, `nfkc` or `none`",
+            )
+            .with_default("nfc"),
+            ArgSpec::optional("lowercase", "Convert into lowercase").with_default(false),
+            ArgSpec::optional(
+                "strip_control",
+                "Drop the control and zero-width characters, except whitespaces",
+            )
+            .with_default(true),
+        ]
+    }
+
+    fn input(&self) -> PipeEdge {
+        PipeEdge {
+            model: Some(vec![super::consts::NAME.into()]),
+            ..Default::default()
+        }
+    }
+
+    fn output(&self) -> PipeEdge {
+        PipeEdge {
+            model: Some(vec![super::consts::NAME.into()]),
+            ..Default::default()
+        }
+    }
+
+    fn is_per_object(&self) -> bool {
+        true
+    }
+
+    async fn build(&self, args: &PlanArguments) -> Result<PipeNodeImpl> {
+        let imp: CleanTextFunc = args.to()?;
+        Ok(PipeNodeImpl::Func(Box::new(imp)))
+    }
+}
+
+#[derive(
+    Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum NormalizationForm {
+    #[default]
+    Nfc,
+    Nfkc,
+    None,
+}
+
+/// Normalizes the documents in place, e.g. before tokenization or embedding.
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+pub struct CleanTextFunc {
+    #[serde(default = "CleanTextFunc::default_true")]
+    collapse_whitespace: bool,
+    #[serde(default)]
+    form: NormalizationForm,
+    #[serde(default)]
+    lowercase: bool,
+    #[serde(default = "CleanTextFunc::default_true")]
+    strip_control: bool,
+}
+
+impl Default for CleanTextFunc {
+    fn default() -> Self {
+        Self {
+            collapse_whitespace: true,
+            form: NormalizationForm::default(),
+            lowercase: false,
+            strip_control: true,
+        }
+    }
+}
+
+impl CleanTextFunc {
+    const fn default_true() -> bool {
+        true
+    }
+
+    fn clean(&self, text: &str) -> String {
+        let &Self {
+            collapse_whitespace,
+            form,
+            lowercase,
+            strip_control,
+        } = self;
+
+        let mut text = match form {
+            NormalizationForm::Nfc => text.nfc().collect(),
+            NormalizationForm::Nfkc => text.nfkc().collect(),
+            NormalizationForm::None => text.to_string(),
+        };
+        if strip_control {
+            text.retain(|c| c.is_whitespace() || !(c.is_control() || is_zero_width(c)));
+        }
+        if collapse_whitespace {
+            text = collapse(&text);
+        }
+        if lowercase {
+            text = text.to_lowercase();
+        }
+        text
+    }
+
+    async fn call_item(&self, item: LazyObject) -> Result<LazyObject> {
+        // Download the whole object
+        let item = item.flatten().await?;
+        let mut item: DocModelView = match item.view() {
+            Ok(item) => item,
+            Err(item) => return Ok(item),
+        };
+
+        let document = self.clean(item.document());
+        *item.document() = document;
+        Ok(item.__into_inner())
+    }
+}
+
+#[async_trait]
+impl PipeFunc for CleanTextFunc {
+    async fn call(&self, channel: PipeChannel) -> Result<PipeChannel> {
+        let func = *self;
+        channel
+            .and_then(move |item| async move { func.call_item(item).await })
+            .await
+    }
+}
+
+/// Returns whether the character is invisible but not a whitespace, e.g. `U+200B`.
+const fn is_zero_width(c: char) -> bool {
+    matches!(
+        c,
+        '\u{200B}' | '\u{200C}' | '\u{200D}' | '\u{2060}' | '\u{FEFF}' | '\u{00AD}'
+    )
+}
+
+/// Replaces each run of whitespaces with a line break if it has one, or a space otherwise.
+fn collapse(text: &str) -> String {
+    let mut buf = String::with_capacity(text.len());
+    let mut run = None;
+    for c in text.trim().chars() {
+        if c.is_whitespace() {
+            if c == '\n' || run.is_none() {
+                run = Some(if c == '\n' { '\n' } else { ' ' });
+            }
+            continue;
+        }
+        if let Some(run) = run.take() {
+            buf.push(run);
+        }
+        buf.push(c);
+    }
+    buf
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{super::DocModelObject, CleanTextFunc, NormalizationForm};
+
+    async fn clean(func: CleanTextFunc, document: &str) -> String {
+        let item = DocModelObject {
+            document: document.into(),
+        };
+        let item = func.call_item(item.into()).await.unwrap();
+        item.get_str("document").unwrap().to_string()
+    }
+
+    #[::tokio::test]
+    async fn test_clean_text() {
+        // "e" followed by a combining acute accent, and a zero-width space
+        let document = "  Cafe\u{301}\u{200B} \t au  lait\r\n\n ﬁn\u{7}  ";
+        assert_eq!(
+            clean(CleanTextFunc::default(), document).await,
+            "Caf\u{e9} au lait\nﬁn",
+        );
+
+        let func = CleanTextFunc {
+            form: NormalizationForm::Nfkc,
+            lowercase: true,
+            ..Default::default()
+        };
+        assert_eq!(clean(func, document).await, "caf\u{e9} au lait\nfin");
+    }
+
+    #[::tokio::test]
+    async fn test_clean_text_disabled() {
+        let func = CleanTextFunc {
+            collapse_whitespace: false,
+            form: NormalizationForm::None,
+            lowercase: false,
+            strip_control: false,
+        };
+        let document = " Cafe\u{301}\u{200B}  ";
+        assert_eq!(clean(func, document).await, document);
+    }
+}
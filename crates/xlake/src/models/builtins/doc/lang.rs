@@ -0,0 +1,138 @@
+use std::fmt;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use xlake_ast::{Number, PlanArguments, PlanKind, Value};
+use xlake_core::{
+    object::LazyObject, ArgSpec, PipeChannel, PipeEdge, PipeFunc, PipeModelOwned,
+    PipeModelOwnedExt, PipeNodeFactory, PipeNodeImpl,
+};
+
+use super::DocModelView;
+
+/// The ISO 639-3 code of the undetermined language.
+const UNDETERMINED: &str = "und";
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct LangDetectFactory;
+
+impl fmt::Display for LangDetectFactory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.kind().fmt(f)
+    }
+}
+
+#[async_trait]
+impl PipeNodeFactory for LangDetectFactory {
+    fn kind(&self) -> PlanKind {
+        PlanKind::Func {
+            model_name: super::consts::NAME.into(),
+            func: self.name(),
+        }
+    }
+
+    fn name(&self) -> String {
+        "lang".into()
+    }
+
+    fn arguments(&self) -> Vec<ArgSpec> {
+        vec![ArgSpec::optional(
+            "min_confidence",
+            "Confidence in [0, 1] below which the language is `und`",
+        )
+        .with_default(0)]
+    }
+
+    fn input(&self) -> PipeEdge {
+        PipeEdge {
+            model: Some(vec![super::consts::NAME.into()]),
+            ..Default::default()
+        }
+    }
+
+    fn is_per_object(&self) -> bool {
+        true
+    }
+
+    async fn build(&self, args: &PlanArguments) -> Result<PipeNodeImpl> {
+        let imp: LangDetectFunc = args.to()?;
+        Ok(PipeNodeImpl::Func(Box::new(imp)))
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, Serialize, Deserialize)]
+pub struct LangDetectFunc {
+    #[serde(default)]
+    min_confidence: f64,
+}
+
+impl LangDetectFunc {
+    async fn detect(&self, item: LazyObject) -> Result<LazyObject> {
+        let &Self { min_confidence } = self;
+
+        // Download the whole object
+        let item = item.flatten().await?;
+        let mut item: DocModelView = match item.view() {
+            Ok(item) => item,
+            Err(item) => return Ok(item),
+        };
+
+        let (lang, confidence) = match ::whatlang::detect(item.document()) {
+            Some(info) if info.confidence() >= min_confidence => {
+                (info.lang().code(), info.confidence())
+            }
+            Some(info) => (UNDETERMINED, info.confidence()),
+            None => (UNDETERMINED, 0.0),
+        };
+        let confidence = ::serde_json::Number::from_f64(confidence)
+            .map(|confidence| Value::Number(Number::Fixed(confidence)))
+            .unwrap_or(Value::Null);
+
+        let mut item = item.__into_inner();
+        item.insert("lang".into(), lang.into());
+        item.insert("lang_confidence".into(), confidence);
+        Ok(item)
+    }
+}
+
+#[async_trait]
+impl PipeFunc for LangDetectFunc {
+    async fn call(&self, channel: PipeChannel) -> Result<PipeChannel> {
+        let func = *self;
+        channel
+            .and_then(move |item| async move { func.detect(item).await })
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{super::DocModelObject, LangDetectFunc};
+
+    async fn detect(min_confidence: f64, document: &str) -> String {
+        let item = DocModelObject {
+            document: document.into(),
+        };
+        let func = LangDetectFunc { min_confidence };
+        let item = func.detect(item.into()).await.unwrap();
+        item.get_raw("lang").unwrap().to_string()
+    }
+
+    #[::tokio::test]
+    async fn test_lang_detect() {
+        let english = "The quick brown fox jumps over the lazy dog near the river bank.";
+        assert_eq!(detect(0.0, english).await, r#""eng""#);
+
+        let german = "Der schnelle braune Fuchs springt über den faulen Hund und die Katze.";
+        assert_eq!(detect(0.0, german).await, r#""deu""#);
+    }
+
+    #[::tokio::test]
+    async fn test_lang_detect_undetermined() {
+        assert_eq!(detect(0.0, "").await, r#""und""#);
+
+        let english = "The quick brown fox jumps over the lazy dog near the river bank.";
+        assert_eq!(detect(1.1, english).await, r#""und""#);
+    }
+}
@@ -1,4 +1,10 @@
+#[cfg(feature = "clean-text")]
+pub mod clean;
+#[cfg(feature = "lang-detect")]
+pub mod lang;
+pub mod parse_json;
 pub mod split;
+pub mod split_delim;
 
 use core::{borrow, fmt};
 
@@ -39,6 +45,6 @@ where
     }
 }
 
-mod consts {
-    pub(super) const NAME: &str = "doc";
+pub(crate) mod consts {
+    pub(crate) const NAME: &str = "doc";
 }
@@ -0,0 +1,169 @@
+use std::{fmt, sync::Arc};
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use xlake_ast::{Object, PlanArguments, PlanKind};
+use xlake_core::{
+    object::{LazyObject, ObjectLayer},
+    ArgSpec, PipeChannel, PipeEdge, PipeFunc, PipeModelOwned, PipeModelOwnedExt, PipeNodeFactory,
+    PipeNodeImpl,
+};
+
+use super::DocModelView;
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct ParseJsonFactory;
+
+impl fmt::Display for ParseJsonFactory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.kind().fmt(f)
+    }
+}
+
+#[async_trait]
+impl PipeNodeFactory for ParseJsonFactory {
+    fn kind(&self) -> PlanKind {
+        PlanKind::Func {
+            model_name: super::consts::NAME.into(),
+            func: self.name(),
+        }
+    }
+
+    fn name(&self) -> String {
+        "parse_json".into()
+    }
+
+    fn arguments(&self) -> Vec<ArgSpec> {
+        vec![
+            ArgSpec::optional("mode", "`merge` into or `replace` the object").with_default("merge"),
+            ArgSpec::optional("target", "Field to nest the parsed object"),
+        ]
+    }
+
+    fn input(&self) -> PipeEdge {
+        PipeEdge {
+            model: Some(vec![super::consts::NAME.into()]),
+            ..Default::default()
+        }
+    }
+
+    fn is_per_object(&self) -> bool {
+        true
+    }
+
+    async fn build(&self, args: &PlanArguments) -> Result<PipeNodeImpl> {
+        let imp: ParseJsonFunc = args.to()?;
+        Ok(PipeNodeImpl::Func(Box::new(imp)))
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ParseJsonFunc {
+    #[serde(default)]
+    mode: ParseJsonMode,
+    #[serde(default)]
+    target: Option<String>,
+}
+
+impl ParseJsonFunc {
+    async fn parse(&self, item: LazyObject) -> Result<LazyObject> {
+        let Self { mode, target } = self;
+
+        // Download the whole object
+        let item = item.flatten().await?;
+        let mut item: DocModelView = match item.view() {
+            Ok(item) => item,
+            Err(item) => return Ok(item),
+        };
+
+        let document = item.document();
+        let mut parsed = Object::from_slice(document.as_bytes()).with_context(|| {
+            const PREVIEW: usize = 64;
+            let preview: String = document.chars().take(PREVIEW).collect();
+            format!("Invalid JSON document: {preview:?}")
+        })?;
+        if let Some(target) = target {
+            parsed = Some((target.clone(), parsed.into())).into_iter().collect();
+        }
+
+        match mode {
+            ParseJsonMode::Merge => {
+                let mut item = item.__into_inner();
+                item.content_mut().append(&mut parsed);
+                Ok(item)
+            }
+            ParseJsonMode::Replace => Ok(ObjectLayer::from_object_dyn(parsed).into()),
+        }
+    }
+}
+
+#[async_trait]
+impl PipeFunc for ParseJsonFunc {
+    async fn call(&self, channel: PipeChannel) -> Result<PipeChannel> {
+        let func = Arc::new(self.clone());
+        channel
+            .and_then(move |item| {
+                let func = func.clone();
+                async move { func.parse(item).await }
+            })
+            .await
+    }
+}
+
+#[derive(
+    Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum ParseJsonMode {
+    /// Insert the parsed fields into the object, keeping the document
+    #[default]
+    Merge,
+    /// Discard the object and keep the parsed fields only
+    Replace,
+}
+
+#[cfg(test)]
+mod tests {
+    use xlake_core::object::LazyObject;
+
+    use super::{super::DocModelObject, ParseJsonFunc, ParseJsonMode};
+
+    async fn parse(func: ParseJsonFunc, document: &str) -> anyhow::Result<LazyObject> {
+        let item = DocModelObject {
+            document: document.into(),
+        };
+        func.parse(item.into()).await
+    }
+
+    fn get(item: &LazyObject, key: &str) -> Option<String> {
+        item.get_raw(key).map(ToString::to_string)
+    }
+
+    #[::tokio::test]
+    async fn test_parse_json_merge() {
+        let func = ParseJsonFunc::default();
+        let item = parse(func, r#"{"name":"foo","size":3}"#).await.unwrap();
+        assert_eq!(get(&item, "name").as_deref(), Some(r#""foo""#));
+        assert_eq!(get(&item, "size").as_deref(), Some("3"));
+        assert!(get(&item, "document").is_some());
+    }
+
+    #[::tokio::test]
+    async fn test_parse_json_replace() {
+        let func = ParseJsonFunc {
+            mode: ParseJsonMode::Replace,
+            target: Some("data".into()),
+        };
+        let item = parse(func, r#"{"name":"foo"}"#).await.unwrap();
+        assert_eq!(get(&item, "data").as_deref(), Some(r#"{"name": "foo"}"#));
+        assert!(get(&item, "document").is_none());
+    }
+
+    #[::tokio::test]
+    async fn test_parse_json_invalid() {
+        let func = ParseJsonFunc::default();
+        let error = parse(func, "not a json").await.unwrap_err();
+        assert_eq!(error.to_string(), r#"Invalid JSON document: "not a json""#);
+    }
+}
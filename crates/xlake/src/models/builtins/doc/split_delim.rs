@@ -0,0 +1,267 @@
+use std::{fmt, mem, sync::Arc};
+
+use anyhow::{bail, Error, Result};
+use async_trait::async_trait;
+use futures::{stream, StreamExt, TryStreamExt};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use xlake_ast::{PlanArguments, PlanKind};
+use xlake_core::{
+    object::LazyObject, stream::DefaultStream, ArgSpec, PipeChannel, PipeEdge, PipeFunc,
+    PipeModelOwnedExt, PipeNodeFactory, PipeNodeImpl,
+};
+
+use super::{DocModelObject, DocModelView};
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct SplitDelimFactory;
+
+impl fmt::Display for SplitDelimFactory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.kind().fmt(f)
+    }
+}
+
+#[async_trait]
+impl PipeNodeFactory for SplitDelimFactory {
+    fn kind(&self) -> PlanKind {
+        PlanKind::Func {
+            model_name: super::consts::NAME.into(),
+            func: self.name(),
+        }
+    }
+
+    fn name(&self) -> String {
+        "split_delim".into()
+    }
+
+    fn arguments(&self) -> Vec<ArgSpec> {
+        vec![
+            ArgSpec::required("delimiter", "Pattern between the records"),
+            ArgSpec::optional("regex", "Treat the delimiter as a regular expression")
+                .with_default(false),
+        ]
+    }
+
+    fn input(&self) -> PipeEdge {
+        PipeEdge {
+            model: Some(vec![super::consts::NAME.into()]),
+            ..Default::default()
+        }
+    }
+
+    fn output(&self) -> PipeEdge {
+        PipeEdge {
+            model: Some(vec![super::consts::NAME.into()]),
+            ..Default::default()
+        }
+    }
+
+    async fn build(&self, args: &PlanArguments) -> Result<PipeNodeImpl> {
+        let args: SplitDelimFuncArgs = args.to()?;
+        let imp = SplitDelimFunc::try_from(args)?;
+        Ok(PipeNodeImpl::Func(Box::new(imp)))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SplitDelimFuncArgs {
+    delimiter: String,
+    #[serde(default)]
+    regex: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct SplitDelimFunc {
+    delimiter: Arc<Delimiter>,
+}
+
+impl TryFrom<SplitDelimFuncArgs> for SplitDelimFunc {
+    type Error = Error;
+
+    fn try_from(args: SplitDelimFuncArgs) -> Result<Self, Self::Error> {
+        let SplitDelimFuncArgs { delimiter, regex } = args;
+
+        // Empty matches would never consume the buffer
+        let delimiter = if regex {
+            let regex = Regex::new(&delimiter)?;
+            if regex.is_match("") {
+                bail!("Delimiter should not match an empty string: {delimiter:?}")
+            }
+            Delimiter::Regex(regex)
+        } else {
+            if delimiter.is_empty() {
+                bail!("Delimiter should not be empty")
+            }
+            Delimiter::Literal(delimiter)
+        };
+        Ok(Self {
+            delimiter: Arc::new(delimiter),
+        })
+    }
+}
+
+#[async_trait]
+impl PipeFunc for SplitDelimFunc {
+    async fn call(&self, channel: PipeChannel) -> Result<PipeChannel> {
+        let state = SplitDelimState {
+            buffer: String::default(),
+            delimiter: self.delimiter.clone(),
+            done: false,
+            index: 0,
+            input: channel.into_stream::<LazyObject>().await?,
+        };
+        let stream = stream::try_unfold(state, |mut state| async move {
+            Ok(state.next().await?.map(|item| (item, state)))
+        })
+        .boxed();
+        Ok(PipeChannel::from_stream(DefaultStream::from_stream(stream)))
+    }
+}
+
+#[derive(Debug)]
+enum Delimiter {
+    Literal(String),
+    Regex(Regex),
+}
+
+impl Delimiter {
+    fn find(&self, text: &str) -> Option<(usize, usize)> {
+        match self {
+            Self::Literal(pattern) => text
+                .find(pattern.as_str())
+                .map(|start| (start, start + pattern.len())),
+            Self::Regex(regex) => regex.find(text).map(|m| (m.start(), m.end())),
+        }
+    }
+}
+
+struct SplitDelimState<S> {
+    buffer: String,
+    delimiter: Arc<Delimiter>,
+    done: bool,
+    index: usize,
+    input: S,
+}
+
+impl<S> SplitDelimState<S>
+where
+    S: Unpin + futures::Stream<Item = Result<LazyObject>>,
+{
+    async fn next(&mut self) -> Result<Option<LazyObject>> {
+        loop {
+            if let Some((start, end)) = self.delimiter.find(&self.buffer) {
+                let document = self.buffer[..start].to_string();
+                self.buffer.drain(..end);
+                if let Some(item) = self.record(document) {
+                    return Ok(Some(item));
+                }
+                continue;
+            }
+
+            // Emit the trailing partial record at the end of stream
+            if self.done {
+                let document = mem::take(&mut self.buffer);
+                return Ok(self.record(document));
+            }
+
+            match self.input.try_next().await? {
+                Some(item) => {
+                    let item = item.flatten().await?;
+                    match item.view::<DocModelView>() {
+                        Ok(mut item) => self.buffer.push_str(item.document()),
+                        // Pass through the other objects
+                        Err(item) => return Ok(Some(item)),
+                    }
+                }
+                None => self.done = true,
+            }
+        }
+    }
+
+    /// Wraps a record into a doc, skipping the empty ones.
+    fn record(&mut self, document: String) -> Option<LazyObject> {
+        if document.is_empty() {
+            return None;
+        }
+        let mut item = LazyObject::from(DocModelObject { document });
+        item.insert("record_index".into(), self.index.into());
+        self.index += 1;
+        Some(item)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::TryStreamExt;
+    use xlake_core::{object::LazyObject, PipeChannel, PipeFunc};
+
+    use super::{super::DocModelObject, SplitDelimFunc, SplitDelimFuncArgs};
+
+    async fn split(delimiter: &str, regex: bool, documents: &[&str]) -> Vec<(String, String)> {
+        let func = SplitDelimFunc::try_from(SplitDelimFuncArgs {
+            delimiter: delimiter.into(),
+            regex,
+        })
+        .unwrap();
+        let channel: PipeChannel = documents
+            .iter()
+            .map(|&document| {
+                LazyObject::from(DocModelObject {
+                    document: document.into(),
+                })
+            })
+            .collect();
+
+        func.call(channel)
+            .await
+            .unwrap()
+            .into_stream::<LazyObject>()
+            .await
+            .unwrap()
+            .map_ok(|item| {
+                let document = item.get_raw("document").unwrap().to_string();
+                let index = item.get_raw("record_index").unwrap().to_string();
+                (document, index)
+            })
+            .try_collect()
+            .await
+            .unwrap()
+    }
+
+    fn records(records: &[(&str, &str)]) -> Vec<(String, String)> {
+        records
+            .iter()
+            .map(|&(document, index)| (format!("{document:?}"), index.into()))
+            .collect()
+    }
+
+    #[::tokio::test]
+    async fn test_split_delim_literal() {
+        // The delimiter spans across the chunks
+        let documents = ["a1\na2\n--", "-\nb1\n---\nc1"];
+        assert_eq!(
+            split("\n---\n", false, &documents).await,
+            records(&[("a1\na2", "0"), ("b1", "1"), ("c1", "2")]),
+        );
+    }
+
+    #[::tokio::test]
+    async fn test_split_delim_regex() {
+        let documents = ["x\n-----\ny\n", "--\nz"];
+        assert_eq!(
+            split(r"\n-{2,}\n", true, &documents).await,
+            records(&[("x", "0"), ("y", "1"), ("z", "2")]),
+        );
+    }
+
+    #[test]
+    fn test_split_delim_empty() {
+        let args = |delimiter: &str, regex| SplitDelimFuncArgs {
+            delimiter: delimiter.into(),
+            regex,
+        };
+        assert!(SplitDelimFunc::try_from(args("", false)).is_err());
+        assert!(SplitDelimFunc::try_from(args("-*", true)).is_err());
+    }
+}
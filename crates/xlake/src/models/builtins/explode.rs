@@ -0,0 +1,198 @@
+use std::{fmt, mem, sync::Arc};
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use futures::{stream, StreamExt, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use xlake_ast::{PlanArguments, PlanKind, Value};
+use xlake_core::{
+    object::LazyObject, stream::DefaultStream, ArgSpec, PipeChannel, PipeFunc, PipeNodeFactory,
+    PipeNodeImpl,
+};
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct ExplodeFactory;
+
+impl fmt::Display for ExplodeFactory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.kind().fmt(f)
+    }
+}
+
+#[async_trait]
+impl PipeNodeFactory for ExplodeFactory {
+    fn kind(&self) -> PlanKind {
+        PlanKind::Func {
+            model_name: super::stream::consts::NAME.into(),
+            func: self.name(),
+        }
+    }
+
+    fn name(&self) -> String {
+        "explode".into()
+    }
+
+    fn arguments(&self) -> Vec<ArgSpec> {
+        vec![
+            ArgSpec::required("key", "Array field to be exploded"),
+            ArgSpec::optional("index", "Field to store the original position"),
+            ArgSpec::optional("keep_empty", "Keep objects having an empty array")
+                .with_default(false),
+        ]
+    }
+
+    fn is_per_object(&self) -> bool {
+        true
+    }
+
+    async fn build(&self, args: &PlanArguments) -> Result<PipeNodeImpl> {
+        let imp: ExplodeFunc = args.to()?;
+        Ok(PipeNodeImpl::Func(Box::new(imp)))
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ExplodeFunc {
+    key: String,
+    #[serde(default)]
+    index: Option<String>,
+    #[serde(default)]
+    keep_empty: bool,
+}
+
+impl ExplodeFunc {
+    async fn explode(&self, item: LazyObject) -> Result<Vec<LazyObject>> {
+        let Self {
+            key,
+            index,
+            keep_empty,
+        } = self;
+
+        // Download the whole object
+        let mut item = item.flatten().await?;
+
+        // Pass through objects without an array
+        let values = match item.get_mut_raw(key) {
+            // Leave a placeholder so that the key keeps its position
+            Some(value @ Value::Array(_)) => match mem::replace(value, Value::Null) {
+                Value::Array(values) => values,
+                _ => unreachable!(),
+            },
+            Some(_) | None => return Ok(vec![item]),
+        };
+
+        if values.is_empty() {
+            return Ok(if *keep_empty {
+                vec![item]
+            } else {
+                Vec::default()
+            });
+        }
+
+        values
+            .into_iter()
+            .enumerate()
+            .map(|(position, value)| {
+                let mut row = item.try_clone().context("Unflattened object")?;
+                row.insert(key.clone(), value);
+                if let Some(index) = index {
+                    row.insert(index.clone(), position.into());
+                }
+                Ok(row.into())
+            })
+            .collect()
+    }
+}
+
+#[async_trait]
+impl PipeFunc for ExplodeFunc {
+    async fn call(&self, channel: PipeChannel) -> Result<PipeChannel> {
+        let func = Arc::new(self.clone());
+        let stream = channel
+            .into_stream::<LazyObject>()
+            .await?
+            .and_then(move |item| {
+                let func = func.clone();
+                async move { func.explode(item).await }
+            })
+            .map_ok(|rows| stream::iter(rows.into_iter().map(Ok)))
+            .try_flatten()
+            .boxed();
+        Ok(PipeChannel::from_stream(DefaultStream::from_stream(stream)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::TryStreamExt;
+    use xlake_ast::Value;
+    use xlake_core::{object::LazyObject, PipeFunc};
+
+    use crate::testing::{channel, object};
+
+    use super::ExplodeFunc;
+
+    async fn explode(func: ExplodeFunc, values: Vec<Value>) -> Vec<[String; 3]> {
+        let channel = channel([object([
+            ("name", Value::from("foo")),
+            ("items", values.into()),
+        ])]);
+
+        let get = |item: &LazyObject, key| {
+            item.get_raw(key)
+                .map(ToString::to_string)
+                .unwrap_or_default()
+        };
+        func.call(channel)
+            .await
+            .unwrap()
+            .into_stream::<LazyObject>()
+            .await
+            .unwrap()
+            .map_ok(|item| [get(&item, "name"), get(&item, "items"), get(&item, "index")])
+            .try_collect()
+            .await
+            .unwrap()
+    }
+
+    fn func(index: Option<&str>, keep_empty: bool) -> ExplodeFunc {
+        ExplodeFunc {
+            key: "items".into(),
+            index: index.map(Into::into),
+            keep_empty,
+        }
+    }
+
+    #[::tokio::test]
+    async fn test_explode_scalars() {
+        let values = vec![1u64.into(), "bar".into()];
+        assert_eq!(
+            explode(func(Some("index"), false), values).await,
+            [[r#""foo""#, "1", "0"], [r#""foo""#, r#""bar""#, "1"],],
+        );
+    }
+
+    #[::tokio::test]
+    async fn test_explode_objects() {
+        let values = ["a", "b"]
+            .into_iter()
+            .map(|id| object([("id", id)]).into())
+            .collect();
+        assert_eq!(
+            explode(func(None, false), values).await,
+            [
+                [r#""foo""#, r#"{"id": "a"}"#, ""],
+                [r#""foo""#, r#"{"id": "b"}"#, ""],
+            ],
+        );
+    }
+
+    #[::tokio::test]
+    async fn test_explode_empty() {
+        assert!(explode(func(None, false), Vec::default()).await.is_empty());
+        assert_eq!(
+            explode(func(None, true), Vec::default()).await,
+            [[r#""foo""#, "null", ""]],
+        );
+    }
+}
@@ -9,6 +9,10 @@ pub struct FileModelObject {
     pub extension: String,
 }
 
+pub(crate) mod consts {
+    pub(crate) const NAME: &str = "file";
+}
+
 impl<T> FileModelView<T>
 where
     T: borrow::BorrowMut<LazyObject>,
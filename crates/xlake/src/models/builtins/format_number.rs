@@ -0,0 +1,217 @@
+use std::{fmt, sync::Arc};
+
+use anyhow::{Context, Error, Result};
+use async_trait::async_trait;
+use num_format::{Locale, ToFormattedString};
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+use xlake_ast::{Number, PlanArguments, PlanKind, Value};
+use xlake_core::{
+    object::LazyObject, ArgSpec, PipeChannel, PipeFunc, PipeNodeFactory, PipeNodeImpl,
+};
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct FormatNumberFactory;
+
+impl fmt::Display for FormatNumberFactory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.kind().fmt(f)
+    }
+}
+
+#[async_trait]
+impl PipeNodeFactory for FormatNumberFactory {
+    fn kind(&self) -> PlanKind {
+        PlanKind::Func {
+            model_name: super::stream::consts::NAME.into(),
+            func: self.name(),
+        }
+    }
+
+    fn name(&self) -> String {
+        "format_number".into()
+    }
+
+    fn arguments(&self) -> Vec<ArgSpec> {
+        vec![
+            ArgSpec::required("key", "Numeric field to be formatted"),
+            ArgSpec::optional("locale", "Locale of the separators, e.g. `en` or `de`")
+                .with_default("en"),
+            ArgSpec::required("target", "String field to store the formatted number"),
+        ]
+    }
+
+    fn is_per_object(&self) -> bool {
+        true
+    }
+
+    async fn build(&self, args: &PlanArguments) -> Result<PipeNodeImpl> {
+        let args: FormatNumberFuncArgs = args.to()?;
+        let imp = FormatNumberFunc::try_from(args)?;
+        Ok(PipeNodeImpl::Func(Box::new(imp)))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct FormatNumberFuncArgs {
+    key: String,
+    #[serde(default = "FormatNumberFuncArgs::default_locale")]
+    locale: String,
+    target: String,
+}
+
+impl FormatNumberFuncArgs {
+    fn default_locale() -> String {
+        Locale::en.name().into()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct FormatNumberFunc {
+    key: String,
+    locale: Locale,
+    target: String,
+}
+
+impl TryFrom<FormatNumberFuncArgs> for FormatNumberFunc {
+    type Error = Error;
+
+    fn try_from(args: FormatNumberFuncArgs) -> Result<Self, Self::Error> {
+        let FormatNumberFuncArgs {
+            key,
+            locale,
+            target,
+        } = args;
+
+        let locale =
+            Locale::from_name(&locale).with_context(|| format!("No such locale: '{locale}'"))?;
+        Ok(Self {
+            key,
+            locale,
+            target,
+        })
+    }
+}
+
+impl FormatNumberFunc {
+    async fn format(&self, item: LazyObject) -> Result<LazyObject> {
+        let Self {
+            key,
+            locale,
+            target,
+        } = self;
+
+        // Download the whole object
+        let mut item = item.flatten().await?;
+
+        let formatted = match item.get_raw(key) {
+            Some(Value::Number(number)) => format_number(number, locale),
+            Some(_) | None => None,
+        };
+        match formatted {
+            Some(formatted) => {
+                item.insert(target.clone(), Value::String(formatted));
+            }
+            None => debug!("Skipping non-numeric field: {key}"),
+        }
+        Ok(item)
+    }
+}
+
+#[async_trait]
+impl PipeFunc for FormatNumberFunc {
+    async fn call(&self, channel: PipeChannel) -> Result<PipeChannel> {
+        let func = Arc::new(self.clone());
+        channel
+            .and_then(move |item| {
+                let func = func.clone();
+                async move { func.format(item).await }
+            })
+            .await
+    }
+}
+
+/// Inserts the thousands separators into the integral part, e.g. `-1234.5` -> `-1,234.5`.
+///
+/// Returns `None` if the number cannot be formatted, e.g. `1e5`.
+fn format_number(number: &Number, locale: &Locale) -> Option<String> {
+    let text = number.to_string();
+    let (sign, text) = match text.strip_prefix('-') {
+        Some(text) => (locale.minus_sign(), text),
+        None => ("", text.as_str()),
+    };
+    let (integral, fraction) = match text.split_once('.') {
+        Some((integral, fraction)) => (integral, Some(fraction)),
+        None => (text, None),
+    };
+
+    let integral = integral.parse::<u128>().ok()?.to_formatted_string(locale);
+    match fraction {
+        Some(fraction) if fraction.bytes().all(|b| b.is_ascii_digit()) => {
+            let decimal = locale.decimal();
+            Some(format!("{sign}{integral}{decimal}{fraction}"))
+        }
+        Some(_) => None,
+        None => Some(format!("{sign}{integral}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use num_format::Locale;
+    use xlake_ast::{Number, Value};
+    use xlake_core::object::{LazyObject, ObjectLayer};
+
+    use crate::testing::object;
+
+    use super::{format_number, FormatNumberFunc, FormatNumberFuncArgs};
+
+    fn format(text: &str, locale: &Locale) -> Option<String> {
+        format_number(&Number::Dynamic(text.into()), locale)
+    }
+
+    #[test]
+    fn test_format_number_locale() {
+        assert_eq!(format("1234567", &Locale::en).unwrap(), "1,234,567");
+        assert_eq!(format("-1234.56", &Locale::en).unwrap(), "-1,234.56");
+        assert_eq!(format("1234.56", &Locale::de).unwrap(), "1.234,56");
+        assert_eq!(format("12", &Locale::en).unwrap(), "12");
+        assert!(format("1e5", &Locale::en).is_none());
+    }
+
+    #[::tokio::test]
+    async fn test_format_number_keeps_original() {
+        let func = FormatNumberFunc::try_from(FormatNumberFuncArgs {
+            key: "count".into(),
+            locale: "en".into(),
+            target: "count_text".into(),
+        })
+        .unwrap();
+
+        let item = |value: Value| {
+            LazyObject::from(ObjectLayer::from_object_dyn(object([("count", value)])))
+        };
+
+        let number = item(Value::Number(Number::Dynamic("1234567".into())));
+        let number = func.format(number).await.unwrap();
+        assert_eq!(number.get_raw("count").unwrap().to_string(), "1234567");
+        assert_eq!(
+            number.get_raw("count_text").unwrap().to_string(),
+            r#""1,234,567""#,
+        );
+
+        let string = item(Value::String("foo".into()));
+        let string = func.format(string).await.unwrap();
+        assert!(string.get_raw("count_text").is_none());
+    }
+
+    #[test]
+    fn test_format_number_unknown_locale() {
+        let args = FormatNumberFuncArgs {
+            key: "count".into(),
+            locale: "unknown".into(),
+            target: "count_text".into(),
+        };
+        assert!(FormatNumberFunc::try_from(args).is_err());
+    }
+}
@@ -0,0 +1,307 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+};
+
+use anyhow::{bail, Error, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize, Serializer};
+use xlake_ast::{PlanArguments, PlanKind, Value};
+use xlake_core::{
+    models::hash::{HashModelView, ETAG},
+    object::LazyObject,
+    ArgSpec, PipeChannel, PipeEdge, PipeFunc, PipeModelOwnedExt, PipeNodeFactory, PipeNodeImpl,
+};
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct HashObjectFactory;
+
+impl fmt::Display for HashObjectFactory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.kind().fmt(f)
+    }
+}
+
+#[async_trait]
+impl PipeNodeFactory for HashObjectFactory {
+    fn kind(&self) -> PlanKind {
+        PlanKind::Func {
+            model_name: self::consts::NAME.into(),
+            func: self.name(),
+        }
+    }
+
+    fn name(&self) -> String {
+        "object".into()
+    }
+
+    fn output(&self) -> PipeEdge {
+        PipeEdge {
+            model: Some(vec![self::consts::NAME.into()]),
+            ..Default::default()
+        }
+    }
+
+    fn is_per_object(&self) -> bool {
+        true
+    }
+
+    async fn build(&self, args: &PlanArguments) -> Result<PipeNodeImpl> {
+        let imp: HashObjectFunc = args.to()?;
+        Ok(PipeNodeImpl::Func(Box::new(imp)))
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, Serialize, Deserialize)]
+pub struct HashObjectFunc {}
+
+impl HashObjectFunc {
+    async fn hash(&self, item: LazyObject) -> Result<LazyObject> {
+        hash_fields(item, |_| true).await
+    }
+}
+
+/// Hashes the canonical JSON of the matched fields into the `hash` field.
+async fn hash_fields(item: LazyObject, filter: impl Fn(&str) -> bool) -> Result<LazyObject> {
+    // Download the whole object
+    let item = item.flatten().await?;
+
+    // Sort the keys so that the output is canonical,
+    // skipping the previous hash and etag so that rehashing is idempotent
+    let content: BTreeMap<_, _> = item
+        .content()
+        .iter()
+        .filter(|&(key, _)| key != self::consts::KEY && key != ETAG && filter(key.as_str()))
+        .map(|(key, value)| (key, Canonical(value)))
+        .collect();
+
+    let canonical = ::serde_json::to_vec(&content)?;
+    Ok(HashModelView::from_item(item, canonical).into_any())
+}
+
+/// Serializes the value with the keys of the nested objects sorted as well.
+///
+/// The objects may keep their insertion order, e.g. with the `preserve-order` feature.
+struct Canonical<'a>(&'a Value);
+
+impl Serialize for Canonical<'_> {
+    fn serialize<S>(&self, serializer: S) -> ::std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self.0 {
+            Value::Array(values) => serializer.collect_seq(values.iter().map(Canonical)),
+            Value::Object(object) => {
+                let object: BTreeMap<_, _> = object
+                    .iter()
+                    .map(|(key, value)| (key, Canonical(value)))
+                    .collect();
+                serializer.collect_map(object)
+            }
+            value => value.serialize(serializer),
+        }
+    }
+}
+
+#[async_trait]
+impl PipeFunc for HashObjectFunc {
+    async fn call(&self, channel: PipeChannel) -> Result<PipeChannel> {
+        let func = *self;
+        channel
+            .and_then(move |item| async move { func.hash(item).await })
+            .await
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct CacheKeyFactory;
+
+impl fmt::Display for CacheKeyFactory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.kind().fmt(f)
+    }
+}
+
+#[async_trait]
+impl PipeNodeFactory for CacheKeyFactory {
+    fn kind(&self) -> PlanKind {
+        PlanKind::Func {
+            model_name: self::consts::NAME.into(),
+            func: self.name(),
+        }
+    }
+
+    fn name(&self) -> String {
+        "cache_key".into()
+    }
+
+    fn arguments(&self) -> Vec<ArgSpec> {
+        vec![ArgSpec::required(
+            "keys",
+            "Comma-separated fields identifying the objects",
+        )]
+    }
+
+    fn output(&self) -> PipeEdge {
+        PipeEdge {
+            model: Some(vec![self::consts::NAME.into()]),
+            ..Default::default()
+        }
+    }
+
+    fn is_per_object(&self) -> bool {
+        true
+    }
+
+    async fn build(&self, args: &PlanArguments) -> Result<PipeNodeImpl> {
+        let args: CacheKeyFuncArgs = args.to()?;
+        let imp = CacheKeyFunc::try_from(args)?;
+        Ok(PipeNodeImpl::Func(Box::new(imp)))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CacheKeyFuncArgs {
+    keys: String,
+}
+
+/// Overrides the hash with the given fields only, e.g. ignoring a volatile timestamp.
+///
+/// The stores then dedup the objects on their identity rather than the whole contents.
+#[derive(Clone, Debug)]
+pub struct CacheKeyFunc {
+    keys: BTreeSet<String>,
+}
+
+impl TryFrom<CacheKeyFuncArgs> for CacheKeyFunc {
+    type Error = Error;
+
+    fn try_from(args: CacheKeyFuncArgs) -> Result<Self, Self::Error> {
+        let CacheKeyFuncArgs { keys } = args;
+
+        let keys: BTreeSet<_> = keys
+            .split(',')
+            .map(str::trim)
+            .filter(|key| !key.is_empty())
+            .map(Into::into)
+            .collect();
+        if keys.is_empty() {
+            bail!("keys should not be empty")
+        }
+        Ok(Self { keys })
+    }
+}
+
+impl CacheKeyFunc {
+    async fn hash(&self, item: LazyObject) -> Result<LazyObject> {
+        hash_fields(item, |key| self.keys.contains(key)).await
+    }
+}
+
+#[async_trait]
+impl PipeFunc for CacheKeyFunc {
+    async fn call(&self, channel: PipeChannel) -> Result<PipeChannel> {
+        let func = self.clone();
+        channel
+            .and_then(move |item| {
+                let func = func.clone();
+                async move { func.hash(item).await }
+            })
+            .await
+    }
+}
+
+pub(crate) mod consts {
+    pub(super) const KEY: &str = "hash";
+    pub(crate) const NAME: &str = "hash";
+}
+
+#[cfg(test)]
+mod tests {
+    use xlake_ast::{Object, Value};
+    use xlake_core::object::{LazyObject, ObjectLayer};
+
+    use crate::testing::object;
+
+    use super::{CacheKeyFunc, CacheKeyFuncArgs, HashObjectFunc};
+
+    async fn hash(fields: &[(&str, &str)]) -> String {
+        hash_object(object(fields.iter().copied())).await
+    }
+
+    async fn hash_object(object: Object) -> String {
+        let item: LazyObject = ObjectLayer::from_object_dyn(object).into();
+
+        let func = HashObjectFunc::default();
+        let item = func.hash(item).await.unwrap();
+        item.get_raw(super::consts::KEY).unwrap().to_string()
+    }
+
+    #[::tokio::test]
+    async fn test_hash_object_equal() {
+        let a = hash(&[("name", "foo"), ("value", "bar")]).await;
+        let b = hash(&[("value", "bar"), ("name", "foo")]).await;
+        assert_eq!(a, b);
+    }
+
+    #[::tokio::test]
+    async fn test_hash_object_nested_equal() {
+        let nested = |fields: &[(&str, Value)]| {
+            let inner = Value::Object(object(fields.iter().cloned()));
+            object([
+                ("items", Value::Array(vec![inner.clone()])),
+                ("meta", inner),
+            ])
+        };
+        let a = nested(&[("name", "foo".into()), ("value", 1.into())]);
+        let b = nested(&[("value", 1.into()), ("name", "foo".into())]);
+        assert_eq!(hash_object(a).await, hash_object(b).await);
+    }
+
+    #[::tokio::test]
+    async fn test_hash_object_not_equal() {
+        let a = hash(&[("name", "foo"), ("value", "bar")]).await;
+        let b = hash(&[("name", "foo"), ("value", "baz")]).await;
+        assert_ne!(a, b);
+    }
+
+    #[::tokio::test]
+    async fn test_hash_object_idempotent() {
+        let a = hash(&[("name", "foo")]).await;
+        let b = hash(&[("hash", &a), ("name", "foo")]).await;
+        assert_eq!(a, b);
+    }
+
+    #[::tokio::test]
+    async fn test_hash_object_etag() {
+        // The objects loaded from a store keep their hashes
+        let a = hash(&[("name", "foo")]).await;
+        let b = hash(&[("etag", "bar"), ("name", "foo")]).await;
+        assert_eq!(a, b);
+    }
+
+    #[::tokio::test]
+    async fn test_cache_key_ignored_field() {
+        let args = CacheKeyFuncArgs {
+            keys: "name".into(),
+        };
+        let func = CacheKeyFunc::try_from(args).unwrap();
+        let cache_key = |at: &'static str| {
+            let object = object([("name", "foo"), ("updated_at", at)]);
+            let item: LazyObject = ObjectLayer::from_object_dyn(object).into();
+            let func = func.clone();
+            async move {
+                let item = func.hash(item).await.unwrap();
+                item.get_raw(super::consts::KEY).unwrap().to_string()
+            }
+        };
+
+        let a = cache_key("2024-01-01").await;
+        let b = cache_key("2024-12-31").await;
+        assert_eq!(a, b);
+        assert_ne!(
+            a,
+            self::hash(&[("name", "foo"), ("updated_at", "2024-01-01")]).await
+        );
+    }
+}
@@ -0,0 +1,12 @@
+use serde::{Deserialize, Serialize};
+use xlake_derive::PipeModelObject;
+
+/// A stable id of the object, stamped by the src, e.g. `ndjsonsrc id=uuid`.
+#[derive(Clone, Debug, Serialize, Deserialize, PipeModelObject)]
+pub struct IdModelObject {
+    pub id: String,
+}
+
+pub(crate) mod consts {
+    pub(crate) const NAME: &str = "id";
+}
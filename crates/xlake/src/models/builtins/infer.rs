@@ -0,0 +1,345 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+    sync::Arc,
+};
+
+use anyhow::{bail, Error, Result};
+use async_trait::async_trait;
+use futures::{stream, StreamExt, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use xlake_ast::{Number, PlanArguments, PlanKind, Value};
+use xlake_core::{
+    object::LazyObject, stream::DefaultStream, ArgSpec, PipeChannel, PipeFunc, PipeNodeFactory,
+    PipeNodeImpl,
+};
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct InferTypesFactory;
+
+impl fmt::Display for InferTypesFactory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.kind().fmt(f)
+    }
+}
+
+#[async_trait]
+impl PipeNodeFactory for InferTypesFactory {
+    fn kind(&self) -> PlanKind {
+        PlanKind::Func {
+            model_name: super::stream::consts::NAME.into(),
+            func: self.name(),
+        }
+    }
+
+    fn name(&self) -> String {
+        "infer_types".into()
+    }
+
+    fn arguments(&self) -> Vec<ArgSpec> {
+        vec![
+            ArgSpec::optional(
+                "columns",
+                "Comma-separated fields to infer, or all if not given",
+            ),
+            ArgSpec::optional(
+                "leading_zero",
+                "`preserve` the numbers with leading zeros as strings, or `parse` them",
+            )
+            .with_default("preserve"),
+            ArgSpec::optional("sample", "Number of the first objects to infer the types")
+                .with_default(InferTypesFuncArgs::default_sample()),
+        ]
+    }
+
+    async fn build(&self, args: &PlanArguments) -> Result<PipeNodeImpl> {
+        let args: InferTypesFuncArgs = args.to()?;
+        let imp = InferTypesFunc::try_from(args)?;
+        Ok(PipeNodeImpl::Func(Box::new(imp)))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct InferTypesFuncArgs {
+    #[serde(default)]
+    columns: Option<String>,
+    #[serde(default)]
+    leading_zero: LeadingZeroAction,
+    #[serde(default = "InferTypesFuncArgs::default_sample")]
+    sample: usize,
+}
+
+impl InferTypesFuncArgs {
+    const fn default_sample() -> usize {
+        100
+    }
+}
+
+/// Narrows the string fields into booleans or numbers, if all the sampled values are so.
+///
+/// The first `sample` objects are buffered to infer the types.
+/// The values not matching the inferred types, e.g. after the sample, are left as strings.
+#[derive(Clone, Debug)]
+pub struct InferTypesFunc {
+    columns: BTreeSet<String>,
+    leading_zero: LeadingZeroAction,
+    sample: usize,
+}
+
+impl TryFrom<InferTypesFuncArgs> for InferTypesFunc {
+    type Error = Error;
+
+    fn try_from(args: InferTypesFuncArgs) -> Result<Self, Self::Error> {
+        let InferTypesFuncArgs {
+            columns,
+            leading_zero,
+            sample,
+        } = args;
+
+        if sample == 0 {
+            bail!("sample should be positive")
+        }
+        let columns = columns
+            .iter()
+            .flat_map(|columns| columns.split(','))
+            .map(str::trim)
+            .filter(|column| !column.is_empty())
+            .map(Into::into)
+            .collect();
+        Ok(Self {
+            columns,
+            leading_zero,
+            sample,
+        })
+    }
+}
+
+impl InferTypesFunc {
+    fn contains(&self, key: &str) -> bool {
+        self.columns.is_empty() || self.columns.contains(key)
+    }
+
+    fn detect(&self, value: &str) -> Option<InferredType> {
+        let value = value.trim();
+        if value.eq_ignore_ascii_case("true") || value.eq_ignore_ascii_case("false") {
+            return Some(InferredType::Bool);
+        }
+        if !is_numeric(value) {
+            return None;
+        }
+        if self.leading_zero == LeadingZeroAction::Preserve && has_leading_zero(value) {
+            return None;
+        }
+        if value.parse::<i64>().is_ok() {
+            Some(InferredType::Integer)
+        } else if value.parse::<f64>().is_ok_and(f64::is_finite) {
+            Some(InferredType::Float)
+        } else {
+            None
+        }
+    }
+
+    fn infer<'a>(
+        &self,
+        items: impl Iterator<Item = &'a LazyObject>,
+    ) -> BTreeMap<String, InferredType> {
+        let mut types = BTreeMap::<_, Option<InferredType>>::default();
+        for item in items {
+            for (key, value) in item.content().iter() {
+                // Skip the empty strings, which are often the missing values
+                let value = match value {
+                    Value::String(value) if value.trim().is_empty() => continue,
+                    Value::String(value) => value,
+                    _ => continue,
+                };
+                if !self.contains(key) {
+                    continue;
+                }
+
+                let detected = self.detect(value);
+                types
+                    .entry(key.clone())
+                    .and_modify(|ty| *ty = ty.zip(detected).and_then(|(a, b)| a.merge(b)))
+                    .or_insert(detected);
+            }
+        }
+        types
+            .into_iter()
+            .filter_map(|(key, ty)| Some((key, ty?)))
+            .collect()
+    }
+
+    fn apply(&self, types: &BTreeMap<String, InferredType>, mut item: LazyObject) -> LazyObject {
+        for (key, ty) in types {
+            let Some(Value::String(value)) = item.get_mut_raw(key) else {
+                continue;
+            };
+            if self.detect(value).and_then(|detected| ty.merge(detected)) != Some(*ty) {
+                continue;
+            }
+
+            let text = value.trim();
+            let parsed = match ty {
+                InferredType::Bool => Some(Value::Bool(text.eq_ignore_ascii_case("true"))),
+                InferredType::Integer => text.parse::<i64>().ok().map(Into::into),
+                InferredType::Float => text
+                    .parse()
+                    .ok()
+                    .and_then(::serde_json::Number::from_f64)
+                    .map(|value| Value::Number(Number::Fixed(value))),
+            };
+            if let Some(parsed) = parsed {
+                item.insert(key.clone(), parsed);
+            }
+        }
+        item
+    }
+}
+
+#[async_trait]
+impl PipeFunc for InferTypesFunc {
+    async fn call(&self, channel: PipeChannel) -> Result<PipeChannel> {
+        let mut iter = channel.into_stream::<LazyObject>().await?;
+
+        // Buffer the sample to infer the types
+        let mut sampled = Vec::with_capacity(self.sample);
+        while sampled.len() < self.sample {
+            match iter.try_next().await? {
+                Some(item) => sampled.push(item.flatten().await?),
+                None => break,
+            }
+        }
+        let types = self.infer(sampled.iter());
+
+        let func = Arc::new((self.clone(), types));
+        let stream = stream::iter(sampled.into_iter().map(Ok))
+            .chain(iter.and_then(|item| item.flatten()))
+            .map_ok(move |item| {
+                let (func, types) = &*func;
+                func.apply(types, item)
+            })
+            .boxed();
+        Ok(PipeChannel::from_stream(DefaultStream::from_stream(stream)))
+    }
+}
+
+#[derive(
+    Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum LeadingZeroAction {
+    Parse,
+    #[default]
+    Preserve,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum InferredType {
+    Bool,
+    Integer,
+    Float,
+}
+
+impl InferredType {
+    /// Returns the narrowest type covering both, e.g. an integer is a float.
+    fn merge(self, other: Self) -> Option<Self> {
+        match (self, other) {
+            (a, b) if a == b => Some(a),
+            (Self::Integer, Self::Float) | (Self::Float, Self::Integer) => Some(Self::Float),
+            _ => None,
+        }
+    }
+}
+
+/// Whether the text consists of a number only, rejecting e.g. `inf` or `NaN`.
+fn is_numeric(value: &str) -> bool {
+    let digits = value.strip_prefix(['+', '-']).unwrap_or(value);
+    digits.starts_with(|c: char| c.is_ascii_digit() || c == '.')
+        && digits
+            .chars()
+            .all(|c| c.is_ascii_digit() || matches!(c, '.' | 'e' | 'E' | '+' | '-'))
+}
+
+/// Whether the integral part starts with a redundant zero, e.g. `007`.
+fn has_leading_zero(value: &str) -> bool {
+    let digits = value.strip_prefix(['+', '-']).unwrap_or(value);
+    let integral = digits.split(['.', 'e', 'E']).next().unwrap_or_default();
+    integral.len() > 1 && integral.starts_with('0')
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::TryStreamExt;
+    use xlake_ast::Value;
+    use xlake_core::{object::LazyObject, PipeFunc};
+
+    use crate::testing::{channel, object};
+
+    use super::{InferTypesFunc, InferTypesFuncArgs, LeadingZeroAction};
+
+    async fn infer(leading_zero: LeadingZeroAction, sample: usize) -> Vec<Vec<String>> {
+        let rows = [
+            ["42", "true", "007", "1.5", "foo"],
+            ["7", "false", "012", "2", "bar"],
+            ["x", "true", "3", "", "baz"],
+        ];
+        let keys = ["id", "ok", "code", "score", "name"];
+        let channel = channel(
+            rows.into_iter()
+                .map(|row| object(keys.into_iter().zip(row))),
+        );
+
+        let func = InferTypesFunc::try_from(InferTypesFuncArgs {
+            columns: None,
+            leading_zero,
+            sample,
+        })
+        .unwrap();
+        func.call(channel)
+            .await
+            .unwrap()
+            .into_stream::<LazyObject>()
+            .await
+            .unwrap()
+            .map_ok(|item| {
+                keys.iter()
+                    .map(|&key| item.get_raw(key).map(Value::to_string).unwrap())
+                    .collect()
+            })
+            .try_collect()
+            .await
+            .unwrap()
+    }
+
+    fn rows(rows: &[[&str; 5]]) -> Vec<Vec<String>> {
+        rows.iter()
+            .map(|row| row.iter().map(|value| value.to_string()).collect())
+            .collect()
+    }
+
+    #[::tokio::test]
+    async fn test_infer_types_preserve() {
+        // The `id` is not a number after the sample
+        assert_eq!(
+            infer(LeadingZeroAction::Preserve, 2).await,
+            rows(&[
+                ["42", "true", r#""007""#, "1.5", r#""foo""#],
+                ["7", "false", r#""012""#, "2.0", r#""bar""#],
+                [r#""x""#, "true", r#""3""#, r#""""#, r#""baz""#],
+            ]),
+        );
+    }
+
+    #[::tokio::test]
+    async fn test_infer_types_parse() {
+        // The `id` is ambiguous within the sample
+        assert_eq!(
+            infer(LeadingZeroAction::Parse, 3).await,
+            rows(&[
+                [r#""42""#, "true", "7", "1.5", r#""foo""#],
+                [r#""7""#, "false", "12", "2.0", r#""bar""#],
+                [r#""x""#, "true", "3", r#""""#, r#""baz""#],
+            ]),
+        );
+    }
+}
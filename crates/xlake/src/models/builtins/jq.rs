@@ -0,0 +1,202 @@
+use std::{cell::RefCell, collections::HashMap, fmt, rc::Rc, sync::Arc};
+
+use anyhow::{anyhow, bail, Context, Result};
+use async_trait::async_trait;
+use futures::{stream, StreamExt, TryStreamExt};
+use jaq_interpret::{Ctx, Filter, FilterT, ParseCtx, RcIter, Val};
+use serde::{Deserialize, Serialize};
+use xlake_ast::{Object, PlanArguments, PlanKind};
+use xlake_core::{
+    object::{LazyObject, ObjectLayer},
+    stream::DefaultStream,
+    ArgSpec, PipeChannel, PipeFunc, PipeNodeFactory, PipeNodeImpl,
+};
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct JqFactory;
+
+impl fmt::Display for JqFactory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.kind().fmt(f)
+    }
+}
+
+#[async_trait]
+impl PipeNodeFactory for JqFactory {
+    fn kind(&self) -> PlanKind {
+        PlanKind::Func {
+            model_name: super::stream::consts::NAME.into(),
+            func: self.name(),
+        }
+    }
+
+    fn name(&self) -> String {
+        "jq".into()
+    }
+
+    fn arguments(&self) -> Vec<ArgSpec> {
+        vec![ArgSpec::required(
+            "program",
+            "jq program yielding the objects, e.g. `{name: .user.name}`",
+        )]
+    }
+
+    fn is_per_object(&self) -> bool {
+        true
+    }
+
+    async fn build(&self, args: &PlanArguments) -> Result<PipeNodeImpl> {
+        let imp: JqFunc = args.to()?;
+        // Fail fast on the invalid programs
+        with_filter(&imp.program, |_| Ok(()))?;
+        Ok(PipeNodeImpl::Func(Box::new(imp)))
+    }
+}
+
+thread_local! {
+    /// The compiled programs, kept per thread as they are not thread-safe.
+    static FILTERS: RefCell<HashMap<String, Rc<Filter>>> = RefCell::default();
+}
+
+fn compile(program: &str) -> Result<Filter> {
+    let mut defs = ParseCtx::new(Vec::default());
+    defs.insert_natives(::jaq_core::core());
+    defs.insert_defs(::jaq_std::std());
+
+    let (filter, errors) = ::jaq_parse::parse(program, ::jaq_parse::main());
+    let filter = match filter {
+        Some(filter) if errors.is_empty() => defs.compile(filter),
+        _ => bail!("Invalid jq program: {program:?}"),
+    };
+    if !defs.errs.is_empty() {
+        bail!("Undefined jq filters in the program: {program:?}")
+    }
+    Ok(filter)
+}
+
+/// Runs the closure with the program compiled once per thread.
+fn with_filter<R>(program: &str, f: impl FnOnce(&Filter) -> Result<R>) -> Result<R> {
+    let filter = FILTERS.with_borrow_mut(|filters| match filters.get(program) {
+        Some(filter) => Ok(filter.clone()),
+        None => {
+            let filter = Rc::new(compile(program)?);
+            filters.insert(program.into(), filter.clone());
+            Ok::<_, anyhow::Error>(filter)
+        }
+    })?;
+    f(&filter)
+}
+
+/// Replaces each object with the outputs of a jq program over its JSON representation.
+///
+/// The program may yield zero or more objects per input, e.g. `.items[]`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct JqFunc {
+    program: String,
+}
+
+impl JqFunc {
+    async fn transform(&self, item: LazyObject) -> Result<Vec<LazyObject>> {
+        // Download the whole object
+        let item = item.flatten().await?;
+        let input = item.content().to_json()?;
+
+        with_filter(&self.program, |filter| {
+            let inputs = RcIter::new(::core::iter::empty());
+            filter
+                .run((Ctx::new([], &inputs), Val::from(input)))
+                .map(|output| {
+                    let output =
+                        output.map_err(|error| anyhow!("Failed to run jq program: {error}"))?;
+                    let object = Object::from_json(output.into())
+                        .context("jq program should yield objects")?;
+                    Ok(ObjectLayer::from_object_dyn(object).into())
+                })
+                .collect()
+        })
+    }
+}
+
+#[async_trait]
+impl PipeFunc for JqFunc {
+    async fn call(&self, channel: PipeChannel) -> Result<PipeChannel> {
+        let func = Arc::new(self.clone());
+        let stream = channel
+            .into_stream::<LazyObject>()
+            .await?
+            .and_then(move |item| {
+                let func = func.clone();
+                async move { func.transform(item).await }
+            })
+            .map_ok(|rows| stream::iter(rows.into_iter().map(Ok)))
+            .try_flatten()
+            .boxed();
+        Ok(PipeChannel::from_stream(DefaultStream::from_stream(stream)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::TryStreamExt;
+    use xlake_ast::Object;
+    use xlake_core::{object::LazyObject, PipeFunc};
+
+    use crate::testing::channel;
+
+    use super::{compile, JqFunc};
+
+    async fn jq(program: &str) -> anyhow::Result<Vec<String>> {
+        let channel = channel(
+            [
+                r#"{"name":"foo","age":20,"tags":["a","b"]}"#,
+                r#"{"name":"bar","age":10,"tags":[]}"#,
+            ]
+            .map(|json| Object::from_slice(json.as_bytes()).unwrap()),
+        );
+
+        let func = JqFunc {
+            program: program.into(),
+        };
+        func.call(channel)
+            .await?
+            .into_stream::<LazyObject>()
+            .await?
+            .and_then(|item| async move { item.content().to_string() })
+            .try_collect()
+            .await
+    }
+
+    #[::tokio::test]
+    async fn test_jq_select() {
+        assert_eq!(
+            jq("select(.age >= 18) | {name}").await.unwrap(),
+            [r#"{"name":"foo"}"#],
+        );
+    }
+
+    #[::tokio::test]
+    async fn test_jq_construct() {
+        assert_eq!(
+            jq("{user: .name, adult: (.age >= 18)}").await.unwrap(),
+            [
+                r#"{"adult":true,"user":"foo"}"#,
+                r#"{"adult":false,"user":"bar"}"#,
+            ],
+        );
+    }
+
+    #[::tokio::test]
+    async fn test_jq_multi_output() {
+        assert_eq!(
+            jq("{name} + (.tags[] | {tag: .})").await.unwrap(),
+            [r#"{"name":"foo","tag":"a"}"#, r#"{"name":"foo","tag":"b"}"#],
+        );
+    }
+
+    #[::tokio::test]
+    async fn test_jq_invalid() {
+        assert!(compile("{name").is_err());
+        assert!(compile("undefined_filter").is_err());
+        assert!(jq(".name").await.is_err());
+    }
+}
@@ -0,0 +1,276 @@
+use std::{fmt, path::PathBuf, sync::Arc};
+
+use anyhow::{anyhow, bail, Context, Result};
+use async_trait::async_trait;
+use futures::{StreamExt, TryStreamExt};
+use jsonschema::Validator;
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+use xlake_ast::{PlanArguments, PlanKind, Value};
+use xlake_core::{
+    object::LazyObject, stream::DefaultStream, ArgSpec, PipeChannel, PipeFunc, PipeNodeFactory,
+    PipeNodeImpl,
+};
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct JsonSchemaFactory;
+
+impl fmt::Display for JsonSchemaFactory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.kind().fmt(f)
+    }
+}
+
+#[async_trait]
+impl PipeNodeFactory for JsonSchemaFactory {
+    fn kind(&self) -> PlanKind {
+        PlanKind::Func {
+            model_name: super::stream::consts::NAME.into(),
+            func: self.name(),
+        }
+    }
+
+    fn name(&self) -> String {
+        "json_schema".into()
+    }
+
+    fn arguments(&self) -> Vec<ArgSpec> {
+        vec![
+            ArgSpec::optional(
+                "annotate",
+                "Attach the validation errors to the kept invalid objects",
+            )
+            .with_default(false),
+            ArgSpec::optional("on_invalid", "`fail`, `drop` or `keep` the invalid objects")
+                .with_default("fail"),
+            ArgSpec::required("schema", "Path of the JSON Schema file"),
+            ArgSpec::optional("target", "Field to store the validation errors")
+                .with_default(JsonSchemaFuncArgs::default_target()),
+        ]
+    }
+
+    fn is_per_object(&self) -> bool {
+        true
+    }
+
+    async fn build(&self, args: &PlanArguments) -> Result<PipeNodeImpl> {
+        let args: JsonSchemaFuncArgs = args.to()?;
+        let schema = fs::read(&args.schema)
+            .await
+            .with_context(|| format!("Failed to read JSON Schema: {}", args.schema.display()))?;
+        let schema = ::serde_json::from_slice(&schema)
+            .with_context(|| format!("Invalid JSON Schema: {}", args.schema.display()))?;
+        let imp = JsonSchemaFunc::new(args, &schema)?;
+        Ok(PipeNodeImpl::Func(Box::new(imp)))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct JsonSchemaFuncArgs {
+    #[serde(default)]
+    annotate: bool,
+    #[serde(default)]
+    on_invalid: OnInvalid,
+    schema: PathBuf,
+    #[serde(default = "JsonSchemaFuncArgs::default_target")]
+    target: String,
+}
+
+impl JsonSchemaFuncArgs {
+    fn default_target() -> String {
+        "schema_errors".into()
+    }
+}
+
+/// Validates each object against a [JSON Schema](https://json-schema.org/), compiled once.
+#[derive(Clone)]
+pub struct JsonSchemaFunc {
+    annotate: bool,
+    on_invalid: OnInvalid,
+    target: String,
+    validator: Arc<Validator>,
+}
+
+impl fmt::Debug for JsonSchemaFunc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JsonSchemaFunc")
+            .field("annotate", &self.annotate)
+            .field("on_invalid", &self.on_invalid)
+            .field("target", &self.target)
+            .finish_non_exhaustive()
+    }
+}
+
+impl JsonSchemaFunc {
+    fn new(args: JsonSchemaFuncArgs, schema: &::serde_json::Value) -> Result<Self> {
+        let JsonSchemaFuncArgs {
+            annotate,
+            on_invalid,
+            schema: _,
+            target,
+        } = args;
+
+        if annotate && on_invalid != OnInvalid::Keep {
+            bail!("annotate requires on_invalid=keep")
+        }
+        let validator = ::jsonschema::validator_for(schema)
+            .map_err(|error| anyhow!("Invalid JSON Schema: {error}"))?;
+        Ok(Self {
+            annotate,
+            on_invalid,
+            target,
+            validator: Arc::new(validator),
+        })
+    }
+
+    async fn validate(&self, item: LazyObject) -> Result<Option<LazyObject>> {
+        // Download the whole object
+        let mut item = item.flatten().await?;
+        let instance = item.content().to_json()?;
+
+        let errors: Vec<String> = self
+            .validator
+            .iter_errors(&instance)
+            .map(|error| {
+                let path = error.instance_path.to_string();
+                if path.is_empty() {
+                    error.to_string()
+                } else {
+                    format!("{path}: {error}")
+                }
+            })
+            .collect();
+        if errors.is_empty() {
+            return Ok(Some(item));
+        }
+
+        match self.on_invalid {
+            OnInvalid::Drop => Ok(None),
+            OnInvalid::Fail => bail!("Invalid object: {}", errors.join(", ")),
+            OnInvalid::Keep => {
+                if self.annotate {
+                    let errors = errors.into_iter().map(Value::String).collect::<Vec<_>>();
+                    item.insert(self.target.clone(), errors.into());
+                }
+                Ok(Some(item))
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl PipeFunc for JsonSchemaFunc {
+    async fn call(&self, channel: PipeChannel) -> Result<PipeChannel> {
+        let func = Arc::new(self.clone());
+        let stream = channel
+            .into_stream::<LazyObject>()
+            .await?
+            .try_filter_map(move |item| {
+                let func = func.clone();
+                async move { func.validate(item).await }
+            })
+            .boxed();
+        Ok(PipeChannel::from_stream(DefaultStream::from_stream(stream)))
+    }
+}
+
+#[derive(
+    Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum OnInvalid {
+    Drop,
+    #[default]
+    Fail,
+    Keep,
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::TryStreamExt;
+    use serde_json::json;
+    use xlake_ast::{Object, Value};
+    use xlake_core::{object::LazyObject, PipeFunc};
+
+    use crate::testing::channel;
+
+    use super::{JsonSchemaFunc, JsonSchemaFuncArgs, OnInvalid};
+
+    async fn validate(on_invalid: OnInvalid, annotate: bool) -> anyhow::Result<Vec<LazyObject>> {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "name": {"type": "string"},
+                "age": {"type": "integer", "minimum": 0},
+            },
+            "required": ["name"],
+        });
+        let args = JsonSchemaFuncArgs {
+            annotate,
+            on_invalid,
+            schema: Default::default(),
+            target: JsonSchemaFuncArgs::default_target(),
+        };
+        let func = JsonSchemaFunc::new(args, &schema)?;
+
+        let channel = channel(
+            [
+                json!({"name": "foo", "age": 20}),
+                json!({"age": 10}),
+                json!({"name": "bar", "age": -1}),
+                json!({"name": "baz"}),
+            ]
+            .map(|object| Object::from_json(object).unwrap()),
+        );
+
+        func.call(channel)
+            .await?
+            .into_stream::<LazyObject>()
+            .await?
+            .try_collect()
+            .await
+    }
+
+    fn errors(item: &LazyObject) -> Vec<String> {
+        match item.get_raw("schema_errors") {
+            Some(Value::Array(errors)) => errors
+                .iter()
+                .map(|error| error.as_str().unwrap().into())
+                .collect(),
+            _ => Vec::default(),
+        }
+    }
+
+    #[::tokio::test]
+    async fn test_json_schema_drop() {
+        let items = validate(OnInvalid::Drop, false).await.unwrap();
+        let names: Vec<_> = items.iter().map(|item| item.get_str("name")).collect();
+        assert_eq!(names, [Some("foo"), Some("baz")]);
+    }
+
+    #[::tokio::test]
+    async fn test_json_schema_fail() {
+        let error = validate(OnInvalid::Fail, false).await.unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("\"name\" is a required property"));
+    }
+
+    #[::tokio::test]
+    async fn test_json_schema_annotate() {
+        let items = validate(OnInvalid::Keep, true).await.unwrap();
+        let errors: Vec<_> = items.iter().map(errors).collect();
+        assert_eq!(
+            errors,
+            [
+                vec![],
+                vec!["\"name\" is a required property".to_string()],
+                vec!["/age: -1 is less than the minimum of 0".to_string()],
+                vec![],
+            ],
+        );
+
+        // The annotations are kept only along with the objects
+        assert!(validate(OnInvalid::Drop, true).await.is_err());
+    }
+}
@@ -0,0 +1,291 @@
+use std::{collections::VecDeque, fmt, sync::Arc, time::Duration};
+
+use anyhow::{bail, Error, Result};
+use async_trait::async_trait;
+use futures::{stream, StreamExt, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use tokio::time::{timeout_at, Instant};
+use xlake_ast::{PlanArguments, PlanKind};
+use xlake_core::{
+    batch::{BatchFormatArgs, DataFusionBatch, DEFAULT_TABLE_REF},
+    object::LazyObject,
+    stream::DefaultStream,
+    ArgSpec, PipeChannel, PipeFunc, PipeNodeFactory, PipeNodeImpl,
+};
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct MicroBatchFactory;
+
+impl fmt::Display for MicroBatchFactory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.kind().fmt(f)
+    }
+}
+
+#[async_trait]
+impl PipeNodeFactory for MicroBatchFactory {
+    fn kind(&self) -> PlanKind {
+        PlanKind::Func {
+            model_name: super::stream::consts::NAME.into(),
+            func: self.name(),
+        }
+    }
+
+    fn name(&self) -> String {
+        "micro_batch".into()
+    }
+
+    fn arguments(&self) -> Vec<ArgSpec> {
+        vec![
+            ArgSpec::required("query", "SQL query over each micro-batch"),
+            ArgSpec::optional("size", "Maximum number of the objects per micro-batch")
+                .with_default(MicroBatchFuncArgs::default_size()),
+            ArgSpec::optional("table", "Name of the table of each micro-batch")
+                .with_default(DEFAULT_TABLE_REF),
+            ArgSpec::optional(
+                "timeout",
+                "Maximum delay after the first object of a micro-batch in milliseconds",
+            )
+            .with_default(MicroBatchFuncArgs::default_timeout()),
+        ]
+    }
+
+    async fn build(&self, args: &PlanArguments) -> Result<PipeNodeImpl> {
+        let args: MicroBatchFuncArgs = args.to()?;
+        let imp = MicroBatchFunc::try_from(args)?;
+        Ok(PipeNodeImpl::Func(Box::new(imp)))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct MicroBatchFuncArgs {
+    query: String,
+    #[serde(default = "MicroBatchFuncArgs::default_size")]
+    size: usize,
+    #[serde(default = "MicroBatchFuncArgs::default_table")]
+    table: String,
+    #[serde(default = "MicroBatchFuncArgs::default_timeout")]
+    timeout: u64,
+}
+
+impl MicroBatchFuncArgs {
+    const fn default_size() -> usize {
+        1000
+    }
+
+    fn default_table() -> String {
+        DEFAULT_TABLE_REF.into()
+    }
+
+    const fn default_timeout() -> u64 {
+        1000
+    }
+}
+
+/// Runs a SQL query over each micro-batch of the input objects, e.g. over an unbounded src.
+///
+/// A micro-batch is closed once it has `size` objects, or `timeout` has passed
+/// since its first object, so that the results follow the input closely.
+/// Note that `default` is a reserved word, so the default table should be quoted in the query.
+#[derive(Clone, Debug)]
+pub struct MicroBatchFunc {
+    query: String,
+    size: usize,
+    table: String,
+    timeout: Duration,
+}
+
+impl TryFrom<MicroBatchFuncArgs> for MicroBatchFunc {
+    type Error = Error;
+
+    fn try_from(args: MicroBatchFuncArgs) -> Result<Self, Self::Error> {
+        let MicroBatchFuncArgs {
+            query,
+            size,
+            table,
+            timeout,
+        } = args;
+
+        if size == 0 {
+            bail!("size should be positive")
+        }
+        if timeout == 0 {
+            bail!("timeout should be positive")
+        }
+        Ok(Self {
+            query,
+            size,
+            table,
+            timeout: Duration::from_millis(timeout),
+        })
+    }
+}
+
+impl MicroBatchFunc {
+    async fn query(&self, rows: &[LazyObject]) -> Result<Vec<LazyObject>> {
+        let rows: Vec<_> = rows.iter().map(|item| item.content().clone()).collect();
+
+        let batch = DataFusionBatch::new(BatchFormatArgs {
+            table: self.table.clone(),
+            ..Default::default()
+        })?;
+        batch.register_rows(&rows)?;
+        batch.query(&self.query).await?;
+
+        PipeChannel::from_batch_streamed(batch)
+            .await?
+            .into_stream::<LazyObject>()
+            .await?
+            .try_collect()
+            .await
+    }
+}
+
+#[async_trait]
+impl PipeFunc for MicroBatchFunc {
+    async fn call(&self, channel: PipeChannel) -> Result<PipeChannel> {
+        let state = MicroBatchState {
+            done: false,
+            func: Arc::new(self.clone()),
+            input: channel.into_stream::<LazyObject>().await?,
+            ready: VecDeque::default(),
+        };
+        let stream = stream::try_unfold(state, |mut state| async move {
+            Ok(state.next().await?.map(|item| (item, state)))
+        })
+        .boxed();
+        Ok(PipeChannel::from_stream(DefaultStream::from_stream(stream)))
+    }
+}
+
+struct MicroBatchState<S> {
+    done: bool,
+    func: Arc<MicroBatchFunc>,
+    input: S,
+    /// The query results of the last micro-batch to be emitted
+    ready: VecDeque<LazyObject>,
+}
+
+impl<S> MicroBatchState<S>
+where
+    S: Unpin + futures::Stream<Item = Result<LazyObject>>,
+{
+    async fn next(&mut self) -> Result<Option<LazyObject>> {
+        loop {
+            if let Some(item) = self.ready.pop_front() {
+                return Ok(Some(item));
+            }
+            if self.done {
+                return Ok(None);
+            }
+
+            let rows = self.collect().await?;
+            if !rows.is_empty() {
+                self.ready.extend(self.func.query(&rows).await?);
+            }
+        }
+    }
+
+    /// Collects the next micro-batch, waiting for its first object without a deadline.
+    async fn collect(&mut self) -> Result<Vec<LazyObject>> {
+        let mut rows = Vec::default();
+        let mut deadline = None;
+        while rows.len() < self.func.size {
+            let next = self.input.try_next();
+            let item = match deadline {
+                Some(deadline) => match timeout_at(deadline, next).await {
+                    Ok(item) => item?,
+                    // Close the micro-batch, keeping the pending object in the input
+                    Err(_) => break,
+                },
+                None => next.await?,
+            };
+            match item {
+                Some(item) => {
+                    rows.push(item.flatten().await?);
+                    deadline.get_or_insert_with(|| Instant::now() + self.func.timeout);
+                }
+                None => {
+                    self.done = true;
+                    break;
+                }
+            }
+        }
+        Ok(rows)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use futures::{stream, StreamExt, TryStreamExt};
+    use xlake_core::{
+        object::{LazyObject, ObjectLayer},
+        stream::DefaultStream,
+        PipeChannel, PipeFunc,
+    };
+
+    use crate::testing::object;
+
+    use super::{MicroBatchFunc, MicroBatchFuncArgs};
+
+    /// Emits the values, pausing for a while before each of the `pauses`.
+    async fn micro_batch(size: usize, values: &[i64], pauses: &[usize]) -> Vec<(i64, i64)> {
+        let pauses = pauses.to_vec();
+        let values = values.to_vec();
+        let stream = stream::iter(values.into_iter().enumerate())
+            .then(move |(index, value)| {
+                let pause = pauses.contains(&index);
+                async move {
+                    if pause {
+                        ::tokio::time::sleep(Duration::from_millis(200)).await;
+                    }
+                    let object = object([("value", value)]);
+                    Ok(ObjectLayer::from_object_dyn(object).into())
+                }
+            })
+            .boxed();
+        let channel = PipeChannel::from_stream(DefaultStream::from_stream(stream));
+
+        let func = MicroBatchFunc::try_from(MicroBatchFuncArgs {
+            query: r#"SELECT COUNT(*) AS count, SUM(value) AS total FROM "default""#.into(),
+            size,
+            table: MicroBatchFuncArgs::default_table(),
+            timeout: 50,
+        })
+        .unwrap();
+        func.call(channel)
+            .await
+            .unwrap()
+            .into_stream::<LazyObject>()
+            .await
+            .unwrap()
+            .map_ok(|item| {
+                let count = item.get_i64("count").unwrap();
+                (count, item.get_i64("total").unwrap())
+            })
+            .try_collect()
+            .await
+            .unwrap()
+    }
+
+    #[::tokio::test]
+    async fn test_micro_batch_size() {
+        let values: Vec<_> = (1..=10).collect();
+        assert_eq!(
+            micro_batch(4, &values, &[]).await,
+            [(4, 10), (4, 26), (2, 19)],
+        );
+    }
+
+    #[::tokio::test]
+    async fn test_micro_batch_timeout() {
+        // The pauses close the micro-batches before they are full
+        let values: Vec<_> = (1..=10).collect();
+        assert_eq!(
+            micro_batch(100, &values, &[3, 5]).await,
+            [(3, 6), (2, 9), (5, 40)],
+        );
+    }
+}
@@ -1,5 +1,34 @@
+pub mod approx;
+pub mod barrier;
 pub mod batch;
+pub mod batch_group;
 pub mod binary;
+pub mod cast;
+pub mod collect;
+pub mod diff;
 pub mod doc;
+pub mod explode;
 pub mod file;
+pub mod format_number;
+pub mod hash;
+pub mod id;
+pub mod infer;
+#[cfg(feature = "jq")]
+pub mod jq;
+#[cfg(feature = "json-schema")]
+pub mod jsonschema;
+pub mod micro_batch;
+pub mod pick;
+pub mod presence;
+pub mod profile;
+pub mod redact;
+pub mod route;
+pub mod seqid;
+pub mod size;
+pub mod sleep;
+pub mod sort;
 pub mod stream;
+pub mod to_batch;
+pub mod topn;
+pub mod unwrap;
+pub mod window_agg;
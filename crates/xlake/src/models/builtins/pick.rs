@@ -0,0 +1,120 @@
+use std::fmt;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use futures::TryStreamExt;
+use serde::{Deserialize, Serialize};
+use xlake_ast::{PlanArguments, PlanKind};
+use xlake_core::{
+    object::LazyObject, ArgSpec, PipeChannel, PipeFunc, PipeNodeFactory, PipeNodeImpl,
+};
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct PickFactory;
+
+impl fmt::Display for PickFactory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.kind().fmt(f)
+    }
+}
+
+#[async_trait]
+impl PipeNodeFactory for PickFactory {
+    fn kind(&self) -> PlanKind {
+        PlanKind::Func {
+            model_name: super::stream::consts::NAME.into(),
+            func: self.name(),
+        }
+    }
+
+    fn name(&self) -> String {
+        "pick".into()
+    }
+
+    fn arguments(&self) -> Vec<ArgSpec> {
+        vec![ArgSpec::optional("which", "`first` or `last`").with_default("first")]
+    }
+
+    async fn build(&self, args: &PlanArguments) -> Result<PipeNodeImpl> {
+        let imp: PickFunc = args.to()?;
+        Ok(PipeNodeImpl::Func(Box::new(imp)))
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PickFunc {
+    #[serde(default)]
+    which: PickType,
+}
+
+#[async_trait]
+impl PipeFunc for PickFunc {
+    async fn call(&self, channel: PipeChannel) -> Result<PipeChannel> {
+        let mut iter = channel.into_stream::<LazyObject>().await?;
+        let item = match self.which {
+            // Stop polling the source as soon as the first item arrives
+            PickType::First => iter.try_next().await?,
+            // Drain the source, keeping only the final item
+            PickType::Last => {
+                let mut last = None;
+                while let Some(item) = iter.try_next().await? {
+                    last = Some(item);
+                }
+                last
+            }
+        };
+        Ok(item.into_iter().collect())
+    }
+}
+
+#[derive(
+    Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum PickType {
+    #[default]
+    First,
+    Last,
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::TryStreamExt;
+    use xlake_core::{object::LazyObject, PipeFunc};
+
+    use crate::testing::{channel, object};
+
+    use super::{PickFunc, PickType};
+
+    async fn pick(which: PickType, len: usize) -> Vec<String> {
+        let channel = channel((0..len).map(|index| object([("index", index)])));
+
+        let func = PickFunc { which };
+        func.call(channel)
+            .await
+            .unwrap()
+            .into_stream::<LazyObject>()
+            .await
+            .unwrap()
+            .map_ok(|item| item.get_raw("index").unwrap().to_string())
+            .try_collect()
+            .await
+            .unwrap()
+    }
+
+    #[::tokio::test]
+    async fn test_pick_first() {
+        assert_eq!(pick(PickType::First, 3).await, ["0"]);
+    }
+
+    #[::tokio::test]
+    async fn test_pick_last() {
+        assert_eq!(pick(PickType::Last, 3).await, ["2"]);
+    }
+
+    #[::tokio::test]
+    async fn test_pick_empty() {
+        assert!(pick(PickType::First, 0).await.is_empty());
+        assert!(pick(PickType::Last, 0).await.is_empty());
+    }
+}
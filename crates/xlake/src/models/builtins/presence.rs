@@ -0,0 +1,162 @@
+use std::{fmt, sync::Arc};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use futures::{StreamExt, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use xlake_ast::{PlanArguments, PlanKind, Value};
+use xlake_core::{
+    object::LazyObject, stream::DefaultStream, ArgSpec, PipeChannel, PipeFunc, PipeNodeFactory,
+    PipeNodeImpl,
+};
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct PresenceFactory;
+
+impl fmt::Display for PresenceFactory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.kind().fmt(f)
+    }
+}
+
+#[async_trait]
+impl PipeNodeFactory for PresenceFactory {
+    fn kind(&self) -> PlanKind {
+        PlanKind::Func {
+            model_name: super::stream::consts::NAME.into(),
+            func: self.name(),
+        }
+    }
+
+    fn name(&self) -> String {
+        "presence".into()
+    }
+
+    fn arguments(&self) -> Vec<ArgSpec> {
+        vec![
+            ArgSpec::required("key", "Field to test the presence"),
+            ArgSpec::optional("mode", "`has` or `missing` to keep the objects").with_default("has"),
+            ArgSpec::optional("null_is_missing", "Treat the null values as missing")
+                .with_default(false),
+        ]
+    }
+
+    fn is_per_object(&self) -> bool {
+        true
+    }
+
+    async fn build(&self, args: &PlanArguments) -> Result<PipeNodeImpl> {
+        let imp: PresenceFunc = args.to()?;
+        Ok(PipeNodeImpl::Func(Box::new(imp)))
+    }
+}
+
+/// Keeps the objects having the field, or the ones missing it.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PresenceFunc {
+    key: String,
+    #[serde(default)]
+    mode: PresenceMode,
+    #[serde(default)]
+    null_is_missing: bool,
+}
+
+impl PresenceFunc {
+    async fn filter(&self, item: LazyObject) -> Result<Option<LazyObject>> {
+        let Self {
+            key,
+            mode,
+            null_is_missing,
+        } = self;
+
+        // Download the whole object
+        let item = item.flatten().await?;
+        let has = match item.get_raw(key) {
+            Some(Value::Null) => !null_is_missing,
+            Some(_) => true,
+            None => false,
+        };
+        let keep = match mode {
+            PresenceMode::Has => has,
+            PresenceMode::Missing => !has,
+        };
+        Ok(keep.then_some(item))
+    }
+}
+
+#[async_trait]
+impl PipeFunc for PresenceFunc {
+    async fn call(&self, channel: PipeChannel) -> Result<PipeChannel> {
+        let func = Arc::new(self.clone());
+        let stream = channel
+            .into_stream::<LazyObject>()
+            .await?
+            .try_filter_map(move |item| {
+                let func = func.clone();
+                async move { func.filter(item).await }
+            })
+            .boxed();
+        Ok(PipeChannel::from_stream(DefaultStream::from_stream(stream)))
+    }
+}
+
+#[derive(
+    Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum PresenceMode {
+    #[default]
+    Has,
+    Missing,
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::TryStreamExt;
+    use xlake_ast::Value;
+    use xlake_core::{object::LazyObject, PipeFunc};
+
+    use crate::testing::{channel, object};
+
+    use super::{PresenceFunc, PresenceMode};
+
+    async fn filter(mode: PresenceMode, null_is_missing: bool) -> Vec<String> {
+        let rows = [
+            ("a", Some("x".into())),
+            ("b", None),
+            ("c", Some(Value::Null)),
+        ];
+        let channel = channel(rows.into_iter().map(|(name, email)| {
+            let email = email.map(|email| ("email", email));
+            object(email.into_iter().chain([("name", name.into())]))
+        }));
+
+        let func = PresenceFunc {
+            key: "email".into(),
+            mode,
+            null_is_missing,
+        };
+        func.call(channel)
+            .await
+            .unwrap()
+            .into_stream::<LazyObject>()
+            .await
+            .unwrap()
+            .map_ok(|item| item.get_str("name").unwrap().to_string())
+            .try_collect()
+            .await
+            .unwrap()
+    }
+
+    #[::tokio::test]
+    async fn test_presence_has() {
+        assert_eq!(filter(PresenceMode::Has, false).await, ["a", "c"]);
+        assert_eq!(filter(PresenceMode::Has, true).await, ["a"]);
+    }
+
+    #[::tokio::test]
+    async fn test_presence_missing() {
+        assert_eq!(filter(PresenceMode::Missing, false).await, ["b"]);
+        assert_eq!(filter(PresenceMode::Missing, true).await, ["b", "c"]);
+    }
+}
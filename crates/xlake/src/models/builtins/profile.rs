@@ -0,0 +1,240 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt,
+};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use futures::TryStreamExt;
+use serde::{Deserialize, Serialize};
+use xlake_ast::{Number, Object, PlanArguments, PlanKind, Value};
+use xlake_core::{
+    object::{LazyObject, ObjectLayer},
+    ArgSpec, PipeChannel, PipeFunc, PipeNodeFactory, PipeNodeImpl,
+};
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct ProfileFactory;
+
+impl fmt::Display for ProfileFactory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.kind().fmt(f)
+    }
+}
+
+#[async_trait]
+impl PipeNodeFactory for ProfileFactory {
+    fn kind(&self) -> PlanKind {
+        PlanKind::Func {
+            model_name: super::stream::consts::NAME.into(),
+            func: self.name(),
+        }
+    }
+
+    fn name(&self) -> String {
+        "profile".into()
+    }
+
+    fn arguments(&self) -> Vec<ArgSpec> {
+        vec![ArgSpec::optional(
+            "max_distinct",
+            "Maximum number of distinct values per column",
+        )
+        .with_default(ProfileFunc::default_max_distinct())]
+    }
+
+    async fn build(&self, args: &PlanArguments) -> Result<PipeNodeImpl> {
+        let imp: ProfileFunc = args.to()?;
+        Ok(PipeNodeImpl::Func(Box::new(imp)))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProfileFunc {
+    #[serde(default = "ProfileFunc::default_max_distinct")]
+    max_distinct: usize,
+}
+
+impl Default for ProfileFunc {
+    fn default() -> Self {
+        Self {
+            max_distinct: Self::default_max_distinct(),
+        }
+    }
+}
+
+impl ProfileFunc {
+    const fn default_max_distinct() -> usize {
+        10_000
+    }
+}
+
+#[async_trait]
+impl PipeFunc for ProfileFunc {
+    async fn call(&self, channel: PipeChannel) -> Result<PipeChannel> {
+        let mut count = 0;
+        let mut columns: BTreeMap<String, ColumnProfile> = BTreeMap::default();
+
+        // Drain the source, emitting the summaries at the end of stream
+        let mut iter = channel.into_stream::<LazyObject>().await?;
+        while let Some(item) = iter.try_next().await? {
+            let item = item.flatten().await?;
+            for (key, value) in item.content().iter() {
+                columns
+                    .entry(key.clone())
+                    .or_default()
+                    .update(value, self.max_distinct);
+            }
+            count += 1;
+        }
+
+        Ok(columns
+            .into_iter()
+            .map(|(column, profile)| {
+                let object = profile.summarize(column, count);
+                ObjectLayer::from_object_dyn(object).into()
+            })
+            .collect())
+    }
+}
+
+#[derive(Debug, Default)]
+struct ColumnProfile {
+    distinct: HashSet<String>,
+    distinct_capped: bool,
+    max: Option<(f64, Number)>,
+    min: Option<(f64, Number)>,
+    non_null_count: usize,
+}
+
+impl ColumnProfile {
+    fn update(&mut self, value: &Value, max_distinct: usize) {
+        if matches!(value, Value::Null) {
+            return;
+        }
+        self.non_null_count += 1;
+
+        // Stop tracking the new values to bound the memory usage
+        let key = value.to_string();
+        if !self.distinct.contains(&key) {
+            if self.distinct.len() < max_distinct {
+                self.distinct.insert(key);
+            } else {
+                self.distinct_capped = true;
+            }
+        }
+
+        if let Value::Number(number) = value {
+            if let Ok(parsed) = number.to_string().parse::<f64>() {
+                if self.min.as_ref().map_or(true, |(min, _)| parsed < *min) {
+                    self.min = Some((parsed, number.clone()));
+                }
+                if self.max.as_ref().map_or(true, |(max, _)| parsed > *max) {
+                    self.max = Some((parsed, number.clone()));
+                }
+            }
+        }
+    }
+
+    /// Builds the summary, counting the missing keys as nulls.
+    fn summarize(self, column: String, count: usize) -> Object {
+        let Self {
+            distinct,
+            distinct_capped,
+            max,
+            min,
+            non_null_count,
+        } = self;
+
+        let null_count = count - non_null_count;
+        let null_rate = ::serde_json::Number::from_f64(null_count as f64 / count as f64)
+            .map(|rate| Value::Number(Number::Fixed(rate)))
+            .unwrap_or(Value::Null);
+        let number = |value: Option<(f64, Number)>| {
+            value
+                .map(|(_, number)| Value::Number(number))
+                .unwrap_or(Value::Null)
+        };
+
+        [
+            ("column", Value::String(column)),
+            ("count", count.into()),
+            ("distinct", distinct.len().into()),
+            ("distinct_capped", distinct_capped.into()),
+            ("max", number(max)),
+            ("min", number(min)),
+            ("null_count", null_count.into()),
+            ("null_rate", null_rate),
+        ]
+        .into_iter()
+        .map(|(key, value)| (key.into(), value))
+        .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::TryStreamExt;
+    use xlake_ast::Value;
+    use xlake_core::{object::LazyObject, PipeFunc};
+
+    use crate::testing::{channel, object};
+
+    use super::ProfileFunc;
+
+    async fn profile(max_distinct: usize) -> Vec<LazyObject> {
+        let rows = [
+            vec![("age", Value::from(30)), ("name", "foo".into())],
+            vec![("age", Value::from(20)), ("name", "bar".into())],
+            vec![("age", Value::Null), ("name", "foo".into())],
+            vec![("name", "baz".into())],
+        ];
+        let channel = channel(rows.into_iter().map(object));
+
+        let func = ProfileFunc { max_distinct };
+        func.call(channel)
+            .await
+            .unwrap()
+            .into_stream::<LazyObject>()
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap()
+    }
+
+    fn get(item: &LazyObject, key: &str) -> String {
+        item.get_raw(key).unwrap().to_string()
+    }
+
+    #[::tokio::test]
+    async fn test_profile_stats() {
+        let items = profile(100).await;
+        assert_eq!(items.len(), 2);
+
+        let age = &items[0];
+        assert_eq!(get(age, "column"), r#""age""#);
+        assert_eq!(get(age, "count"), "4");
+        assert_eq!(get(age, "distinct"), "2");
+        assert_eq!(get(age, "max"), "30");
+        assert_eq!(get(age, "min"), "20");
+        assert_eq!(get(age, "null_count"), "2");
+        assert_eq!(get(age, "null_rate"), "0.5");
+
+        let name = &items[1];
+        assert_eq!(get(name, "column"), r#""name""#);
+        assert_eq!(get(name, "distinct"), "3");
+        assert_eq!(get(name, "distinct_capped"), "false");
+        assert_eq!(get(name, "max"), "null");
+        assert_eq!(get(name, "null_count"), "0");
+    }
+
+    #[::tokio::test]
+    async fn test_profile_max_distinct() {
+        let items = profile(2).await;
+
+        let name = &items[1];
+        assert_eq!(get(name, "distinct"), "2");
+        assert_eq!(get(name, "distinct_capped"), "true");
+    }
+}
@@ -0,0 +1,262 @@
+use std::{collections::BTreeSet, fmt, sync::Arc};
+
+use anyhow::{Error, Result};
+use async_trait::async_trait;
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use xlake_ast::{PlanArguments, PlanKind, Value};
+use xlake_core::{
+    models::hash::Hash, object::LazyObject, ArgSpec, PipeChannel, PipeFunc, PipeNodeFactory,
+    PipeNodeImpl,
+};
+
+/// The replacement of the sensitive values in the `mask` mode.
+const MASK: &str = "***";
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct RedactFactory;
+
+impl fmt::Display for RedactFactory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.kind().fmt(f)
+    }
+}
+
+#[async_trait]
+impl PipeNodeFactory for RedactFactory {
+    fn kind(&self) -> PlanKind {
+        PlanKind::Func {
+            model_name: super::stream::consts::NAME.into(),
+            func: self.name(),
+        }
+    }
+
+    fn name(&self) -> String {
+        "redact".into()
+    }
+
+    fn arguments(&self) -> Vec<ArgSpec> {
+        vec![
+            ArgSpec::optional(
+                "detect",
+                "Mask the emails, phone and card numbers in all string fields",
+            )
+            .with_default(false),
+            ArgSpec::optional("keys", "Comma-separated fields to be masked entirely"),
+            ArgSpec::optional("mode", "`mask` with `***` or `hash` the values")
+                .with_default("mask"),
+        ]
+    }
+
+    fn is_per_object(&self) -> bool {
+        true
+    }
+
+    async fn build(&self, args: &PlanArguments) -> Result<PipeNodeImpl> {
+        let args: RedactFuncArgs = args.to()?;
+        let imp = RedactFunc::try_from(args)?;
+        Ok(PipeNodeImpl::Func(Box::new(imp)))
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct RedactFuncArgs {
+    detect: bool,
+    keys: Option<String>,
+    mode: RedactMode,
+}
+
+#[derive(Clone, Debug)]
+pub struct RedactFunc {
+    detectors: Vec<Regex>,
+    keys: BTreeSet<String>,
+    mode: RedactMode,
+}
+
+impl TryFrom<RedactFuncArgs> for RedactFunc {
+    type Error = Error;
+
+    fn try_from(args: RedactFuncArgs) -> Result<Self, Self::Error> {
+        let RedactFuncArgs { detect, keys, mode } = args;
+
+        // Match the longer digit sequences first, so that card numbers are not taken as phones
+        let detectors = if detect {
+            [
+                r"\b(?:\d[ -]?){12,18}\d\b",
+                r"[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}",
+                r"\+?\d{1,3}[ .-]?\(?\d{2,4}\)?[ .-]?\d{3,4}[ .-]?\d{4}\b",
+            ]
+            .into_iter()
+            .map(Regex::new)
+            .collect::<Result<_, _>>()?
+        } else {
+            Vec::default()
+        };
+        let keys = keys
+            .iter()
+            .flat_map(|keys| keys.split(','))
+            .map(str::trim)
+            .filter(|key| !key.is_empty())
+            .map(Into::into)
+            .collect();
+        Ok(Self {
+            detectors,
+            keys,
+            mode,
+        })
+    }
+}
+
+impl RedactFunc {
+    async fn redact(&self, item: LazyObject) -> Result<LazyObject> {
+        // Download the whole object
+        let mut item = item.flatten().await?;
+
+        for (key, value) in item.content_mut().iter_mut() {
+            if self.keys.contains(key) {
+                if !matches!(value, Value::Null) {
+                    *value = Value::String(self.mode.apply(value));
+                }
+            } else if !self.detectors.is_empty() {
+                self.detect(value);
+            }
+        }
+        Ok(item)
+    }
+
+    fn detect(&self, value: &mut Value) {
+        match value {
+            Value::String(text) => {
+                for detector in &self.detectors {
+                    let redacted = detector
+                        .replace_all(text, |captures: &Captures| {
+                            self.mode.apply_str(&captures[0])
+                        })
+                        .into_owned();
+                    *text = redacted;
+                }
+            }
+            Value::Array(values) => values.iter_mut().for_each(|value| self.detect(value)),
+            Value::Object(object) => object.values_mut().for_each(|value| self.detect(value)),
+            Value::Null | Value::Bool(_) | Value::Number(_) | Value::Binary(_) => (),
+        }
+    }
+}
+
+#[async_trait]
+impl PipeFunc for RedactFunc {
+    async fn call(&self, channel: PipeChannel) -> Result<PipeChannel> {
+        let func = Arc::new(self.clone());
+        channel
+            .and_then(move |item| {
+                let func = func.clone();
+                async move { func.redact(item).await }
+            })
+            .await
+    }
+}
+
+#[derive(
+    Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum RedactMode {
+    /// Replace the values with `***`
+    #[default]
+    Mask,
+    /// Replace the values with their hashes, keeping them joinable
+    Hash,
+}
+
+impl RedactMode {
+    fn apply(&self, value: &Value) -> String {
+        match value {
+            Value::String(text) => self.apply_str(text),
+            value => self.apply_str(&value.to_string()),
+        }
+    }
+
+    fn apply_str(&self, text: &str) -> String {
+        match self {
+            Self::Mask => MASK.into(),
+            Self::Hash => Hash::digest(text).to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use xlake_ast::Value;
+    use xlake_core::object::{LazyObject, ObjectLayer};
+
+    use crate::testing::object;
+
+    use super::{RedactFunc, RedactFuncArgs, RedactMode};
+
+    async fn redact(args: RedactFuncArgs) -> LazyObject {
+        let object = object([
+            ("email", Value::from("alice@example.com")),
+            ("name", "Alice".into()),
+            (
+                "note",
+                "Call +1 555-123-4567 or mail bob@example.org".into(),
+            ),
+            ("card", "4111 1111 1111 1111".into()),
+            ("age", 30.into()),
+        ]);
+
+        let func = RedactFunc::try_from(args).unwrap();
+        let item = ObjectLayer::from_object_dyn(object).into();
+        func.redact(item).await.unwrap()
+    }
+
+    fn get(item: &LazyObject, key: &str) -> String {
+        item.get_raw(key).unwrap().to_string()
+    }
+
+    #[::tokio::test]
+    async fn test_redact_keys_mask() {
+        let item = redact(RedactFuncArgs {
+            keys: Some("email, age".into()),
+            ..Default::default()
+        })
+        .await;
+
+        assert_eq!(get(&item, "email"), r#""***""#);
+        assert_eq!(get(&item, "age"), r#""***""#);
+
+        // Non-targeted fields are untouched
+        assert_eq!(get(&item, "name"), r#""Alice""#);
+        assert_eq!(get(&item, "card"), r#""4111 1111 1111 1111""#);
+    }
+
+    #[::tokio::test]
+    async fn test_redact_keys_hash() {
+        let args = || RedactFuncArgs {
+            keys: Some("email".into()),
+            mode: RedactMode::Hash,
+            ..Default::default()
+        };
+        let item = redact(args()).await;
+
+        let email = get(&item, "email");
+        assert!(!email.contains("alice"));
+        assert_eq!(email, get(&redact(args()).await, "email"));
+    }
+
+    #[::tokio::test]
+    async fn test_redact_detect() {
+        let item = redact(RedactFuncArgs {
+            detect: true,
+            ..Default::default()
+        })
+        .await;
+
+        assert_eq!(get(&item, "email"), r#""***""#);
+        assert_eq!(get(&item, "card"), r#""***""#);
+        assert_eq!(get(&item, "note"), r#""Call *** or mail ***""#);
+        assert_eq!(get(&item, "name"), r#""Alice""#);
+        assert_eq!(get(&item, "age"), "30");
+    }
+}
@@ -0,0 +1,141 @@
+use std::{fmt, future, sync::Arc};
+
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use futures::{stream, StreamExt, TryStreamExt};
+use tokio::sync::mpsc;
+use xlake_ast::{PlanArguments, PlanKind};
+use xlake_core::{
+    context::PipeContext, object::LazyObject, stream::DefaultStream, PipeChannel, PipeFunc,
+    PipeNodeFactory, PipeNodeImpl,
+};
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct RouteFactory;
+
+impl fmt::Display for RouteFactory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.kind().fmt(f)
+    }
+}
+
+#[async_trait]
+impl PipeNodeFactory for RouteFactory {
+    fn kind(&self) -> PlanKind {
+        PlanKind::Func {
+            model_name: super::stream::consts::NAME.into(),
+            func: self.name(),
+        }
+    }
+
+    fn name(&self) -> String {
+        "route".into()
+    }
+
+    async fn build(&self, _args: &PlanArguments) -> Result<PipeNodeImpl> {
+        // The funcs of the branches are resolved by the session, e.g. `doc='doc:split_delim'`
+        bail!("Cannot build {self} without a session")
+    }
+}
+
+/// A sub-pipeline of funcs applied to the objects of a model.
+#[derive(Clone, Debug)]
+pub struct RouteBranch {
+    pub model: String,
+    pub funcs: Vec<Arc<dyn PipeFunc>>,
+}
+
+impl RouteBranch {
+    async fn call(&self, ctx: &PipeContext, mut channel: PipeChannel) -> Result<PipeChannel> {
+        for func in &self.funcs {
+            channel = func.call_with_context(ctx, channel).await?;
+        }
+        Ok(channel)
+    }
+}
+
+/// Applies a sub-pipeline per model, merging their outputs back into one stream.
+///
+/// An object goes to the first branch whose model it has, in the order of the arguments.
+/// The objects of no branch pass through as they are.
+/// The branches are polled together, so the inputs of the slower ones are buffered in memory.
+#[derive(Clone, Debug)]
+pub struct RouteFunc {
+    branches: Vec<RouteBranch>,
+}
+
+impl RouteFunc {
+    pub fn new(branches: Vec<RouteBranch>) -> Result<Self> {
+        if branches.is_empty() {
+            bail!("route should have at least one branch, e.g. doc='doc:split_delim'")
+        }
+        Ok(Self { branches })
+    }
+}
+
+#[async_trait]
+impl PipeFunc for RouteFunc {
+    async fn call(&self, channel: PipeChannel) -> Result<PipeChannel> {
+        self.call_with_context(&PipeContext::default(), channel)
+            .await
+    }
+
+    async fn call_with_context(
+        &self,
+        ctx: &PipeContext,
+        channel: PipeChannel,
+    ) -> Result<PipeChannel> {
+        let mut senders = Vec::with_capacity(self.branches.len());
+        let mut outputs = Vec::with_capacity(self.branches.len() + 1);
+        for branch in &self.branches {
+            let (tx, rx) = mpsc::unbounded_channel();
+            senders.push((branch.model.clone(), tx));
+
+            let input = stream::unfold(rx, |mut rx| async move {
+                rx.recv().await.map(|item| (Ok(item), rx))
+            })
+            .boxed();
+            let input = PipeChannel::from_stream(DefaultStream::from_stream(input));
+
+            // Call the branch lazily, as it may drain its input first, e.g. `stream:sort`
+            let branch = branch.clone();
+            let ctx = ctx.clone();
+            let output = stream::once(async move {
+                branch
+                    .call(&ctx, input)
+                    .await?
+                    .into_stream::<LazyObject>()
+                    .await
+            })
+            .try_flatten()
+            .boxed();
+            outputs.push(output);
+        }
+
+        // The inputs of the branches are closed along with the senders at the end of stream
+        let unmatched = channel
+            .into_stream::<LazyObject>()
+            .await?
+            .try_filter_map(move |item| future::ready(Ok(dispatch(&senders, item))))
+            .boxed();
+        outputs.push(unmatched);
+
+        let stream = stream::select_all(outputs).boxed();
+        Ok(PipeChannel::from_stream(DefaultStream::from_stream(stream)))
+    }
+}
+
+/// Sends the object to the first matching branch, or returns it back if none.
+fn dispatch(
+    senders: &[(String, mpsc::UnboundedSender<LazyObject>)],
+    item: LazyObject,
+) -> Option<LazyObject> {
+    match senders.iter().find(|(model, _)| item.has_model(model)) {
+        Some((_, tx)) => {
+            // The branch may stop reading early, e.g. after enough objects
+            let _ = tx.send(item);
+            None
+        }
+        None => Some(item),
+    }
+}
@@ -0,0 +1,148 @@
+use std::fmt;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use xlake_ast::{PlanArguments, PlanKind, Value};
+use xlake_core::{
+    object::LazyObject, stream::DefaultStream, ArgSpec, PipeChannel, PipeFunc, PipeNodeFactory,
+    PipeNodeImpl,
+};
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct SeqIdFactory;
+
+impl fmt::Display for SeqIdFactory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.kind().fmt(f)
+    }
+}
+
+#[async_trait]
+impl PipeNodeFactory for SeqIdFactory {
+    fn kind(&self) -> PlanKind {
+        PlanKind::Func {
+            model_name: super::stream::consts::NAME.into(),
+            func: self.name(),
+        }
+    }
+
+    fn name(&self) -> String {
+        "seqid".into()
+    }
+
+    fn arguments(&self) -> Vec<ArgSpec> {
+        vec![
+            ArgSpec::optional("start", "The id of the first object").with_default(0),
+            ArgSpec::optional("target", "Field to store the id").with_default("id"),
+            ArgSpec::optional("uuid", "Emit a random v4 UUID string instead").with_default(false),
+        ]
+    }
+
+    async fn build(&self, args: &PlanArguments) -> Result<PipeNodeImpl> {
+        let imp: SeqIdFunc = args.to()?;
+        Ok(PipeNodeImpl::Func(Box::new(imp)))
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SeqIdFunc {
+    #[serde(default)]
+    start: u64,
+    #[serde(default = "SeqIdFunc::default_target")]
+    target: String,
+    #[serde(default)]
+    uuid: bool,
+}
+
+impl Default for SeqIdFunc {
+    fn default() -> Self {
+        Self {
+            start: 0,
+            target: Self::default_target(),
+            uuid: false,
+        }
+    }
+}
+
+impl SeqIdFunc {
+    fn default_target() -> String {
+        "id".into()
+    }
+
+    fn id(&self, index: usize) -> Value {
+        if self.uuid {
+            Uuid::new_v4().to_string().into()
+        } else {
+            (self.start + index as u64).into()
+        }
+    }
+}
+
+#[async_trait]
+impl PipeFunc for SeqIdFunc {
+    async fn call(&self, channel: PipeChannel) -> Result<PipeChannel> {
+        let func = self.clone();
+        let stream = channel
+            .into_stream::<LazyObject>()
+            .await?
+            .enumerate()
+            .map(move |(index, item)| {
+                let mut item = item?;
+                item.insert(func.target.clone(), func.id(index));
+                Ok(item)
+            })
+            .boxed();
+        Ok(PipeChannel::from_stream(DefaultStream::from_stream(stream)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::TryStreamExt;
+    use xlake_ast::Object;
+    use xlake_core::{object::LazyObject, PipeFunc};
+
+    use crate::testing::channel;
+
+    use super::SeqIdFunc;
+
+    async fn assign(func: SeqIdFunc, len: usize) -> Vec<String> {
+        let channel = channel((0..len).map(|_| Object::default()));
+
+        func.call(channel)
+            .await
+            .unwrap()
+            .into_stream::<LazyObject>()
+            .await
+            .unwrap()
+            .map_ok(|item| item.get_raw("id").unwrap().to_string())
+            .try_collect()
+            .await
+            .unwrap()
+    }
+
+    #[::tokio::test]
+    async fn test_seqid_contiguous() {
+        let func = SeqIdFunc {
+            start: 10,
+            ..Default::default()
+        };
+        assert_eq!(assign(func, 4).await, ["10", "11", "12", "13"]);
+    }
+
+    #[::tokio::test]
+    async fn test_seqid_uuid() {
+        let func = SeqIdFunc {
+            uuid: true,
+            ..Default::default()
+        };
+        let ids = assign(func, 2).await;
+        assert_ne!(ids[0], ids[1]);
+        assert!(ids
+            .iter()
+            .all(|id| id.len() == r#""00000000-0000-4000-8000-000000000000""#.len()));
+    }
+}
@@ -0,0 +1,138 @@
+use std::{fmt, sync::Arc};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use xlake_ast::{PlanArguments, PlanKind};
+use xlake_core::{
+    object::LazyObject, ArgSpec, PipeChannel, PipeFunc, PipeNodeFactory, PipeNodeImpl,
+};
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct SizeFactory;
+
+impl fmt::Display for SizeFactory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.kind().fmt(f)
+    }
+}
+
+#[async_trait]
+impl PipeNodeFactory for SizeFactory {
+    fn kind(&self) -> PlanKind {
+        PlanKind::Func {
+            model_name: super::stream::consts::NAME.into(),
+            func: self.name(),
+        }
+    }
+
+    fn name(&self) -> String {
+        "size".into()
+    }
+
+    fn arguments(&self) -> Vec<ArgSpec> {
+        vec![
+            ArgSpec::optional("content", "Binary field to measure its length separately"),
+            ArgSpec::optional("target", "Field to store the serialized JSON length")
+                .with_default("size_bytes"),
+        ]
+    }
+
+    fn is_per_object(&self) -> bool {
+        true
+    }
+
+    async fn build(&self, args: &PlanArguments) -> Result<PipeNodeImpl> {
+        let imp: SizeFunc = args.to()?;
+        Ok(PipeNodeImpl::Func(Box::new(imp)))
+    }
+}
+
+/// Writes the byte length of each object serialized in JSON, e.g. for the cost estimation.
+///
+/// The length excludes the `target` fields themselves.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SizeFunc {
+    #[serde(default)]
+    content: Option<String>,
+    #[serde(default = "SizeFunc::default_target")]
+    target: String,
+}
+
+impl SizeFunc {
+    fn default_target() -> String {
+        "size_bytes".into()
+    }
+
+    async fn measure(&self, item: LazyObject) -> Result<LazyObject> {
+        let Self { content, target } = self;
+
+        // Download the whole object
+        let mut item = item.flatten().await?;
+        let size = item.content().to_vec()?.len();
+        let content_size = content
+            .as_ref()
+            .and_then(|key| item.get_bytes(key))
+            .map(|bytes| bytes.len());
+
+        item.insert(target.clone(), size.into());
+        if let (Some(key), Some(size)) = (content, content_size) {
+            item.insert(format!("{key}_{target}"), size.into());
+        }
+        Ok(item)
+    }
+}
+
+#[async_trait]
+impl PipeFunc for SizeFunc {
+    async fn call(&self, channel: PipeChannel) -> Result<PipeChannel> {
+        let func = Arc::new(self.clone());
+        channel
+            .and_then(move |item| {
+                let func = func.clone();
+                async move { func.measure(item).await }
+            })
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::TryStreamExt;
+    use xlake_ast::{Binary, Value};
+    use xlake_core::{object::LazyObject, PipeFunc};
+
+    use crate::testing::{channel, object};
+
+    use super::SizeFunc;
+
+    #[::tokio::test]
+    async fn test_size_bytes() {
+        let object = object([
+            ("content", Value::from(Binary(b"hello world".to_vec()))),
+            ("name", "foo".into()),
+        ]);
+        let expected = object.to_vec().unwrap().len();
+
+        let channel = channel([object]);
+        let func = SizeFunc {
+            content: Some("content".into()),
+            target: SizeFunc::default_target(),
+        };
+        let items: Vec<LazyObject> = func
+            .call(channel)
+            .await
+            .unwrap()
+            .into_stream()
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(items.len(), 1);
+        let item = &items[0];
+        assert_eq!(item.get_i64("size_bytes"), Some(expected as i64));
+        assert_eq!(item.get_i64("content_size_bytes"), Some(11));
+    }
+}
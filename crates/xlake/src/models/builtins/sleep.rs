@@ -0,0 +1,124 @@
+use std::{fmt, time::Duration};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use futures::{StreamExt, TryStreamExt};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use tokio::time::sleep;
+use xlake_ast::{PlanArguments, PlanKind};
+use xlake_core::{
+    object::LazyObject, stream::DefaultStream, ArgSpec, PipeChannel, PipeFunc, PipeNodeFactory,
+    PipeNodeImpl,
+};
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct SleepFactory;
+
+impl fmt::Display for SleepFactory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.kind().fmt(f)
+    }
+}
+
+#[async_trait]
+impl PipeNodeFactory for SleepFactory {
+    fn kind(&self) -> PlanKind {
+        PlanKind::Func {
+            model_name: super::stream::consts::NAME.into(),
+            func: self.name(),
+        }
+    }
+
+    fn name(&self) -> String {
+        "sleep".into()
+    }
+
+    fn arguments(&self) -> Vec<ArgSpec> {
+        vec![
+            ArgSpec::optional("ms", "Delay per item in milliseconds").with_default(0),
+            ArgSpec::optional("jitter", "Maximum extra random delay in milliseconds")
+                .with_default(0),
+        ]
+    }
+
+    fn is_per_object(&self) -> bool {
+        true
+    }
+
+    async fn build(&self, args: &PlanArguments) -> Result<PipeNodeImpl> {
+        let imp: SleepFunc = args.to()?;
+        Ok(PipeNodeImpl::Func(Box::new(imp)))
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, Serialize, Deserialize)]
+pub struct SleepFunc {
+    #[serde(default)]
+    ms: u64,
+    #[serde(default)]
+    jitter: u64,
+}
+
+impl SleepFunc {
+    fn duration(&self) -> Duration {
+        let &Self { ms, jitter } = self;
+        let jitter = if jitter > 0 {
+            ::rand::thread_rng().gen_range(0..=jitter)
+        } else {
+            0
+        };
+        Duration::from_millis(ms + jitter)
+    }
+}
+
+#[async_trait]
+impl PipeFunc for SleepFunc {
+    async fn call(&self, channel: PipeChannel) -> Result<PipeChannel> {
+        let func = *self;
+        let stream = channel
+            .into_stream::<LazyObject>()
+            .await?
+            .and_then(move |item| async move {
+                sleep(func.duration()).await;
+                Ok(item)
+            })
+            .boxed();
+        Ok(PipeChannel::from_stream(DefaultStream::from_stream(stream)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use futures::TryStreamExt;
+    use xlake_ast::Object;
+    use xlake_core::{object::LazyObject, PipeFunc};
+
+    use crate::testing::channel;
+
+    use super::SleepFunc;
+
+    #[::tokio::test]
+    async fn test_sleep_scales_with_items() {
+        let len = 5;
+        let channel = channel((0..len).map(|_| Object::default()));
+
+        let func = SleepFunc { ms: 20, jitter: 0 };
+        let start = Instant::now();
+        let items: Vec<LazyObject> = func
+            .call(channel)
+            .await
+            .unwrap()
+            .into_stream()
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(items.len(), len);
+        assert!(start.elapsed() >= Duration::from_millis(20 * len as u64));
+    }
+}
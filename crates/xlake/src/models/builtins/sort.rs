@@ -0,0 +1,550 @@
+use std::{cmp::Ordering, fmt};
+
+use anyhow::{bail, Error, Result};
+use async_trait::async_trait;
+use futures::TryStreamExt;
+use serde::{Deserialize, Serialize};
+use xlake_ast::{PlanArguments, PlanKind, Value};
+use xlake_core::{
+    object::{LazyObject, ObjectLayer},
+    ArgSpec, PipeChannel, PipeFunc, PipeNodeFactory, PipeNodeImpl,
+};
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct SortFactory;
+
+impl fmt::Display for SortFactory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.kind().fmt(f)
+    }
+}
+
+#[async_trait]
+impl PipeNodeFactory for SortFactory {
+    fn kind(&self) -> PlanKind {
+        PlanKind::Func {
+            model_name: super::stream::consts::NAME.into(),
+            func: self.name(),
+        }
+    }
+
+    fn name(&self) -> String {
+        "sort".into()
+    }
+
+    fn arguments(&self) -> Vec<ArgSpec> {
+        vec![
+            ArgSpec::required("by", "Field to sort the objects"),
+            ArgSpec::optional("order", "`asc` or `desc`").with_default("asc"),
+            ArgSpec::optional(
+                "spill_threshold",
+                "Bytes of the buffered objects to spill into temp files, requires `spill`",
+            ),
+        ]
+    }
+
+    async fn build(&self, args: &PlanArguments) -> Result<PipeNodeImpl> {
+        let args: SortFuncArgs = args.to()?;
+        let imp = SortFunc::try_from(args)?;
+        Ok(PipeNodeImpl::Func(Box::new(imp)))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SortFuncArgs {
+    by: String,
+    #[serde(default)]
+    order: SortOrder,
+    #[serde(default)]
+    spill_threshold: Option<usize>,
+}
+
+/// Sorts the objects by a field, keeping the order of the ties.
+///
+/// It materializes the whole stream in memory, unless the `spill_threshold` is given;
+/// then the sorted runs are written into temp files and merged at the end of stream.
+#[derive(Clone, Debug)]
+pub struct SortFunc {
+    by: String,
+    order: SortOrder,
+    spill_threshold: Option<usize>,
+}
+
+impl TryFrom<SortFuncArgs> for SortFunc {
+    type Error = Error;
+
+    fn try_from(args: SortFuncArgs) -> Result<Self, Self::Error> {
+        let SortFuncArgs {
+            by,
+            order,
+            spill_threshold,
+        } = args;
+
+        if spill_threshold == Some(0) {
+            bail!("spill_threshold should be positive")
+        }
+        #[cfg(not(feature = "spill"))]
+        if spill_threshold.is_some() {
+            bail!("spill_threshold requires the `spill` feature")
+        }
+        Ok(Self {
+            by,
+            order,
+            spill_threshold,
+        })
+    }
+}
+
+impl SortFunc {
+    fn entry<T>(&self, key: Option<SortKey>, seq: usize, value: T) -> Entry<T> {
+        Entry {
+            key,
+            order: self.order,
+            seq,
+            value,
+        }
+    }
+
+    fn key(&self, item: &ObjectLayer) -> Option<SortKey> {
+        item.get_raw(&self.by).and_then(SortKey::new)
+    }
+}
+
+#[async_trait]
+impl PipeFunc for SortFunc {
+    async fn call(&self, channel: PipeChannel) -> Result<PipeChannel> {
+        let iter = channel.into_stream::<LazyObject>().await?;
+        #[cfg(feature = "spill")]
+        if let Some(threshold) = self.spill_threshold {
+            return self::spill::call(self, iter, threshold).await;
+        }
+
+        // Drain the source, emitting the objects at the end of stream
+        let mut entries: Vec<_> = iter
+            .and_then(|item| item.flatten())
+            .try_collect::<Vec<_>>()
+            .await?
+            .into_iter()
+            .enumerate()
+            .map(|(seq, item)| self.entry(self.key(&item), seq, item))
+            .collect();
+        entries.sort();
+        Ok(entries.into_iter().map(|entry| entry.value).collect())
+    }
+}
+
+#[cfg(feature = "spill")]
+mod spill {
+    use std::{
+        cmp::Reverse,
+        collections::{BTreeSet, BinaryHeap},
+        vec,
+    };
+
+    use anyhow::Result;
+    use async_tempfile::TempFile;
+    use futures::{stream, StreamExt, TryStreamExt};
+    use serde::{Deserialize, Serialize};
+    use tokio::io::{AsyncBufReadExt, AsyncSeekExt, AsyncWriteExt, BufReader, BufWriter, Lines};
+    use tracing::debug;
+    use xlake_ast::{Binary, Number, Object, Value};
+    use xlake_core::{
+        object::{LazyObject, ObjectLayer},
+        stream::DefaultStream,
+        PipeChannel, PipeChannelStream,
+    };
+
+    use super::{Entry, SortFunc};
+
+    pub(super) async fn call(
+        func: &SortFunc,
+        mut iter: PipeChannelStream<LazyObject>,
+        threshold: usize,
+    ) -> Result<PipeChannel> {
+        let mut buffer = Vec::default();
+        let mut bytes = 0;
+        let mut runs = Vec::default();
+        let mut seq = 0;
+        while let Some(item) = iter.try_next().await? {
+            let item = item.flatten().await?;
+            let line = ::serde_json::to_string(&Line::new(&item))?;
+            bytes += line.len();
+            buffer.push(func.entry(func.key(&item), seq, line));
+            seq += 1;
+
+            if bytes > threshold {
+                runs.push(write_run(&mut buffer).await?);
+                bytes = 0;
+            }
+        }
+
+        // Keep the last run in memory
+        buffer.sort();
+        let lines: Vec<_> = buffer.into_iter().map(|entry| entry.value).collect();
+        runs.push(Run::Memory(lines.into_iter()));
+
+        let merge = Merge::try_new(func.clone(), runs).await?;
+        let stream = stream::try_unfold(merge, |mut merge| async move {
+            Ok(merge.next().await?.map(|item| (item, merge)))
+        })
+        .boxed();
+        Ok(PipeChannel::from_stream(DefaultStream::from_stream(stream)))
+    }
+
+    async fn write_run(buffer: &mut Vec<Entry<String>>) -> Result<Run> {
+        buffer.sort();
+        debug!("Spilling a sorted run of {} objects", buffer.len());
+
+        let mut file = TempFile::new().await?;
+        let mut writer = BufWriter::new(&mut file);
+        for entry in buffer.drain(..) {
+            writer.write_all(entry.value.as_bytes()).await?;
+            writer.write_all(b"\n").await?;
+        }
+        writer.flush().await?;
+        drop(writer);
+
+        file.rewind().await?;
+        Ok(Run::File(BufReader::new(file).lines()))
+    }
+
+    /// A serialized object of the sorted runs, tagging each value with its type.
+    ///
+    /// The plain JSON would turn the binaries into base64 strings.
+    #[derive(Serialize, Deserialize)]
+    struct Line {
+        content: Vec<(String, Tagged)>,
+        models: BTreeSet<String>,
+    }
+
+    impl Line {
+        fn new(item: &ObjectLayer) -> Self {
+            Self {
+                content: Tagged::fields(item.content()),
+                models: item.models().clone(),
+            }
+        }
+
+        fn into_layer(self) -> ObjectLayer {
+            let Self { content, models } = self;
+            ObjectLayer::from_object(Tagged::into_object(content), models)
+        }
+    }
+
+    #[derive(Serialize, Deserialize)]
+    enum Tagged {
+        Null,
+        Bool(bool),
+        Number(Number),
+        Binary(Binary),
+        String(String),
+        Array(Vec<Tagged>),
+        Object(Vec<(String, Tagged)>),
+    }
+
+    impl Tagged {
+        fn fields(object: &Object) -> Vec<(String, Self)> {
+            object
+                .iter()
+                .map(|(key, value)| (key.clone(), value.into()))
+                .collect()
+        }
+
+        fn into_object(fields: Vec<(String, Self)>) -> Object {
+            fields
+                .into_iter()
+                .map(|(key, value)| (key, value.into()))
+                .collect()
+        }
+    }
+
+    impl From<&Value> for Tagged {
+        fn from(value: &Value) -> Self {
+            match value {
+                Value::Null => Self::Null,
+                Value::Bool(value) => Self::Bool(*value),
+                Value::Number(value) => Self::Number(value.clone()),
+                Value::Binary(value) => Self::Binary(value.clone()),
+                Value::String(value) => Self::String(value.clone()),
+                Value::Array(values) => Self::Array(values.iter().map(Into::into).collect()),
+                Value::Object(object) => Self::Object(Self::fields(object)),
+            }
+        }
+    }
+
+    impl From<Tagged> for Value {
+        fn from(value: Tagged) -> Self {
+            match value {
+                Tagged::Null => Self::Null,
+                Tagged::Bool(value) => Self::Bool(value),
+                Tagged::Number(value) => Self::Number(value),
+                Tagged::Binary(value) => Self::Binary(value),
+                Tagged::String(value) => Self::String(value),
+                Tagged::Array(values) => Self::Array(values.into_iter().map(Into::into).collect()),
+                Tagged::Object(fields) => Self::Object(Tagged::into_object(fields)),
+            }
+        }
+    }
+
+    /// A sorted run of the serialized objects.
+    enum Run {
+        File(Lines<BufReader<TempFile>>),
+        Memory(vec::IntoIter<String>),
+    }
+
+    impl Run {
+        async fn next_line(&mut self) -> Result<Option<String>> {
+            match self {
+                Self::File(lines) => lines.next_line().await.map_err(Into::into),
+                Self::Memory(lines) => Ok(lines.next()),
+            }
+        }
+    }
+
+    /// Merges the sorted runs, taking the earlier run first on ties.
+    struct Merge {
+        func: SortFunc,
+        heap: BinaryHeap<Reverse<Entry<ObjectLayer>>>,
+        runs: Vec<Run>,
+    }
+
+    impl Merge {
+        async fn try_new(func: SortFunc, runs: Vec<Run>) -> Result<Self> {
+            let mut merge = Self {
+                func,
+                heap: BinaryHeap::with_capacity(runs.len()),
+                runs,
+            };
+            for index in 0..merge.runs.len() {
+                merge.pull(index).await?;
+            }
+            Ok(merge)
+        }
+
+        async fn next(&mut self) -> Result<Option<LazyObject>> {
+            let Some(Reverse(entry)) = self.heap.pop() else {
+                return Ok(None);
+            };
+            self.pull(entry.seq).await?;
+            Ok(Some(entry.value.into()))
+        }
+
+        async fn pull(&mut self, index: usize) -> Result<()> {
+            if let Some(line) = self.runs[index].next_line().await? {
+                let layer = ::serde_json::from_str::<Line>(&line)?.into_layer();
+                let entry = self.func.entry(self.func.key(&layer), index, layer);
+                self.heap.push(Reverse(entry));
+            }
+            Ok(())
+        }
+    }
+}
+
+#[derive(
+    Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+impl SortOrder {
+    /// Compares the keys in this order, where the missing keys are always the last.
+    pub(crate) fn compare(self, a: Option<&SortKey>, b: Option<&SortKey>) -> Ordering {
+        match (a, b) {
+            (Some(a), Some(b)) => match self {
+                Self::Asc => a.cmp(b),
+                Self::Desc => b.cmp(a),
+            },
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        }
+    }
+}
+
+/// A comparable field value; booleans, then numbers, then strings.
+#[derive(Clone, Debug)]
+pub(crate) enum SortKey {
+    Bool(bool),
+    Number(f64),
+    String(String),
+}
+
+impl SortKey {
+    pub(crate) fn new(value: &Value) -> Option<Self> {
+        match value {
+            Value::Bool(value) => Some(Self::Bool(*value)),
+            Value::Number(value) => value.as_f64().map(Self::Number),
+            Value::String(value) => Some(Self::String(value.clone())),
+            _ => None,
+        }
+    }
+
+    const fn rank(&self) -> u8 {
+        match self {
+            Self::Bool(_) => 0,
+            Self::Number(_) => 1,
+            Self::String(_) => 2,
+        }
+    }
+}
+
+impl PartialEq for SortKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+impl Eq for SortKey {}
+
+impl PartialOrd for SortKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for SortKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Self::Bool(a), Self::Bool(b)) => a.cmp(b),
+            (Self::Number(a), Self::Number(b)) => a.total_cmp(b),
+            (Self::String(a), Self::String(b)) => a.cmp(b),
+            (a, b) => a.rank().cmp(&b.rank()),
+        }
+    }
+}
+
+/// A value ordered by its key, then by its sequence on ties.
+#[derive(Debug)]
+struct Entry<T> {
+    key: Option<SortKey>,
+    order: SortOrder,
+    seq: usize,
+    value: T,
+}
+
+impl<T> PartialEq for Entry<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+impl<T> Eq for Entry<T> {}
+
+impl<T> PartialOrd for Entry<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for Entry<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.order
+            .compare(self.key.as_ref(), other.key.as_ref())
+            .then(self.seq.cmp(&other.seq))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::TryStreamExt;
+    use xlake_ast::Value;
+    use xlake_core::{object::LazyObject, PipeFunc};
+
+    use crate::testing::{channel, object};
+
+    use super::{SortFunc, SortFuncArgs, SortOrder};
+
+    async fn sort(order: SortOrder, spill_threshold: Option<usize>) -> Vec<String> {
+        let rows = [
+            (3, "a"),
+            (1, "b"),
+            (4, "c"),
+            (1, "d"),
+            (5, "e"),
+            (9, "f"),
+            (2, "g"),
+        ];
+        let channel =
+            channel(rows.map(|(score, name)| {
+                object([("name", Value::from(name)), ("score", score.into())])
+            }));
+
+        let func = SortFunc::try_from(SortFuncArgs {
+            by: "score".into(),
+            order,
+            spill_threshold,
+        })
+        .unwrap();
+        func.call(channel)
+            .await
+            .unwrap()
+            .into_stream::<LazyObject>()
+            .await
+            .unwrap()
+            .map_ok(|item| item.get_str("name").unwrap().to_string())
+            .try_collect()
+            .await
+            .unwrap()
+    }
+
+    #[::tokio::test]
+    async fn test_sort_in_memory() {
+        let sorted = sort(SortOrder::Asc, None).await;
+        assert_eq!(sorted, ["b", "d", "g", "a", "c", "e", "f"]);
+
+        let sorted = sort(SortOrder::Desc, None).await;
+        assert_eq!(sorted, ["f", "e", "c", "a", "g", "b", "d"]);
+    }
+
+    #[cfg(feature = "spill")]
+    #[::tokio::test]
+    async fn test_sort_spill() {
+        // Spill every two objects
+        let threshold = 40;
+        let sorted = sort(SortOrder::Asc, Some(threshold)).await;
+        assert_eq!(sorted, ["b", "d", "g", "a", "c", "e", "f"]);
+
+        let sorted = sort(SortOrder::Desc, Some(threshold)).await;
+        assert_eq!(sorted, ["f", "e", "c", "a", "g", "b", "d"]);
+    }
+
+    #[cfg(feature = "spill")]
+    #[::tokio::test]
+    async fn test_sort_spill_binary() {
+        let channel = channel((0..4u8).rev().map(|score| {
+            object([
+                ("score", Value::from(score)),
+                ("data", vec![score; 4].into()),
+            ])
+        }));
+
+        // Spill every object
+        let func = SortFunc::try_from(SortFuncArgs {
+            by: "score".into(),
+            order: SortOrder::Asc,
+            spill_threshold: Some(1),
+        })
+        .unwrap();
+        let items: Vec<LazyObject> = func
+            .call(channel)
+            .await
+            .unwrap()
+            .into_stream()
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(items.len(), 4);
+        for (score, item) in (0..4u8).zip(&items) {
+            assert_eq!(item.get_i64("score"), Some(score.into()));
+            assert!(matches!(item.get_raw("data"), Some(Value::Binary(_))));
+            assert_eq!(item.get_bytes("data"), Some(&[score; 4][..]));
+        }
+    }
+}
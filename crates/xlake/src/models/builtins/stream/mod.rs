@@ -1,3 +1,7 @@
 pub mod python;
 
 // StreamModel is virtual and auto-driven by the format.
+
+pub(crate) mod consts {
+    pub(crate) const NAME: &str = "stream";
+}
@@ -0,0 +1,140 @@
+use std::fmt;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use futures::TryStreamExt;
+use serde::{Deserialize, Serialize};
+use xlake_ast::{PlanArguments, PlanKind};
+use xlake_core::{
+    batch::{BatchFormatArgs, DataFusionBatch, DEFAULT_TABLE_REF, NAME},
+    object::LazyObject,
+    ArgSpec, PipeChannel, PipeEdge, PipeFunc, PipeNodeFactory, PipeNodeImpl,
+};
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct ToBatchFactory;
+
+impl fmt::Display for ToBatchFactory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.kind().fmt(f)
+    }
+}
+
+#[async_trait]
+impl PipeNodeFactory for ToBatchFactory {
+    fn kind(&self) -> PlanKind {
+        PlanKind::Func {
+            model_name: super::stream::consts::NAME.into(),
+            func: self.name(),
+        }
+    }
+
+    fn name(&self) -> String {
+        "to_batch".into()
+    }
+
+    fn arguments(&self) -> Vec<ArgSpec> {
+        vec![
+            ArgSpec::optional("table", "Name of the table to be registered")
+                .with_default(DEFAULT_TABLE_REF),
+        ]
+    }
+
+    fn output(&self) -> PipeEdge {
+        PipeEdge {
+            batch: NAME.into(),
+            model: Some(vec![super::batch::consts::NAME.into()]),
+            ..Default::default()
+        }
+    }
+
+    async fn build(&self, args: &PlanArguments) -> Result<PipeNodeImpl> {
+        let imp: ToBatchFunc = args.to()?;
+        Ok(PipeNodeImpl::Func(Box::new(imp)))
+    }
+}
+
+/// Collects the objects into a table, so that the batch nodes can query them.
+///
+/// The following `sql` func reuses the table as is, rather than reading its rows back.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ToBatchFunc {
+    #[serde(default = "ToBatchFunc::default_table")]
+    table: String,
+}
+
+impl ToBatchFunc {
+    fn default_table() -> String {
+        DEFAULT_TABLE_REF.into()
+    }
+}
+
+#[async_trait]
+impl PipeFunc for ToBatchFunc {
+    async fn call(&self, channel: PipeChannel) -> Result<PipeChannel> {
+        let rows: Vec<_> = channel
+            .into_stream::<LazyObject>()
+            .await?
+            .and_then(|item| item.flatten())
+            .map_ok(|item| item.content().clone())
+            .try_collect()
+            .await?;
+
+        let batch = DataFusionBatch::new(BatchFormatArgs {
+            table: self.table.clone(),
+            ..Default::default()
+        })?;
+        batch.register_rows(&rows)?;
+        PipeChannel::from_batch_streamed(batch).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use crate::{testing::CollectSinkFactory, PipeSession};
+
+    #[::tokio::test]
+    async fn test_to_batch_sql() {
+        let path = ::std::env::temp_dir().join(format!("xlake-to-batch-{}", ::std::process::id()));
+        ::std::fs::write(
+            &path,
+            concat!(
+                r#"{"name":"foo","score":10}"#,
+                "\n",
+                r#"{"name":"bar","score":20,"ok":true}"#,
+                "\n",
+                r#"{"name":"baz"}"#,
+                "\n",
+            ),
+        )
+        .unwrap();
+        // The query reads the table registered by `to_batch`, not the one of `sql`
+        let command = |sink: &str| {
+            format!(
+                "ndjsonsrc path='{}'
+                ! stream:to_batch table=events
+                ! batch:sql query='SELECT name, score FROM events WHERE score > 15'
+                ! {sink}",
+                path.display(),
+            )
+        };
+
+        let items = Arc::new(Mutex::new(Vec::default()));
+        let mut session = PipeSession::default();
+        session.insert_factory(Box::new(CollectSinkFactory(items.clone())));
+        let result = async {
+            session.call(&command("stdoutsink")).await?;
+            session.call(&command("collectsink")).await
+        }
+        .await;
+        ::std::fs::remove_file(&path).unwrap();
+        result.unwrap();
+
+        let items = items.lock().unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].get_str("name"), Some("bar"));
+        assert_eq!(items[0].get_i64("score"), Some(20));
+    }
+}
@@ -0,0 +1,277 @@
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, HashMap},
+    fmt,
+};
+
+use anyhow::{bail, Error, Result};
+use async_trait::async_trait;
+use futures::TryStreamExt;
+use serde::{Deserialize, Serialize};
+use xlake_ast::{PlanArguments, PlanKind};
+use xlake_core::{
+    args::PipeArgs, object::LazyObject, ArgSpec, PipeChannel, PipeFunc, PipeNodeFactory,
+    PipeNodeImpl,
+};
+use xlake_derive::PipeArgs;
+
+use super::sort::{SortKey, SortOrder};
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct TopNPerKeyFactory;
+
+impl fmt::Display for TopNPerKeyFactory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.kind().fmt(f)
+    }
+}
+
+#[async_trait]
+impl PipeNodeFactory for TopNPerKeyFactory {
+    fn kind(&self) -> PlanKind {
+        PlanKind::Func {
+            model_name: super::stream::consts::NAME.into(),
+            func: self.name(),
+        }
+    }
+
+    fn name(&self) -> String {
+        "topn".into()
+    }
+
+    fn arguments(&self) -> Vec<ArgSpec> {
+        TopNPerKeyFuncArgs::arg_specs()
+    }
+
+    async fn build(&self, args: &PlanArguments) -> Result<PipeNodeImpl> {
+        let args: TopNPerKeyFuncArgs = args.to()?;
+        let imp = TopNPerKeyFunc::try_from(args)?;
+        Ok(PipeNodeImpl::Func(Box::new(imp)))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PipeArgs)]
+struct TopNPerKeyFuncArgs {
+    /// Field to group the objects
+    by: String,
+    /// Maximum number of the objects per group
+    n: usize,
+    /// `asc` or `desc` by the `order_by` field
+    #[serde(default = "TopNPerKeyFuncArgs::default_order")]
+    order: SortOrder,
+    /// Field to rank the objects in each group
+    order_by: String,
+}
+
+impl TopNPerKeyFuncArgs {
+    const fn default_order() -> SortOrder {
+        SortOrder::Desc
+    }
+}
+
+/// Keeps the first `n` objects per group by the `order_by` field.
+///
+/// It materializes a bounded heap of `n` objects per group, emitting them at the end of stream.
+#[derive(Clone, Debug)]
+pub struct TopNPerKeyFunc {
+    by: String,
+    n: usize,
+    order: SortOrder,
+    order_by: String,
+}
+
+impl TryFrom<TopNPerKeyFuncArgs> for TopNPerKeyFunc {
+    type Error = Error;
+
+    fn try_from(args: TopNPerKeyFuncArgs) -> Result<Self, Self::Error> {
+        let TopNPerKeyFuncArgs {
+            by,
+            n,
+            order,
+            order_by,
+        } = args;
+
+        if n == 0 {
+            bail!("n should be positive")
+        }
+        Ok(Self {
+            by,
+            n,
+            order,
+            order_by,
+        })
+    }
+}
+
+#[async_trait]
+impl PipeFunc for TopNPerKeyFunc {
+    async fn call(&self, channel: PipeChannel) -> Result<PipeChannel> {
+        let mut groups: Vec<BinaryHeap<Entry>> = Vec::default();
+        let mut indices: HashMap<String, usize> = HashMap::default();
+
+        // Drain the source, keeping the groups in the order of appearance
+        let mut iter = channel.into_stream::<LazyObject>().await?;
+        let mut seq = 0;
+        while let Some(item) = iter.try_next().await? {
+            let item = item.flatten().await?;
+            let group = item
+                .get_raw(&self.by)
+                .map(ToString::to_string)
+                .unwrap_or_default();
+            let index = *indices.entry(group).or_insert_with(|| {
+                groups.push(BinaryHeap::with_capacity(self.n + 1));
+                groups.len() - 1
+            });
+
+            let heap = &mut groups[index];
+            heap.push(Entry {
+                key: item.get_raw(&self.order_by).and_then(SortKey::new),
+                order: self.order,
+                seq,
+                item,
+            });
+            if heap.len() > self.n {
+                // Drop the worst one
+                heap.pop();
+            }
+            seq += 1;
+        }
+
+        Ok(groups
+            .into_iter()
+            .flat_map(|heap| heap.into_sorted_vec())
+            .map(|entry| entry.item)
+            .collect())
+    }
+}
+
+/// An object in a group, where the greater is the worse.
+#[derive(Debug)]
+struct Entry {
+    /// The missing and non-comparable values are always the worst
+    key: Option<SortKey>,
+    order: SortOrder,
+    /// The later one is the worse on ties
+    seq: usize,
+    item: LazyObject,
+}
+
+impl PartialEq for Entry {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+impl Eq for Entry {}
+
+impl PartialOrd for Entry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Entry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.order
+            .compare(self.key.as_ref(), other.key.as_ref())
+            .then(self.seq.cmp(&other.seq))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::TryStreamExt;
+    use xlake_ast::Value;
+    use xlake_core::{object::LazyObject, ArgSpec, PipeFunc, PipeNodeFactory};
+
+    use crate::testing::{channel, object};
+
+    use super::{SortOrder, TopNPerKeyFactory, TopNPerKeyFunc, TopNPerKeyFuncArgs};
+
+    async fn topn(order: SortOrder) -> Vec<(String, String)> {
+        let events = [
+            ("alice", 3),
+            ("bob", 1),
+            ("alice", 1),
+            ("alice", 4),
+            ("bob", 5),
+            ("alice", 2),
+        ];
+        let channel = channel(
+            events
+                .into_iter()
+                .map(|(user, time)| object([("user", Value::from(user)), ("time", time.into())])),
+        );
+
+        let func = TopNPerKeyFunc::try_from(TopNPerKeyFuncArgs {
+            by: "user".into(),
+            n: 3,
+            order,
+            order_by: "time".into(),
+        })
+        .unwrap();
+        func.call(channel)
+            .await
+            .unwrap()
+            .into_stream::<LazyObject>()
+            .await
+            .unwrap()
+            .map_ok(|item| {
+                let user = item.get_raw("user").unwrap().to_string();
+                let time = item.get_raw("time").unwrap().to_string();
+                (user.trim_matches('"').into(), time)
+            })
+            .try_collect()
+            .await
+            .unwrap()
+    }
+
+    fn rows(rows: &[(&str, u32)]) -> Vec<(String, String)> {
+        rows.iter()
+            .map(|&(user, time)| (user.into(), time.to_string()))
+            .collect()
+    }
+
+    #[::tokio::test]
+    async fn test_topn_desc() {
+        assert_eq!(
+            topn(SortOrder::Desc).await,
+            rows(&[
+                ("alice", 4),
+                ("alice", 3),
+                ("alice", 2),
+                ("bob", 5),
+                ("bob", 1)
+            ]),
+        );
+    }
+
+    #[::tokio::test]
+    async fn test_topn_asc() {
+        assert_eq!(
+            topn(SortOrder::Asc).await,
+            rows(&[
+                ("alice", 1),
+                ("alice", 2),
+                ("alice", 3),
+                ("bob", 1),
+                ("bob", 5)
+            ]),
+        );
+    }
+
+    #[test]
+    fn test_topn_arguments() {
+        // Derived from the fields of the arguments
+        assert_eq!(
+            TopNPerKeyFactory.arguments(),
+            [
+                ArgSpec::required("by", "Field to group the objects"),
+                ArgSpec::required("n", "Maximum number of the objects per group"),
+                ArgSpec::optional("order", "`asc` or `desc` by the `order_by` field")
+                    .with_default("desc"),
+                ArgSpec::required("order_by", "Field to rank the objects in each group"),
+            ],
+        );
+    }
+}
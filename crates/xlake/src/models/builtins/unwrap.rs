@@ -0,0 +1,195 @@
+use std::{fmt, sync::Arc};
+
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use futures::{StreamExt, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use xlake_ast::{PlanArguments, PlanKind, Value};
+use xlake_core::{
+    object::LazyObject, stream::DefaultStream, ArgSpec, PipeChannel, PipeFunc, PipeNodeFactory,
+    PipeNodeImpl,
+};
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct UnwrapFactory;
+
+impl fmt::Display for UnwrapFactory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.kind().fmt(f)
+    }
+}
+
+#[async_trait]
+impl PipeNodeFactory for UnwrapFactory {
+    fn kind(&self) -> PlanKind {
+        PlanKind::Func {
+            model_name: super::stream::consts::NAME.into(),
+            func: self.name(),
+        }
+    }
+
+    fn name(&self) -> String {
+        "unwrap".into()
+    }
+
+    fn arguments(&self) -> Vec<ArgSpec> {
+        vec![
+            ArgSpec::required("key", "Object field to be promoted to the top level"),
+            ArgSpec::optional(
+                "on_conflict",
+                "`error`, `keep` or `overwrite` on existing fields",
+            )
+            .with_default("overwrite"),
+            ArgSpec::optional("prefix", "Prefix of the promoted field names"),
+        ]
+    }
+
+    fn is_per_object(&self) -> bool {
+        true
+    }
+
+    async fn build(&self, args: &PlanArguments) -> Result<PipeNodeImpl> {
+        let imp: UnwrapFunc = args.to()?;
+        Ok(PipeNodeImpl::Func(Box::new(imp)))
+    }
+}
+
+/// Merges the fields of a nested object up to the top level; the inverse of nesting.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct UnwrapFunc {
+    key: String,
+    #[serde(default)]
+    on_conflict: UnwrapConflictAction,
+    #[serde(default)]
+    prefix: Option<String>,
+}
+
+impl UnwrapFunc {
+    async fn unwrap(&self, item: LazyObject) -> Result<LazyObject> {
+        let Self {
+            key,
+            on_conflict,
+            prefix,
+        } = self;
+
+        // Download the whole object
+        let mut item = item.flatten().await?;
+
+        // Pass through objects without a nested object
+        if !matches!(item.get_raw(key), Some(Value::Object(_))) {
+            return Ok(item);
+        }
+        let nested = match item.content_mut().remove(key) {
+            Some(Value::Object(nested)) => nested,
+            _ => unreachable!(),
+        };
+
+        for (name, value) in nested.iter() {
+            let name = match prefix {
+                Some(prefix) => format!("{prefix}{name}"),
+                None => name.clone(),
+            };
+            if item.get_raw(&name).is_some() {
+                match on_conflict {
+                    UnwrapConflictAction::Error => bail!("Conflicted field: '{name}'"),
+                    UnwrapConflictAction::Keep => continue,
+                    UnwrapConflictAction::Overwrite => (),
+                }
+            }
+            item.insert(name, value.clone());
+        }
+        Ok(item)
+    }
+}
+
+#[async_trait]
+impl PipeFunc for UnwrapFunc {
+    async fn call(&self, channel: PipeChannel) -> Result<PipeChannel> {
+        let func = Arc::new(self.clone());
+        let stream = channel
+            .into_stream::<LazyObject>()
+            .await?
+            .and_then(move |item| {
+                let func = func.clone();
+                async move { func.unwrap(item).await }
+            })
+            .boxed();
+        Ok(PipeChannel::from_stream(DefaultStream::from_stream(stream)))
+    }
+}
+
+#[derive(
+    Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum UnwrapConflictAction {
+    Error,
+    Keep,
+    #[default]
+    Overwrite,
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::TryStreamExt;
+    use xlake_ast::{Object, Value};
+    use xlake_core::{object::LazyObject, PipeFunc};
+
+    use crate::testing::{channel, object};
+
+    use super::{UnwrapConflictAction, UnwrapFunc};
+
+    async fn unwrap(
+        on_conflict: UnwrapConflictAction,
+        prefix: Option<&str>,
+    ) -> anyhow::Result<Object> {
+        let user = object([("id", Value::from(2)), ("name", "foo".into())]);
+        let channel = channel([object([("id", Value::from(1)), ("user", user.into())])]);
+
+        let func = UnwrapFunc {
+            key: "user".into(),
+            on_conflict,
+            prefix: prefix.map(Into::into),
+        };
+        let mut items: Vec<LazyObject> = func
+            .call(channel)
+            .await?
+            .into_stream()
+            .await?
+            .try_collect()
+            .await?;
+        Ok(items.pop().unwrap().content().clone())
+    }
+
+    fn fields(object: &Object) -> Vec<String> {
+        let mut fields: Vec<_> = object
+            .iter()
+            .map(|(key, value)| format!("{key}={value}"))
+            .collect();
+        fields.sort();
+        fields
+    }
+
+    #[::tokio::test]
+    async fn test_unwrap_promote() {
+        let object = unwrap(UnwrapConflictAction::Overwrite, None).await.unwrap();
+        assert_eq!(fields(&object), ["id=2", r#"name="foo""#]);
+        assert!(!matches!(object.get("user"), Some(Value::Object(_))));
+    }
+
+    #[::tokio::test]
+    async fn test_unwrap_prefix() {
+        let object = unwrap(UnwrapConflictAction::Error, Some("user_"))
+            .await
+            .unwrap();
+        assert_eq!(fields(&object), ["id=1", "user_id=2", r#"user_name="foo""#]);
+    }
+
+    #[::tokio::test]
+    async fn test_unwrap_conflict() {
+        let object = unwrap(UnwrapConflictAction::Keep, None).await.unwrap();
+        assert_eq!(fields(&object), ["id=1", r#"name="foo""#]);
+
+        assert!(unwrap(UnwrapConflictAction::Error, None).await.is_err());
+    }
+}
@@ -0,0 +1,355 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    fmt, mem,
+    sync::Arc,
+};
+
+use anyhow::{anyhow, bail, Error, Result};
+use async_trait::async_trait;
+use futures::{stream, StreamExt, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+use xlake_ast::{Number, Object, PlanArguments, PlanKind, Value};
+use xlake_core::{
+    object::{LazyObject, ObjectLayer},
+    stream::DefaultStream,
+    ArgSpec, PipeChannel, PipeFunc, PipeNodeFactory, PipeNodeImpl,
+};
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct WindowAggFactory;
+
+impl fmt::Display for WindowAggFactory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.kind().fmt(f)
+    }
+}
+
+#[async_trait]
+impl PipeNodeFactory for WindowAggFactory {
+    fn kind(&self) -> PlanKind {
+        PlanKind::Func {
+            model_name: super::stream::consts::NAME.into(),
+            func: self.name(),
+        }
+    }
+
+    fn name(&self) -> String {
+        "window".into()
+    }
+
+    fn arguments(&self) -> Vec<ArgSpec> {
+        vec![
+            ArgSpec::optional("agg", "Comma-separated numeric fields to sum and average"),
+            ArgSpec::optional(
+                "allowed_lateness",
+                "Delay to close a window after a later timestamp, in the `time_key` unit",
+            )
+            .with_default(0),
+            ArgSpec::required(
+                "time_key",
+                "Integer timestamp field, e.g. epoch milliseconds",
+            ),
+            ArgSpec::required("window", "Window size, in the `time_key` unit"),
+        ]
+    }
+
+    async fn build(&self, args: &PlanArguments) -> Result<PipeNodeImpl> {
+        let args: WindowAggFuncArgs = args.to()?;
+        let imp = WindowAggFunc::try_from(args)?;
+        Ok(PipeNodeImpl::Func(Box::new(imp)))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct WindowAggFuncArgs {
+    #[serde(default)]
+    agg: Option<String>,
+    #[serde(default)]
+    allowed_lateness: i64,
+    time_key: String,
+    window: i64,
+}
+
+/// Aggregates the objects into the tumbling windows by their timestamps.
+///
+/// A window is emitted once the observed timestamps pass its end by `allowed_lateness`,
+/// so that only the open windows are kept in memory.
+/// The objects arriving after their window has been emitted are dropped.
+#[derive(Clone, Debug)]
+pub struct WindowAggFunc {
+    agg: Vec<String>,
+    allowed_lateness: i64,
+    time_key: String,
+    window: i64,
+}
+
+impl TryFrom<WindowAggFuncArgs> for WindowAggFunc {
+    type Error = Error;
+
+    fn try_from(args: WindowAggFuncArgs) -> Result<Self, Self::Error> {
+        let WindowAggFuncArgs {
+            agg,
+            allowed_lateness,
+            time_key,
+            window,
+        } = args;
+
+        if window <= 0 {
+            bail!("window should be positive")
+        }
+        if allowed_lateness < 0 {
+            bail!("allowed_lateness should not be negative")
+        }
+        Ok(Self {
+            agg: agg
+                .iter()
+                .flat_map(|agg| agg.split(','))
+                .map(str::trim)
+                .filter(|field| !field.is_empty())
+                .map(Into::into)
+                .collect(),
+            allowed_lateness,
+            time_key,
+            window,
+        })
+    }
+}
+
+#[async_trait]
+impl PipeFunc for WindowAggFunc {
+    async fn call(&self, channel: PipeChannel) -> Result<PipeChannel> {
+        let state = WindowAggState {
+            dropped: 0,
+            done: false,
+            func: Arc::new(self.clone()),
+            input: channel.into_stream::<LazyObject>().await?,
+            max_time: None,
+            ready: VecDeque::default(),
+            windows: BTreeMap::default(),
+        };
+        let stream = stream::try_unfold(state, |mut state| async move {
+            Ok(state.next().await?.map(|item| (item, state)))
+        })
+        .boxed();
+        Ok(PipeChannel::from_stream(DefaultStream::from_stream(stream)))
+    }
+}
+
+/// The accumulators of an open window.
+#[derive(Debug)]
+struct Window {
+    count: usize,
+    /// The sum and the number of the numeric values per `agg` field
+    sums: Vec<(f64, usize)>,
+}
+
+impl Window {
+    fn new(func: &WindowAggFunc) -> Self {
+        Self {
+            count: 0,
+            sums: vec![(0.0, 0); func.agg.len()],
+        }
+    }
+
+    fn push(&mut self, func: &WindowAggFunc, item: &LazyObject) {
+        self.count += 1;
+        for (field, (sum, count)) in func.agg.iter().zip(&mut self.sums) {
+            // Skip the missing and non-numeric values
+            if let Some(value) = item.get_f64(field) {
+                *sum += value;
+                *count += 1;
+            }
+        }
+    }
+
+    fn finish(self, func: &WindowAggFunc, start: i64) -> LazyObject {
+        let float = |value: f64| {
+            ::serde_json::Number::from_f64(value)
+                .map(|value| Value::Number(Number::Fixed(value)))
+                .unwrap_or(Value::Null)
+        };
+
+        let mut object = Object::default();
+        object.insert("window_start".into(), start.into());
+        object.insert("window_end".into(), (start + func.window).into());
+        object.insert("count".into(), self.count.into());
+        for (field, (sum, count)) in func.agg.iter().zip(self.sums) {
+            object.insert(format!("{field}_sum"), float(sum));
+            let avg = if count > 0 {
+                float(sum / count as f64)
+            } else {
+                Value::Null
+            };
+            object.insert(format!("{field}_avg"), avg);
+        }
+        ObjectLayer::from_object_dyn(object).into()
+    }
+}
+
+struct WindowAggState<S> {
+    /// The number of the late objects
+    dropped: usize,
+    done: bool,
+    func: Arc<WindowAggFunc>,
+    input: S,
+    max_time: Option<i64>,
+    /// The closed windows to be emitted
+    ready: VecDeque<LazyObject>,
+    /// The open windows by their start
+    windows: BTreeMap<i64, Window>,
+}
+
+impl<S> WindowAggState<S>
+where
+    S: Unpin + futures::Stream<Item = Result<LazyObject>>,
+{
+    async fn next(&mut self) -> Result<Option<LazyObject>> {
+        loop {
+            if let Some(item) = self.ready.pop_front() {
+                return Ok(Some(item));
+            }
+            if self.done {
+                return Ok(None);
+            }
+
+            match self.input.try_next().await? {
+                Some(item) => {
+                    let item = item.flatten().await?;
+                    self.push(&item)?;
+                }
+                None => {
+                    // Flush the remaining windows at the end of stream
+                    self.done = true;
+                    let windows = mem::take(&mut self.windows);
+                    self.ready.extend(
+                        windows
+                            .into_iter()
+                            .map(|(start, window)| window.finish(&self.func, start)),
+                    );
+                    if self.dropped > 0 {
+                        warn!(
+                            "Dropped {} late objects beyond the allowed lateness",
+                            self.dropped,
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    fn push(&mut self, item: &LazyObject) -> Result<()> {
+        let WindowAggFunc {
+            allowed_lateness,
+            time_key,
+            window,
+            ..
+        } = &*self.func;
+
+        let time = item
+            .get_i64(time_key)
+            .ok_or_else(|| anyhow!("Missing or non-integer timestamp: {time_key}"))?;
+        let start = time.div_euclid(*window) * window;
+
+        let max_time = self.max_time.map_or(time, |max_time| max_time.max(time));
+        self.max_time = Some(max_time);
+        let watermark = max_time.saturating_sub(*allowed_lateness);
+
+        // The window has already been emitted
+        if start + window <= watermark && !self.windows.contains_key(&start) {
+            self.dropped += 1;
+            return Ok(());
+        }
+        self.windows
+            .entry(start)
+            .or_insert_with(|| Window::new(&self.func))
+            .push(&self.func, item);
+
+        // Close the windows passed by the watermark, in order
+        while let Some(entry) = self.windows.first_entry() {
+            if *entry.key() + window > watermark {
+                break;
+            }
+            let (start, closed) = entry.remove_entry();
+            self.ready.push_back(closed.finish(&self.func, start));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::TryStreamExt;
+    use xlake_core::{object::LazyObject, PipeFunc};
+
+    use crate::testing::{channel, object};
+
+    use super::{WindowAggFunc, WindowAggFuncArgs};
+
+    async fn aggregate(allowed_lateness: i64, events: &[(i64, i64)]) -> Vec<String> {
+        let channel = channel(
+            events
+                .iter()
+                .map(|&(time, value)| object([("time", time), ("value", value)])),
+        );
+
+        let func = WindowAggFunc::try_from(WindowAggFuncArgs {
+            agg: Some("value, missing".into()),
+            allowed_lateness,
+            time_key: "time".into(),
+            window: 10,
+        })
+        .unwrap();
+        func.call(channel)
+            .await
+            .unwrap()
+            .into_stream::<LazyObject>()
+            .await
+            .unwrap()
+            .map_ok(|item| {
+                [
+                    "window_start",
+                    "window_end",
+                    "count",
+                    "value_sum",
+                    "value_avg",
+                    "missing_avg",
+                ]
+                .into_iter()
+                .map(|key| item.get_raw(key).unwrap().to_string())
+                .collect::<Vec<_>>()
+                .join(" ")
+            })
+            .try_collect()
+            .await
+            .unwrap()
+    }
+
+    #[::tokio::test]
+    async fn test_window_agg() {
+        let events = [(0, 1), (3, 2), (9, 3), (10, 4), (25, 5), (27, 6), (31, 7)];
+        assert_eq!(
+            aggregate(0, &events).await,
+            [
+                "0 10 3 6.0 2.0 null",
+                "10 20 1 4.0 4.0 null",
+                "20 30 2 11.0 5.5 null",
+                "30 40 1 7.0 7.0 null",
+            ],
+        );
+    }
+
+    #[::tokio::test]
+    async fn test_window_agg_late() {
+        // `5` is late after `12`, while `8` is within the lateness after `14`
+        let events = [(1, 1), (12, 2), (8, 3), (14, 4), (5, 5)];
+        assert_eq!(
+            aggregate(3, &events).await,
+            ["0 10 2 4.0 2.0 null", "10 20 2 6.0 3.0 null"],
+        );
+        assert_eq!(
+            aggregate(0, &events).await,
+            ["0 10 1 1.0 1.0 null", "10 20 2 6.0 3.0 null"],
+        );
+    }
+}
@@ -0,0 +1,60 @@
+use std::iter;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use futures::{stream, StreamExt, TryStreamExt};
+use xlake_core::{
+    context::PipeContext, object::LazyObject, stream::DefaultStream, PipeChannel, PipeFunc,
+};
+
+/// A func decorating another one per object, e.g. to retry each object on its own.
+///
+/// The decorated func should be per-object, as it is given a single object at a time.
+#[async_trait]
+pub(crate) trait PerObject: 'static + Clone + Send + Sync {
+    /// Returns all the outputs of the object.
+    async fn call_item(&self, ctx: &PipeContext, item: LazyObject) -> Result<Vec<LazyObject>>;
+}
+
+/// Calls the decorator on each object of the channel, emitting the outputs in order.
+pub(crate) async fn call<T>(
+    func: &T,
+    ctx: &PipeContext,
+    channel: PipeChannel,
+) -> Result<PipeChannel>
+where
+    T: PerObject,
+{
+    let func = func.clone();
+    let ctx = ctx.clone();
+    let stream = channel
+        .into_stream::<LazyObject>()
+        .await?
+        .and_then(move |item| {
+            let func = func.clone();
+            let ctx = ctx.clone();
+            async move { func.call_item(&ctx, item).await }
+        })
+        .map_ok(|rows| stream::iter(rows.into_iter().map(Ok)))
+        .try_flatten()
+        .boxed();
+    Ok(PipeChannel::from_stream(DefaultStream::from_stream(stream)))
+}
+
+/// Calls the inner func with a single object, resolving all its outputs.
+///
+/// The outputs are resolved here, so that their failures are caught along with the call.
+pub(crate) async fn call_once(
+    inner: &dyn PipeFunc,
+    ctx: &PipeContext,
+    item: LazyObject,
+) -> Result<Vec<LazyObject>> {
+    inner
+        .call_with_context(ctx, iter::once(item).collect())
+        .await?
+        .into_stream::<LazyObject>()
+        .await?
+        .and_then(|item| item.flatten())
+        .try_collect()
+        .await
+}
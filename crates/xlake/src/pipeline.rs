@@ -0,0 +1,105 @@
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+use xlake_ast::{Plan, PlanArguments};
+use xlake_parser::SeqParser;
+
+/// A pipeline declared in a file, e.g. in TOML, rather than in a single line.
+///
+/// The nodes are linked in order, each named as printed by `--list`, e.g. `filesrc` or `doc:split`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PipelineFile {
+    #[serde(default)]
+    pub nodes: Vec<PipelineNode>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PipelineNode {
+    pub kind: String,
+    #[serde(default)]
+    pub args: PlanArguments,
+}
+
+impl PipelineFile {
+    /// Converts the nodes into the plans, as if they were given in the pipeline syntax.
+    pub fn into_plans(self) -> Result<Vec<Plan>> {
+        let Self { nodes } = self;
+        if nodes.is_empty() {
+            bail!("Pipeline file has no nodes")
+        }
+
+        // Resolve the kinds in the same way as the pipeline syntax
+        let parser = SeqParser::new();
+        nodes
+            .into_iter()
+            .enumerate()
+            .map(|(index, PipelineNode { kind, args })| {
+                let mut plans = parser
+                    .parse(&kind)
+                    .map_err(|error| anyhow!("Invalid kind of the node {index}: {error}"))?;
+                match plans.pop() {
+                    Some(plan) if plans.is_empty() && plan.args.is_empty() => Ok(Plan {
+                        kind: plan.kind,
+                        args,
+                    }),
+                    _ => bail!("Expected a single kind of the node {index}, but given '{kind}'"),
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use xlake_ast::Plan;
+    use xlake_parser::SeqParser;
+
+    use crate::PipeSession;
+
+    use super::PipelineFile;
+
+    const FILE: &str = r#"
+# The objects are checked by the assert sink
+[[nodes]]
+kind = "repeatsrc"
+
+[nodes.args]
+count = 3
+json = '{"name": "foo"}'
+
+[[nodes]]
+kind = "assertsink"
+args = { max_rows = 3, min_rows = 3, require_field = "name" }
+"#;
+
+    #[::tokio::test]
+    async fn test_pipeline_file() {
+        let file: PipelineFile = ::toml::from_str(FILE).unwrap();
+        let plans = file.into_plans().unwrap();
+
+        let input = r#"repeatsrc count=3 json='{"name": "foo"}'
+            ! assertsink max_rows=3 min_rows=3 require_field='name'"#;
+        let expected = SeqParser::new().parse(input).unwrap();
+        assert_eq!(
+            Plan::display_seq(&plans).to_string(),
+            Plan::display_seq(&expected).to_string(),
+        );
+
+        PipeSession::default().call_with(plans).await.unwrap();
+    }
+
+    #[test]
+    fn test_pipeline_file_invalid() {
+        let parse = |file: &str| {
+            ::toml::from_str::<PipelineFile>(file)
+                .map_err(Into::into)
+                .and_then(PipelineFile::into_plans)
+        };
+        assert!(parse("").is_err());
+        assert!(parse("[[nodes]]\nkind = 'Filesrc'").is_err());
+        assert!(parse("[[nodes]]\nkind = 'stdinsrc ! stdoutsink'").is_err());
+        assert!(parse("[[nodes]]\nkind = 'stdinsrc'\nname = 'foo'").is_err());
+        assert!(parse("[[nodes]]\nkind = 'stdinsrc'\n[[nodes]]\nkind = 'stdoutsink'").is_ok());
+    }
+}
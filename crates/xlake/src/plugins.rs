@@ -0,0 +1,273 @@
+use std::{
+    collections::BTreeMap,
+    fmt, fs,
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+};
+
+use anyhow::{Context, Result};
+use tokio::sync::watch;
+use tracing::{info, warn};
+use xlake_ast::PlanKind;
+use xlake_core::PipeNodeFactory;
+
+/// The symbol exported by a plugin, returning its factories.
+///
+/// A plugin is a `cdylib` built with the same compiler and `xlake-core` as the host, e.g.:
+///
+/// ```ignore
+/// #[no_mangle]
+/// pub fn xlake_plugin_factories() -> Vec<Box<dyn PipeNodeFactory>> {
+///     vec![Box::new(MyFuncFactory)]
+/// }
+/// ```
+pub const SYMBOL: &[u8] = b"xlake_plugin_factories";
+
+/// Loads the factories of a plugin file.
+pub trait PluginLoader: fmt::Debug + Send + Sync {
+    /// Returns whether the file is a plugin, skipping the others in the directory.
+    fn is_plugin(&self, path: &Path) -> bool;
+
+    fn load(&self, path: &Path) -> Result<Vec<Box<dyn PipeNodeFactory>>>;
+}
+
+/// Loads the shared objects of the platform, e.g. `libmy_func.so`.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct SharedObjectLoader;
+
+impl PluginLoader for SharedObjectLoader {
+    fn is_plugin(&self, path: &Path) -> bool {
+        path.extension()
+            .is_some_and(|ext| ext == ::std::env::consts::DLL_EXTENSION)
+    }
+
+    fn load(&self, path: &Path) -> Result<Vec<Box<dyn PipeNodeFactory>>> {
+        // The loader caches the libraries by path, so load a fresh copy to see the changes
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let copy = ::std::env::temp_dir().join(format!("xlake-{}-{name}", ::uuid::Uuid::new_v4()));
+        fs::copy(path, &copy)
+            .with_context(|| format!("Failed to copy plugin: {}", path.display()))?;
+
+        let factories = unsafe {
+            let library = ::libloading::Library::new(&copy);
+            let _ = fs::remove_file(&copy);
+            let library =
+                library.with_context(|| format!("Failed to load plugin: {}", path.display()))?;
+            let factories = {
+                let constructor = library
+                    .get::<fn() -> Vec<Box<dyn PipeNodeFactory>>>(SYMBOL)
+                    .with_context(|| format!("Not a plugin: {}", path.display()))?;
+                constructor()
+            };
+
+            // Never unload it, as the nodes built by its factories may still be running
+            ::std::mem::forget(library);
+            factories
+        };
+        Ok(factories)
+    }
+}
+
+/// Registers the factories of the plugins in a directory, overriding the builtins.
+///
+/// Reloading swaps the factories at once, so the new pipelines use the reloaded ones.
+/// The old factories and the nodes built by them in a running pipeline keep working.
+/// If any plugin fails to load, the previous factories are kept as they are.
+#[derive(Debug)]
+pub struct PluginRegistry {
+    dir: PathBuf,
+    factories: RwLock<BTreeMap<PlanKind, Arc<dyn PipeNodeFactory>>>,
+    generation: watch::Sender<u64>,
+    loader: Box<dyn PluginLoader>,
+}
+
+impl PluginRegistry {
+    /// Loads the shared objects in the directory.
+    pub fn load(dir: impl Into<PathBuf>) -> Result<Self> {
+        Self::load_with(dir, Box::new(SharedObjectLoader))
+    }
+
+    pub fn load_with(dir: impl Into<PathBuf>, loader: Box<dyn PluginLoader>) -> Result<Self> {
+        let registry = Self {
+            dir: dir.into(),
+            factories: Default::default(),
+            generation: watch::Sender::new(0),
+            loader,
+        };
+        registry.reload()?;
+        Ok(registry)
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn get(&self, kind: &PlanKind) -> Option<Arc<dyn PipeNodeFactory>> {
+        self.factories.read().unwrap().get(kind).cloned()
+    }
+
+    pub fn kinds(&self) -> Vec<PlanKind> {
+        self.factories.read().unwrap().keys().cloned().collect()
+    }
+
+    /// Loads the plugins in the directory again, replacing all the factories.
+    pub fn reload(&self) -> Result<()> {
+        let mut paths = Vec::default();
+        let entries = fs::read_dir(&self.dir)
+            .with_context(|| format!("Failed to read plugins: {}", self.dir.display()))?;
+        for entry in entries {
+            let path = entry?.path();
+            if path.is_file() && self.loader.is_plugin(&path) {
+                paths.push(path);
+            }
+        }
+        // Let the plugins override each other in a stable order
+        paths.sort();
+
+        let mut factories = BTreeMap::default();
+        for path in &paths {
+            for factory in self.loader.load(path)? {
+                let factory: Arc<dyn PipeNodeFactory> = Arc::from(factory);
+                info!("Loaded {} from {}", factory.kind(), path.display());
+                factories.insert(factory.kind(), factory);
+            }
+        }
+
+        *self.factories.write().unwrap() = factories;
+        self.generation.send_modify(|generation| *generation += 1);
+        Ok(())
+    }
+
+    /// Returns a receiver notified whenever the plugins are reloaded.
+    pub fn subscribe(&self) -> watch::Receiver<u64> {
+        self.generation.subscribe()
+    }
+
+    /// Reloads the plugins whenever a file in the directory changes, until the watcher is dropped.
+    ///
+    /// The failures are logged, keeping the previous factories.
+    pub fn watch(self: &Arc<Self>) -> Result<::notify::RecommendedWatcher> {
+        use notify::{EventKind, RecursiveMode, Watcher};
+
+        let registry = Arc::downgrade(self);
+        let mut watcher = ::notify::recommended_watcher(move |event| {
+            let event: ::notify::Event = match event {
+                Ok(event) => event,
+                Err(error) => return warn!("Failed to watch plugins: {error}"),
+            };
+            if !matches!(
+                event.kind,
+                EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_),
+            ) {
+                return;
+            }
+            if let Some(registry) = registry.upgrade() {
+                if let Err(error) = registry.reload() {
+                    warn!("Failed to reload plugins: {error:#}");
+                }
+            }
+        })?;
+        watcher.watch(&self.dir, RecursiveMode::NonRecursive)?;
+        Ok(watcher)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use anyhow::Result;
+    use async_trait::async_trait;
+    use xlake_ast::{Object, PlanArguments, PlanKind};
+    use xlake_core::{PipeNodeFactory, PipeNodeImpl};
+
+    use crate::{srcs::local::inline::InlineSrcFactory, testing::CollectSinkFactory, PipeSession};
+
+    use super::*;
+
+    /// Emits the JSON object written in the plugin file.
+    #[derive(Debug)]
+    struct JsonPluginFactory(String);
+
+    impl fmt::Display for JsonPluginFactory {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            self.kind().fmt(f)
+        }
+    }
+
+    #[async_trait]
+    impl PipeNodeFactory for JsonPluginFactory {
+        fn kind(&self) -> PlanKind {
+            PlanKind::Src { name: self.name() }
+        }
+
+        fn name(&self) -> String {
+            "plugin".into()
+        }
+
+        async fn build(&self, _args: &PlanArguments) -> Result<PipeNodeImpl> {
+            let args = PlanArguments::from_json(::serde_json::json!({ "json": &self.0 }))?;
+            InlineSrcFactory.build(&args).await
+        }
+    }
+
+    #[derive(Debug)]
+    struct JsonPluginLoader;
+
+    impl PluginLoader for JsonPluginLoader {
+        fn is_plugin(&self, path: &Path) -> bool {
+            path.extension().is_some_and(|ext| ext == "json")
+        }
+
+        fn load(&self, path: &Path) -> Result<Vec<Box<dyn PipeNodeFactory>>> {
+            let json = fs::read_to_string(path)?;
+            Object::from_slice(json.as_bytes())?;
+            Ok(vec![Box::new(JsonPluginFactory(json))])
+        }
+    }
+
+    #[::tokio::test]
+    async fn test_plugins_reload() {
+        let dir = ::std::env::temp_dir().join(format!("xlake-plugins-{}", ::uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("plugin.json"), r#"{"version":1}"#).unwrap();
+        fs::write(dir.join("README.md"), "Not a plugin").unwrap();
+
+        let plugins =
+            Arc::new(PluginRegistry::load_with(&dir, Box::new(JsonPluginLoader)).unwrap());
+        let mut generation = plugins.subscribe();
+        let items = Arc::new(Mutex::new(Vec::default()));
+        let mut session = PipeSession::default();
+        session.insert_factory(Box::new(CollectSinkFactory(items.clone())));
+        session.set_plugins(plugins.clone());
+        session.call("pluginsrc ! collectsink").await.unwrap();
+
+        // Swap the plugin, used on the next call
+        fs::write(dir.join("plugin.json"), r#"{"version":2}"#).unwrap();
+        plugins.reload().unwrap();
+        assert!(generation.has_changed().unwrap());
+        generation.mark_unchanged();
+        session.call("pluginsrc ! collectsink").await.unwrap();
+
+        // Keep the previous factories on failure
+        fs::write(dir.join("plugin.json"), "{").unwrap();
+        assert!(plugins.reload().is_err());
+        assert!(!generation.has_changed().unwrap());
+        session.call("pluginsrc ! collectsink").await.unwrap();
+
+        // Unregister the removed plugin
+        fs::remove_file(dir.join("plugin.json")).unwrap();
+        plugins.reload().unwrap();
+        assert!(plugins.kinds().is_empty());
+        assert!(session.call("pluginsrc ! collectsink").await.is_err());
+
+        let versions: Vec<_> = items
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|item| item.get_i64("version"))
+            .collect();
+        assert_eq!(versions, vec![Some(1), Some(2), Some(2)]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
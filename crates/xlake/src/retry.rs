@@ -0,0 +1,261 @@
+use std::{sync::Arc, time::Duration};
+
+use anyhow::{anyhow, bail, Context, Result};
+use async_trait::async_trait;
+use tokio::time;
+use tracing::warn;
+use xlake_ast::Value;
+use xlake_core::{
+    context::PipeContext, object::LazyObject, PipeChannel, PipeFunc, RetryOptions, TransientError,
+};
+
+use crate::per_object::{self, PerObject};
+
+/// The argument of the per-object funcs to retry each failed object, e.g. `retries=3`.
+pub const RETRIES: &str = "retries";
+
+/// The argument of the per-object funcs to time out each attempt in millis, e.g. `timeout=1000`.
+pub const TIMEOUT: &str = "timeout";
+
+/// The upper bound of the backoff, which doubles per retry.
+pub const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Overrides the retry options of the inner func with `retries` and `timeout`.
+///
+/// The given retries apply to every failure, as most funcs do not mark them as transient.
+#[derive(Debug)]
+pub struct RetryArgsFunc {
+    inner: Box<dyn PipeFunc>,
+    options: RetryOptions,
+}
+
+impl RetryArgsFunc {
+    /// Wraps the func if any of the arguments is given.
+    pub fn wrap(
+        inner: Box<dyn PipeFunc>,
+        retries: Option<Value>,
+        timeout: Option<Value>,
+    ) -> Result<Box<dyn PipeFunc>> {
+        if retries.is_none() && timeout.is_none() {
+            return Ok(inner);
+        }
+
+        let mut options = inner.retry_options().unwrap_or_default();
+        if let Some(retries) = retries {
+            options.max_retries = match retries.as_i64() {
+                Some(retries) if retries >= 0 => retries as usize,
+                _ => bail!("Expected a non-negative number of the retries, but given {retries}"),
+            };
+            options.all_errors = true;
+        }
+        if let Some(timeout) = timeout {
+            options.timeout = match timeout.as_i64() {
+                Some(timeout) if timeout > 0 => Some(Duration::from_millis(timeout as u64)),
+                _ => bail!("Expected a positive timeout in millis, but given {timeout}"),
+            };
+        }
+        Ok(Box::new(Self { inner, options }))
+    }
+}
+
+#[async_trait]
+impl PipeFunc for RetryArgsFunc {
+    async fn call(&self, channel: PipeChannel) -> Result<PipeChannel> {
+        self.inner.call(channel).await
+    }
+
+    async fn call_with_context(
+        &self,
+        ctx: &PipeContext,
+        channel: PipeChannel,
+    ) -> Result<PipeChannel> {
+        self.inner.call_with_context(ctx, channel).await
+    }
+
+    fn retry_options(&self) -> Option<RetryOptions> {
+        Some(self.options)
+    }
+}
+
+/// Calls the inner func once per object, retrying the transient failures with backoff.
+#[derive(Clone, Debug)]
+pub struct RetryFunc {
+    inner: Arc<dyn PipeFunc>,
+    options: RetryOptions,
+}
+
+impl RetryFunc {
+    pub fn new(inner: Arc<dyn PipeFunc>, options: RetryOptions) -> Self {
+        Self { inner, options }
+    }
+
+    /// Returns the delay of the next retry, up to [`MAX_BACKOFF`].
+    fn next_delay(delay: Duration) -> Duration {
+        delay.saturating_mul(2).min(MAX_BACKOFF)
+    }
+}
+
+#[async_trait]
+impl PerObject for RetryFunc {
+    async fn call_item(&self, ctx: &PipeContext, item: LazyObject) -> Result<Vec<LazyObject>> {
+        let RetryOptions {
+            backoff,
+            max_retries,
+            timeout,
+            all_errors,
+        } = self.options;
+
+        // Download the whole object, to be cloned per attempt
+        let item = item.flatten().await?;
+
+        let mut attempt = 0;
+        let mut delay = backoff.min(MAX_BACKOFF);
+        loop {
+            let input = item.try_clone().context("Unflattened object")?;
+            // Resolve the outputs within the attempt, so that their failures are retried too
+            let output = per_object::call_once(&*self.inner, ctx, input.into());
+            let result = match timeout {
+                Some(timeout) => time::timeout(timeout, output).await.unwrap_or_else(|_| {
+                    let error = anyhow!("Timed out after {timeout:?}");
+                    Err(TransientError(error).into())
+                }),
+                None => output.await,
+            };
+
+            match result {
+                Ok(rows) => break Ok(rows),
+                Err(error)
+                    if attempt < max_retries && (all_errors || error.is::<TransientError>()) =>
+                {
+                    attempt += 1;
+                    warn!("Retrying {attempt}/{max_retries} after {delay:?}: {error}");
+                    time::sleep(delay).await;
+                    delay = Self::next_delay(delay);
+                }
+                Err(error) => break Err(error),
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl PipeFunc for RetryFunc {
+    async fn call(&self, channel: PipeChannel) -> Result<PipeChannel> {
+        self.call_with_context(&PipeContext::default(), channel)
+            .await
+    }
+
+    async fn call_with_context(
+        &self,
+        ctx: &PipeContext,
+        channel: PipeChannel,
+    ) -> Result<PipeChannel> {
+        per_object::call(self, ctx, channel).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+
+    use anyhow::{anyhow, Result};
+    use async_trait::async_trait;
+    use futures::TryStreamExt;
+    use xlake_core::{object::LazyObject, PipeChannel, PipeFunc, RetryOptions, TransientError};
+
+    use crate::testing::{channel, object};
+
+    use super::{RetryFunc, MAX_BACKOFF};
+
+    /// Fails or hangs on the first attempt only.
+    #[derive(Debug, Default)]
+    struct FlakyFunc {
+        attempts: AtomicUsize,
+        hang: bool,
+        transient: bool,
+    }
+
+    #[async_trait]
+    impl PipeFunc for FlakyFunc {
+        async fn call(&self, channel: PipeChannel) -> Result<PipeChannel> {
+            if self.attempts.fetch_add(1, Ordering::SeqCst) > 0 {
+                return Ok(channel);
+            }
+            if self.hang {
+                ::tokio::time::sleep(Duration::from_secs(60)).await;
+            }
+            let error = anyhow!("flaky");
+            if self.transient {
+                Err(TransientError(error).into())
+            } else {
+                Err(error)
+            }
+        }
+    }
+
+    async fn call(func: Arc<FlakyFunc>) -> Result<Vec<LazyObject>> {
+        let options = RetryOptions {
+            backoff: Duration::from_millis(1),
+            max_retries: 2,
+            timeout: Some(Duration::from_millis(50)),
+            all_errors: false,
+        };
+        let channel = channel([object([("name", "foo")])]);
+
+        RetryFunc::new(func, options)
+            .call(channel)
+            .await?
+            .into_stream()
+            .await?
+            .try_collect()
+            .await
+    }
+
+    #[::tokio::test]
+    async fn test_retry_transient() {
+        let func = Arc::new(FlakyFunc {
+            transient: true,
+            ..Default::default()
+        });
+        let items = call(func.clone()).await.unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(func.attempts.load(Ordering::SeqCst), 2);
+    }
+
+    #[::tokio::test]
+    async fn test_retry_timeout() {
+        let func = Arc::new(FlakyFunc {
+            hang: true,
+            ..Default::default()
+        });
+        let items = call(func.clone()).await.unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(func.attempts.load(Ordering::SeqCst), 2);
+    }
+
+    #[::tokio::test]
+    async fn test_retry_permanent() {
+        let func = Arc::new(FlakyFunc::default());
+        assert!(call(func.clone()).await.is_err());
+        assert_eq!(func.attempts.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_retry_backoff_cap() {
+        let mut delay = Duration::from_millis(100);
+        for _ in 0..64 {
+            delay = RetryFunc::next_delay(delay);
+            assert!(delay <= MAX_BACKOFF);
+        }
+        assert_eq!(delay, MAX_BACKOFF);
+
+        // The backoff given beyond the cap is clamped too
+        assert_eq!(RetryFunc::next_delay(Duration::MAX), MAX_BACKOFF);
+    }
+}
@@ -0,0 +1,87 @@
+use serde::{Deserialize, Serialize};
+use xlake_ast::{Object, Value};
+
+/// How a sink writes the binary values, e.g. to keep the human-facing output readable.
+#[derive(
+    Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum BinaryMode {
+    /// Encodes them in base64, as serialized.
+    #[default]
+    Base64,
+    /// Drops them, along with their keys.
+    Omit,
+    /// Replaces them with their sizes, e.g. `"Binary(1,024 bytes)"`.
+    Summary,
+}
+
+impl BinaryMode {
+    /// Rewrites the nested binary values of the object before serialization.
+    pub fn apply(self, object: &mut Object) {
+        if self != Self::Base64 {
+            object.retain(|_, value| self.apply_value(value));
+        }
+    }
+
+    /// Returns whether the value should be kept.
+    fn apply_value(self, value: &mut Value) -> bool {
+        match value {
+            Value::Binary(binary) => match self {
+                Self::Base64 => true,
+                Self::Omit => false,
+                Self::Summary => {
+                    *value = Value::String(binary.to_string());
+                    true
+                }
+            },
+            Value::Array(values) => {
+                values.retain_mut(|value| self.apply_value(value));
+                true
+            }
+            Value::Object(object) => {
+                self.apply(object);
+                true
+            }
+            _ => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use xlake_ast::{Binary, Object, Value};
+
+    use super::BinaryMode;
+
+    #[test]
+    fn test_binary_mode() {
+        let render = |mode: BinaryMode| {
+            let mut nested = Object::default();
+            nested.insert("thumbnail".into(), Binary(vec![0; 16]).into());
+            let mut object = Object::default();
+            object.insert("content".into(), Binary(vec![0; 4096]).into());
+            object.insert("name".into(), "foo".into());
+            object.insert(
+                "parts".into(),
+                Value::Array(vec![Binary(vec![0; 2]).into(), Value::Object(nested)]),
+            );
+
+            mode.apply(&mut object);
+            object.to_string().unwrap()
+        };
+
+        let output = render(BinaryMode::Base64);
+        assert!(output.starts_with(r#"{"content":"AAAA"#));
+        assert!(output.len() > 4096 * 4 / 3);
+
+        assert_eq!(render(BinaryMode::Omit), r#"{"name":"foo","parts":[{}]}"#);
+        assert_eq!(
+            render(BinaryMode::Summary),
+            concat!(
+                r#"{"content":"Binary(4,096 bytes)","name":"foo","#,
+                r#""parts":["Binary(2 bytes)",{"thumbnail":"Binary(16 bytes)"}]}"#,
+            ),
+        );
+    }
+}
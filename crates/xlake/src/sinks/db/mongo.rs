@@ -0,0 +1,126 @@
+use std::{fmt, mem};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{spec::BinarySubtype, Binary, Bson, Document},
+    Client,
+};
+use serde::{Deserialize, Serialize};
+use xlake_ast::{Number, Object, PlanArguments, PlanKind, Value};
+use xlake_core::{
+    object::LazyObject, ArgSpec, PipeChannel, PipeNodeFactory, PipeNodeImpl, PipeSink,
+};
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct MongoSinkFactory;
+
+impl fmt::Display for MongoSinkFactory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.kind().fmt(f)
+    }
+}
+
+#[async_trait]
+impl PipeNodeFactory for MongoSinkFactory {
+    fn kind(&self) -> PlanKind {
+        PlanKind::Sink { name: self.name() }
+    }
+
+    fn name(&self) -> String {
+        "mongo".into()
+    }
+
+    fn arguments(&self) -> Vec<ArgSpec> {
+        vec![
+            ArgSpec::required("uri", "MongoDB connection string"),
+            ArgSpec::required("db", "Database name"),
+            ArgSpec::required("collection", "Collection name"),
+            ArgSpec::optional("batch", "Number of documents per insert")
+                .with_default(MongoSink::default_batch()),
+        ]
+    }
+
+    async fn build(&self, args: &PlanArguments) -> Result<PipeNodeImpl> {
+        let imp: MongoSink = args.to()?;
+        Ok(PipeNodeImpl::Sink(Box::new(imp)))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MongoSink {
+    uri: String,
+    db: String,
+    collection: String,
+    #[serde(default = "MongoSink::default_batch")]
+    batch: usize,
+}
+
+impl MongoSink {
+    const fn default_batch() -> usize {
+        1_000
+    }
+}
+
+#[async_trait]
+impl PipeSink for MongoSink {
+    async fn call(&self, channel: PipeChannel) -> Result<()> {
+        let Self {
+            uri,
+            db,
+            collection,
+            batch,
+        } = self;
+
+        let client = Client::with_uri_str(uri).await?;
+        let collection = client.database(db).collection::<Document>(collection);
+
+        let batch = (*batch).max(1);
+        let mut documents = Vec::with_capacity(batch);
+        let mut iter = channel.into_stream::<LazyObject>().await?;
+        while let Some(item) = iter.try_next().await? {
+            let item = item.flatten().await?;
+            documents.push(object_to_document(item.content()));
+
+            if documents.len() >= batch {
+                collection.insert_many(mem::take(&mut documents)).await?;
+            }
+        }
+        if !documents.is_empty() {
+            collection.insert_many(documents).await?;
+        }
+        Ok(())
+    }
+}
+
+fn object_to_document(object: &Object) -> Document {
+    object
+        .iter()
+        .map(|(key, value)| (key.clone(), value_to_bson(value)))
+        .collect()
+}
+
+fn value_to_bson(value: &Value) -> Bson {
+    match value {
+        Value::Null => Bson::Null,
+        Value::Bool(v) => Bson::Boolean(*v),
+        Value::Number(Number::Fixed(v)) => v
+            .as_i64()
+            .map(Bson::Int64)
+            .or_else(|| v.as_f64().map(Bson::Double))
+            .unwrap_or_else(|| Bson::String(v.to_string())),
+        Value::Number(Number::Dynamic(v)) => v
+            .parse()
+            .map(Bson::Int64)
+            .or_else(|_| v.parse().map(Bson::Double))
+            .unwrap_or_else(|_| Bson::String(v.clone())),
+        Value::Binary(v) => Bson::Binary(Binary {
+            subtype: BinarySubtype::Generic,
+            bytes: v.0.clone(),
+        }),
+        Value::String(v) => Bson::String(v.clone()),
+        Value::Array(v) => Bson::Array(v.iter().map(value_to_bson).collect()),
+        Value::Object(v) => Bson::Document(object_to_document(v)),
+    }
+}
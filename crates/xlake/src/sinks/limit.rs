@@ -0,0 +1,103 @@
+use anyhow::{bail, Error, Result};
+use serde::{Deserialize, Serialize};
+
+/// What a sink does on reaching `max_bytes`, given as `on_exceed`.
+///
+/// It differs from [`LimitExceedAction`] of the srcs, which truncate the input instead.
+///
+/// [`LimitExceedAction`]: crate::srcs::limit::LimitExceedAction
+#[derive(
+    Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case", try_from = "String")]
+pub enum QuotaExceedAction {
+    #[default]
+    Error,
+    Stop,
+}
+
+impl TryFrom<String> for QuotaExceedAction {
+    type Error = Error;
+
+    fn try_from(value: String) -> Result<Self> {
+        match value.as_str() {
+            "error" => Ok(Self::Error),
+            "stop" => Ok(Self::Stop),
+            "truncate" => bail!("Unsupported on_exceed for the sinks: 'truncate'; use 'stop'"),
+            _ => bail!("Unknown on_exceed: '{value}'; expected 'error' or 'stop'"),
+        }
+    }
+}
+
+/// Tracks the cumulative bytes written by a sink against `max_bytes`.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ByteQuota {
+    pub max_bytes: Option<u64>,
+    pub on_exceed: QuotaExceedAction,
+    written: u64,
+}
+
+impl ByteQuota {
+    pub const fn new(max_bytes: Option<u64>, on_exceed: QuotaExceedAction) -> Self {
+        Self {
+            max_bytes,
+            on_exceed,
+            written: 0,
+        }
+    }
+
+    /// Accounts the next write of `len` bytes, returning `false` to stop before writing it.
+    ///
+    /// A write is never split, so that the output keeps only the whole writes.
+    pub fn try_consume(&mut self, len: usize) -> Result<bool> {
+        let written = self.written.saturating_add(len as u64);
+        match self.max_bytes {
+            Some(max_bytes) if written > max_bytes => match self.on_exceed {
+                QuotaExceedAction::Error => {
+                    bail!("Output too large: exceeded {max_bytes} bytes (max_bytes)")
+                }
+                QuotaExceedAction::Stop => Ok(false),
+            },
+            _ => {
+                self.written = written;
+                Ok(true)
+            }
+        }
+    }
+
+    /// Returns the bytes accounted so far.
+    pub const fn written(&self) -> u64 {
+        self.written
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ByteQuota, QuotaExceedAction};
+
+    #[test]
+    fn test_quota_exceeded() {
+        let mut quota = ByteQuota::new(Some(8), QuotaExceedAction::Stop);
+        assert!(quota.try_consume(5).unwrap());
+        assert!(!quota.try_consume(5).unwrap());
+        assert_eq!(quota.written(), 5);
+
+        let mut quota = ByteQuota::new(Some(8), QuotaExceedAction::Error);
+        assert!(quota.try_consume(8).unwrap());
+        assert!(quota.try_consume(1).is_err());
+
+        let mut quota = ByteQuota::new(None, QuotaExceedAction::Error);
+        assert!(quota.try_consume(usize::MAX).unwrap());
+    }
+
+    #[test]
+    fn test_quota_exceed_action() {
+        let parse = |value: &str| QuotaExceedAction::try_from(value.to_string());
+        assert_eq!(parse("stop").unwrap(), QuotaExceedAction::Stop);
+
+        // The action of the srcs is told apart from the typos
+        let error = parse("truncate").unwrap_err();
+        assert!(error.to_string().contains("use 'stop'"));
+        assert!(parse("drop").is_err());
+    }
+}
@@ -0,0 +1,203 @@
+use std::fmt;
+
+use anyhow::{bail, Error, Result};
+use async_trait::async_trait;
+use futures::TryStreamExt;
+use serde::{Deserialize, Serialize};
+use xlake_ast::{PlanArguments, PlanKind, Value};
+use xlake_core::{
+    object::LazyObject, ArgSpec, PipeChannel, PipeNodeFactory, PipeNodeImpl, PipeSink,
+};
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct AssertSinkFactory;
+
+impl fmt::Display for AssertSinkFactory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.kind().fmt(f)
+    }
+}
+
+#[async_trait]
+impl PipeNodeFactory for AssertSinkFactory {
+    fn kind(&self) -> PlanKind {
+        PlanKind::Sink { name: self.name() }
+    }
+
+    fn name(&self) -> String {
+        "assert".into()
+    }
+
+    fn arguments(&self) -> Vec<ArgSpec> {
+        vec![
+            ArgSpec::optional("fail_if_empty", "Fail if no objects are consumed")
+                .with_default(false),
+            ArgSpec::optional("max_rows", "Maximum number of the objects"),
+            ArgSpec::optional("min_rows", "Minimum number of the objects"),
+            ArgSpec::optional("require_field", "Field which every object should have"),
+        ]
+    }
+
+    async fn build(&self, args: &PlanArguments) -> Result<PipeNodeImpl> {
+        let args: AssertSinkArgs = args.to()?;
+        let imp = AssertSink::try_from(args)?;
+        Ok(PipeNodeImpl::Sink(Box::new(imp)))
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct AssertSinkArgs {
+    #[serde(default)]
+    fail_if_empty: bool,
+    #[serde(default)]
+    max_rows: Option<usize>,
+    #[serde(default)]
+    min_rows: Option<usize>,
+    #[serde(default)]
+    require_field: Option<String>,
+}
+
+/// Checks the conditions over the consumed objects, failing the pipeline on violation.
+///
+/// The CLI exits with a non-zero code then, e.g. for the data checks in CI.
+#[derive(Debug)]
+pub struct AssertSink {
+    fail_if_empty: bool,
+    max_rows: Option<usize>,
+    min_rows: Option<usize>,
+    require_field: Option<String>,
+}
+
+impl TryFrom<AssertSinkArgs> for AssertSink {
+    type Error = Error;
+
+    fn try_from(args: AssertSinkArgs) -> Result<Self, Self::Error> {
+        let AssertSinkArgs {
+            fail_if_empty,
+            max_rows,
+            min_rows,
+            require_field,
+        } = args;
+
+        if let (Some(min_rows), Some(max_rows)) = (min_rows, max_rows) {
+            if min_rows > max_rows {
+                bail!("min_rows should not be greater than max_rows: {min_rows} > {max_rows}")
+            }
+        }
+        Ok(Self {
+            fail_if_empty,
+            max_rows,
+            min_rows,
+            require_field,
+        })
+    }
+}
+
+#[async_trait]
+impl PipeSink for AssertSink {
+    async fn call(&self, channel: PipeChannel) -> Result<()> {
+        let Self {
+            fail_if_empty,
+            max_rows,
+            min_rows,
+            require_field,
+        } = self;
+
+        // Fail fast on the per-object conditions
+        let mut iter = channel.into_stream::<LazyObject>().await?;
+        let mut rows = 0usize;
+        while let Some(item) = iter.try_next().await? {
+            let item = item.flatten().await?;
+            if let Some(key) = require_field {
+                if matches!(item.get_raw(key), Some(Value::Null) | None) {
+                    bail!("Assertion failed: object #{rows} has no field '{key}'")
+                }
+            }
+            rows += 1;
+            if let Some(max_rows) = *max_rows {
+                if rows > max_rows {
+                    bail!("Assertion failed: more than {max_rows} objects")
+                }
+            }
+        }
+
+        if *fail_if_empty && rows == 0 {
+            bail!("Assertion failed: no objects")
+        }
+        if let Some(min_rows) = *min_rows {
+            if rows < min_rows {
+                bail!("Assertion failed: {rows} objects, expected at least {min_rows}")
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use xlake_ast::Value;
+    use xlake_core::PipeSink;
+
+    use crate::testing::{channel, object};
+
+    use super::{AssertSink, AssertSinkArgs};
+
+    async fn check(args: AssertSinkArgs) -> anyhow::Result<()> {
+        let names = [Some("a".into()), Some(Value::Null), None];
+        let channel = channel(names.into_iter().map(|name| {
+            let name = name.map(|name| ("name", name));
+            object([("id", Value::from(1))].into_iter().chain(name))
+        }));
+        AssertSink::try_from(args)?.call(channel).await
+    }
+
+    #[::tokio::test]
+    async fn test_assert_pass() {
+        let args = AssertSinkArgs {
+            fail_if_empty: true,
+            max_rows: Some(3),
+            min_rows: Some(3),
+            require_field: Some("id".into()),
+        };
+        check(args).await.unwrap();
+    }
+
+    #[::tokio::test]
+    async fn test_assert_fail() {
+        let error = |args| async { check(args).await.unwrap_err().to_string() };
+        assert_eq!(
+            error(AssertSinkArgs {
+                min_rows: Some(4),
+                ..Default::default()
+            })
+            .await,
+            "Assertion failed: 3 objects, expected at least 4",
+        );
+        assert_eq!(
+            error(AssertSinkArgs {
+                max_rows: Some(2),
+                ..Default::default()
+            })
+            .await,
+            "Assertion failed: more than 2 objects",
+        );
+        assert_eq!(
+            error(AssertSinkArgs {
+                require_field: Some("name".into()),
+                ..Default::default()
+            })
+            .await,
+            "Assertion failed: object #1 has no field 'name'",
+        );
+    }
+
+    #[test]
+    fn test_assert_invalid_range() {
+        let args = AssertSinkArgs {
+            max_rows: Some(1),
+            min_rows: Some(2),
+            ..Default::default()
+        };
+        assert!(AssertSink::try_from(args).is_err());
+    }
+}
@@ -0,0 +1,261 @@
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use futures::{StreamExt, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+use tracing::debug;
+use xlake_ast::{PlanArguments, PlanKind};
+use xlake_core::{
+    object::LazyObject, stream::DefaultStream, ArgSpec, PipeChannel, PipeNodeFactory, PipeNodeImpl,
+    PipeSink, SinkSummary,
+};
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct CheckpointSinkFactory;
+
+impl fmt::Display for CheckpointSinkFactory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.kind().fmt(f)
+    }
+}
+
+#[async_trait]
+impl PipeNodeFactory for CheckpointSinkFactory {
+    fn kind(&self) -> PlanKind {
+        PlanKind::Sink { name: self.name() }
+    }
+
+    fn name(&self) -> String {
+        "checkpoint".into()
+    }
+
+    fn arguments(&self) -> Vec<ArgSpec> {
+        vec![
+            ArgSpec::optional("every", "Number of the written objects between the commits")
+                .with_default(CheckpointSinkArgs::default_every()),
+            ArgSpec::required(
+                "sink",
+                "Inner sink to write the objects, e.g. `'stdoutsink'`",
+            ),
+            ArgSpec::required(
+                "state",
+                "JSON file to commit the number of the written objects",
+            ),
+        ]
+    }
+
+    async fn build(&self, _args: &PlanArguments) -> Result<PipeNodeImpl> {
+        // The inner sink is resolved by the session
+        bail!("Cannot build {self} without a session")
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CheckpointSinkArgs {
+    #[serde(default = "CheckpointSinkArgs::default_every")]
+    pub every: u64,
+    pub sink: String,
+    pub state: PathBuf,
+}
+
+impl CheckpointSinkArgs {
+    const fn default_every() -> u64 {
+        100
+    }
+}
+
+/// The progress committed into the state file.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CheckpointState {
+    pub committed: u64,
+}
+
+/// Commits the number of the objects written by the inner sink, skipping them on the next run.
+///
+/// An object counts as written once the inner sink pulls the next one, or succeeds at the end,
+/// so the inner sink should write each object before pulling the next one.
+/// On a crash, the objects after the last commit are written again: at-least-once delivery.
+/// The src should replay the same objects from the start, e.g. without `offset`.
+#[derive(Debug)]
+pub struct CheckpointSink {
+    every: u64,
+    inner: Box<dyn PipeSink>,
+    state: PathBuf,
+}
+
+impl CheckpointSink {
+    pub fn new(inner: Box<dyn PipeSink>, every: u64, state: PathBuf) -> Result<Self> {
+        if every == 0 {
+            bail!("every should be positive")
+        }
+        Ok(Self {
+            every,
+            inner,
+            state,
+        })
+    }
+}
+
+#[async_trait]
+impl PipeSink for CheckpointSink {
+    async fn call(&self, channel: PipeChannel) -> Result<()> {
+        let Self {
+            every,
+            inner,
+            state,
+        } = self;
+        let every = *every;
+
+        let CheckpointState { committed } = load_state(state).await?;
+        if committed > 0 {
+            debug!(
+                "Resume from {}: skipping {committed} objects",
+                state.display()
+            );
+        }
+
+        // The number of the objects pulled by the inner sink in this run
+        let pulled = Arc::new(AtomicU64::default());
+        let models = channel.models();
+        let stream = {
+            let pulled = pulled.clone();
+            let state = state.clone();
+            channel
+                .into_stream::<LazyObject>()
+                .await?
+                .skip(committed.try_into()?)
+                .and_then(move |item| {
+                    // The objects pulled before are written
+                    let written = pulled.fetch_add(1, Ordering::SeqCst);
+                    let state = state.clone();
+                    async move {
+                        if written > 0 && written % every == 0 {
+                            let committed = committed + written;
+                            save_state(&state, CheckpointState { committed }).await?;
+                        }
+                        Ok(item)
+                    }
+                })
+                .boxed()
+        };
+        let channel =
+            PipeChannel::from_stream(DefaultStream::from_stream(stream)).with_models(models);
+
+        inner.call(channel).await?;
+        let committed = committed + pulled.load(Ordering::SeqCst);
+        save_state(state, CheckpointState { committed }).await
+    }
+
+    fn summary(&self) -> Option<SinkSummary> {
+        self.inner.summary()
+    }
+}
+
+/// Loads the progress, or starts from scratch if the state file is missing.
+async fn load_state(path: &Path) -> Result<CheckpointState> {
+    if !fs::try_exists(path).await? {
+        return Ok(CheckpointState::default());
+    }
+    let state = fs::read_to_string(path)
+        .await
+        .with_context(|| format!("Failed to read checkpoint state: {}", path.display()))?;
+    ::serde_json::from_str(&state)
+        .with_context(|| format!("Invalid checkpoint state: {}", path.display()))
+}
+
+/// Replaces the state file atomically, so that a crash keeps the previous commit.
+async fn save_state(path: &Path, state: CheckpointState) -> Result<()> {
+    debug!("Commit {}: {}", path.display(), state.committed);
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(".tmp");
+    fs::write(&temp, ::serde_json::to_vec(&state)?).await?;
+    fs::rename(&temp, path)
+        .await
+        .with_context(|| format!("Failed to write checkpoint state: {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use anyhow::{bail, Result};
+    use async_trait::async_trait;
+    use futures::TryStreamExt;
+    use xlake_core::{object::LazyObject, PipeChannel, PipeSink};
+
+    use crate::testing::object;
+
+    use super::{load_state, CheckpointSink, CheckpointState};
+
+    /// Records the indices of the objects, failing at the given one as if crashed.
+    #[derive(Debug)]
+    struct RecordSink {
+        crash_at: Option<i64>,
+        written: Arc<Mutex<Vec<i64>>>,
+    }
+
+    #[async_trait]
+    impl PipeSink for RecordSink {
+        async fn call(&self, channel: PipeChannel) -> Result<()> {
+            let mut stream = channel.into_stream::<LazyObject>().await?;
+            while let Some(item) = stream.try_next().await? {
+                let index = item.get_i64("index").unwrap();
+                if Some(index) == self.crash_at {
+                    bail!("Crashed at {index}")
+                }
+                self.written.lock().unwrap().push(index);
+            }
+            Ok(())
+        }
+    }
+
+    fn channel() -> PipeChannel {
+        crate::testing::channel((0..10).map(|index| object([("index", index)])))
+    }
+
+    #[::tokio::test]
+    async fn test_checkpoint_resume() {
+        let state =
+            ::std::env::temp_dir().join(format!("xlake-checkpoint-{}.json", ::std::process::id()));
+        let _ = ::std::fs::remove_file(&state);
+
+        let run = |crash_at| {
+            let written = Arc::new(Mutex::new(Vec::default()));
+            let inner = RecordSink {
+                crash_at,
+                written: written.clone(),
+            };
+            let sink = CheckpointSink::new(Box::new(inner), 3, state.clone()).unwrap();
+            async move {
+                let result = sink.call(channel()).await;
+                let written = written.lock().unwrap().clone();
+                (result, written)
+            }
+        };
+
+        // The commits are made on pulling the 4th and the 7th objects
+        let (result, written) = run(Some(7)).await;
+        assert!(result.is_err());
+        assert_eq!(written, [0, 1, 2, 3, 4, 5, 6]);
+        let committed = load_state(&state).await.unwrap();
+        assert_eq!(committed, CheckpointState { committed: 6 });
+
+        // The committed objects are skipped, while the uncommitted one is written again
+        let (result, written) = run(None).await;
+        result.unwrap();
+        assert_eq!(written, [6, 7, 8, 9]);
+        let committed = load_state(&state).await.unwrap();
+        assert_eq!(committed, CheckpointState { committed: 10 });
+
+        ::std::fs::remove_file(&state).unwrap();
+    }
+}
@@ -0,0 +1,300 @@
+use std::{fmt, path::PathBuf, time::Instant};
+
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use futures::TryStreamExt;
+use serde::{Deserialize, Serialize};
+use tokio::{fs, io::AsyncWriteExt};
+use tracing::{info, warn};
+use xlake_ast::{Object, PlanArguments, PlanKind, Value};
+use xlake_core::{
+    object::LazyObject, ArgSpec, PipeChannel, PipeNodeFactory, PipeNodeImpl, PipeSink, SinkSummary,
+    SinkSummaryCell,
+};
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct CsvSinkFactory;
+
+impl fmt::Display for CsvSinkFactory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.kind().fmt(f)
+    }
+}
+
+#[async_trait]
+impl PipeNodeFactory for CsvSinkFactory {
+    fn kind(&self) -> PlanKind {
+        PlanKind::Sink { name: self.name() }
+    }
+
+    fn name(&self) -> String {
+        "csv".into()
+    }
+
+    fn arguments(&self) -> Vec<ArgSpec> {
+        vec![
+            ArgSpec::optional(
+                "header_sample",
+                "Number of the leading objects to collect the columns from",
+            )
+            .with_default(CsvSink::default_header_sample()),
+            ArgSpec::optional("out", "Path of the output file, or stdout if not given"),
+        ]
+    }
+
+    async fn build(&self, args: &PlanArguments) -> Result<PipeNodeImpl> {
+        let imp: CsvSink = args.to()?;
+        if imp.header_sample == 0 {
+            bail!("header_sample should be positive")
+        }
+        Ok(PipeNodeImpl::Sink(Box::new(imp)))
+    }
+}
+
+/// Writes the objects as [RFC 4180](https://www.rfc-editor.org/rfc/rfc4180) rows,
+/// without converting them into the batches.
+///
+/// The columns are the keys of the leading `header_sample` objects, in the order of appearance.
+/// The keys appearing later are dropped, while the missing ones are left empty.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CsvSink {
+    #[serde(default = "CsvSink::default_header_sample")]
+    header_sample: usize,
+    #[serde(default)]
+    out: Option<PathBuf>,
+    #[serde(skip)]
+    summary: SinkSummaryCell,
+}
+
+impl CsvSink {
+    const fn default_header_sample() -> usize {
+        100
+    }
+}
+
+#[async_trait]
+impl PipeSink for CsvSink {
+    async fn call(&self, channel: PipeChannel) -> Result<()> {
+        let started = Instant::now();
+        let mut iter = channel.into_stream::<LazyObject>().await?;
+
+        // Collect the columns from the leading objects
+        let mut sample = Vec::with_capacity(self.header_sample);
+        while sample.len() < self.header_sample {
+            match iter.try_next().await? {
+                Some(item) => sample.push(item.flatten().await?),
+                None => break,
+            }
+        }
+        let mut header: Vec<String> = Vec::default();
+        for item in &sample {
+            for key in item.content().keys() {
+                if !header.contains(key) {
+                    header.push(key.clone());
+                }
+            }
+        }
+
+        let mut file = match &self.out {
+            Some(path) => Some(fs::File::create(path).await?),
+            None => None,
+        };
+        let mut writer = CsvWriter {
+            file: file.as_mut(),
+            header: &header,
+            dropped: Vec::default(),
+            bytes: 0,
+        };
+        writer
+            .write_record(header.iter().map(String::as_str))
+            .await?;
+
+        let mut count = 0usize;
+        for item in sample {
+            writer.write_object(item.content()).await?;
+            count += 1;
+        }
+        while let Some(item) = iter.try_next().await? {
+            let item = item.flatten().await?;
+            writer.write_object(item.content()).await?;
+            count += 1;
+        }
+
+        if !writer.dropped.is_empty() {
+            warn!(
+                "Dropped the columns beyond the header sample: {:?}",
+                &writer.dropped,
+            );
+        }
+        let bytes = writer.bytes;
+        if let Some(file) = file.as_mut() {
+            file.flush().await?;
+            info!(
+                "Wrote {count} objects into {:?}",
+                self.out.as_ref().unwrap()
+            );
+        }
+
+        self.summary.set(SinkSummary {
+            items: count,
+            bytes,
+            duration: started.elapsed(),
+        });
+        Ok(())
+    }
+
+    fn summary(&self) -> Option<SinkSummary> {
+        self.summary.get()
+    }
+}
+
+struct CsvWriter<'a> {
+    file: Option<&'a mut fs::File>,
+    header: &'a [String],
+    /// The keys missing in the header, to be reported once
+    dropped: Vec<String>,
+    /// The bytes written so far, including the header
+    bytes: u64,
+}
+
+impl CsvWriter<'_> {
+    async fn write_object(&mut self, object: &Object) -> Result<()> {
+        for key in object.keys() {
+            if !self.header.contains(key) && !self.dropped.contains(key) {
+                self.dropped.push(key.clone());
+            }
+        }
+
+        let fields = self
+            .header
+            .iter()
+            .map(|key| object.get(key).map(render).transpose())
+            .collect::<Result<Vec<_>>>()?;
+        self.write_record(fields.iter().map(|field| field.as_deref().unwrap_or("")))
+            .await
+    }
+
+    async fn write_record<'a>(&mut self, fields: impl Iterator<Item = &'a str>) -> Result<()> {
+        let row = encode_record(fields)?;
+        self.bytes += row.len() as u64;
+        match self.file.as_mut() {
+            Some(file) => file.write_all(&row).await?,
+            None => print!("{}", String::from_utf8(row)?),
+        }
+        Ok(())
+    }
+}
+
+/// Encodes a row, quoting the fields having the delimiters, quotes or newlines.
+fn encode_record<'a>(fields: impl Iterator<Item = &'a str>) -> Result<Vec<u8>> {
+    let mut writer = ::csv::WriterBuilder::new()
+        .terminator(::csv::Terminator::CRLF)
+        .from_writer(Vec::default());
+    writer.write_record(fields)?;
+    writer
+        .into_inner()
+        .map_err(|error| error.into_error().into())
+}
+
+/// Renders a value as a field, encoding the binaries in base64 and the others in JSON.
+fn render(value: &Value) -> Result<String> {
+    match value {
+        Value::Null => Ok(String::default()),
+        Value::String(value) => Ok(value.clone()),
+        // The serialized form of the binaries is base64
+        Value::Binary(_) => match ::serde_json::to_value(value)? {
+            ::serde_json::Value::String(value) => Ok(value),
+            _ => bail!("Binary is not serialized as base64"),
+        },
+        value => ::serde_json::to_string(value).map_err(Into::into),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use xlake_ast::{Binary, Value};
+    use xlake_core::PipeSink;
+
+    use crate::testing::{channel, object};
+
+    use super::CsvSink;
+
+    #[cfg(feature = "preserve-order")]
+    #[::tokio::test]
+    async fn test_csv_column_order() {
+        let dir = ::std::env::temp_dir().join(format!("xlake-csv-order-{}", ::std::process::id()));
+        ::tokio::fs::create_dir_all(&dir).await.unwrap();
+        let (input, out) = (dir.join("input.csv"), dir.join("output.csv"));
+
+        // The columns are not sorted, to be kept in the file order
+        let text = "name,id,age\r\nfoo,1,42\r\nbar,2,7\r\n";
+        ::tokio::fs::write(&input, text).await.unwrap();
+        crate::PipeSession::default()
+            .call(&format!(
+                "csvsrc ordered=true path='{}' ! csvsink out='{}'",
+                input.display(),
+                out.display(),
+            ))
+            .await
+            .unwrap();
+
+        let output = ::tokio::fs::read_to_string(&out).await.unwrap();
+        ::tokio::fs::remove_dir_all(&dir).await.unwrap();
+        assert_eq!(output, text);
+    }
+
+    #[::tokio::test]
+    async fn test_csv_escape() {
+        let out = ::std::env::temp_dir().join(format!("xlake-csv-{}.csv", ::std::process::id()));
+        let sink = CsvSink {
+            header_sample: 2,
+            out: Some(out.clone()),
+            summary: Default::default(),
+        };
+
+        let rows: [&[(&str, Value)]; 3] = [
+            &[
+                ("name", "Doe, John".into()),
+                ("quote", r#"say "hi""#.into()),
+            ],
+            &[
+                ("name", "multi\nline".into()),
+                ("data", Binary(b"xyz".to_vec()).into()),
+            ],
+            // The key beyond the header sample is dropped
+            &[("name", 42.into()), ("late", true.into())],
+        ];
+        let channel = channel(rows.into_iter().map(|row| object(row.iter().cloned())));
+        sink.call(channel).await.unwrap();
+
+        let output = ::tokio::fs::read_to_string(&out).await.unwrap();
+        ::tokio::fs::remove_file(&out).await.unwrap();
+        let summary = sink.summary().unwrap();
+        assert_eq!(summary.items, 3);
+        assert_eq!(summary.bytes, output.len() as u64);
+        assert_eq!(
+            output,
+            concat!(
+                "name,quote,data\r\n",
+                "\"Doe, John\",\"say \"\"hi\"\"\",\r\n",
+                "\"multi\nline\",,eHl6\r\n",
+                "42,,\r\n",
+            ),
+        );
+
+        // The output is read back as it was
+        let mut reader = ::csv::Reader::from_reader(output.as_bytes());
+        let records: Vec<Vec<String>> = reader
+            .records()
+            .map(|record| record.unwrap().iter().map(Into::into).collect())
+            .collect();
+        assert_eq!(
+            records,
+            [
+                ["Doe, John", r#"say "hi""#, ""],
+                ["multi\nline", "", "eHl6"],
+                ["42", "", ""],
+            ],
+        );
+    }
+}
@@ -0,0 +1,113 @@
+use std::fmt;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use futures::TryStreamExt;
+use serde::{Deserialize, Serialize};
+use xlake_ast::{Object, PlanArguments, PlanKind};
+use xlake_core::{object::LazyObject, PipeChannel, PipeNodeFactory, PipeNodeImpl, PipeSink};
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct KvSinkFactory;
+
+impl fmt::Display for KvSinkFactory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.kind().fmt(f)
+    }
+}
+
+#[async_trait]
+impl PipeNodeFactory for KvSinkFactory {
+    fn kind(&self) -> PlanKind {
+        PlanKind::Sink { name: self.name() }
+    }
+
+    fn name(&self) -> String {
+        "kv".into()
+    }
+
+    async fn build(&self, args: &PlanArguments) -> Result<PipeNodeImpl> {
+        let imp: KvSink = args.to()?;
+        Ok(PipeNodeImpl::Sink(Box::new(imp)))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct KvSink {}
+
+impl KvSink {
+    /// Renders the object as the `key: value` lines, aligning the values.
+    fn render(content: &Object) -> String {
+        let width = content
+            .keys()
+            .map(|key| key.chars().count())
+            .max()
+            .unwrap_or_default();
+        content
+            .iter()
+            .map(|(key, value)| format!("{key:width$}: {value}"))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+#[async_trait]
+impl PipeSink for KvSink {
+    async fn call(&self, channel: PipeChannel) -> Result<()> {
+        let mut iter = channel.into_stream::<LazyObject>().await?;
+        let mut index = 0usize;
+        while let Some(item) = iter.try_next().await? {
+            let item = item.flatten().await?;
+            if index > 0 {
+                println!("---");
+            }
+            index += 1;
+
+            let content = item.content();
+            if !content.is_empty() {
+                println!("{}", Self::render(content));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use xlake_ast::{Binary, Object, Value};
+    use xlake_core::PipeSink;
+
+    use crate::testing::{channel, object};
+
+    use super::KvSink;
+
+    fn sample() -> Object {
+        object([
+            ("id", Value::from(1)),
+            ("name", "foo".into()),
+            ("thumbnail", Value::Binary(Binary(vec![0; 4]))),
+        ])
+    }
+
+    #[::tokio::test]
+    async fn test_kv_sink() {
+        let channel = channel((0..2).map(|_| sample()));
+        KvSink {}.call(channel).await.unwrap();
+    }
+
+    #[test]
+    fn test_kv_render() {
+        let object = sample();
+        let output = KvSink::render(&object);
+
+        // Each key is read back from its own line, with the values aligned
+        let lines: Vec<_> = output
+            .lines()
+            .map(|line| line.split_once(": ").unwrap())
+            .collect();
+        let keys: Vec<_> = lines.iter().map(|(key, _)| key.trim_end()).collect();
+        assert_eq!(keys, object.keys().map(String::as_str).collect::<Vec<_>>());
+        assert!(lines.iter().all(|(key, _)| key.len() == "thumbnail".len()));
+        assert_eq!(lines[2].1, "Binary(4 bytes)");
+    }
+}
@@ -1,2 +1,15 @@
+pub mod assert;
+#[cfg(feature = "fs")]
+pub mod checkpoint;
+#[cfg(feature = "csv")]
+pub mod csv;
+#[cfg(feature = "io-std")]
+pub mod kv;
+#[cfg(feature = "io-std")]
+pub mod model_histogram;
+#[cfg(feature = "fs")]
+pub mod rotate;
 #[cfg(feature = "io-std")]
 pub mod stdout;
+#[cfg(feature = "template")]
+pub mod template;
@@ -0,0 +1,128 @@
+use std::{collections::BTreeMap, fmt};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use futures::TryStreamExt;
+use serde::{Deserialize, Serialize};
+use xlake_ast::{PlanArguments, PlanKind};
+use xlake_core::{object::LazyObject, PipeChannel, PipeNodeFactory, PipeNodeImpl, PipeSink};
+
+/// The label of the number of all the objects, which is not a valid model name.
+const TOTAL: &str = "(total)";
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct ModelHistogramSinkFactory;
+
+impl fmt::Display for ModelHistogramSinkFactory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.kind().fmt(f)
+    }
+}
+
+#[async_trait]
+impl PipeNodeFactory for ModelHistogramSinkFactory {
+    fn kind(&self) -> PlanKind {
+        PlanKind::Sink { name: self.name() }
+    }
+
+    fn name(&self) -> String {
+        "model_histogram".into()
+    }
+
+    async fn build(&self, args: &PlanArguments) -> Result<PipeNodeImpl> {
+        let imp: ModelHistogramSink = args.to()?;
+        Ok(PipeNodeImpl::Sink(Box::new(imp)))
+    }
+}
+
+/// Prints how many objects provide each model at the end of stream, e.g. to debug the models.
+///
+/// The objects are not flattened, so the models of the pending layers are counted too.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ModelHistogramSink {}
+
+impl ModelHistogramSink {
+    async fn tally(&self, channel: PipeChannel) -> Result<ModelHistogram> {
+        let mut iter = channel.into_stream::<LazyObject>().await?;
+        let mut histogram = ModelHistogram::default();
+        while let Some(item) = iter.try_next().await? {
+            histogram.total += 1;
+            for model in item.models() {
+                *histogram.models.entry(model).or_default() += 1;
+            }
+        }
+        Ok(histogram)
+    }
+}
+
+#[async_trait]
+impl PipeSink for ModelHistogramSink {
+    async fn call(&self, channel: PipeChannel) -> Result<()> {
+        let ModelHistogram { models, total } = self.tally(channel).await?;
+
+        let width = models
+            .keys()
+            .map(|model| model.chars().count())
+            .chain([TOTAL.len()])
+            .max()
+            .unwrap_or_default();
+        for (model, count) in &models {
+            println!("{model:width$}: {count}");
+        }
+        println!("{TOTAL:width$}: {total}");
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default)]
+struct ModelHistogram {
+    /// The number of the objects per model
+    models: BTreeMap<String, usize>,
+    total: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use xlake_ast::Object;
+    use xlake_core::{
+        object::{LazyObject, ObjectLayer},
+        PipeChannel,
+    };
+
+    use super::ModelHistogramSink;
+
+    #[::tokio::test]
+    async fn test_model_histogram() {
+        let item = |models: &[&[&str]]| {
+            let mut layers = models.iter().map(|models| {
+                let models: BTreeSet<_> = models.iter().map(ToString::to_string).collect();
+                ObjectLayer::from_object(Object::default(), models)
+            });
+            let mut item = LazyObject::from(layers.next().unwrap());
+            layers.for_each(|layer| item.append_layer(layer));
+            item
+        };
+        let channel: PipeChannel = [
+            item(&[&["binary", "file"], &["hash"]]),
+            item(&[&["binary"], &["doc"]]),
+            item(&[&["doc"]]),
+            item(&[&[]]),
+        ]
+        .into_iter()
+        .collect();
+
+        let histogram = ModelHistogramSink {}.tally(channel).await.unwrap();
+        assert_eq!(histogram.total, 4);
+        assert_eq!(
+            histogram.models.into_iter().collect::<Vec<_>>(),
+            [
+                ("binary".into(), 2),
+                ("doc".into(), 2),
+                ("file".into(), 1),
+                ("hash".into(), 1),
+            ],
+        );
+    }
+}
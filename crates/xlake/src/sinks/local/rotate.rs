@@ -0,0 +1,213 @@
+use std::{ffi::OsString, fmt, path::PathBuf, time::Instant};
+
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use futures::TryStreamExt;
+use serde::{Deserialize, Serialize};
+use tokio::{
+    fs,
+    io::{AsyncWriteExt, BufWriter},
+};
+use tracing::{debug, info};
+use xlake_ast::{PlanArguments, PlanKind};
+use xlake_core::{
+    object::LazyObject, ArgSpec, PipeChannel, PipeNodeFactory, PipeNodeImpl, PipeSink, SinkSummary,
+    SinkSummaryCell,
+};
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct RotateSinkFactory;
+
+impl fmt::Display for RotateSinkFactory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.kind().fmt(f)
+    }
+}
+
+#[async_trait]
+impl PipeNodeFactory for RotateSinkFactory {
+    fn kind(&self) -> PlanKind {
+        PlanKind::Sink { name: self.name() }
+    }
+
+    fn name(&self) -> String {
+        "rotate".into()
+    }
+
+    fn arguments(&self) -> Vec<ArgSpec> {
+        vec![
+            ArgSpec::optional("max_bytes", "Maximum size of each file in bytes")
+                .with_default(RotateSink::default_max_bytes()),
+            ArgSpec::optional(
+                "max_files",
+                "Number of the latest files to keep, or all if not given",
+            ),
+            ArgSpec::required("path", "Path of the output files, suffixed with `.N`"),
+        ]
+    }
+
+    async fn build(&self, args: &PlanArguments) -> Result<PipeNodeImpl> {
+        let imp: RotateSink = args.to()?;
+        if imp.max_bytes == 0 {
+            bail!("max_bytes should be positive")
+        }
+        if imp.max_files == Some(0) {
+            bail!("max_files should be positive")
+        }
+        Ok(PipeNodeImpl::Sink(Box::new(imp)))
+    }
+}
+
+/// Writes the objects as NDJSON into `path.0`, `path.1`, ..., rolling over at `max_bytes`.
+///
+/// An object larger than `max_bytes` is written into a file of its own, rather than split.
+/// Once there are more than `max_files` files, the oldest ones are removed.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RotateSink {
+    #[serde(default = "RotateSink::default_max_bytes")]
+    max_bytes: u64,
+    #[serde(default)]
+    max_files: Option<usize>,
+    path: PathBuf,
+    #[serde(skip)]
+    summary: SinkSummaryCell,
+}
+
+impl RotateSink {
+    const fn default_max_bytes() -> u64 {
+        10 * 1024 * 1024
+    }
+
+    fn path(&self, index: usize) -> PathBuf {
+        let mut path = OsString::from(&self.path);
+        path.push(format!(".{index}"));
+        path.into()
+    }
+}
+
+#[async_trait]
+impl PipeSink for RotateSink {
+    async fn call(&self, channel: PipeChannel) -> Result<()> {
+        let started = Instant::now();
+        let mut iter = channel.into_stream::<LazyObject>().await?;
+        let mut writer = RotateWriter::open(self).await?;
+
+        let mut count = 0usize;
+        let mut bytes = 0u64;
+        while let Some(item) = iter.try_next().await? {
+            let item = item.flatten().await?;
+            let mut line = item.content().to_vec()?;
+            line.push(b'\n');
+            writer.write_line(&line).await?;
+            count += 1;
+            bytes += line.len() as u64;
+        }
+
+        let files = writer.index + 1;
+        writer.file.flush().await?;
+        info!("Wrote {count} objects into {files} files: {:?}", &self.path);
+
+        self.summary.set(SinkSummary {
+            items: count,
+            bytes,
+            duration: started.elapsed(),
+        });
+        Ok(())
+    }
+
+    fn summary(&self) -> Option<SinkSummary> {
+        self.summary.get()
+    }
+}
+
+struct RotateWriter<'a> {
+    file: BufWriter<fs::File>,
+    /// The index of the current file
+    index: usize,
+    sink: &'a RotateSink,
+    /// The bytes written into the current file
+    size: u64,
+}
+
+impl<'a> RotateWriter<'a> {
+    async fn open(sink: &'a RotateSink) -> Result<Self> {
+        let file = fs::File::create(sink.path(0)).await?;
+        Ok(Self {
+            file: BufWriter::new(file),
+            index: 0,
+            sink,
+            size: 0,
+        })
+    }
+
+    async fn write_line(&mut self, line: &[u8]) -> Result<()> {
+        let len = line.len() as u64;
+        if self.size > 0 && self.size + len > self.sink.max_bytes {
+            self.roll().await?;
+        }
+        self.file.write_all(line).await?;
+        self.size += len;
+        Ok(())
+    }
+
+    async fn roll(&mut self) -> Result<()> {
+        // Complete the current file before moving on
+        self.file.flush().await?;
+
+        self.index += 1;
+        let path = self.sink.path(self.index);
+        debug!("Roll over to {path:?}");
+        self.file = BufWriter::new(fs::File::create(path).await?);
+        self.size = 0;
+
+        // Remove the oldest file beyond the retention
+        if let Some(max_files) = self.sink.max_files {
+            if let Some(index) = self.index.checked_sub(max_files) {
+                fs::remove_file(self.sink.path(index)).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use xlake_ast::Value;
+    use xlake_core::PipeSink;
+
+    use crate::testing::{channel, object};
+
+    use super::RotateSink;
+
+    #[::tokio::test]
+    async fn test_rotate_sink() {
+        let dir = ::std::env::temp_dir().join(format!("xlake-rotate-{}", ::std::process::id()));
+        ::tokio::fs::create_dir_all(&dir).await.unwrap();
+        let sink = RotateSink {
+            max_bytes: 64,
+            max_files: Some(3),
+            path: dir.join("out.ndjson"),
+            summary: Default::default(),
+        };
+
+        // Each line is 22 bytes, so that each file has 2 lines
+        let channel =
+            channel((0..10).map(|id| object([("id", Value::from(id)), ("name", "foo".into())])));
+        sink.call(channel).await.unwrap();
+
+        // The oldest files are removed, while the last one is flushed
+        for index in [0, 1, 5] {
+            assert!(!sink.path(index).exists(), "{index}");
+        }
+        for (index, ids) in [(2, [4, 5]), (3, [6, 7]), (4, [8, 9])] {
+            let output = ::tokio::fs::read_to_string(sink.path(index)).await.unwrap();
+            let expected: String = ids
+                .iter()
+                .map(|id| format!("{{\"id\":{id},\"name\":\"foo\"}}\n"))
+                .collect();
+            assert_eq!(output, expected);
+        }
+
+        ::tokio::fs::remove_dir_all(&dir).await.unwrap();
+    }
+}
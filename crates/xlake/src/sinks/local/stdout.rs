@@ -1,15 +1,26 @@
-use std::fmt;
+use std::{collections::BTreeMap, fmt, time::Instant};
 
-use anyhow::Result;
+use anyhow::{anyhow, bail, Error, Result};
 use async_trait::async_trait;
 use futures::TryStreamExt;
 use serde::{Deserialize, Serialize};
+use tracing::warn;
 use xlake_ast::{PlanArguments, PlanKind};
 use xlake_core::{
-    object::LazyObject, PipeChannel, PipeModelOwnedExt, PipeNodeFactory, PipeNodeImpl, PipeSink,
+    object::LazyObject, ArgSpec, PipeChannel, PipeModelOwnedExt, PipeNodeFactory, PipeNodeImpl,
+    PipeSink, SinkSummary, SinkSummaryCell,
 };
 
-use crate::models::builtins::{binary::BinaryModelView, doc::DocModelView};
+use crate::{
+    models::builtins::{
+        binary::{self, BinaryModelView},
+        doc::{self, DocModelView},
+    },
+    sinks::{
+        binary::BinaryMode,
+        limit::{ByteQuota, QuotaExceedAction},
+    },
+};
 
 #[derive(Copy, Clone, Debug, Default)]
 pub struct StdoutSinkFactory;
@@ -30,38 +41,299 @@ impl PipeNodeFactory for StdoutSinkFactory {
         "stdout".into()
     }
 
+    fn arguments(&self) -> Vec<ArgSpec> {
+        vec![
+            ArgSpec::optional(
+                "binary",
+                "`base64`, `omit` or `summary` of the binary values in JSON",
+            )
+            .with_default("base64"),
+            ArgSpec::optional("max_bytes", "Maximum total output size in bytes"),
+            ArgSpec::optional("on_exceed", "`error` or `stop` on reaching max_bytes")
+                .with_default("error"),
+            ArgSpec::optional("pretty", "Print each object as a multi-line JSON")
+                .with_default(true),
+            ArgSpec::optional("prefer", "Comma-separated views to be attempted in order")
+                .with_default(StdoutSinkArgs::default_prefer()),
+        ]
+    }
+
     async fn build(&self, args: &PlanArguments) -> Result<PipeNodeImpl> {
-        let imp: StdoutSink = args.to()?;
+        let args: StdoutSinkArgs = args.to()?;
+        let imp = StdoutSink::try_from(args)?;
         Ok(PipeNodeImpl::Sink(Box::new(imp)))
     }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-pub struct StdoutSink {}
+struct StdoutSinkArgs {
+    #[serde(default)]
+    binary: BinaryMode,
+    #[serde(default)]
+    max_bytes: Option<u64>,
+    #[serde(default)]
+    on_exceed: QuotaExceedAction,
+    #[serde(default = "StdoutSinkArgs::default_pretty")]
+    pretty: bool,
+    #[serde(default = "StdoutSinkArgs::default_prefer")]
+    prefer: String,
+}
+
+impl StdoutSinkArgs {
+    const fn default_pretty() -> bool {
+        true
+    }
+
+    fn default_prefer() -> String {
+        "doc,binary,json".into()
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+struct RenderOptions {
+    binary: BinaryMode,
+    pretty: bool,
+}
+
+/// Renders the object if it fits the view, or gives it back otherwise.
+type RenderFn = fn(LazyObject, RenderOptions) -> Result<Result<String, LazyObject>>;
+
+fn render_doc(item: LazyObject, _options: RenderOptions) -> Result<Result<String, LazyObject>> {
+    Ok(item
+        .view::<DocModelView>()
+        .map(|mut item| item.document().to_string()))
+}
+
+fn render_binary(item: LazyObject, _options: RenderOptions) -> Result<Result<String, LazyObject>> {
+    Ok(item.view::<BinaryModelView>().map(|mut item| {
+        let _ = item.content();
+        item.to_string()
+    }))
+}
+
+fn render_json(mut item: LazyObject, options: RenderOptions) -> Result<Result<String, LazyObject>> {
+    options.binary.apply(item.content_mut());
+    if options.pretty {
+        item.to_string_pretty().map(Ok)
+    } else {
+        // One object per line
+        item.to_string_compact().map(Ok)
+    }
+}
+
+/// Maps each view name into its render function.
+fn registry() -> BTreeMap<&'static str, RenderFn> {
+    [
+        (binary::consts::NAME, render_binary as RenderFn),
+        (doc::consts::NAME, render_doc),
+        ("json", render_json),
+    ]
+    .into_iter()
+    .collect()
+}
+
+#[derive(Clone, Debug)]
+pub struct StdoutSink {
+    max_bytes: Option<u64>,
+    on_exceed: QuotaExceedAction,
+    options: RenderOptions,
+    prefer: Vec<RenderFn>,
+    summary: SinkSummaryCell,
+}
+
+impl TryFrom<StdoutSinkArgs> for StdoutSink {
+    type Error = Error;
+
+    fn try_from(args: StdoutSinkArgs) -> Result<Self, Self::Error> {
+        let StdoutSinkArgs {
+            binary,
+            max_bytes,
+            on_exceed,
+            pretty,
+            prefer,
+        } = args;
+
+        let registry = registry();
+        let prefer = prefer
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(|name| {
+                registry
+                    .get(name)
+                    .copied()
+                    .ok_or_else(|| anyhow!("No such view: '{name}'"))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        if prefer.is_empty() {
+            bail!("prefer should not be empty")
+        }
+        Ok(Self {
+            max_bytes,
+            on_exceed,
+            options: RenderOptions { binary, pretty },
+            prefer,
+            summary: Default::default(),
+        })
+    }
+}
+
+impl StdoutSink {
+    /// Renders the object with the first matched view.
+    fn render(&self, mut item: LazyObject) -> Result<String> {
+        for render in &self.prefer {
+            match render(item, self.options)? {
+                Ok(output) => return Ok(output),
+                Err(next) => item = next,
+            }
+        }
+        bail!("No preferred view matched: {item:?}")
+    }
+}
 
 #[async_trait]
 impl PipeSink for StdoutSink {
     async fn call(&self, channel: PipeChannel) -> Result<()> {
+        let started = Instant::now();
+        let mut quota = ByteQuota::new(self.max_bytes, self.on_exceed);
+        let mut items = 0;
         let mut iter = channel.into_stream::<LazyObject>().await?;
         while let Some(item) = iter.try_next().await? {
             let item = item.flatten().await?;
-            let item = match item.view::<DocModelView>() {
-                Ok(mut item) => {
-                    println!("{}", item.document());
-                    continue;
-                }
-                Err(item) => item,
-            };
-            let item = match item.view::<BinaryModelView>() {
-                Ok(mut item) => {
-                    let _ = item.content();
-                    println!("{item}");
-                    continue;
-                }
-                Err(item) => item,
-            };
-            println!("{}", item.to_string_pretty()?);
+            let output = self.render(item)?;
+
+            // Count the trailing newline as well
+            if !quota.try_consume(output.len() + 1)? {
+                warn!(
+                    "Stopped writing: reached {} bytes (max_bytes)",
+                    quota.written(),
+                );
+                break;
+            }
+            println!("{output}");
+            items += 1;
         }
+
+        self.summary.set(SinkSummary {
+            items,
+            bytes: quota.written(),
+            duration: started.elapsed(),
+        });
         Ok(())
     }
+
+    fn summary(&self) -> Option<SinkSummary> {
+        self.summary.get()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use xlake_ast::{Binary, Value};
+    use xlake_core::{
+        object::{LazyObject, ObjectLayer},
+        PipeSink,
+    };
+
+    use crate::{
+        models::builtins::doc::DocModelObject,
+        sinks::limit::QuotaExceedAction,
+        testing::{channel, object},
+    };
+
+    use super::{StdoutSink, StdoutSinkArgs};
+
+    fn render(prefer: &str) -> String {
+        let args = StdoutSinkArgs {
+            binary: Default::default(),
+            max_bytes: None,
+            on_exceed: Default::default(),
+            pretty: false,
+            prefer: prefer.into(),
+        };
+        let sink = StdoutSink::try_from(args).unwrap();
+
+        let item: LazyObject = DocModelObject {
+            document: "hello".into(),
+        }
+        .into();
+        sink.render(item).unwrap()
+    }
+
+    #[test]
+    fn test_stdout_prefer_doc() {
+        assert_eq!(render("doc,binary,json"), "hello");
+    }
+
+    #[test]
+    fn test_stdout_prefer_json() {
+        assert_eq!(render("json"), r#"{"document":"hello"}"#);
+        assert_eq!(render("binary, json"), r#"{"document":"hello"}"#);
+    }
+
+    #[test]
+    fn test_stdout_prefer_invalid() {
+        let args = |prefer: &str| StdoutSinkArgs {
+            binary: Default::default(),
+            max_bytes: None,
+            on_exceed: Default::default(),
+            pretty: true,
+            prefer: prefer.into(),
+        };
+        assert!(StdoutSink::try_from(args("xml")).is_err());
+        assert!(StdoutSink::try_from(args("")).is_err());
+    }
+
+    #[test]
+    fn test_stdout_binary() {
+        let render = |binary: &str| {
+            let args = StdoutSinkArgs {
+                binary: ::serde_json::from_value(binary.into()).unwrap(),
+                max_bytes: None,
+                on_exceed: Default::default(),
+                pretty: false,
+                prefer: "json".into(),
+            };
+            let sink = StdoutSink::try_from(args).unwrap();
+
+            let object = object([
+                ("content", Value::from(Binary(vec![0; 4096]))),
+                ("name", "foo".into()),
+            ]);
+            sink.render(ObjectLayer::from_object_dyn(object).into())
+                .unwrap()
+        };
+
+        assert!(render("base64").starts_with(r#"{"content":"AAAA"#));
+        assert_eq!(render("omit"), r#"{"name":"foo"}"#);
+        assert_eq!(
+            render("summary"),
+            r#"{"content":"Binary(4,096 bytes)","name":"foo"}"#,
+        );
+    }
+
+    #[::tokio::test]
+    async fn test_stdout_summary() {
+        let summary = |max_bytes| async move {
+            let args = StdoutSinkArgs {
+                binary: Default::default(),
+                max_bytes,
+                on_exceed: QuotaExceedAction::Stop,
+                pretty: false,
+                prefer: "json".into(),
+            };
+            let sink = StdoutSink::try_from(args).unwrap();
+            assert!(sink.summary().is_none());
+
+            // Each line is 9 bytes, including the newline
+            let channel = channel((0..3).map(|id| object([("id", id)])));
+            sink.call(channel).await.unwrap();
+            let summary = sink.summary().unwrap();
+            (summary.items, summary.bytes)
+        };
+
+        assert_eq!(summary(None).await, (3, 27));
+        // The objects beyond the quota are not counted
+        assert_eq!(summary(Some(20)).await, (2, 18));
+    }
 }
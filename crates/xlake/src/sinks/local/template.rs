@@ -0,0 +1,306 @@
+use std::{fmt, io, path::PathBuf};
+
+use anyhow::{Error, Result};
+use async_trait::async_trait;
+use futures::TryStreamExt;
+use handlebars::Handlebars;
+use serde::{Deserialize, Serialize};
+use tokio::{fs, io::AsyncWriteExt};
+use tracing::{debug, info, warn};
+use xlake_ast::{Object, PlanArguments, PlanKind};
+use xlake_core::{
+    object::LazyObject, ArgSpec, PipeChannel, PipeNodeFactory, PipeNodeImpl, PipeSink,
+};
+
+use crate::sinks::limit::{ByteQuota, QuotaExceedAction};
+
+/// The name of the only registered template.
+const NAME: &str = "template";
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct TemplateSinkFactory;
+
+impl fmt::Display for TemplateSinkFactory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.kind().fmt(f)
+    }
+}
+
+#[async_trait]
+impl PipeNodeFactory for TemplateSinkFactory {
+    fn kind(&self) -> PlanKind {
+        PlanKind::Sink { name: self.name() }
+    }
+
+    fn name(&self) -> String {
+        "template".into()
+    }
+
+    fn arguments(&self) -> Vec<ArgSpec> {
+        vec![
+            ArgSpec::required("template", "Path of the Handlebars template file"),
+            ArgSpec::optional(
+                "if_absent",
+                "Skip writing if the output file already exists",
+            )
+            .with_default(false),
+            ArgSpec::optional("max_bytes", "Maximum total output size in bytes"),
+            ArgSpec::optional("on_exceed", "`error` or `stop` on reaching max_bytes")
+                .with_default("error"),
+            ArgSpec::optional("out", "Path of the output file, or stdout if not given"),
+            ArgSpec::optional("separator", "Text between the renders").with_default("\n"),
+        ]
+    }
+
+    async fn build(&self, args: &PlanArguments) -> Result<PipeNodeImpl> {
+        let args: TemplateSinkArgs = args.to()?;
+        let template = fs::read_to_string(&args.template).await?;
+        let imp = TemplateSink::new(args, &template)?;
+        Ok(PipeNodeImpl::Sink(Box::new(imp)))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct TemplateSinkArgs {
+    template: PathBuf,
+    #[serde(default)]
+    if_absent: bool,
+    #[serde(default)]
+    max_bytes: Option<u64>,
+    #[serde(default)]
+    on_exceed: QuotaExceedAction,
+    #[serde(default)]
+    out: Option<PathBuf>,
+    #[serde(default = "TemplateSinkArgs::default_separator")]
+    separator: String,
+}
+
+impl TemplateSinkArgs {
+    fn default_separator() -> String {
+        "\n".into()
+    }
+}
+
+#[derive(Debug)]
+pub struct TemplateSink {
+    if_absent: bool,
+    lenient: Handlebars<'static>,
+    max_bytes: Option<u64>,
+    on_exceed: QuotaExceedAction,
+    out: Option<PathBuf>,
+    separator: String,
+    strict: Handlebars<'static>,
+}
+
+impl TemplateSink {
+    fn new(args: TemplateSinkArgs, template: &str) -> Result<Self> {
+        let TemplateSinkArgs {
+            template: _,
+            if_absent,
+            max_bytes,
+            on_exceed,
+            out,
+            separator,
+        } = args;
+
+        // The outputs are not necessarily HTML
+        let mut lenient = Handlebars::new();
+        lenient.register_escape_fn(::handlebars::no_escape);
+        lenient.register_template_string(NAME, template)?;
+
+        let mut strict = lenient.clone();
+        strict.set_strict_mode(true);
+        Ok(Self {
+            if_absent,
+            lenient,
+            max_bytes,
+            on_exceed,
+            out,
+            separator,
+            strict,
+        })
+    }
+
+    fn render(&self, object: &Object) -> Result<String> {
+        // Detect the missing variables first, then render them empty
+        self.strict.render(NAME, object).or_else(|error| {
+            debug!("Missing template variable: {error}");
+            self.lenient.render(NAME, object).map_err(Error::from)
+        })
+    }
+}
+
+#[async_trait]
+impl PipeSink for TemplateSink {
+    async fn call(&self, channel: PipeChannel) -> Result<()> {
+        let mut iter = channel.into_stream::<LazyObject>().await?;
+
+        let mut file = match &self.out {
+            Some(path) if self.if_absent => {
+                let file = fs::OpenOptions::new()
+                    .write(true)
+                    .create_new(true)
+                    .open(path)
+                    .await;
+                match file {
+                    Ok(file) => Some(file),
+                    // Consume the objects without writing, e.g. to be cached by the stores
+                    Err(error) if error.kind() == io::ErrorKind::AlreadyExists => {
+                        let mut skipped = 0usize;
+                        while let Some(item) = iter.try_next().await? {
+                            item.flatten().await?;
+                            skipped += 1;
+                        }
+                        info!("Skipped {skipped} objects: {path:?} already exists");
+                        return Ok(());
+                    }
+                    Err(error) => return Err(error.into()),
+                }
+            }
+            Some(path) => Some(fs::File::create(path).await?),
+            None => None,
+        };
+
+        // Keep the whole renders only, so that the partial output stays clean
+        let mut quota = ByteQuota::new(self.max_bytes, self.on_exceed);
+        let mut index = 0usize;
+        while let Some(item) = iter.try_next().await? {
+            let item = item.flatten().await?;
+            let mut output = if index > 0 {
+                self.separator.clone()
+            } else {
+                String::default()
+            };
+            output.push_str(&self.render(item.content())?);
+
+            match quota.try_consume(output.len()) {
+                Ok(true) => index += 1,
+                Ok(false) => {
+                    warn!(
+                        "Stopped writing after {index} objects: reached {} bytes (max_bytes)",
+                        quota.written(),
+                    );
+                    break;
+                }
+                Err(error) => {
+                    if let Some(file) = file.as_mut() {
+                        file.flush().await?;
+                    }
+                    return Err(error);
+                }
+            }
+            match file.as_mut() {
+                Some(file) => file.write_all(output.as_bytes()).await?,
+                None => print!("{output}"),
+            }
+        }
+
+        match file.as_mut() {
+            Some(file) => {
+                file.flush().await?;
+                info!(
+                    "Wrote {index} objects into {:?}",
+                    self.out.as_ref().unwrap()
+                );
+            }
+            None if index > 0 => println!(),
+            None => (),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use xlake_ast::Value;
+    use xlake_core::PipeSink;
+
+    use crate::{
+        sinks::limit::QuotaExceedAction,
+        testing::{channel, object},
+    };
+
+    use super::{TemplateSink, TemplateSinkArgs};
+
+    #[::tokio::test]
+    async fn test_template_render() {
+        let out = ::std::env::temp_dir().join(format!("xlake-template-{}", ::std::process::id()));
+        let args = TemplateSinkArgs {
+            template: Default::default(),
+            if_absent: false,
+            max_bytes: None,
+            on_exceed: Default::default(),
+            out: Some(out.clone()),
+            separator: ", ".into(),
+        };
+        let sink = TemplateSink::new(args, "{{name}}:{{age}}").unwrap();
+
+        let channel = channel([("alice", Some(30)), ("bob", None)].map(|(name, age)| {
+            let age = age.map(|age| ("age", Value::from(age)));
+            object([("name", Value::from(name))].into_iter().chain(age))
+        }));
+        sink.call(channel).await.unwrap();
+
+        // The missing variables are rendered empty
+        let output = ::tokio::fs::read_to_string(&out).await.unwrap();
+        ::tokio::fs::remove_file(&out).await.unwrap();
+        assert_eq!(output, "alice:30, bob:");
+    }
+
+    #[::tokio::test]
+    async fn test_template_if_absent() {
+        let out = ::std::env::temp_dir().join(format!("xlake-if-absent-{}", ::std::process::id()));
+        let run = |name: &'static str| {
+            let args = TemplateSinkArgs {
+                template: Default::default(),
+                if_absent: true,
+                max_bytes: None,
+                on_exceed: Default::default(),
+                out: Some(out.clone()),
+                separator: "\n".into(),
+            };
+            let sink = TemplateSink::new(args, "{{name}}").unwrap();
+
+            let channel = channel([object([("name", name)])]);
+            async move { sink.call(channel).await }
+        };
+
+        // The second run is skipped, keeping the first output
+        run("alice").await.unwrap();
+        run("bob").await.unwrap();
+
+        let output = ::tokio::fs::read_to_string(&out).await.unwrap();
+        ::tokio::fs::remove_file(&out).await.unwrap();
+        assert_eq!(output, "alice");
+    }
+
+    #[::tokio::test]
+    async fn test_template_max_bytes() {
+        let out = ::std::env::temp_dir().join(format!("xlake-max-bytes-{}", ::std::process::id()));
+        let run = |on_exceed| {
+            let args = TemplateSinkArgs {
+                template: Default::default(),
+                if_absent: false,
+                max_bytes: Some(12),
+                on_exceed,
+                out: Some(out.clone()),
+                separator: ",".into(),
+            };
+            let sink = TemplateSink::new(args, "{{name}}").unwrap();
+
+            let channel =
+                channel(["alice", "bob", "charlie", "dave"].map(|name| object([("name", name)])));
+            async move { sink.call(channel).await }
+        };
+
+        // "alice,bob" fits, while ",charlie" would exceed the limit
+        run(QuotaExceedAction::Stop).await.unwrap();
+        let output = ::tokio::fs::read_to_string(&out).await.unwrap();
+        assert_eq!(output, "alice,bob");
+
+        assert!(run(QuotaExceedAction::Error).await.is_err());
+        let output = ::tokio::fs::read_to_string(&out).await.unwrap();
+        ::tokio::fs::remove_file(&out).await.unwrap();
+        assert_eq!(output, "alice,bob");
+    }
+}
@@ -1 +1,7 @@
+pub mod binary;
+pub mod db;
+pub mod limit;
 pub mod local;
+pub mod monitoring;
+pub mod mq;
+pub mod remote;
@@ -0,0 +1,2 @@
+#[cfg(feature = "otel")]
+pub mod otel;
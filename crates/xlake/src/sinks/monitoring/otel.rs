@@ -0,0 +1,165 @@
+use std::fmt;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use futures::TryStreamExt;
+use opentelemetry::{metrics::Counter, KeyValue};
+use serde::{Deserialize, Serialize};
+use xlake_ast::{PlanArguments, PlanKind, Value};
+use xlake_core::{
+    object::LazyObject, ArgSpec, PipeChannel, PipeNodeFactory, PipeNodeImpl, PipeSink,
+};
+
+/// The instrumentation scope of the metrics.
+const METER: &str = "xlake";
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct OtelSinkFactory;
+
+impl fmt::Display for OtelSinkFactory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.kind().fmt(f)
+    }
+}
+
+#[async_trait]
+impl PipeNodeFactory for OtelSinkFactory {
+    fn kind(&self) -> PlanKind {
+        PlanKind::Sink { name: self.name() }
+    }
+
+    fn name(&self) -> String {
+        "otel".into()
+    }
+
+    fn arguments(&self) -> Vec<ArgSpec> {
+        vec![
+            ArgSpec::optional("labels", "Comma-separated fields to be attached as labels"),
+            ArgSpec::optional("metric", "Name of the counter metric")
+                .with_default(OtelSinkArgs::default_metric()),
+        ]
+    }
+
+    async fn build(&self, args: &PlanArguments) -> Result<PipeNodeImpl> {
+        let args: OtelSinkArgs = args.to()?;
+
+        // Export via the meter provider installed by the otel setup, e.g. in the CLI
+        let counter = ::opentelemetry::global::meter(METER)
+            .u64_counter(args.metric.clone())
+            .with_description("Number of the objects passed through the pipeline")
+            .build();
+        let imp = OtelSink::new(args, counter);
+        Ok(PipeNodeImpl::Sink(Box::new(imp)))
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct OtelSinkArgs {
+    #[serde(default)]
+    labels: Option<String>,
+    #[serde(default = "OtelSinkArgs::default_metric")]
+    metric: String,
+}
+
+impl OtelSinkArgs {
+    fn default_metric() -> String {
+        "xlake_objects".into()
+    }
+}
+
+#[derive(Debug)]
+pub struct OtelSink {
+    counter: Counter<u64>,
+    labels: Vec<String>,
+}
+
+impl OtelSink {
+    fn new(args: OtelSinkArgs, counter: Counter<u64>) -> Self {
+        let labels = args
+            .labels
+            .iter()
+            .flat_map(|labels| labels.split(','))
+            .map(str::trim)
+            .filter(|label| !label.is_empty())
+            .map(Into::into)
+            .collect();
+        Self { counter, labels }
+    }
+
+    /// Collects the labels, skipping the missing and null fields.
+    fn attributes(&self, item: &LazyObject) -> Vec<KeyValue> {
+        self.labels
+            .iter()
+            .filter_map(|label| {
+                let value = match item.get_raw(label)? {
+                    Value::Null => return None,
+                    Value::String(value) => value.clone(),
+                    value => value.to_string(),
+                };
+                Some(KeyValue::new(label.clone(), value))
+            })
+            .collect()
+    }
+}
+
+#[async_trait]
+impl PipeSink for OtelSink {
+    async fn call(&self, channel: PipeChannel) -> Result<()> {
+        let mut iter = channel.into_stream::<LazyObject>().await?;
+        while let Some(item) = iter.try_next().await? {
+            let item = item.flatten().await?;
+            self.counter.add(1, &self.attributes(&item));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::BTreeMap,
+        sync::{Arc, Mutex},
+    };
+
+    use opentelemetry::{
+        metrics::{Counter, SyncInstrument},
+        KeyValue,
+    };
+    use xlake_core::PipeSink;
+
+    use crate::testing::{channel, object};
+
+    use super::{OtelSink, OtelSinkArgs};
+
+    /// Sums up the measurements per label set in process.
+    #[derive(Default)]
+    struct MockExporter(Mutex<BTreeMap<String, u64>>);
+
+    impl SyncInstrument<u64> for MockExporter {
+        fn measure(&self, measurement: u64, attributes: &[KeyValue]) {
+            let labels: Vec<_> = attributes
+                .iter()
+                .map(|KeyValue { key, value }| format!("{}={value}", key.as_str()))
+                .collect();
+            *self.0.lock().unwrap().entry(labels.join(",")).or_default() += measurement;
+        }
+    }
+
+    #[::tokio::test]
+    async fn test_otel_counts() {
+        let exporter = Arc::new(MockExporter::default());
+        let args = OtelSinkArgs {
+            labels: Some("status, missing".into()),
+            ..Default::default()
+        };
+        let sink = OtelSink::new(args, Counter::new(exporter.clone()));
+
+        let channel = channel(["ok", "ok", "error"].map(|status| object([("status", status)])));
+        sink.call(channel).await.unwrap();
+
+        let counts = exporter.0.lock().unwrap();
+        assert_eq!(counts.len(), 2);
+        assert_eq!(counts["status=ok"], 2);
+        assert_eq!(counts["status=error"], 1);
+    }
+}
@@ -0,0 +1,95 @@
+use std::fmt;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use futures::TryStreamExt;
+use rdkafka::{
+    producer::{FutureProducer, FutureRecord},
+    util::Timeout,
+    ClientConfig,
+};
+use serde::{Deserialize, Serialize};
+use xlake_ast::{PlanArguments, PlanKind, Value};
+use xlake_core::{
+    object::LazyObject, ArgSpec, PipeChannel, PipeNodeFactory, PipeNodeImpl, PipeSink,
+};
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct KafkaSinkFactory;
+
+impl fmt::Display for KafkaSinkFactory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.kind().fmt(f)
+    }
+}
+
+#[async_trait]
+impl PipeNodeFactory for KafkaSinkFactory {
+    fn kind(&self) -> PlanKind {
+        PlanKind::Sink { name: self.name() }
+    }
+
+    fn name(&self) -> String {
+        "kafka".into()
+    }
+
+    fn arguments(&self) -> Vec<ArgSpec> {
+        vec![
+            ArgSpec::required("brokers", "Comma-separated list of Kafka brokers"),
+            ArgSpec::optional("key_field", "Field to be used as the message key"),
+            ArgSpec::required("topic", "Topic to produce"),
+        ]
+    }
+
+    async fn build(&self, args: &PlanArguments) -> Result<PipeNodeImpl> {
+        let imp: KafkaSink = args.to()?;
+        Ok(PipeNodeImpl::Sink(Box::new(imp)))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct KafkaSink {
+    brokers: String,
+    #[serde(default)]
+    key_field: Option<String>,
+    topic: String,
+}
+
+#[async_trait]
+impl PipeSink for KafkaSink {
+    async fn call(&self, channel: PipeChannel) -> Result<()> {
+        let Self {
+            brokers,
+            key_field,
+            topic,
+        } = self;
+
+        let producer: FutureProducer = ClientConfig::new()
+            .set("bootstrap.servers", brokers)
+            .create()?;
+
+        let mut iter = channel.into_stream::<LazyObject>().await?;
+        while let Some(item) = iter.try_next().await? {
+            let item = item.flatten().await?;
+            let payload = item.content().to_string()?;
+            let key =
+                key_field
+                    .as_deref()
+                    .and_then(|key| item.get_raw(key))
+                    .map(|value| match value {
+                        Value::String(value) => value.clone(),
+                        value => value.to_string(),
+                    });
+
+            let mut record: FutureRecord<'_, str, str> = FutureRecord::to(topic).payload(&payload);
+            if let Some(key) = &key {
+                record = record.key(key);
+            }
+            producer
+                .send(record, Timeout::Never)
+                .await
+                .map_err(|(error, _)| error)?;
+        }
+        Ok(())
+    }
+}
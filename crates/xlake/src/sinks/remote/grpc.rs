@@ -0,0 +1,124 @@
+use std::{
+    fmt,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use futures::{future, StreamExt, TryStreamExt};
+use prost_reflect::{DynamicMessage, MessageDescriptor};
+use serde::{Deserialize, Serialize};
+use tonic::Request;
+use tracing::debug;
+use xlake_ast::{PlanArguments, PlanKind};
+use xlake_core::{
+    object::LazyObject, ArgSpec, PipeChannel, PipeNodeFactory, PipeNodeImpl, PipeSink,
+};
+
+use crate::srcs::remote::grpc::{connect, load_method, method_path, to_message, DynamicCodec};
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct GrpcSinkFactory;
+
+impl fmt::Display for GrpcSinkFactory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.kind().fmt(f)
+    }
+}
+
+#[async_trait]
+impl PipeNodeFactory for GrpcSinkFactory {
+    fn kind(&self) -> PlanKind {
+        PlanKind::Sink { name: self.name() }
+    }
+
+    fn name(&self) -> String {
+        "grpc".into()
+    }
+
+    fn arguments(&self) -> Vec<ArgSpec> {
+        vec![
+            ArgSpec::required(
+                "descriptor",
+                "Encoded `FileDescriptorSet` file, e.g. by `protoc --descriptor_set_out`",
+            ),
+            ArgSpec::required("endpoint", "gRPC server, e.g. `http://localhost:50051`"),
+            ArgSpec::required(
+                "method",
+                "Client-streaming method, e.g. `my.package.MyService/MyMethod`",
+            ),
+        ]
+    }
+
+    async fn build(&self, args: &PlanArguments) -> Result<PipeNodeImpl> {
+        let imp: GrpcSink = args.to()?;
+        Ok(PipeNodeImpl::Sink(Box::new(imp)))
+    }
+}
+
+/// Streams the objects as the messages of a client-streaming gRPC method.
+///
+/// The fields unknown to the request message are ignored.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GrpcSink {
+    descriptor: PathBuf,
+    endpoint: String,
+    method: String,
+}
+
+#[async_trait]
+impl PipeSink for GrpcSink {
+    async fn call(&self, channel: PipeChannel) -> Result<()> {
+        let Self {
+            descriptor,
+            endpoint,
+            method,
+        } = self;
+
+        let method = load_method(descriptor, method).await?;
+        if !method.is_client_streaming() || method.is_server_streaming() {
+            bail!("Not a client-streaming method: {}", method.full_name())
+        }
+
+        // Close the request stream on the first invalid object, keeping the error
+        let failure = Arc::new(Mutex::new(None));
+        let messages = {
+            let failure = failure.clone();
+            let input = method.input();
+            channel
+                .into_stream::<LazyObject>()
+                .await?
+                .and_then(move |item| to_request(input.clone(), item))
+                .scan((), move |_, result| {
+                    future::ready(match result {
+                        Ok(message) => Some(message),
+                        Err(error) => {
+                            failure.lock().unwrap().replace(error);
+                            None
+                        }
+                    })
+                })
+        };
+
+        let mut client = connect(endpoint).await?;
+        let path = method_path(&method)?;
+        let codec = DynamicCodec::new(method.output());
+        let response = client
+            .client_streaming(Request::new(messages), path, codec)
+            .await;
+
+        if let Some(error) = failure.lock().unwrap().take() {
+            return Err(error);
+        }
+        let response = response?.into_inner();
+        debug!("Sent to {}: {response:?}", method.full_name());
+        Ok(())
+    }
+}
+
+async fn to_request(input: MessageDescriptor, item: LazyObject) -> Result<DynamicMessage> {
+    let item = item.flatten().await?;
+    let json = item.content().to_json()?;
+    to_message(input, json)
+}
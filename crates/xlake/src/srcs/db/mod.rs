@@ -0,0 +1,2 @@
+#[cfg(feature = "mongodb")]
+pub mod mongo;
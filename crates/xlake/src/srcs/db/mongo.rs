@@ -0,0 +1,128 @@
+use std::fmt;
+
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use futures::{StreamExt, TryStreamExt};
+use mongodb::{
+    bson::{Bson, Document},
+    Client,
+};
+use serde::{Deserialize, Serialize};
+use xlake_ast::{Binary, Number, Object, PlanArguments, PlanKind, Value};
+use xlake_core::{
+    object::ObjectLayer, stream::DefaultStream, ArgSpec, PipeChannel, PipeEdge, PipeNodeFactory,
+    PipeNodeImpl, PipeSrc,
+};
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct MongoSrcFactory;
+
+impl fmt::Display for MongoSrcFactory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.kind().fmt(f)
+    }
+}
+
+#[async_trait]
+impl PipeNodeFactory for MongoSrcFactory {
+    fn kind(&self) -> PlanKind {
+        PlanKind::Src { name: self.name() }
+    }
+
+    fn name(&self) -> String {
+        "mongo".into()
+    }
+
+    fn arguments(&self) -> Vec<ArgSpec> {
+        vec![
+            ArgSpec::required("uri", "MongoDB connection string"),
+            ArgSpec::required("db", "Database name"),
+            ArgSpec::required("collection", "Collection name"),
+            ArgSpec::optional("filter", "Query filter in extended JSON"),
+        ]
+    }
+
+    fn output(&self) -> PipeEdge {
+        PipeEdge {
+            model: Some(vec!["stream".into()]),
+            ..Default::default()
+        }
+    }
+
+    async fn build(&self, args: &PlanArguments) -> Result<PipeNodeImpl> {
+        let imp: MongoSrc = args.to()?;
+        Ok(PipeNodeImpl::Src(Box::new(imp)))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MongoSrc {
+    uri: String,
+    db: String,
+    collection: String,
+    #[serde(default)]
+    filter: Option<String>,
+}
+
+#[async_trait]
+impl PipeSrc for MongoSrc {
+    async fn call(&self) -> Result<PipeChannel> {
+        let Self {
+            uri,
+            db,
+            collection,
+            filter,
+        } = self;
+
+        let filter = match filter {
+            Some(filter) => ::serde_json::from_str(filter).context("Invalid filter")?,
+            None => Document::default(),
+        };
+
+        let client = Client::with_uri_str(uri).await?;
+        let cursor = client
+            .database(db)
+            .collection::<Document>(collection)
+            .find(filter)
+            .await?;
+
+        let stream = cursor
+            .map(|result| result.map_err(Into::into).and_then(document_to_object))
+            .map_ok(ObjectLayer::from_object_dyn)
+            .map_ok(Into::into)
+            .boxed();
+        Ok(PipeChannel::from_stream(DefaultStream::from_stream(stream)))
+    }
+}
+
+fn document_to_object(document: Document) -> Result<Object> {
+    let mut object = Object::default();
+    for (key, value) in document {
+        object.insert(key, bson_to_value(value)?);
+    }
+    Ok(object)
+}
+
+fn bson_to_value(bson: Bson) -> Result<Value> {
+    match bson {
+        Bson::Null | Bson::Undefined => Ok(Value::Null),
+        Bson::Boolean(v) => Ok(Value::Bool(v)),
+        Bson::Int32(v) => Ok(v.into()),
+        Bson::Int64(v) => Ok(v.into()),
+        Bson::Double(v) => ::serde_json::Number::from_f64(v)
+            .map(|v| Value::Number(Number::Fixed(v)))
+            .with_context(|| format!("Non-finite number is not supported: {v}")),
+        Bson::Decimal128(v) => Ok(Value::Number(Number::Dynamic(v.to_string()))),
+        Bson::String(v) | Bson::Symbol(v) => Ok(Value::String(v)),
+        Bson::Binary(v) => Ok(Value::Binary(Binary(v.bytes))),
+        Bson::ObjectId(v) => Ok(Value::String(v.to_hex())),
+        Bson::DateTime(v) => Ok(Value::String(v.try_to_rfc3339_string()?)),
+        Bson::Array(v) => v
+            .into_iter()
+            .map(bson_to_value)
+            .collect::<Result<_>>()
+            .map(Value::Array),
+        Bson::Document(v) => document_to_object(v).map(Value::Object),
+        bson => bail!("BSON type {:?} is not supported yet", bson.element_type()),
+    }
+}
@@ -0,0 +1,153 @@
+use std::collections::BTreeMap;
+
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use xlake_ast::{PlanArguments, Value};
+use xlake_core::{
+    models::hash,
+    object::{LazyObject, ObjectLayer},
+    PipeChannel, PipeSrc, Resumable,
+};
+
+use crate::models::builtins::id::IdModelObject;
+
+/// The argument accepted by every src to stamp the ids.
+pub const ARG: &str = "id";
+
+#[derive(
+    Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum IdMode {
+    /// The canonical JSON digest, stable across the runs
+    Hash,
+    #[default]
+    None,
+    /// A random v4 UUID, unique per object
+    Uuid,
+}
+
+impl IdMode {
+    /// Takes the `id` argument out of the src arguments, as the src itself does not know it.
+    pub fn take_from(args: &mut PlanArguments) -> Result<Self> {
+        match args.remove(ARG) {
+            Some(Value::String(mode)) => ::serde_json::from_value(mode.clone().into())
+                .with_context(|| format!("Invalid id: expected uuid, hash or none, got {mode}")),
+            Some(mode) => bail!("Invalid id: expected uuid, hash or none, got {mode}"),
+            None => Ok(Self::None),
+        }
+    }
+}
+
+/// Stamps the [`IdModelObject`] onto the objects as soon as the inner src emits them.
+#[derive(Debug)]
+pub struct IdSrc {
+    inner: Box<dyn PipeSrc>,
+    mode: IdMode,
+}
+
+impl IdSrc {
+    pub fn new(inner: Box<dyn PipeSrc>, mode: IdMode) -> Self {
+        Self { inner, mode }
+    }
+}
+
+#[async_trait]
+impl PipeSrc for IdSrc {
+    async fn call(&self) -> Result<PipeChannel> {
+        let mode = self.mode;
+        self.inner
+            .call()
+            .await?
+            .and_then(move |item| async move { stamp(mode, item).await })
+            .await
+    }
+
+    fn as_resumable(&self) -> Option<&dyn Resumable> {
+        self.inner.as_resumable()
+    }
+}
+
+async fn stamp(mode: IdMode, item: LazyObject) -> Result<LazyObject> {
+    let (mut item, id) = match mode {
+        IdMode::Hash => {
+            // Download the whole object, then sort the keys so that the output is canonical
+            let item = item.flatten().await?;
+            let content: BTreeMap<_, _> = item.content().iter().collect();
+            let id = hash::Hash::digest(::serde_json::to_vec(&content)?).to_string();
+            (item, id)
+        }
+        IdMode::None => return Ok(item),
+        IdMode::Uuid => (item, Uuid::new_v4().to_string()),
+    };
+    item.append_layer(ObjectLayer::from_owned(&IdModelObject { id })?);
+    Ok(item)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use futures::TryStreamExt;
+    use xlake_ast::PlanArguments;
+    use xlake_core::{object::LazyObject, PipeNodeFactory, PipeNodeImpl, PipeSrc};
+
+    use crate::srcs::local::repeat::RepeatSrcFactory;
+
+    use super::{IdMode, IdSrc};
+
+    async fn ids(mode: IdMode) -> Vec<String> {
+        let mut args = PlanArguments::default();
+        args.insert("count".into(), 4.into());
+        args.insert("json".into(), r#"{"name":"foo"}"#.into());
+        let inner = match RepeatSrcFactory.build(&args).await.unwrap() {
+            PipeNodeImpl::Src(inner) => inner,
+            _ => unreachable!(),
+        };
+        IdSrc::new(inner, mode)
+            .call()
+            .await
+            .unwrap()
+            .into_stream::<LazyObject>()
+            .await
+            .unwrap()
+            .and_then(|item| async move {
+                assert!(item.has_model("id"));
+                let item = item.flatten().await?;
+                assert_eq!(item.get_str("name"), Some("foo"));
+                Ok(item.get_str("id").unwrap().to_string())
+            })
+            .try_collect()
+            .await
+            .unwrap()
+    }
+
+    #[::tokio::test]
+    async fn test_id_uuid() {
+        let ids = ids(IdMode::Uuid).await;
+        assert_eq!(ids.len(), 4);
+        assert_eq!(ids.iter().collect::<BTreeSet<_>>().len(), 4);
+    }
+
+    #[::tokio::test]
+    async fn test_id_hash() {
+        // The same contents share the same id
+        let ids = ids(IdMode::Hash).await;
+        assert_eq!(ids.len(), 4);
+        assert!(ids.iter().all(|id| *id == ids[0]));
+    }
+
+    #[test]
+    fn test_id_take_from() {
+        let mut args = PlanArguments::default();
+        args.insert("id".into(), "uuid".into());
+        assert_eq!(IdMode::take_from(&mut args).unwrap(), IdMode::Uuid);
+        assert!(args.get_str("id").is_none());
+        assert_eq!(IdMode::take_from(&mut args).unwrap(), IdMode::None);
+
+        args.insert("id".into(), "random".into());
+        assert!(IdMode::take_from(&mut args).is_err());
+    }
+}
@@ -0,0 +1,158 @@
+use anyhow::{bail, Error, Result};
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+/// What a src does on an input over `max_bytes`, given as `on_exceed`.
+///
+/// It differs from [`QuotaExceedAction`] of the sinks, which stop the output instead.
+///
+/// [`QuotaExceedAction`]: crate::sinks::limit::QuotaExceedAction
+#[derive(
+    Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case", try_from = "String")]
+pub enum LimitExceedAction {
+    #[default]
+    Error,
+    Truncate,
+}
+
+impl TryFrom<String> for LimitExceedAction {
+    type Error = Error;
+
+    fn try_from(value: String) -> Result<Self> {
+        match value.as_str() {
+            "error" => Ok(Self::Error),
+            "truncate" => Ok(Self::Truncate),
+            "stop" => bail!("Unsupported on_exceed for the srcs: 'stop'; use 'truncate'"),
+            _ => bail!("Unknown on_exceed: '{value}'; expected 'error' or 'truncate'"),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ByteLimit {
+    pub max_bytes: Option<u64>,
+    pub on_exceed: LimitExceedAction,
+}
+
+impl ByteLimit {
+    /// Validates a known content length before reading it.
+    pub fn check_len(&self, len: u64) -> Result<()> {
+        match self.max_bytes {
+            Some(max_bytes) if len > max_bytes && self.on_exceed == LimitExceedAction::Error => {
+                bail!("Content too large: {len} bytes (max_bytes={max_bytes})")
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Reads the whole content, stopping right after the limit is exceeded.
+    pub async fn read_to_end<R>(&self, reader: R) -> Result<Vec<u8>>
+    where
+        R: Unpin + AsyncRead,
+    {
+        let mut buf = Vec::default();
+        match self.max_bytes {
+            Some(max_bytes) => {
+                let mut reader = reader.take(max_bytes.saturating_add(1));
+                reader.read_to_end(&mut buf).await?;
+                if buf.len() as u64 > max_bytes {
+                    match self.on_exceed {
+                        LimitExceedAction::Error => {
+                            bail!("Content too large: exceeded {max_bytes} bytes (max_bytes)")
+                        }
+                        LimitExceedAction::Truncate => buf.truncate(max_bytes as usize),
+                    }
+                }
+            }
+            None => {
+                let mut reader = reader;
+                reader.read_to_end(&mut buf).await?;
+            }
+        }
+        Ok(buf)
+    }
+
+    /// Reads the whole content as UTF-8 text.
+    pub async fn read_to_string<R>(&self, reader: R) -> Result<String>
+    where
+        R: Unpin + AsyncRead,
+    {
+        let buf = self.read_to_end(reader).await?;
+        self.decode_utf8(buf)
+    }
+
+    /// Converts the content read by [`Self::read_to_end`] into UTF-8 text.
+    ///
+    /// A multi-byte character split by the truncation is dropped.
+    pub fn decode_utf8(&self, mut buf: Vec<u8>) -> Result<String> {
+        if self.on_exceed == LimitExceedAction::Truncate {
+            if let Err(error) = ::std::str::from_utf8(&buf) {
+                if error.error_len().is_none() {
+                    buf.truncate(error.valid_up_to());
+                }
+            }
+        }
+        String::from_utf8(buf).map_err(Into::into)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ByteLimit, LimitExceedAction};
+
+    #[::tokio::test]
+    async fn test_limit_exceeded_error() {
+        let limit = ByteLimit {
+            max_bytes: Some(4),
+            on_exceed: LimitExceedAction::Error,
+        };
+        assert!(limit.read_to_string(&b"hello world"[..]).await.is_err());
+        assert_eq!(limit.read_to_string(&b"hell"[..]).await.unwrap(), "hell");
+    }
+
+    #[::tokio::test]
+    async fn test_limit_exceeded_truncate() {
+        let limit = ByteLimit {
+            max_bytes: Some(4),
+            on_exceed: LimitExceedAction::Truncate,
+        };
+        assert_eq!(
+            limit.read_to_string(&b"hello world"[..]).await.unwrap(),
+            "hell",
+        );
+        // "안" takes 3 bytes, so the second one is cut in the middle
+        assert_eq!(limit.read_to_string("안녕".as_bytes()).await.unwrap(), "안",);
+    }
+
+    #[cfg(feature = "io-std")]
+    #[::tokio::test]
+    async fn test_limit_exceeded_stdin() {
+        use xlake_ast::PlanArguments;
+
+        use crate::srcs::local::stdin::StdinSrc;
+
+        let args = PlanArguments::from_json(::serde_json::json!({
+            "max_bytes": 4,
+            "on_exceed": "error",
+        }))
+        .unwrap();
+        let src: StdinSrc = args.to().unwrap();
+
+        let error = src.read_from(&b"hello world"[..]).await.unwrap_err();
+        assert!(error.to_string().contains("Content too large"), "{error}");
+        assert!(src.read_from(&b"hell"[..]).await.is_ok());
+    }
+
+    #[test]
+    fn test_limit_exceed_action() {
+        let parse = |value: &str| LimitExceedAction::try_from(value.to_string());
+        assert_eq!(parse("truncate").unwrap(), LimitExceedAction::Truncate);
+
+        // The action of the sinks is told apart from the typos
+        let error = parse("stop").unwrap_err();
+        assert!(error.to_string().contains("use 'truncate'"));
+        assert!(parse("drop").is_err());
+    }
+}
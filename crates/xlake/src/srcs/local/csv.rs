@@ -6,8 +6,8 @@ use datafusion::prelude::CsvReadOptions;
 use serde::{Deserialize, Serialize};
 use xlake_ast::{PlanArguments, PlanKind};
 use xlake_core::{
-    batch::{DataFusionBatch, DEFAULT_TABLE_REF},
-    PipeChannel, PipeEdge, PipeNodeFactory, PipeNodeImpl, PipeSrc,
+    batch::{BatchFormatArgs, DataFusionBatch, DEFAULT_TABLE_REF},
+    ArgSpec, PipeChannel, PipeEdge, PipeNodeFactory, PipeNodeImpl, PipeSrc,
 };
 
 #[derive(Copy, Clone, Debug, Default)]
@@ -29,6 +29,19 @@ impl PipeNodeFactory for CsvSrcFactory {
         "csv".into()
     }
 
+    fn arguments(&self) -> Vec<ArgSpec> {
+        vec![
+            ArgSpec::optional(
+                "ordered",
+                "Emit the rows in the file order, in a single partition",
+            )
+            .with_default(false),
+            ArgSpec::required("path", "CSV file path to read"),
+            ArgSpec::optional("table", "Name of the registered table")
+                .with_default(DEFAULT_TABLE_REF),
+        ]
+    }
+
     fn output(&self) -> PipeEdge {
         PipeEdge {
             batch: "datafusion".into(),
@@ -45,18 +58,37 @@ impl PipeNodeFactory for CsvSrcFactory {
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CsvSrc {
+    #[serde(default)]
+    ordered: bool,
     path: PathBuf,
+    #[serde(default = "CsvSrc::default_table")]
+    table: String,
+}
+
+impl CsvSrc {
+    fn default_table() -> String {
+        DEFAULT_TABLE_REF.into()
+    }
 }
 
 #[async_trait]
 impl PipeSrc for CsvSrc {
     async fn call(&self) -> Result<PipeChannel> {
-        let Self { path } = self;
+        let Self {
+            ordered,
+            path,
+            table,
+        } = self;
         let path = path.to_string_lossy();
 
-        let batch = DataFusionBatch::default();
+        let batch = DataFusionBatch::new(BatchFormatArgs {
+            ordered: *ordered,
+            table: table.clone(),
+            ..Default::default()
+        })?;
         let options = CsvReadOptions::default();
-        batch.register_csv(DEFAULT_TABLE_REF, path, options).await?;
-        Ok(PipeChannel::from_batch(batch))
+        batch.register_csv(table.as_str(), path, options).await?;
+        // Expose the rows to the stream consumers as well, e.g. `csvsink`
+        PipeChannel::from_batch_streamed(batch).await
     }
 }
@@ -1,17 +1,29 @@
-use std::{fmt, path::PathBuf};
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
 use async_trait::async_trait;
-use futures::FutureExt;
+use futures::{stream, FutureExt, StreamExt};
 use serde::{Deserialize, Serialize};
-use tokio::fs;
+use tokio::{fs, io::AsyncReadExt};
 use xlake_ast::{PlanArguments, PlanKind};
 use xlake_core::{
-    models::hash::HashModelView, PipeChannel, PipeEdge, PipeModelOwnedExt, PipeNodeFactory,
-    PipeNodeImpl, PipeSrc,
+    models::hash::HashModelView,
+    object::{LazyObject, PeekFn},
+    stream::DefaultStream,
+    ArgSpec, PipeChannel, PipeEdge, PipeModelOwnedExt, PipeNodeFactory, PipeNodeImpl, PipeSrc,
 };
 
-use crate::models::builtins::{binary::BinaryModelObject, file::FileModelView};
+use crate::{
+    models::builtins::{binary::BinaryModelObject, file::FileModelView},
+    srcs::{
+        limit::{ByteLimit, LimitExceedAction},
+        trace::trace_bytes,
+    },
+};
 
 #[derive(Copy, Clone, Debug, Default)]
 pub struct FileSrcFactory;
@@ -32,6 +44,28 @@ impl PipeNodeFactory for FileSrcFactory {
         "file".into()
     }
 
+    fn arguments(&self) -> Vec<ArgSpec> {
+        vec![
+            ArgSpec::optional(
+                "allow_empty",
+                "Emit nothing rather than failing if no files match",
+            )
+            .with_default(false),
+            ArgSpec::optional("cache", "Cache the file `path` or the whole `content`")
+                .with_default("path"),
+            ArgSpec::optional("glob", "Expand the `path` as a glob pattern, e.g. `*.pdf`")
+                .with_default(false),
+            ArgSpec::optional("max_bytes", "Maximum file size in bytes"),
+            ArgSpec::optional("on_exceed", "`error` or `truncate` on oversized files")
+                .with_default("error"),
+            ArgSpec::required("path", "File path to read"),
+            ArgSpec::optional("trace", "Log the leading bytes read as a hexdump")
+                .with_default(false),
+            ArgSpec::optional("watch", "Keep emitting the new or changed files")
+                .with_default(false),
+        ]
+    }
+
     fn output(&self) -> PipeEdge {
         PipeEdge {
             model: Some(vec![
@@ -50,18 +84,88 @@ impl PipeNodeFactory for FileSrcFactory {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct FileSrc {
+    #[serde(default)]
+    allow_empty: bool,
     #[serde(default)]
     cache: FileCacheType,
+    /// Expands the path as a glob pattern, emitting the matched files in order.
+    #[serde(default)]
+    glob: bool,
+    #[serde(default)]
+    max_bytes: Option<u64>,
+    #[serde(default)]
+    on_exceed: LimitExceedAction,
     path: PathBuf,
+    /// Logs the leading bytes of each file at debug level, e.g. for garbled outputs.
+    #[serde(default)]
+    trace: bool,
+    /// Keeps the channel open, making the pipeline long-running.
+    #[serde(default)]
+    watch: bool,
 }
 
 #[async_trait]
 impl PipeSrc for FileSrc {
     async fn call(&self) -> Result<PipeChannel> {
-        let Self { cache, path } = self;
+        if self.glob {
+            if self.watch {
+                bail!("Watching files cannot be combined with glob patterns")
+            }
+            return self.glob();
+        }
+        if self.watch {
+            return self.watch().await;
+        }
+
+        let item = self.read(&self.path).await?;
+        Ok(PipeChannel::from_unit(item))
+    }
+}
+
+impl FileSrc {
+    fn glob(&self) -> Result<PipeChannel> {
+        let pattern = self.path.to_str().context("Non UTF-8 glob pattern")?;
+        let mut paths = Vec::default();
+        for path in ::glob::glob(pattern)? {
+            let path = path?;
+            // Skip the directories
+            if path.is_file() {
+                paths.push(path);
+            }
+        }
+        if paths.is_empty() && !self.allow_empty {
+            bail!("No files match: {pattern}")
+        }
+
+        let src = Arc::new(self.clone());
+        let stream = stream::iter(paths)
+            .then(move |path| {
+                let src = src.clone();
+                async move { Ok(src.read(&path).await?.into_any()) }
+            })
+            .boxed();
+        Ok(PipeChannel::from_stream(DefaultStream::from_stream(stream)))
+    }
+
+    async fn read(&self, path: &Path) -> Result<FileModelView<LazyObject>> {
+        let Self {
+            cache,
+            max_bytes,
+            on_exceed,
+            trace,
+            ..
+        } = *self;
         let path = fs::canonicalize(path).await?;
+
+        // Fail fast before deferring the read
+        let limit = ByteLimit {
+            max_bytes,
+            on_exceed,
+        };
+        limit.check_len(fs::metadata(&path).await?.len())?;
+
         let extension = path
             .extension()
             .and_then(|ext| ext.to_str())
@@ -70,7 +174,10 @@ impl PipeSrc for FileSrc {
         let content = {
             let path = path.clone();
             async move {
-                let content = fs::read(&path).await?;
+                let content = limit.read_to_end(fs::File::open(&path).await?).await?;
+                if trace {
+                    trace_bytes(&path.display().to_string(), &content);
+                }
                 Ok(BinaryModelObject {
                     content: content.into(),
                 })
@@ -82,11 +189,98 @@ impl PipeSrc for FileSrc {
             FileCacheType::Path => {
                 let mut item = HashModelView::new(&path).into_any();
                 item.append_future(content.boxed());
+                item.set_peek("content", peek(path.clone(), max_bytes));
                 item
             }
         };
-        let item = FileModelView::new(item, extension.into());
-        Ok(PipeChannel::from_unit(item))
+        Ok(FileModelView::new(item, extension.into()))
+    }
+}
+
+/// Reads the leading bytes of the file, so that the cheap inspections skip the whole read.
+fn peek(path: PathBuf, max_bytes: Option<u64>) -> PeekFn {
+    Arc::new(move |len| {
+        let path = path.clone();
+        async move {
+            let len = max_bytes.map_or(len as u64, |max_bytes| max_bytes.min(len as u64));
+            let mut head = Vec::default();
+            fs::File::open(&path)
+                .await?
+                .take(len)
+                .read_to_end(&mut head)
+                .await?;
+            Ok::<_, ::anyhow::Error>(head)
+        }
+        .boxed()
+    })
+}
+
+#[cfg(feature = "watch")]
+impl FileSrc {
+    /// Emits the file and then its changes, or the new or changed files in a directory.
+    ///
+    /// The watcher is dropped along with the channel, e.g. when the pipeline is cancelled.
+    async fn watch(&self) -> Result<PipeChannel> {
+        use futures::TryStreamExt;
+        use notify::{EventKind, RecursiveMode, Watcher};
+        use tokio::sync::mpsc;
+
+        let path = fs::canonicalize(&self.path).await?;
+        let is_dir = fs::metadata(&path).await?.is_dir();
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        let mut watcher = ::notify::recommended_watcher(move |event| {
+            // The receiver is gone when the pipeline is cancelled
+            let _ = tx.send(event);
+        })?;
+        watcher.watch(&path, RecursiveMode::NonRecursive)?;
+
+        let initial = if is_dir {
+            Vec::default()
+        } else {
+            vec![self.read(&path).await?.into_any()]
+        };
+
+        let src = Arc::new(self.clone());
+        let changes = stream::try_unfold((watcher, rx), move |(watcher, mut rx)| {
+            let src = src.clone();
+            async move {
+                while let Some(event) = rx.recv().await {
+                    let event = event?;
+                    if !matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+                        continue;
+                    }
+
+                    let mut items = Vec::default();
+                    for path in &event.paths {
+                        // Skip the subdirectories and the files removed in the meantime
+                        if fs::metadata(path).await.is_ok_and(|meta| meta.is_file()) {
+                            items.push(src.read(path).await?.into_any());
+                        }
+                    }
+                    if !items.is_empty() {
+                        return Ok(Some((items, (watcher, rx))));
+                    }
+                }
+                Ok::<_, ::anyhow::Error>(None)
+            }
+        });
+
+        let stream = stream::iter(initial.into_iter().map(Ok))
+            .chain(
+                changes
+                    .map_ok(|items| stream::iter(items.into_iter().map(Ok)))
+                    .try_flatten(),
+            )
+            .boxed();
+        Ok(PipeChannel::from_stream(DefaultStream::from_stream(stream)))
+    }
+}
+
+#[cfg(not(feature = "watch"))]
+impl FileSrc {
+    async fn watch(&self) -> Result<PipeChannel> {
+        bail!("Watching files requires the `watch` feature")
     }
 }
 
@@ -99,3 +293,165 @@ pub enum FileCacheType {
     #[default]
     Path,
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io,
+        sync::{Arc, Mutex},
+    };
+
+    use futures::TryStreamExt;
+    use tokio::fs;
+    use xlake_core::{object::LazyObject, PipeSrc};
+
+    use super::{FileCacheType, FileSrc};
+
+    /// Collects the formatted logs.
+    #[derive(Clone, Default)]
+    struct LogBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl io::Write for LogBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[::tokio::test]
+    async fn test_file_trace() {
+        let path = ::std::env::temp_dir().join(format!("xlake-trace-{}.txt", ::std::process::id()));
+        fs::write(&path, b"hi\xff").await.unwrap();
+
+        let logs = LogBuffer::default();
+        let subscriber = ::tracing_subscriber::fmt()
+            .with_max_level(::tracing::Level::DEBUG)
+            .with_writer({
+                let logs = logs.clone();
+                move || logs.clone()
+            })
+            .finish();
+        let _guard = ::tracing::subscriber::set_default(subscriber);
+
+        let src = FileSrc {
+            allow_empty: false,
+            cache: FileCacheType::Content,
+            glob: false,
+            max_bytes: None,
+            on_exceed: Default::default(),
+            path: path.clone(),
+            trace: true,
+            watch: false,
+        };
+        let items: Vec<LazyObject> = src
+            .call()
+            .await
+            .unwrap()
+            .into_stream()
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        fs::remove_file(&path).await.unwrap();
+        assert_eq!(items.len(), 1);
+
+        // The raw bytes are dumped, including the invalid UTF-8 ones
+        let logs = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+        assert!(logs.contains("Read 3 bytes from"));
+        assert!(logs.contains("00000000  68 69 ff "));
+        assert!(logs.contains("|hi.|"));
+    }
+
+    #[::tokio::test]
+    async fn test_file_glob() {
+        let dir = ::std::env::temp_dir().join(format!("xlake-glob-{}", ::std::process::id()));
+        fs::create_dir_all(&dir).await.unwrap();
+        for name in ["a.txt", "b.txt", "c.md"] {
+            fs::write(dir.join(name), name).await.unwrap();
+        }
+
+        let src = |pattern: &str, allow_empty| FileSrc {
+            allow_empty,
+            cache: FileCacheType::Content,
+            glob: true,
+            max_bytes: None,
+            on_exceed: Default::default(),
+            path: dir.join(pattern),
+            trace: false,
+            watch: false,
+        };
+        let contents: Vec<_> = src("*.txt", false)
+            .call()
+            .await
+            .unwrap()
+            .into_stream::<LazyObject>()
+            .await
+            .unwrap()
+            .map_ok(|item| item.get_bytes("content").unwrap().to_vec())
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(contents, [b"a.txt", b"b.txt"]);
+
+        // No files match
+        assert!(src("*.pdf", false).call().await.is_err());
+        let items: Vec<LazyObject> = src("*.pdf", true)
+            .call()
+            .await
+            .unwrap()
+            .into_stream()
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        assert!(items.is_empty());
+
+        fs::remove_dir_all(&dir).await.unwrap();
+    }
+
+    #[cfg(feature = "watch")]
+    #[::tokio::test]
+    async fn test_file_watch_dir() {
+        use std::time::Duration;
+
+        use tokio::time::timeout;
+
+        let dir = ::std::env::temp_dir().join(format!("xlake-watch-{}", ::std::process::id()));
+        fs::create_dir_all(&dir).await.unwrap();
+
+        let src = FileSrc {
+            allow_empty: false,
+            cache: FileCacheType::Content,
+            glob: false,
+            max_bytes: None,
+            on_exceed: Default::default(),
+            path: dir.clone(),
+            trace: false,
+            watch: true,
+        };
+        let mut stream = src
+            .call()
+            .await
+            .unwrap()
+            .into_stream::<LazyObject>()
+            .await
+            .unwrap();
+
+        fs::write(dir.join("new.txt"), b"hello").await.unwrap();
+        let item = timeout(Duration::from_secs(10), stream.try_next())
+            .await
+            .expect("no file event")
+            .unwrap()
+            .unwrap();
+        assert_eq!(item.get_raw("extension").unwrap().to_string(), r#""txt""#);
+
+        drop(stream);
+        fs::remove_dir_all(&dir).await.unwrap();
+    }
+}
@@ -0,0 +1,151 @@
+use std::fmt;
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use futures::{stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use xlake_ast::{Object, PlanArguments, PlanKind};
+use xlake_core::{
+    object::ObjectLayer, stream::DefaultStream, ArgSpec, PipeChannel, PipeNodeFactory,
+    PipeNodeImpl, PipeSrc,
+};
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct InlineSrcFactory;
+
+impl fmt::Display for InlineSrcFactory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.kind().fmt(f)
+    }
+}
+
+#[async_trait]
+impl PipeNodeFactory for InlineSrcFactory {
+    fn kind(&self) -> PlanKind {
+        PlanKind::Src { name: self.name() }
+    }
+
+    fn name(&self) -> String {
+        "inline".into()
+    }
+
+    fn arguments(&self) -> Vec<ArgSpec> {
+        vec![ArgSpec::required(
+            "json",
+            "Inline JSON object, or an array of them, e.g. `json='{\"a\":1}'`",
+        )]
+    }
+
+    async fn build(&self, args: &PlanArguments) -> Result<PipeNodeImpl> {
+        let args: InlineSrcArgs = args.to()?;
+        let imp = InlineSrc::try_from(args)?;
+        Ok(PipeNodeImpl::Src(Box::new(imp)))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct InlineSrcArgs {
+    json: String,
+}
+
+/// Emits the literal objects once, e.g. for quick tests.
+#[derive(Debug)]
+pub struct InlineSrc {
+    objects: Vec<Object>,
+}
+
+impl TryFrom<InlineSrcArgs> for InlineSrc {
+    type Error = anyhow::Error;
+
+    fn try_from(args: InlineSrcArgs) -> Result<Self, Self::Error> {
+        let InlineSrcArgs { json } = args;
+
+        let objects = if json.trim_start().starts_with('[') {
+            ::serde_json::from_str(&json).context("Invalid JSON array of objects")?
+        } else {
+            vec![Object::from_slice(json.as_bytes()).context("Invalid JSON object")?]
+        };
+        Ok(Self { objects })
+    }
+}
+
+#[async_trait]
+impl PipeSrc for InlineSrc {
+    async fn call(&self) -> Result<PipeChannel> {
+        let objects = self.objects.clone();
+        let stream = stream::iter(objects)
+            .map(|object| Ok(ObjectLayer::from_object_dyn(object).into()))
+            .boxed();
+        Ok(PipeChannel::from_stream(DefaultStream::from_stream(stream)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::TryStreamExt;
+    use xlake_core::{object::LazyObject, PipeNodeFactory, PipeNodeImpl};
+    use xlake_parser::SeqParser;
+
+    use crate::PipeSession;
+
+    use super::InlineSrcFactory;
+
+    /// Parses a single src as given in the command line, and collects its objects.
+    async fn inline(input: &str) -> anyhow::Result<Vec<LazyObject>> {
+        let mut plans = SeqParser::new().parse(input).unwrap();
+        let plan = plans.pop().unwrap();
+        assert_eq!(plan.kind, InlineSrcFactory.kind());
+
+        let imp = match InlineSrcFactory.build(&plan.args).await? {
+            PipeNodeImpl::Src(imp) => imp,
+            _ => unreachable!(),
+        };
+        imp.call()
+            .await?
+            .into_stream::<LazyObject>()
+            .await?
+            .try_collect()
+            .await
+    }
+
+    #[::tokio::test]
+    async fn test_inline_object() {
+        let items = inline(r#"inlinesrc json='{"a":1,"b":"x ! y"}'"#)
+            .await
+            .unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].get_i64("a"), Some(1));
+        assert_eq!(items[0].get_str("b"), Some("x ! y"));
+    }
+
+    #[::tokio::test]
+    async fn test_inline_array() {
+        let items = inline(r#"inlinesrc json='[{"a":1}, {"a":2}, {"a":3}]'"#)
+            .await
+            .unwrap();
+        let values: Vec<_> = items.iter().map(|item| item.get_i64("a")).collect();
+        assert_eq!(values, [Some(1), Some(2), Some(3)]);
+
+        assert!(inline("inlinesrc json='[]'").await.unwrap().is_empty());
+    }
+
+    #[::tokio::test]
+    async fn test_inline_invalid() {
+        assert!(inline("inlinesrc json='1'").await.is_err());
+        assert!(inline("inlinesrc json='[1]'").await.is_err());
+        assert!(inline(r#"inlinesrc json='{"a":'"#).await.is_err());
+        // The double quotes are not unescaped
+        assert!(inline(r#"inlinesrc json="{\"a\":1}""#).await.is_err());
+    }
+
+    #[::tokio::test]
+    async fn test_inline_pipeline() {
+        PipeSession::default()
+            .call(
+                r#"inlinesrc json='[{"a":1},{"a":2}]'
+                ! assertsink max_rows=2 min_rows=2 require_field='a'"#,
+            )
+            .await
+            .unwrap();
+    }
+}
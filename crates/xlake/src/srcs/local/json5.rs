@@ -0,0 +1,118 @@
+use std::{fmt, path::PathBuf};
+
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+use xlake_ast::{Object, PlanArguments, PlanKind};
+use xlake_core::{
+    object::ObjectLayer, ArgSpec, PipeChannel, PipeNodeFactory, PipeNodeImpl, PipeSrc,
+};
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Json5SrcFactory;
+
+impl fmt::Display for Json5SrcFactory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.kind().fmt(f)
+    }
+}
+
+#[async_trait]
+impl PipeNodeFactory for Json5SrcFactory {
+    fn kind(&self) -> PlanKind {
+        PlanKind::Src { name: self.name() }
+    }
+
+    fn name(&self) -> String {
+        "json5".into()
+    }
+
+    fn arguments(&self) -> Vec<ArgSpec> {
+        vec![ArgSpec::required(
+            "path",
+            "JSON5 file path with an object or an array of objects",
+        )]
+    }
+
+    async fn build(&self, args: &PlanArguments) -> Result<PipeNodeImpl> {
+        let imp: Json5Src = args.to()?;
+        Ok(PipeNodeImpl::Src(Box::new(imp)))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Json5Src {
+    path: PathBuf,
+}
+
+#[async_trait]
+impl PipeSrc for Json5Src {
+    async fn call(&self) -> Result<PipeChannel> {
+        let Self { path } = self;
+        let text = fs::read_to_string(path)
+            .await
+            .with_context(|| format!("Failed to read JSON5 file: {}", path.display()))?;
+
+        let objects = parse(&text)?;
+        Ok(objects
+            .into_iter()
+            .map(|object| ObjectLayer::from_object_dyn(object).into())
+            .collect())
+    }
+}
+
+/// Parses a single object or a top-level array of objects.
+fn parse(text: &str) -> Result<Vec<Object>> {
+    let json: ::serde_json::Value = ::json5::from_str(text).context("Invalid JSON5 document")?;
+    match json {
+        ::serde_json::Value::Array(values) => values.into_iter().map(Object::from_json).collect(),
+        json @ ::serde_json::Value::Object(_) => Object::from_json(json).map(|object| vec![object]),
+        _ => bail!("Expected a JSON5 object or an array of objects"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse;
+
+    fn get(text: &str, index: usize, key: &str) -> String {
+        let objects = parse(text).unwrap();
+        objects[index].get(key).unwrap().to_string()
+    }
+
+    #[test]
+    fn test_json5_comments() {
+        let text = r#"
+            // The name of the item
+            {
+                "name": "foo" /* inline comment */,
+            }
+        "#;
+        assert_eq!(get(text, 0, "name"), r#""foo""#);
+    }
+
+    #[test]
+    fn test_json5_trailing_commas() {
+        let text = r#"[
+            {"tags": ["a", "b",], "count": 1,},
+            {"count": 2},
+        ]"#;
+        assert_eq!(parse(text).unwrap().len(), 2);
+        assert_eq!(get(text, 0, "count"), "1");
+        assert_eq!(get(text, 1, "count"), "2");
+    }
+
+    #[test]
+    fn test_json5_unquoted_keys() {
+        let text = "{name: 'foo', count: 3}";
+        assert_eq!(get(text, 0, "name"), r#""foo""#);
+        assert_eq!(get(text, 0, "count"), "3");
+    }
+
+    #[test]
+    fn test_json5_invalid() {
+        assert!(parse("42").is_err());
+        assert!(parse("{name: ").is_err());
+    }
+}
@@ -1,5 +1,11 @@
 pub mod csv;
 #[cfg(feature = "fs")]
 pub mod file;
+pub mod inline;
+#[cfg(feature = "json5")]
+pub mod json5;
+#[cfg(feature = "fs")]
+pub mod ndjson;
+pub mod repeat;
 #[cfg(feature = "io-std")]
 pub mod stdin;
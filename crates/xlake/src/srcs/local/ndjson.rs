@@ -0,0 +1,181 @@
+use std::{
+    fmt,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use futures::{stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use tokio::{
+    fs,
+    io::{AsyncBufReadExt, BufReader},
+};
+use xlake_ast::{Object, PlanArguments, PlanKind, Value};
+use xlake_core::{
+    object::ObjectLayer, stream::DefaultStream, ArgSpec, PipeChannel, PipeEdge, PipeNodeFactory,
+    PipeNodeImpl, PipeSrc, Resumable,
+};
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct NdjsonSrcFactory;
+
+impl fmt::Display for NdjsonSrcFactory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.kind().fmt(f)
+    }
+}
+
+#[async_trait]
+impl PipeNodeFactory for NdjsonSrcFactory {
+    fn kind(&self) -> PlanKind {
+        PlanKind::Src { name: self.name() }
+    }
+
+    fn name(&self) -> String {
+        "ndjson".into()
+    }
+
+    fn arguments(&self) -> Vec<ArgSpec> {
+        vec![
+            ArgSpec::optional(
+                "offset",
+                "Number of the lines to skip, e.g. from a checkpoint",
+            )
+            .with_default(0),
+            ArgSpec::required("path", "Newline-delimited JSON file path"),
+        ]
+    }
+
+    fn output(&self) -> PipeEdge {
+        PipeEdge {
+            model: Some(vec!["stream".into()]),
+            ..Default::default()
+        }
+    }
+
+    async fn build(&self, args: &PlanArguments) -> Result<PipeNodeImpl> {
+        let imp: NdjsonSrc = args.to()?;
+        Ok(PipeNodeImpl::Src(Box::new(imp)))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NdjsonSrc {
+    #[serde(default)]
+    offset: u64,
+    path: PathBuf,
+    /// The number of the lines consumed so far, including the skipped ones
+    #[serde(skip)]
+    position: Arc<AtomicU64>,
+}
+
+#[async_trait]
+impl PipeSrc for NdjsonSrc {
+    async fn call(&self) -> Result<PipeChannel> {
+        let Self {
+            offset,
+            path,
+            position,
+        } = self;
+        let file = fs::File::open(path)
+            .await
+            .with_context(|| format!("Failed to open NDJSON file: {}", path.display()))?;
+        let mut lines = BufReader::new(file).lines();
+
+        // Skip the lines processed by a previous run
+        let mut skipped = 0;
+        while skipped < *offset && lines.next_line().await?.is_some() {
+            skipped += 1;
+        }
+        position.store(skipped, Ordering::SeqCst);
+
+        let position = position.clone();
+        let stream = stream::try_unfold(lines, move |mut lines| {
+            let position = position.clone();
+            async move {
+                while let Some(line) = lines.next_line().await? {
+                    let line_number = position.fetch_add(1, Ordering::SeqCst) + 1;
+                    if line.trim().is_empty() {
+                        continue;
+                    }
+                    let object = Object::from_slice(line.as_bytes())
+                        .with_context(|| format!("Invalid JSON at line {line_number}"))?;
+                    let item = ObjectLayer::from_object_dyn(object).into();
+                    return Ok(Some((item, lines)));
+                }
+                Ok(None)
+            }
+        })
+        .boxed();
+        Ok(PipeChannel::from_stream(DefaultStream::from_stream(stream)))
+    }
+
+    fn as_resumable(&self) -> Option<&dyn Resumable> {
+        Some(self)
+    }
+}
+
+impl Resumable for NdjsonSrc {
+    fn offset(&self) -> Option<Value> {
+        Some(self.position.load(Ordering::SeqCst).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use xlake_core::object::LazyObject;
+
+    use crate::{testing::CollectSinkFactory, PipeSession};
+
+    /// Takes the values of the `id` fields collected so far.
+    fn take_ids(items: &Mutex<Vec<LazyObject>>) -> Vec<String> {
+        items
+            .lock()
+            .unwrap()
+            .drain(..)
+            .map(|item| item.get_raw("id").unwrap().to_string())
+            .collect()
+    }
+
+    #[::tokio::test]
+    async fn test_ndjson_resume() {
+        let path = ::std::env::temp_dir().join(format!("xlake-ndjson-{}", ::std::process::id()));
+        let write = |ids: &[u32]| {
+            let text: String = ids.iter().map(|id| format!("{{\"id\":{id}}}\n")).collect();
+            ::std::fs::write(&path, text).unwrap();
+        };
+        let command = format!("ndjsonsrc path='{}' ! collectsink", path.display());
+
+        let items = Arc::new(Mutex::new(Vec::default()));
+        let mut session = PipeSession::default();
+        session.insert_factory(Box::new(CollectSinkFactory(items.clone())));
+
+        write(&[1, 2, 3]);
+        session.call(&command).await.unwrap();
+        assert_eq!(take_ids(&items), ["1", "2", "3"]);
+
+        // Restart with the saved offset, after more lines are appended
+        let checkpoint = session.checkpoint();
+        // The offset is keyed on the path, not to be shared with the other files
+        assert_eq!(
+            ::serde_json::to_string(&checkpoint).unwrap(),
+            format!(r#"{{"ndjsonsrc path='{}'":3}}"#, path.display()),
+        );
+        let mut session = PipeSession::default();
+        session.insert_factory(Box::new(CollectSinkFactory(items.clone())));
+        session.set_checkpoint(checkpoint);
+
+        write(&[1, 2, 3, 4, 5]);
+        session.call(&command).await.unwrap();
+        assert_eq!(take_ids(&items), ["4", "5"]);
+
+        ::std::fs::remove_file(&path).unwrap();
+    }
+}
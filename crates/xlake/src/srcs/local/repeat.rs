@@ -0,0 +1,153 @@
+use std::fmt;
+
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use futures::{stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use xlake_ast::{Object, PlanArguments, PlanKind};
+use xlake_core::{
+    object::ObjectLayer, stream::DefaultStream, ArgSpec, PipeChannel, PipeNodeFactory,
+    PipeNodeImpl, PipeSrc,
+};
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct RepeatSrcFactory;
+
+impl fmt::Display for RepeatSrcFactory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.kind().fmt(f)
+    }
+}
+
+#[async_trait]
+impl PipeNodeFactory for RepeatSrcFactory {
+    fn kind(&self) -> PlanKind {
+        PlanKind::Src { name: self.name() }
+    }
+
+    fn name(&self) -> String {
+        "repeat".into()
+    }
+
+    fn arguments(&self) -> Vec<ArgSpec> {
+        vec![
+            ArgSpec::optional("count", "Number of objects to emit"),
+            ArgSpec::optional("infinite", "Emit objects until cancelled").with_default(false),
+            ArgSpec::required("json", "Inline JSON object to be repeated"),
+        ]
+    }
+
+    async fn build(&self, args: &PlanArguments) -> Result<PipeNodeImpl> {
+        let args: RepeatSrcArgs = args.to()?;
+        let imp = RepeatSrc::try_from(args)?;
+        Ok(PipeNodeImpl::Src(Box::new(imp)))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct RepeatSrcArgs {
+    #[serde(default)]
+    count: Option<usize>,
+    #[serde(default)]
+    infinite: bool,
+    json: String,
+}
+
+#[derive(Debug)]
+pub struct RepeatSrc {
+    count: Option<usize>,
+    object: Object,
+}
+
+impl TryFrom<RepeatSrcArgs> for RepeatSrc {
+    type Error = anyhow::Error;
+
+    fn try_from(args: RepeatSrcArgs) -> Result<Self, Self::Error> {
+        let RepeatSrcArgs {
+            count,
+            infinite,
+            json,
+        } = args;
+
+        let count = match (count, infinite) {
+            (Some(count), false) => Some(count),
+            (None, true) => None,
+            (Some(_), true) => bail!("Cannot use both count and infinite=true"),
+            (None, false) => bail!("Either count or infinite=true is required"),
+        };
+        let object = Object::from_slice(json.as_bytes()).context("Invalid JSON object")?;
+        Ok(Self { count, object })
+    }
+}
+
+#[async_trait]
+impl PipeSrc for RepeatSrc {
+    async fn call(&self) -> Result<PipeChannel> {
+        let object = self.object.clone();
+        let stream =
+            stream::repeat_with(move || Ok(ObjectLayer::from_object_dyn(object.clone()).into()));
+        let stream = match self.count {
+            Some(count) => stream.take(count).boxed(),
+            None => stream.boxed(),
+        };
+        Ok(PipeChannel::from_stream(DefaultStream::from_stream(stream)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::{StreamExt, TryStreamExt};
+    use xlake_core::{object::LazyObject, PipeSrc};
+
+    use super::{RepeatSrc, RepeatSrcArgs};
+
+    fn repeat(count: Option<usize>, infinite: bool) -> anyhow::Result<RepeatSrc> {
+        RepeatSrc::try_from(RepeatSrcArgs {
+            count,
+            infinite,
+            json: r#"{"name":"foo"}"#.into(),
+        })
+    }
+
+    #[::tokio::test]
+    async fn test_repeat_count() {
+        let items: Vec<LazyObject> = repeat(Some(3), false)
+            .unwrap()
+            .call()
+            .await
+            .unwrap()
+            .into_stream::<LazyObject>()
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(items.len(), 3);
+        assert!(items
+            .iter()
+            .all(|item| item.get_raw("name").unwrap().to_string() == r#""foo""#));
+    }
+
+    #[::tokio::test]
+    async fn test_repeat_infinite() {
+        let items: Vec<LazyObject> = repeat(None, true)
+            .unwrap()
+            .call()
+            .await
+            .unwrap()
+            .into_stream::<LazyObject>()
+            .await
+            .unwrap()
+            .take(1000)
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(items.len(), 1000);
+    }
+
+    #[test]
+    fn test_repeat_invalid_args() {
+        assert!(repeat(None, false).is_err());
+        assert!(repeat(Some(3), true).is_err());
+    }
+}
@@ -1,15 +1,24 @@
-use std::fmt;
+use std::{fmt, io::Cursor, sync::Arc};
 
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
 use async_trait::async_trait;
+use datafusion::arrow::csv::{reader::Format, ReaderBuilder};
 use serde::{Deserialize, Serialize};
-use tokio::io::{self, AsyncReadExt};
-use xlake_ast::{PlanArguments, PlanKind};
+use tokio::io;
+use tracing::debug;
+use xlake_ast::{Object, PlanArguments, PlanKind};
 use xlake_core::{
-    models::hash::HashModelView, PipeChannel, PipeEdge, PipeNodeFactory, PipeNodeImpl, PipeSrc,
+    batch::record_batches_to_rows, models::hash::HashModelView, object::ObjectLayer, ArgSpec,
+    PipeChannel, PipeEdge, PipeNodeFactory, PipeNodeImpl, PipeSrc,
 };
 
-use crate::models::builtins::doc::DocModelObject;
+use crate::{
+    models::builtins::doc::DocModelObject,
+    srcs::{
+        limit::{ByteLimit, LimitExceedAction},
+        trace::trace_bytes,
+    },
+};
 
 #[derive(Copy, Clone, Debug, Default)]
 pub struct StdinSrcFactory;
@@ -30,11 +39,32 @@ impl PipeNodeFactory for StdinSrcFactory {
         "stdin".into()
     }
 
+    fn arguments(&self) -> Vec<ArgSpec> {
+        vec![
+            ArgSpec::optional(
+                "format",
+                "`doc`, `json`, `ndjson`, `csv` or `auto` to sniff the input",
+            )
+            .with_default("doc"),
+            ArgSpec::optional("max_bytes", "Maximum input size in bytes"),
+            ArgSpec::optional("on_exceed", "`error` or `truncate` on oversized input")
+                .with_default("error"),
+            ArgSpec::optional("trace", "Log the leading bytes read as a hexdump")
+                .with_default(false),
+        ]
+    }
+
     fn output(&self) -> PipeEdge {
-        PipeEdge {
-            model: Some(vec!["doc".into(), "hash".into()]),
-            ..Default::default()
-        }
+        StdinFormat::default().edge()
+    }
+
+    fn output_with(&self, args: &PlanArguments) -> PipeEdge {
+        // The invalid arguments fail on build
+        let format = args
+            .to::<StdinSrc>()
+            .map(|imp| imp.format)
+            .unwrap_or_default();
+        format.edge()
     }
 
     async fn build(&self, args: &PlanArguments) -> Result<PipeNodeImpl> {
@@ -44,16 +74,290 @@ impl PipeNodeFactory for StdinSrcFactory {
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-pub struct StdinSrc {}
+pub struct StdinSrc {
+    #[serde(default)]
+    format: StdinFormat,
+    #[serde(default)]
+    max_bytes: Option<u64>,
+    #[serde(default)]
+    on_exceed: LimitExceedAction,
+    #[serde(default)]
+    trace: bool,
+}
 
 #[async_trait]
 impl PipeSrc for StdinSrc {
     async fn call(&self) -> Result<PipeChannel> {
-        let mut document = String::new();
-        io::stdin().read_to_string(&mut document).await?;
+        self.read_from(io::stdin()).await
+    }
+}
+
+impl StdinSrc {
+    /// Reads the whole input as stdin, e.g. of the tests.
+    pub(crate) async fn read_from<R>(&self, reader: R) -> Result<PipeChannel>
+    where
+        R: Unpin + io::AsyncRead,
+    {
+        let &Self {
+            format,
+            max_bytes,
+            on_exceed,
+            trace,
+        } = self;
+        let limit = ByteLimit {
+            max_bytes,
+            on_exceed,
+        };
+        let buf = limit.read_to_end(reader).await?;
+        if trace {
+            trace_bytes("stdin", &buf);
+        }
+        let document = limit.decode_utf8(buf)?;
+        parse(format, document)
+    }
+}
+
+#[derive(
+    Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum StdinFormat {
+    Auto,
+    Csv,
+    #[default]
+    Doc,
+    Json,
+    Ndjson,
+}
+
+impl StdinFormat {
+    /// Returns the output edge of the format.
+    ///
+    /// The models of `auto` are not known until the input is read, except the stream.
+    fn edge(self) -> PipeEdge {
+        let model = match self {
+            Self::Doc => vec!["doc".into(), "hash".into(), "stream".into()],
+            Self::Auto | Self::Csv | Self::Json | Self::Ndjson => vec!["stream".into()],
+        };
+        PipeEdge {
+            model: Some(model),
+            ..Default::default()
+        }
+    }
+
+    /// Sniffs the format of the input, falling back to `doc` unless sure.
+    fn detect(document: &str) -> Self {
+        let text = document.trim();
+        if text.starts_with(['[', '{']) {
+            match ::serde_json::from_str(text) {
+                Ok(::serde_json::Value::Object(_)) => return Self::Json,
+                // Only an array of objects, e.g. not `[1, 2]`
+                Ok(::serde_json::Value::Array(values))
+                    if values.iter().all(::serde_json::Value::is_object) =>
+                {
+                    return Self::Json
+                }
+                _ => (),
+            }
+            if text.starts_with('{') && is_ndjson(text) {
+                return Self::Ndjson;
+            }
+            return Self::Doc;
+        }
+        if is_csv(text) {
+            return Self::Csv;
+        }
+        Self::Doc
+    }
+}
+
+/// Returns whether every non-empty line is a JSON object, in two or more lines.
+fn is_ndjson(text: &str) -> bool {
+    let lines: Vec<_> = text
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .collect();
+    lines.len() >= 2
+        && lines
+            .iter()
+            .all(|line| Object::from_slice(line.as_bytes()).is_ok())
+}
+
+/// Returns whether a header row with commas is followed by the rows of the same width.
+///
+/// Quoted commas make the widths differ, so such inputs are not detected.
+fn is_csv(text: &str) -> bool {
+    let mut lines = text.lines();
+    let header = match lines.next() {
+        Some(header) if header.contains(',') => header,
+        _ => return false,
+    };
+    if header.split(',').any(|name| name.trim().is_empty()) {
+        return false;
+    }
+    let width = header.matches(',').count();
+    let mut rows = lines.filter(|line| !line.trim().is_empty()).peekable();
+    rows.peek().is_some() && rows.all(|row| row.matches(',').count() == width)
+}
+
+fn parse(format: StdinFormat, document: String) -> Result<PipeChannel> {
+    let format = match format {
+        StdinFormat::Auto => {
+            let format = StdinFormat::detect(&document);
+            debug!("Detected stdin format: {format:?}");
+            format
+        }
+        format => format,
+    };
+
+    let objects = match format {
+        StdinFormat::Auto => unreachable!("the format should be detected"),
+        StdinFormat::Csv => parse_csv(&document)?,
+        StdinFormat::Doc => {
+            let item = DocModelObject { document };
+            let item = HashModelView::try_from(item)?;
+            return Ok(PipeChannel::from_unit(item));
+        }
+        StdinFormat::Json => match ::serde_json::from_str(&document).context("Invalid JSON")? {
+            ::serde_json::Value::Array(values) => values
+                .into_iter()
+                .map(Object::from_json)
+                .collect::<Result<_>>()
+                .context("Expected an array of objects")?,
+            value @ ::serde_json::Value::Object(_) => vec![Object::from_json(value)?],
+            _ => bail!("Expected an object or an array of objects"),
+        },
+        StdinFormat::Ndjson => document
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(index, line)| {
+                Object::from_slice(line.as_bytes())
+                    .with_context(|| format!("Invalid JSON at line {}", index + 1))
+            })
+            .collect::<Result<_>>()?,
+    };
+    Ok(objects
+        .into_iter()
+        .map(|object| ObjectLayer::from_object_dyn(object).into())
+        .collect())
+}
+
+fn parse_csv(document: &str) -> Result<Vec<Object>> {
+    let format = Format::default().with_header(true);
+    let (schema, _) = format.infer_schema(Cursor::new(document), None)?;
+    let reader = ReaderBuilder::new(Arc::new(schema))
+        .with_header(true)
+        .build(Cursor::new(document))?;
+
+    let mut rows = Vec::default();
+    for batch in reader {
+        rows.extend(record_batches_to_rows(&batch?)?);
+    }
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::TryStreamExt;
+    use xlake_ast::PlanArguments;
+    use xlake_core::{object::LazyObject, PipeNodeFactory};
+
+    use super::{parse, StdinFormat, StdinSrcFactory};
+
+    async fn parse_auto(document: &str) -> (StdinFormat, Vec<LazyObject>) {
+        let format = StdinFormat::detect(document);
+        let items = parse(StdinFormat::Auto, document.into())
+            .unwrap()
+            .into_stream()
+            .await
+            .unwrap()
+            .and_then(LazyObject::flatten)
+            .try_collect()
+            .await
+            .unwrap();
+        (format, items)
+    }
+
+    #[::tokio::test]
+    async fn test_stdin_detect_json() {
+        let (format, items) = parse_auto(r#"[{"name":"foo"},{"name":"bar"}]"#).await;
+        assert_eq!(format, StdinFormat::Json);
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[1].get_str("name"), Some("bar"));
+
+        let (format, items) = parse_auto("{\n  \"name\": \"foo\"\n}\n").await;
+        assert_eq!(format, StdinFormat::Json);
+        assert_eq!(items.len(), 1);
+    }
+
+    #[::tokio::test]
+    async fn test_stdin_detect_ndjson() {
+        let (format, items) = parse_auto("{\"id\":1}\n\n{\"id\":2}\n{\"id\":3}\n").await;
+        assert_eq!(format, StdinFormat::Ndjson);
+        let ids: Vec<_> = items.iter().map(|item| item.get_i64("id")).collect();
+        assert_eq!(ids, [Some(1), Some(2), Some(3)]);
+    }
+
+    #[::tokio::test]
+    async fn test_stdin_detect_csv() {
+        let (format, items) = parse_auto("name,score\nfoo,10\nbar,20\n").await;
+        assert_eq!(format, StdinFormat::Csv);
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].get_str("name"), Some("foo"));
+        assert_eq!(items[1].get_i64("score"), Some(20));
+    }
+
+    #[::tokio::test]
+    async fn test_stdin_detect_doc() {
+        for document in [
+            "Hello, world!",
+            "Hello, world!\nNo commas here",
+            "{not json}\n{neither}",
+            "[1] is a footnote",
+            "[1, 2]",
+            r#"[{"name":"foo"}, "bar"]"#,
+        ] {
+            let (format, items) = parse_auto(document).await;
+            assert_eq!(format, StdinFormat::Doc, "{document}");
+            assert_eq!(items.len(), 1);
+            assert_eq!(items[0].get_str("document"), Some(document));
+        }
+    }
+
+    #[::tokio::test]
+    async fn test_stdin_explicit_format() {
+        // The explicit format is not overridden by the detection
+        let document = "name,score\nfoo,10\n";
+        let items: Vec<LazyObject> = parse(StdinFormat::Doc, document.into())
+            .unwrap()
+            .into_stream()
+            .await
+            .unwrap()
+            .and_then(LazyObject::flatten)
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].get_str("document"), Some(document));
+
+        assert!(parse(StdinFormat::Json, document.into()).is_err());
+    }
 
-        let item = DocModelObject { document };
-        let item = HashModelView::try_from(item)?;
-        Ok(PipeChannel::from_unit(item))
+    #[test]
+    fn test_stdin_output_models() {
+        let models = |format: &str| {
+            let args = PlanArguments::from_json(::serde_json::json!({ "format": format })).unwrap();
+            StdinSrcFactory.output_with(&args).model.unwrap()
+        };
+        assert_eq!(models("doc"), ["doc", "hash", "stream"]);
+        assert_eq!(models("json"), ["stream"]);
+        assert_eq!(models("csv"), ["stream"]);
+        // The models of the detected format are known at runtime only
+        assert_eq!(models("auto"), ["stream"]);
+        assert_eq!(
+            StdinSrcFactory.output_with(&PlanArguments::default()),
+            StdinSrcFactory.output(),
+        );
     }
 }
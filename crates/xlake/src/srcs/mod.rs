@@ -1 +1,7 @@
+pub mod db;
+pub mod id;
+pub mod limit;
 pub mod local;
+pub mod mq;
+pub mod remote;
+pub mod trace;
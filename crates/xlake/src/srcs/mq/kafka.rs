@@ -0,0 +1,184 @@
+use std::{
+    collections::BTreeMap,
+    fmt,
+    sync::{Arc, Mutex},
+};
+
+use anyhow::{Error, Result};
+use async_trait::async_trait;
+use futures::{stream, StreamExt};
+use rdkafka::{
+    consumer::{Consumer, StreamConsumer},
+    ClientConfig, Message, Offset, TopicPartitionList,
+};
+use serde::{Deserialize, Serialize};
+use xlake_ast::{Binary, Object, PlanArguments, PlanKind, Value};
+use xlake_core::{
+    object::{LazyObject, ObjectLayer},
+    stream::DefaultStream,
+    ArgSpec, PipeChannel, PipeEdge, PipeNodeFactory, PipeNodeImpl, PipeSrc, Resumable,
+};
+
+use crate::models::builtins::binary::BinaryModelObject;
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct KafkaSrcFactory;
+
+impl fmt::Display for KafkaSrcFactory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.kind().fmt(f)
+    }
+}
+
+#[async_trait]
+impl PipeNodeFactory for KafkaSrcFactory {
+    fn kind(&self) -> PlanKind {
+        PlanKind::Src { name: self.name() }
+    }
+
+    fn name(&self) -> String {
+        "kafka".into()
+    }
+
+    fn arguments(&self) -> Vec<ArgSpec> {
+        vec![
+            ArgSpec::required("brokers", "Comma-separated list of Kafka brokers"),
+            ArgSpec::optional("format", "Parse each payload as `json` or keep it `raw`")
+                .with_default("json"),
+            ArgSpec::required("group", "Consumer group id"),
+            ArgSpec::optional(
+                "offset",
+                "Next offsets per partition, e.g. `{\"0\": 42}`, bypassing the group assignment",
+            ),
+            ArgSpec::required("topic", "Topic to subscribe"),
+        ]
+    }
+
+    fn output(&self) -> PipeEdge {
+        PipeEdge {
+            model: Some(vec!["stream".into()]),
+            ..Default::default()
+        }
+    }
+
+    async fn build(&self, args: &PlanArguments) -> Result<PipeNodeImpl> {
+        let imp: KafkaSrc = args.to()?;
+        Ok(PipeNodeImpl::Src(Box::new(imp)))
+    }
+}
+
+/// Consumes the messages of a topic, as JSON objects or raw binaries.
+///
+/// The stream never ends by itself, so the offsets are recorded into the checkpoint
+/// only if the pipeline is stopped by a deadline, e.g. [`PipeSession::call_with_deadline`].
+///
+/// [`PipeSession::call_with_deadline`]: crate::PipeSession::call_with_deadline
+#[derive(Debug, Serialize, Deserialize)]
+pub struct KafkaSrc {
+    brokers: String,
+    #[serde(default)]
+    format: KafkaFormat,
+    group: String,
+    #[serde(default)]
+    offset: Option<BTreeMap<i32, i64>>,
+    topic: String,
+    /// The next offsets per partition after the consumed messages
+    #[serde(skip)]
+    position: Arc<Mutex<BTreeMap<i32, i64>>>,
+}
+
+#[async_trait]
+impl PipeSrc for KafkaSrc {
+    async fn call(&self) -> Result<PipeChannel> {
+        let Self {
+            brokers,
+            format,
+            group,
+            offset,
+            topic,
+            position,
+        } = self;
+
+        let consumer: StreamConsumer = ClientConfig::new()
+            .set("bootstrap.servers", brokers)
+            .set("group.id", group)
+            .set("enable.auto.commit", "true")
+            .set("auto.offset.reset", "earliest")
+            .create()?;
+        match offset {
+            // Resume from the given offsets, e.g. from a checkpoint
+            Some(offset) => {
+                let mut assignment = TopicPartitionList::new();
+                for (&partition, &next) in offset {
+                    assignment.add_partition_offset(topic, partition, Offset::Offset(next))?;
+                }
+                consumer.assign(&assignment)?;
+                *position.lock().unwrap() = offset.clone();
+            }
+            None => consumer.subscribe(&[topic.as_str()])?,
+        }
+
+        // Kafka is unbounded; the stream ends only when the pipeline is cancelled
+        let format = *format;
+        let position = position.clone();
+        let stream = stream::try_unfold(consumer, move |consumer| {
+            let position = position.clone();
+            async move {
+                let item = {
+                    let message = consumer.recv().await?;
+                    position
+                        .lock()
+                        .unwrap()
+                        .insert(message.partition(), message.offset() + 1);
+                    format.parse(message.payload().unwrap_or_default())?
+                };
+                Ok::<_, Error>(Some((item, consumer)))
+            }
+        })
+        .boxed();
+        Ok(PipeChannel::from_stream(DefaultStream::from_stream(stream)))
+    }
+
+    fn as_resumable(&self) -> Option<&dyn Resumable> {
+        Some(self)
+    }
+}
+
+impl Resumable for KafkaSrc {
+    fn offset(&self) -> Option<Value> {
+        let position = self.position.lock().unwrap();
+        if position.is_empty() {
+            return None;
+        }
+        Some(Value::Object(
+            position
+                .iter()
+                .map(|(partition, next)| (partition.to_string(), (*next).into()))
+                .collect(),
+        ))
+    }
+}
+
+#[derive(
+    Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum KafkaFormat {
+    #[default]
+    Json,
+    Raw,
+}
+
+impl KafkaFormat {
+    fn parse(&self, payload: &[u8]) -> Result<LazyObject> {
+        match self {
+            Self::Json => Object::from_slice(payload)
+                .map(ObjectLayer::from_object_dyn)
+                .map(Into::into),
+            Self::Raw => Ok(BinaryModelObject {
+                content: Binary(payload.to_vec()),
+            }
+            .into()),
+        }
+    }
+}
@@ -0,0 +1,261 @@
+use std::fmt;
+
+use anyhow::{Context, Result};
+use arrow_flight::sql::client::FlightSqlServiceClient;
+use async_trait::async_trait;
+use futures::{stream, StreamExt, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use tonic::transport::Endpoint;
+use xlake_ast::{PlanArguments, PlanKind};
+use xlake_core::{
+    batch::record_batches_to_rows, object::ObjectLayer, stream::DefaultStream, ArgSpec,
+    PipeChannel, PipeEdge, PipeNodeFactory, PipeNodeImpl, PipeSrc,
+};
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct FlightSrcFactory;
+
+impl fmt::Display for FlightSrcFactory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.kind().fmt(f)
+    }
+}
+
+#[async_trait]
+impl PipeNodeFactory for FlightSrcFactory {
+    fn kind(&self) -> PlanKind {
+        PlanKind::Src { name: self.name() }
+    }
+
+    fn name(&self) -> String {
+        "flight".into()
+    }
+
+    fn arguments(&self) -> Vec<ArgSpec> {
+        vec![
+            ArgSpec::required(
+                "endpoint",
+                "Arrow Flight SQL server, e.g. `http://localhost:50051`",
+            ),
+            ArgSpec::required("query", "SQL query to be run by the server"),
+            ArgSpec::optional("token", "Bearer token to authenticate"),
+        ]
+    }
+
+    fn output(&self) -> PipeEdge {
+        PipeEdge {
+            model: Some(vec!["batch".into()]),
+            ..Default::default()
+        }
+    }
+
+    async fn build(&self, args: &PlanArguments) -> Result<PipeNodeImpl> {
+        let imp: FlightSrc = args.to()?;
+        Ok(PipeNodeImpl::Src(Box::new(imp)))
+    }
+}
+
+/// Streams the result of a query from an Arrow Flight SQL server.
+#[derive(Serialize, Deserialize)]
+pub struct FlightSrc {
+    endpoint: String,
+    query: String,
+    #[serde(default)]
+    token: Option<String>,
+}
+
+impl fmt::Debug for FlightSrc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Hide the token
+        f.debug_struct("FlightSrc")
+            .field("endpoint", &self.endpoint)
+            .field("query", &self.query)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl PipeSrc for FlightSrc {
+    async fn call(&self) -> Result<PipeChannel> {
+        let Self {
+            endpoint,
+            query,
+            token,
+        } = self;
+
+        let channel = Endpoint::from_shared(endpoint.clone())
+            .with_context(|| format!("Invalid endpoint: {endpoint}"))?
+            .connect()
+            .await
+            .with_context(|| format!("Failed to connect to Flight server: {endpoint}"))?;
+        let mut client = FlightSqlServiceClient::new(channel);
+        if let Some(token) = token {
+            client.set_token(token.clone());
+        }
+
+        // Fetch the partitions of the result in order
+        let info = client.execute(query.clone(), None).await?;
+        let tickets = info
+            .endpoint
+            .into_iter()
+            .map(|endpoint| endpoint.ticket.context("Missing ticket"))
+            .collect::<Result<Vec<_>>>()?;
+
+        let stream = stream::iter(tickets)
+            .then(move |ticket| {
+                let mut client = client.clone();
+                async move { client.do_get(ticket).await.map_err(Into::into) }
+            })
+            .map_ok(|batches| batches.map_err(::anyhow::Error::from))
+            .try_flatten()
+            .map(|batch| batch.and_then(|ref batch| record_batches_to_rows(batch)))
+            .map_ok(|rows| stream::iter(rows.into_iter().map(Ok)))
+            .try_flatten()
+            .map_ok(ObjectLayer::from_object_dyn)
+            .map_ok(Into::into)
+            .boxed();
+        Ok(PipeChannel::from_stream(DefaultStream::from_stream(stream)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{pin::Pin, sync::Arc};
+
+    use arrow_flight::{
+        encode::FlightDataEncoderBuilder,
+        flight_service_server::FlightServiceServer,
+        sql::{
+            server::FlightSqlService, CommandStatementQuery, ProstMessageExt, SqlInfo,
+            TicketStatementQuery,
+        },
+        FlightData, FlightDescriptor, FlightEndpoint, FlightInfo, Ticket,
+    };
+    use datafusion::arrow::{
+        array::{Int64Array, RecordBatch, StringArray},
+        datatypes::{DataType, Field, Schema},
+    };
+    use futures::{stream, Stream, StreamExt, TryStreamExt};
+    use prost::Message;
+    use tokio::net::TcpListener;
+    use tokio_stream::wrappers::TcpListenerStream;
+    use tonic::{transport::Server, Request, Response, Status};
+    use xlake_core::{object::LazyObject, PipeSrc};
+
+    use super::FlightSrc;
+
+    const TOKEN: &str = "secret";
+
+    /// Answers any query with a fixed table, if authenticated.
+    #[derive(Clone)]
+    struct MockFlightService;
+
+    #[::tonic::async_trait]
+    impl FlightSqlService for MockFlightService {
+        type FlightService = Self;
+
+        async fn get_flight_info_statement(
+            &self,
+            query: CommandStatementQuery,
+            _request: Request<FlightDescriptor>,
+        ) -> Result<Response<FlightInfo>, Status> {
+            let handle = TicketStatementQuery {
+                statement_handle: query.query.into(),
+            };
+            let ticket = Ticket::new(handle.as_any().encode_to_vec());
+            let endpoint = FlightEndpoint::new().with_ticket(ticket);
+            Ok(Response::new(FlightInfo::new().with_endpoint(endpoint)))
+        }
+
+        async fn do_get_statement(
+            &self,
+            _ticket: TicketStatementQuery,
+            request: Request<Ticket>,
+        ) -> Result<Response<Pin<Box<dyn Send + Stream<Item = Result<FlightData, Status>>>>>, Status>
+        {
+            let authorization = request
+                .metadata()
+                .get("authorization")
+                .and_then(|value| value.to_str().ok());
+            let expected = format!("Bearer {TOKEN}");
+            if authorization != Some(expected.as_str()) {
+                return Err(Status::unauthenticated("Invalid token"));
+            }
+
+            let schema = Schema::new(vec![
+                Field::new("id", DataType::Int64, false),
+                Field::new("name", DataType::Utf8, false),
+            ]);
+            let ids = Int64Array::from(vec![1, 2]);
+            let names = StringArray::from(vec!["foo", "bar"]);
+            let batch =
+                RecordBatch::try_new(Arc::new(schema), vec![Arc::new(ids), Arc::new(names)])
+                    .unwrap();
+
+            let stream = FlightDataEncoderBuilder::new()
+                .build(stream::iter([Ok(batch)]))
+                .map_err(Status::from)
+                .boxed();
+            Ok(Response::new(stream))
+        }
+
+        async fn register_sql_info(&self, _id: i32, _result: &SqlInfo) {}
+    }
+
+    async fn serve() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let incoming = TcpListenerStream::new(listener);
+        let service = FlightServiceServer::new(MockFlightService);
+        ::tokio::spawn(
+            Server::builder()
+                .add_service(service)
+                .serve_with_incoming(incoming),
+        );
+        format!("http://{addr}")
+    }
+
+    fn src(endpoint: &str, token: Option<&str>) -> FlightSrc {
+        FlightSrc {
+            endpoint: endpoint.into(),
+            query: "SELECT * FROM users".into(),
+            token: token.map(Into::into),
+        }
+    }
+
+    #[::tokio::test]
+    async fn test_flight_query() {
+        let endpoint = serve().await;
+        let rows: Vec<_> = src(&endpoint, Some(TOKEN))
+            .call()
+            .await
+            .unwrap()
+            .into_stream::<LazyObject>()
+            .await
+            .unwrap()
+            .map_ok(|item| {
+                let name = item.get_str("name").unwrap().to_string();
+                (item.get_i64("id").unwrap(), name)
+            })
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(rows, [(1, "foo".into()), (2, "bar".into())]);
+    }
+
+    #[::tokio::test]
+    async fn test_flight_unauthenticated() {
+        let endpoint = serve().await;
+        let result: ::anyhow::Result<Vec<LazyObject>> = async {
+            src(&endpoint, None)
+                .call()
+                .await?
+                .into_stream::<LazyObject>()
+                .await?
+                .try_collect()
+                .await
+        }
+        .await;
+        assert!(result.is_err());
+    }
+}
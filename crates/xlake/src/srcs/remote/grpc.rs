@@ -0,0 +1,481 @@
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use futures::{future, StreamExt, TryStreamExt};
+use prost_reflect::{
+    prost::Message, DescriptorPool, DeserializeOptions, DynamicMessage, MessageDescriptor,
+    MethodDescriptor,
+};
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+use tonic::{
+    client::Grpc,
+    codec::{Codec, DecodeBuf, Decoder, EncodeBuf, Encoder},
+    codegen::http::uri::PathAndQuery,
+    transport::{Channel, Endpoint},
+    Request, Status,
+};
+use xlake_ast::{Object, PlanArguments, PlanKind};
+use xlake_core::{
+    object::ObjectLayer, stream::DefaultStream, ArgSpec, PipeChannel, PipeEdge, PipeNodeFactory,
+    PipeNodeImpl, PipeSrc,
+};
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct GrpcSrcFactory;
+
+impl fmt::Display for GrpcSrcFactory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.kind().fmt(f)
+    }
+}
+
+#[async_trait]
+impl PipeNodeFactory for GrpcSrcFactory {
+    fn kind(&self) -> PlanKind {
+        PlanKind::Src { name: self.name() }
+    }
+
+    fn name(&self) -> String {
+        "grpc".into()
+    }
+
+    fn arguments(&self) -> Vec<ArgSpec> {
+        vec![
+            ArgSpec::required(
+                "descriptor",
+                "Encoded `FileDescriptorSet` file, e.g. by `protoc --descriptor_set_out`",
+            ),
+            ArgSpec::required("endpoint", "gRPC server, e.g. `http://localhost:50051`"),
+            ArgSpec::required(
+                "method",
+                "Server-streaming method, e.g. `my.package.MyService/MyMethod`",
+            ),
+            ArgSpec::optional("request", "Request message in JSON").with_default("{}"),
+        ]
+    }
+
+    fn output(&self) -> PipeEdge {
+        PipeEdge {
+            model: Some(vec!["stream".into()]),
+            ..Default::default()
+        }
+    }
+
+    async fn build(&self, args: &PlanArguments) -> Result<PipeNodeImpl> {
+        let imp: GrpcSrc = args.to()?;
+        Ok(PipeNodeImpl::Src(Box::new(imp)))
+    }
+}
+
+/// Emits each message streamed back by a server-streaming gRPC method.
+///
+/// The messages are decoded dynamically by the given descriptors, as in the protobuf JSON mapping.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GrpcSrc {
+    descriptor: PathBuf,
+    endpoint: String,
+    method: String,
+    #[serde(default = "GrpcSrc::default_request")]
+    request: String,
+}
+
+impl GrpcSrc {
+    fn default_request() -> String {
+        "{}".into()
+    }
+}
+
+#[async_trait]
+impl PipeSrc for GrpcSrc {
+    async fn call(&self) -> Result<PipeChannel> {
+        let Self {
+            descriptor,
+            endpoint,
+            method,
+            request,
+        } = self;
+
+        let method = load_method(descriptor, method).await?;
+        if method.is_client_streaming() || !method.is_server_streaming() {
+            bail!("Not a server-streaming method: {}", method.full_name())
+        }
+        let request = {
+            let json: ::serde_json::Value = ::serde_json::from_str(request)
+                .with_context(|| format!("Invalid request: {request}"))?;
+            to_message(method.input(), json)?
+        };
+
+        let mut client = connect(endpoint).await?;
+        let path = method_path(&method)?;
+        let codec = DynamicCodec::new(method.output());
+        let stream = client
+            .server_streaming(Request::new(request), path, codec)
+            .await?
+            .into_inner()
+            .map_err(::anyhow::Error::from)
+            .and_then(|message| future::ready(Object::from_value(&message)))
+            .map_ok(ObjectLayer::from_object_dyn)
+            .map_ok(Into::into)
+            .boxed();
+        Ok(PipeChannel::from_stream(DefaultStream::from_stream(stream)))
+    }
+}
+
+/// Resolves the method, e.g. `my.package.MyService/MyMethod`, from a `FileDescriptorSet` file.
+pub(crate) async fn load_method(descriptor: &Path, method: &str) -> Result<MethodDescriptor> {
+    let buf = fs::read(descriptor)
+        .await
+        .with_context(|| format!("Failed to read descriptor: {}", descriptor.display()))?;
+    let pool = DescriptorPool::decode(buf.as_slice())
+        .with_context(|| format!("Invalid descriptor: {}", descriptor.display()))?;
+
+    let (service, name) = method
+        .rsplit_once(['/', '.'])
+        .with_context(|| format!("Invalid method: {method}"))?;
+    let service = pool
+        .get_service_by_name(service)
+        .with_context(|| format!("No such service: {service}"))?;
+    let method = service.methods().find(|method| method.name() == name);
+    method.with_context(|| format!("No such method: {name}"))
+}
+
+pub(crate) async fn connect(endpoint: &str) -> Result<Grpc<Channel>> {
+    let channel = Endpoint::from_shared(endpoint.to_string())
+        .with_context(|| format!("Invalid endpoint: {endpoint}"))?
+        .connect()
+        .await
+        .with_context(|| format!("Failed to connect to gRPC server: {endpoint}"))?;
+    let mut client = Grpc::new(channel);
+    client.ready().await?;
+    Ok(client)
+}
+
+pub(crate) fn method_path(method: &MethodDescriptor) -> Result<PathAndQuery> {
+    let path = format!("/{}/{}", method.parent_service().full_name(), method.name());
+    PathAndQuery::try_from(path).map_err(Into::into)
+}
+
+/// Converts a JSON value into the message, ignoring the unknown fields, e.g. `hash`.
+pub(crate) fn to_message(
+    descriptor: MessageDescriptor,
+    json: ::serde_json::Value,
+) -> Result<DynamicMessage> {
+    let options = DeserializeOptions::new().deny_unknown_fields(false);
+    DynamicMessage::deserialize_with_options(descriptor, json, &options).map_err(Into::into)
+}
+
+/// Encodes any message, decoding the ones of the given type.
+#[derive(Clone, Debug)]
+pub(crate) struct DynamicCodec {
+    decode: MessageDescriptor,
+}
+
+impl DynamicCodec {
+    pub(crate) fn new(decode: MessageDescriptor) -> Self {
+        Self { decode }
+    }
+}
+
+impl Codec for DynamicCodec {
+    type Encode = DynamicMessage;
+    type Decode = DynamicMessage;
+    type Encoder = DynamicEncoder;
+    type Decoder = DynamicDecoder;
+
+    fn encoder(&mut self) -> Self::Encoder {
+        DynamicEncoder
+    }
+
+    fn decoder(&mut self) -> Self::Decoder {
+        DynamicDecoder(self.decode.clone())
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct DynamicEncoder;
+
+impl Encoder for DynamicEncoder {
+    type Item = DynamicMessage;
+    type Error = Status;
+
+    fn encode(&mut self, item: Self::Item, dst: &mut EncodeBuf<'_>) -> Result<(), Self::Error> {
+        item.encode(dst)
+            .map_err(|error| Status::internal(error.to_string()))
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct DynamicDecoder(MessageDescriptor);
+
+impl Decoder for DynamicDecoder {
+    type Item = DynamicMessage;
+    type Error = Status;
+
+    fn decode(&mut self, src: &mut DecodeBuf<'_>) -> Result<Option<Self::Item>, Self::Error> {
+        DynamicMessage::decode(self.0.clone(), src)
+            .map(Some)
+            .map_err(|error| Status::internal(error.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        convert::Infallible,
+        path::PathBuf,
+        sync::{Arc, Mutex},
+        task::{Context, Poll},
+    };
+
+    use futures::{stream, TryStreamExt};
+    use prost::Message;
+    use prost_reflect::{DescriptorPool, DynamicMessage, MessageDescriptor, Value};
+    use prost_types::{
+        field_descriptor_proto::{Label, Type},
+        DescriptorProto, FieldDescriptorProto, FileDescriptorProto, FileDescriptorSet,
+        MethodDescriptorProto, ServiceDescriptorProto,
+    };
+    use tokio::net::TcpListener;
+    use tokio_stream::wrappers::TcpListenerStream;
+    use tonic::{
+        body::BoxBody,
+        codegen::{http, Body, BoxFuture, Service, StdError},
+        server::{ClientStreamingService, Grpc, NamedService, ServerStreamingService},
+        transport::Server,
+        Request, Response, Status, Streaming,
+    };
+    use xlake_ast::PlanArguments;
+    use xlake_core::{object::LazyObject, PipeSink, PipeSrc};
+
+    use crate::{
+        sinks::remote::grpc::GrpcSink,
+        testing::{channel, object},
+    };
+
+    use super::{DynamicCodec, GrpcSrc};
+
+    /// Encodes the descriptors of `echo.EchoService`, as `protoc --descriptor_set_out` does.
+    fn descriptor_set() -> Vec<u8> {
+        let field = |name: &str, number, r#type: Type| FieldDescriptorProto {
+            name: Some(name.into()),
+            number: Some(number),
+            label: Some(Label::Optional as i32),
+            r#type: Some(r#type as i32),
+            json_name: Some(name.into()),
+            ..Default::default()
+        };
+        let method = |name: &str, client_streaming, server_streaming| MethodDescriptorProto {
+            name: Some(name.into()),
+            input_type: Some(".echo.Echo".into()),
+            output_type: Some(".echo.Echo".into()),
+            client_streaming: Some(client_streaming),
+            server_streaming: Some(server_streaming),
+            ..Default::default()
+        };
+        let file = FileDescriptorProto {
+            name: Some("echo.proto".into()),
+            package: Some("echo".into()),
+            message_type: vec![DescriptorProto {
+                name: Some("Echo".into()),
+                field: vec![
+                    field("message", 1, Type::String),
+                    field("count", 2, Type::Int32),
+                ],
+                ..Default::default()
+            }],
+            service: vec![ServiceDescriptorProto {
+                name: Some("EchoService".into()),
+                method: vec![
+                    method("Repeat", false, true),
+                    method("Collect", true, false),
+                ],
+                ..Default::default()
+            }],
+            syntax: Some("proto3".into()),
+            ..Default::default()
+        };
+        FileDescriptorSet { file: vec![file] }.encode_to_vec()
+    }
+
+    fn write_descriptor(name: &str) -> PathBuf {
+        let path = ::std::env::temp_dir()
+            .join(format!("xlake-grpc-{name}-{}.binpb", ::std::process::id()));
+        ::std::fs::write(&path, descriptor_set()).unwrap();
+        path
+    }
+
+    /// Repeats the requested message `count` times, or collects the streamed messages.
+    #[derive(Clone)]
+    struct MockEchoService {
+        echo: MessageDescriptor,
+        received: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl MockEchoService {
+        fn echo(&self, message: &str, count: i32) -> DynamicMessage {
+            let mut echo = DynamicMessage::new(self.echo.clone());
+            echo.set_field_by_name("message", Value::String(message.into()));
+            echo.set_field_by_name("count", Value::I32(count));
+            echo
+        }
+    }
+
+    impl NamedService for MockEchoService {
+        const NAME: &'static str = "echo.EchoService";
+    }
+
+    impl ServerStreamingService<DynamicMessage> for MockEchoService {
+        type Response = DynamicMessage;
+        type ResponseStream = stream::Iter<::std::vec::IntoIter<Result<DynamicMessage, Status>>>;
+        type Future = BoxFuture<Response<Self::ResponseStream>, Status>;
+
+        fn call(&mut self, request: Request<DynamicMessage>) -> Self::Future {
+            let request = request.into_inner();
+            let message = request.get_field_by_name("message").unwrap();
+            let count = request
+                .get_field_by_name("count")
+                .unwrap()
+                .as_i32()
+                .unwrap();
+            let items: Vec<_> = (0..count)
+                .map(|index| Ok(self.echo(message.as_str().unwrap(), index)))
+                .collect();
+            Box::pin(async move { Ok(Response::new(stream::iter(items))) })
+        }
+    }
+
+    impl ClientStreamingService<DynamicMessage> for MockEchoService {
+        type Response = DynamicMessage;
+        type Future = BoxFuture<Response<Self::Response>, Status>;
+
+        fn call(&mut self, request: Request<Streaming<DynamicMessage>>) -> Self::Future {
+            let service = self.clone();
+            Box::pin(async move {
+                let messages: Vec<_> = request.into_inner().try_collect().await?;
+                let names = messages.iter().map(|message| {
+                    let message = message.get_field_by_name("message").unwrap();
+                    message.as_str().unwrap().to_string()
+                });
+                service.received.lock().unwrap().extend(names);
+                Ok(Response::new(service.echo("", messages.len() as i32)))
+            })
+        }
+    }
+
+    impl<B> Service<http::Request<B>> for MockEchoService
+    where
+        B: Body + Send + 'static,
+        B::Error: Into<StdError> + Send + 'static,
+    {
+        type Response = http::Response<BoxBody>;
+        type Error = Infallible;
+        type Future = BoxFuture<Self::Response, Self::Error>;
+
+        fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, request: http::Request<B>) -> Self::Future {
+            let service = self.clone();
+            Box::pin(async move {
+                let mut grpc = Grpc::new(DynamicCodec::new(service.echo.clone()));
+                let response = match request.uri().path() {
+                    "/echo.EchoService/Repeat" => grpc.server_streaming(service, request).await,
+                    _ => grpc.client_streaming(service, request).await,
+                };
+                Ok(response)
+            })
+        }
+    }
+
+    async fn serve() -> (String, Arc<Mutex<Vec<String>>>) {
+        let pool = DescriptorPool::decode(descriptor_set().as_slice()).unwrap();
+        let received = Arc::new(Mutex::new(Vec::default()));
+        let service = MockEchoService {
+            echo: pool.get_message_by_name("echo.Echo").unwrap(),
+            received: received.clone(),
+        };
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let incoming = TcpListenerStream::new(listener);
+        ::tokio::spawn(
+            Server::builder()
+                .add_service(service)
+                .serve_with_incoming(incoming),
+        );
+        (format!("http://{addr}"), received)
+    }
+
+    #[::tokio::test]
+    async fn test_grpc_server_streaming() {
+        let (endpoint, _) = serve().await;
+        let descriptor = write_descriptor("src");
+
+        let src = |method: &str| GrpcSrc {
+            descriptor: descriptor.clone(),
+            endpoint: endpoint.clone(),
+            method: method.into(),
+            request: r#"{"message":"hello","count":3}"#.into(),
+        };
+        let items: ::anyhow::Result<Vec<_>> = async {
+            src("echo.EchoService/Repeat")
+                .call()
+                .await?
+                .into_stream::<LazyObject>()
+                .await?
+                .map_ok(|item| {
+                    let message = item.get_str("message").unwrap().to_string();
+                    // The default values are omitted as in the protobuf JSON mapping
+                    (message, item.get_i64("count").unwrap_or_default())
+                })
+                .try_collect()
+                .await
+        }
+        .await;
+
+        // Not a server-streaming method
+        let result = src("echo.EchoService.Collect").call().await;
+        ::std::fs::remove_file(&descriptor).unwrap();
+        assert!(result.is_err());
+        assert_eq!(
+            items.unwrap(),
+            [
+                ("hello".into(), 0),
+                ("hello".into(), 1),
+                ("hello".into(), 2),
+            ],
+        );
+    }
+
+    #[::tokio::test]
+    async fn test_grpc_client_streaming() {
+        let (endpoint, received) = serve().await;
+        let descriptor = write_descriptor("sink");
+
+        let channel = channel(["foo", "bar"].map(|message| {
+            // The unknown fields are ignored
+            object([
+                ("message", ::xlake_ast::Value::from(message)),
+                ("extra", true.into()),
+            ])
+        }));
+        let sink: GrpcSink = PlanArguments::from_json(::serde_json::json!({
+            "descriptor": &descriptor,
+            "endpoint": endpoint,
+            "method": "echo.EchoService/Collect",
+        }))
+        .and_then(|args| args.to())
+        .unwrap();
+        let result = sink.call(channel).await;
+        ::std::fs::remove_file(&descriptor).unwrap();
+        result.unwrap();
+
+        assert_eq!(*received.lock().unwrap(), ["foo", "bar"]);
+    }
+}
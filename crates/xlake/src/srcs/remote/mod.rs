@@ -0,0 +1,6 @@
+#[cfg(feature = "flight")]
+pub mod flight;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+#[cfg(feature = "http")]
+pub mod prom;
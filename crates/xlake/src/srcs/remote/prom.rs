@@ -0,0 +1,246 @@
+use std::fmt;
+
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use xlake_ast::{Number, Object, PlanArguments, PlanKind, Value};
+use xlake_core::{
+    object::{LazyObject, ObjectLayer},
+    ArgSpec, PipeChannel, PipeEdge, PipeNodeFactory, PipeNodeImpl, PipeSrc,
+};
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct PromSrcFactory;
+
+impl fmt::Display for PromSrcFactory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.kind().fmt(f)
+    }
+}
+
+#[async_trait]
+impl PipeNodeFactory for PromSrcFactory {
+    fn kind(&self) -> PlanKind {
+        PlanKind::Src { name: self.name() }
+    }
+
+    fn name(&self) -> String {
+        "prom".into()
+    }
+
+    fn arguments(&self) -> Vec<ArgSpec> {
+        vec![ArgSpec::required(
+            "url",
+            "Prometheus exposition endpoint, e.g. `http://localhost:9100/metrics`",
+        )]
+    }
+
+    fn output(&self) -> PipeEdge {
+        PipeEdge {
+            model: Some(vec!["stream".into()]),
+            ..Default::default()
+        }
+    }
+
+    async fn build(&self, args: &PlanArguments) -> Result<PipeNodeImpl> {
+        let imp: PromSrc = args.to()?;
+        Ok(PipeNodeImpl::Src(Box::new(imp)))
+    }
+}
+
+/// Scrapes the metrics once from a Prometheus text exposition endpoint.
+///
+/// Each sample becomes an object of `name`, `labels`, `value` and `timestamp`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PromSrc {
+    url: String,
+}
+
+#[async_trait]
+impl PipeSrc for PromSrc {
+    async fn call(&self) -> Result<PipeChannel> {
+        let Self { url } = self;
+
+        let text = ::reqwest::get(url)
+            .await
+            .with_context(|| format!("Failed to scrape metrics: {url}"))?
+            .error_for_status()?
+            .text()
+            .await?;
+        Ok(parse_exposition(&text)?
+            .into_iter()
+            .map(|object| LazyObject::from(ObjectLayer::from_object_dyn(object)))
+            .collect())
+    }
+}
+
+/// Parses the samples of the text exposition format.
+///
+/// The comments, including `# HELP` and `# TYPE`, are skipped.
+pub fn parse_exposition(text: &str) -> Result<Vec<Object>> {
+    text.lines()
+        .enumerate()
+        .map(|(index, line)| (index, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(index, line)| {
+            parse_sample(line)
+                .with_context(|| format!("Invalid sample at line {}: {line}", index + 1))
+        })
+        .collect()
+}
+
+/// Parses a line of `name{key="value",...} value [timestamp]`.
+fn parse_sample(line: &str) -> Result<Object> {
+    let end = line
+        .find(|c: char| c == '{' || c.is_whitespace())
+        .context("Missing value")?;
+    let (name, rest) = line.split_at(end);
+    if name.is_empty() {
+        bail!("Missing name")
+    }
+
+    let mut labels = Object::default();
+    let rest = match rest.strip_prefix('{') {
+        Some(rest) => parse_labels(rest, &mut labels)?,
+        None => rest,
+    };
+
+    let mut fields = rest.split_whitespace();
+    let value = parse_value(fields.next().context("Missing value")?)?;
+    let timestamp = match fields.next() {
+        Some(timestamp) => timestamp
+            .parse::<i64>()
+            .map(Into::into)
+            .with_context(|| format!("Invalid timestamp: {timestamp}"))?,
+        None => Value::Null,
+    };
+    if let Some(field) = fields.next() {
+        bail!("Unexpected field: {field}")
+    }
+
+    let mut object = Object::default();
+    object.insert("name".into(), name.to_string().into());
+    object.insert("labels".into(), labels.into());
+    object.insert("value".into(), value);
+    object.insert("timestamp".into(), timestamp);
+    Ok(object)
+}
+
+/// Parses the labels following `{`, returning the rest after the closing `}`.
+fn parse_labels<'a>(mut text: &'a str, labels: &mut Object) -> Result<&'a str> {
+    loop {
+        text = text.trim_start();
+        if let Some(rest) = text.strip_prefix('}') {
+            return Ok(rest);
+        }
+
+        let (key, rest) = text.split_once('=').context("Missing label value")?;
+        let rest = rest
+            .trim_start()
+            .strip_prefix('"')
+            .with_context(|| format!("Unquoted label value: {}", key.trim()))?;
+
+        // Unescape `\\`, `\"` and `\n`
+        let mut value = String::default();
+        let mut chars = rest.char_indices();
+        let end = loop {
+            match chars.next() {
+                Some((index, '"')) => break index,
+                Some((_, '\\')) => match chars.next() {
+                    Some((_, 'n')) => value.push('\n'),
+                    Some((_, c)) => value.push(c),
+                    None => bail!("Unterminated label value: {}", key.trim()),
+                },
+                Some((_, c)) => value.push(c),
+                None => bail!("Unterminated label value: {}", key.trim()),
+            }
+        };
+        labels.insert(key.trim().into(), value.into());
+
+        text = rest[end + 1..].trim_start();
+        if let Some(rest) = text.strip_prefix(',') {
+            text = rest;
+        }
+    }
+}
+
+/// Parses a sample value, keeping `NaN` and `±Inf` as strings beyond JSON.
+fn parse_value(value: &str) -> Result<Value> {
+    if let Ok(integer) = value.parse::<i64>() {
+        return Ok(integer.into());
+    }
+    let float: f64 = value
+        .parse()
+        .with_context(|| format!("Invalid value: {value}"))?;
+    Ok(::serde_json::Number::from_f64(float)
+        .map(|float| Value::Number(Number::Fixed(float)))
+        .unwrap_or_else(|| Value::String(value.into())))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::parse_exposition;
+
+    #[test]
+    fn test_parse_exposition() {
+        let text = r#"
+# HELP http_requests_total The total number of HTTP requests.
+# TYPE http_requests_total counter
+http_requests_total{method="post",code="200"} 1027 1395066363000
+http_requests_total{method="post",code="400"}    3 1395066363000
+
+# Escaped label values and a trailing comma
+msdos_file_access_time_seconds{path="C:\\DIR\\FILE.TXT",error="Cannot find file:\n\"FILE.TXT\"",} 1.458255915e9
+metric_without_timestamp_and_labels 12.47
+node_load1 NaN
+"#;
+        let samples: Vec<_> = parse_exposition(text)
+            .unwrap()
+            .into_iter()
+            .map(|object| object.to_json().unwrap())
+            .collect();
+        assert_eq!(
+            samples,
+            [
+                json!({
+                    "name": "http_requests_total",
+                    "labels": {"method": "post", "code": "200"},
+                    "value": 1027,
+                    "timestamp": 1395066363000i64,
+                }),
+                json!({
+                    "name": "http_requests_total",
+                    "labels": {"method": "post", "code": "400"},
+                    "value": 3,
+                    "timestamp": 1395066363000i64,
+                }),
+                json!({
+                    "name": "msdos_file_access_time_seconds",
+                    "labels": {
+                        "path": "C:\\DIR\\FILE.TXT",
+                        "error": "Cannot find file:\n\"FILE.TXT\"",
+                    },
+                    "value": 1458255915.0,
+                    "timestamp": null,
+                }),
+                json!({
+                    "name": "metric_without_timestamp_and_labels",
+                    "labels": {},
+                    "value": 12.47,
+                    "timestamp": null,
+                }),
+                json!({
+                    "name": "node_load1",
+                    "labels": {},
+                    "value": "NaN",
+                    "timestamp": null,
+                }),
+            ],
+        );
+
+        assert!(parse_exposition("broken{label=value} 1").is_err());
+        assert!(parse_exposition("broken 1 2 3").is_err());
+    }
+}
@@ -0,0 +1,56 @@
+use tracing::debug;
+
+/// Number of the leading bytes to dump.
+pub const TRACE_BYTES: usize = 256;
+
+/// Logs the leading bytes read by the src, before wrapping them into the models.
+pub fn trace_bytes(src: &str, bytes: &[u8]) {
+    let len = bytes.len().min(TRACE_BYTES);
+    debug!(
+        "Read {} bytes from {src}; leading {len} bytes:\n{}",
+        bytes.len(),
+        hexdump(&bytes[..len]),
+    );
+}
+
+/// Formats the bytes as `hexdump -C` does, 16 bytes per line.
+pub fn hexdump(bytes: &[u8]) -> String {
+    let mut dump = String::default();
+    for (index, line) in bytes.chunks(16).enumerate() {
+        dump.push_str(&format!("{:08x} ", index * 16));
+        for column in 0..16 {
+            if column % 8 == 0 {
+                dump.push(' ');
+            }
+            match line.get(column) {
+                Some(byte) => dump.push_str(&format!("{byte:02x} ")),
+                None => dump.push_str("   "),
+            }
+        }
+        dump.push_str(" |");
+        dump.extend(line.iter().map(|&byte| {
+            if byte.is_ascii_graphic() || byte == b' ' {
+                byte as char
+            } else {
+                '.'
+            }
+        }));
+        dump.push_str("|\n");
+    }
+    dump
+}
+
+#[cfg(test)]
+mod tests {
+    use super::hexdump;
+
+    #[test]
+    fn test_hexdump() {
+        assert_eq!(hexdump(b""), "");
+        assert_eq!(
+            hexdump(b"hello, world!\n\x00\xffxlake"),
+            "00000000  68 65 6c 6c 6f 2c 20 77  6f 72 6c 64 21 0a 00 ff  |hello, world!...|\n\
+             00000010  78 6c 61 6b 65                                    |xlake|\n",
+        );
+    }
+}
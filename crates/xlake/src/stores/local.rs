@@ -1,11 +1,11 @@
-use std::{fmt, path::PathBuf, sync::Arc};
+use std::{fmt, io, path::PathBuf, sync::Arc, time::UNIX_EPOCH};
 
-use anyhow::Result;
+use anyhow::{bail, Result};
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use tokio::fs;
 use xlake_ast::{Object, PlanArguments, PlanKind};
-use xlake_core::{models::hash::Hash, PipeNodeFactory, PipeNodeImpl, PipeStore};
+use xlake_core::{models::hash::Hash, ArgSpec, PipeNodeFactory, PipeNodeImpl, PipeStore};
 
 #[derive(Copy, Clone, Debug, Default)]
 pub struct LocalStoreFactory;
@@ -26,6 +26,11 @@ impl PipeNodeFactory for LocalStoreFactory {
         "local".into()
     }
 
+    fn arguments(&self) -> Vec<ArgSpec> {
+        vec![ArgSpec::optional("path", "Directory to store the objects")
+            .with_default(LocalStore::default_path().display())]
+    }
+
     async fn build(&self, args: &PlanArguments) -> Result<PipeNodeImpl> {
         let imp: LocalStore = args.to()?;
         imp.init().await?;
@@ -82,4 +87,98 @@ impl PipeStore for LocalStore {
             .await
             .map_err(Into::into)
     }
+
+    async fn checksum(&self, hash: &Hash) -> Result<Option<String>> {
+        // Derive from the metadata rather than reading the whole file
+        let metadata = match fs::metadata(self.path(hash)).await {
+            Ok(metadata) => metadata,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(error) => return Err(error.into()),
+        };
+        let modified = metadata.modified()?.duration_since(UNIX_EPOCH)?;
+        let checksum = format!("{:x}-{:x}", modified.as_nanos(), metadata.len());
+        Ok(Some(checksum))
+    }
+
+    async fn read_item_with_checksum(&self, hash: &Hash) -> Result<(Object, String)> {
+        let object = self.read_item(hash).await?;
+        match self.checksum(hash).await? {
+            Some(checksum) => Ok((object, checksum)),
+            None => bail!("No such item: {hash}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use xlake_core::{models::hash::Hash, PipeStore};
+
+    use crate::{
+        testing::{object, CollectSinkFactory},
+        PipeSession,
+    };
+
+    use super::LocalStore;
+
+    #[::tokio::test]
+    async fn test_local_store_checksum() {
+        let path = ::std::env::temp_dir().join(format!("xlake-store-{}", ::std::process::id()));
+        let store = LocalStore { path: path.clone() };
+        store.init().await.unwrap();
+
+        let hash = Hash::digest("foo");
+        assert!(store.checksum(&hash).await.unwrap().is_none());
+
+        let object = object([("name", "foo")]);
+        store.write_item(&hash, &object).await.unwrap();
+
+        let checksum = store.checksum(&hash).await.unwrap().unwrap();
+        store.read_item(&hash).await.unwrap();
+        assert_eq!(store.checksum(&hash).await.unwrap().unwrap(), checksum);
+
+        ::tokio::fs::remove_dir_all(&path).await.unwrap();
+    }
+
+    #[::tokio::test]
+    async fn test_local_store_etag() {
+        let path = ::std::env::temp_dir().join(format!("xlake-etag-{}", ::std::process::id()));
+
+        let items = Arc::new(Mutex::new(Vec::default()));
+        let mut session = PipeSession::default();
+        session.insert_factory(Box::new(CollectSinkFactory(items.clone())));
+        let result = session
+            .call(&format!(
+                r#"repeatsrc count=2 json='{{"name":"foo"}}'
+                ! hash:object
+                ! localstore path='{}'
+                ! collectsink"#,
+                path.display(),
+            ))
+            .await;
+
+        // The etag of the hit is the checksum of the store, i.e. of the file metadata
+        let store = LocalStore { path: path.clone() };
+        let checksum: ::anyhow::Result<Option<String>> = async {
+            result?;
+            let mut entries = ::tokio::fs::read_dir(&path).await?;
+            let entry = entries.next_entry().await?.unwrap();
+            let stem = entry
+                .path()
+                .file_stem()
+                .unwrap()
+                .to_string_lossy()
+                .into_owned();
+            let hash: Hash = ::serde_json::from_value(stem.into())?;
+            store.checksum(&hash).await
+        }
+        .await;
+        ::tokio::fs::remove_dir_all(&path).await.unwrap();
+
+        let items = items.lock().unwrap();
+        assert_eq!(items.len(), 2);
+        assert!(items[0].get_raw("etag").is_none());
+        assert_eq!(items[1].get_str("etag"), checksum.unwrap().as_deref());
+    }
 }
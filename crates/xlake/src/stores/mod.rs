@@ -1,2 +1,3 @@
 #[cfg(feature = "fs")]
 pub mod local;
+pub mod tiered;
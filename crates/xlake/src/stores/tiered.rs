@@ -0,0 +1,231 @@
+use std::{fmt, sync::Arc};
+
+use anyhow::{anyhow, bail, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+use xlake_ast::{Object, PlanArguments, PlanKind};
+use xlake_core::{models::hash::Hash, ArgSpec, PipeNodeFactory, PipeNodeImpl, PipeStore};
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct TieredStoreFactory;
+
+impl fmt::Display for TieredStoreFactory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.kind().fmt(f)
+    }
+}
+
+#[async_trait]
+impl PipeNodeFactory for TieredStoreFactory {
+    fn kind(&self) -> PlanKind {
+        PlanKind::Store { name: self.name() }
+    }
+
+    fn name(&self) -> String {
+        "tiered".into()
+    }
+
+    fn arguments(&self) -> Vec<ArgSpec> {
+        vec![
+            ArgSpec::required(
+                "tiers",
+                "Inner stores from the fastest, e.g. `'localstore path=hot ! localstore path=cold'`",
+            ),
+            ArgSpec::optional(
+                "write",
+                "Comma-separated indices of the tiers to write into, or all if not given",
+            ),
+        ]
+    }
+
+    async fn build(&self, _args: &PlanArguments) -> Result<PipeNodeImpl> {
+        // The inner stores are resolved by the session
+        bail!("Cannot build {self} without a session")
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TieredStoreArgs {
+    pub tiers: String,
+    #[serde(default)]
+    pub write: Option<String>,
+}
+
+/// Composes the stores from the fastest, e.g. a hot local cache in front of a remote one.
+///
+/// The lookups check the tiers in order, back-filling the faster writable tiers on a hit.
+/// The writes go into all the writable tiers, which are all the tiers unless `write` is given.
+#[derive(Debug)]
+pub struct TieredStore {
+    tiers: Vec<Arc<dyn PipeStore>>,
+    /// Whether each tier is writable
+    writable: Vec<bool>,
+}
+
+impl TieredStore {
+    pub fn new(tiers: Vec<Arc<dyn PipeStore>>, write: Option<&str>) -> Result<Self> {
+        if tiers.is_empty() {
+            bail!("tiers should not be empty")
+        }
+        let writable = match write {
+            Some(write) => {
+                let mut writable = vec![false; tiers.len()];
+                for index in write.split(',').map(str::trim) {
+                    let index: usize = index
+                        .parse()
+                        .map_err(|_| anyhow!("Invalid tier index: '{index}'"))?;
+                    match writable.get_mut(index) {
+                        Some(writable) => *writable = true,
+                        None => bail!("No such tier: {index}"),
+                    }
+                }
+                writable
+            }
+            None => vec![true; tiers.len()],
+        };
+        Ok(Self { tiers, writable })
+    }
+
+    fn writable_tiers(&self) -> impl Iterator<Item = (usize, &Arc<dyn PipeStore>)> {
+        self.tiers
+            .iter()
+            .enumerate()
+            .filter(|&(index, _)| self.writable[index])
+    }
+
+    /// Back-fills the faster tiers than the given one, returning the fastest one written.
+    async fn back_fill(&self, hash: &Hash, object: &Object, index: usize) -> Result<Option<usize>> {
+        let mut fastest = None;
+        for (faster, tier) in self.writable_tiers().take_while(|&(i, _)| i < index) {
+            debug!("Back-fill the tier {faster} from {index}: {hash}");
+            tier.write_item(hash, object).await?;
+            fastest.get_or_insert(faster);
+        }
+        Ok(fastest)
+    }
+}
+
+#[async_trait]
+impl PipeStore for TieredStore {
+    async fn contains(&self, hash: &Hash) -> Result<bool> {
+        for tier in &self.tiers {
+            if tier.contains(hash).await? {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    async fn read_item(&self, hash: &Hash) -> Result<Object> {
+        for (index, tier) in self.tiers.iter().enumerate() {
+            if !tier.contains(hash).await? {
+                continue;
+            }
+            let object = tier.read_item(hash).await?;
+
+            // Back-fill the faster tiers, so that the next lookups hit earlier
+            self.back_fill(hash, &object, index).await?;
+            return Ok(object);
+        }
+        bail!("No such item in any tier: {hash}")
+    }
+
+    async fn write_item(&self, hash: &Hash, object: &Object) -> Result<()> {
+        for (_, tier) in self.writable_tiers() {
+            tier.write_item(hash, object).await?;
+        }
+        Ok(())
+    }
+
+    async fn checksum(&self, hash: &Hash) -> Result<Option<String>> {
+        for tier in &self.tiers {
+            if let Some(checksum) = tier.checksum(hash).await? {
+                return Ok(Some(checksum));
+            }
+        }
+        Ok(None)
+    }
+
+    async fn read_item_with_checksum(&self, hash: &Hash) -> Result<(Object, String)> {
+        for (index, tier) in self.tiers.iter().enumerate() {
+            if !tier.contains(hash).await? {
+                continue;
+            }
+            let (object, checksum) = tier.read_item_with_checksum(hash).await?;
+
+            // Once back-filled, the fastest tier written is the one checked first
+            let checksum = match self.back_fill(hash, &object, index).await? {
+                Some(faster) => self.tiers[faster]
+                    .checksum(hash)
+                    .await?
+                    .ok_or_else(|| anyhow!("No such item in the tier {faster}: {hash}"))?,
+                None => checksum,
+            };
+            return Ok((object, checksum));
+        }
+        bail!("No such item in any tier: {hash}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use xlake_ast::Object;
+    use xlake_core::{models::hash::Hash, PipeStore};
+
+    use crate::testing::{object, MemoryStore};
+
+    use super::TieredStore;
+
+    fn tiers(write: Option<&str>) -> (Arc<MemoryStore>, Arc<MemoryStore>, TieredStore) {
+        let hot = Arc::new(MemoryStore::default());
+        let cold = Arc::new(MemoryStore::default());
+        let tiers: Vec<Arc<dyn PipeStore>> = vec![hot.clone(), cold.clone()];
+        let store = TieredStore::new(tiers, write).unwrap();
+        (hot, cold, store)
+    }
+
+    #[::tokio::test]
+    async fn test_tiered_store_back_fill() {
+        let (hot, cold, store) = tiers(None);
+
+        let hash = Hash::digest("foo");
+        let object = object([("name", "foo")]);
+        cold.write_item(&hash, &object).await.unwrap();
+
+        // A miss in the hot tier and a hit in the cold one back-fill the hot tier
+        assert!(!hot.contains(&hash).await.unwrap());
+        assert!(store.contains(&hash).await.unwrap());
+        assert_eq!(store.read_item(&hash).await.unwrap(), object);
+        assert_eq!(hot.read_item(&hash).await.unwrap(), object);
+
+        let missing = Hash::digest("bar");
+        assert!(!store.contains(&missing).await.unwrap());
+        assert!(store.read_item(&missing).await.is_err());
+    }
+
+    #[::tokio::test]
+    async fn test_tiered_store_write() {
+        let hash = Hash::digest("foo");
+        let object = Object::default();
+
+        let (hot, cold, store) = tiers(None);
+        store.write_item(&hash, &object).await.unwrap();
+        assert!(hot.contains(&hash).await.unwrap());
+        assert!(cold.contains(&hash).await.unwrap());
+
+        // The read-only tiers are neither written nor back-filled
+        let (hot, cold, store) = tiers(Some("1"));
+        store.write_item(&hash, &object).await.unwrap();
+        assert!(!hot.contains(&hash).await.unwrap());
+        assert!(cold.contains(&hash).await.unwrap());
+        store.read_item(&hash).await.unwrap();
+        assert!(!hot.contains(&hash).await.unwrap());
+
+        let tiers = || -> Vec<Arc<dyn PipeStore>> { vec![hot.clone()] };
+        assert!(TieredStore::new(tiers(), Some("1")).is_err());
+        assert!(TieredStore::new(tiers(), Some("hot")).is_err());
+    }
+}
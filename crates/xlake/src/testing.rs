@@ -0,0 +1,202 @@
+use std::{
+    collections::BTreeMap,
+    fmt,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use futures::TryStreamExt;
+use xlake_ast::{Object, PlanArguments, PlanKind, PlanType, Value};
+use xlake_core::{
+    models::hash::Hash,
+    object::{LazyObject, ObjectLayer},
+    ArgSpec, PipeChannel, PipeNodeFactory, PipeNodeImpl, PipeSink, PipeStore,
+};
+
+/// Builds an object of the fields, e.g. `object([("id", 1)])`.
+pub(crate) fn object<K, V>(fields: impl IntoIterator<Item = (K, V)>) -> Object
+where
+    K: Into<String>,
+    V: Into<Value>,
+{
+    fields
+        .into_iter()
+        .map(|(key, value)| (key.into(), value.into()))
+        .collect()
+}
+
+/// Builds a channel of the objects without any models.
+pub(crate) fn channel(objects: impl IntoIterator<Item = Object>) -> PipeChannel {
+    objects
+        .into_iter()
+        .map(|object| LazyObject::from(ObjectLayer::from_object_dyn(object)))
+        .collect()
+}
+
+/// Keeps the items in memory, counting the reads.
+#[derive(Debug, Default)]
+pub(crate) struct MemoryStore {
+    pub(crate) items: Mutex<BTreeMap<String, Object>>,
+    pub(crate) reads: AtomicUsize,
+}
+
+#[async_trait]
+impl PipeStore for MemoryStore {
+    async fn contains(&self, hash: &Hash) -> Result<bool> {
+        Ok(self.items.lock().unwrap().contains_key(&hash.to_string()))
+    }
+
+    async fn read_item(&self, hash: &Hash) -> Result<Object> {
+        self.reads.fetch_add(1, Ordering::SeqCst);
+        Ok(self.items.lock().unwrap()[&hash.to_string()].clone())
+    }
+
+    async fn write_item(&self, hash: &Hash, object: &Object) -> Result<()> {
+        let mut items = self.items.lock().unwrap();
+        items.insert(hash.to_string(), object.clone());
+        Ok(())
+    }
+}
+
+/// Builds `memorystore` sharing the given store.
+#[derive(Debug)]
+pub(crate) struct MemoryStoreFactory(pub(crate) Arc<MemoryStore>);
+
+#[async_trait]
+impl PipeNodeFactory for MemoryStoreFactory {
+    fn kind(&self) -> PlanKind {
+        PlanKind::Store { name: self.name() }
+    }
+
+    fn name(&self) -> String {
+        "memory".into()
+    }
+
+    async fn build(&self, _: &PlanArguments) -> Result<PipeNodeImpl> {
+        Ok(PipeNodeImpl::Store(self.0.clone()))
+    }
+}
+
+/// Collects the flattened items into the shared buffer.
+#[derive(Debug)]
+pub(crate) struct CollectSink(pub(crate) Arc<Mutex<Vec<LazyObject>>>);
+
+#[async_trait]
+impl PipeSink for CollectSink {
+    async fn call(&self, channel: PipeChannel) -> Result<()> {
+        let mut stream = channel.into_stream::<LazyObject>().await?;
+        while let Some(item) = stream.try_next().await? {
+            let item = item.flatten().await?;
+            self.0.lock().unwrap().push(item);
+        }
+        Ok(())
+    }
+}
+
+/// Builds `collectsink` sharing the given buffer.
+#[derive(Debug)]
+pub(crate) struct CollectSinkFactory(pub(crate) Arc<Mutex<Vec<LazyObject>>>);
+
+#[async_trait]
+impl PipeNodeFactory for CollectSinkFactory {
+    fn kind(&self) -> PlanKind {
+        PlanKind::Sink { name: self.name() }
+    }
+
+    fn name(&self) -> String {
+        "collect".into()
+    }
+
+    async fn build(&self, _: &PlanArguments) -> Result<PipeNodeImpl> {
+        Ok(PipeNodeImpl::Sink(Box::new(CollectSink(self.0.clone()))))
+    }
+}
+
+/// Builds the nodes of the given kind and name with the closure, e.g. of the mocks.
+///
+/// The funcs are of the `stream` model.
+pub(crate) struct NodeFactory<F> {
+    kind: PlanType,
+    name: String,
+    arguments: Vec<ArgSpec>,
+    per_object: bool,
+    build: F,
+}
+
+impl<F> NodeFactory<F>
+where
+    F: Send + Sync + Fn(&PlanArguments) -> Result<PipeNodeImpl>,
+{
+    pub(crate) fn new(kind: PlanType, name: impl Into<String>, build: F) -> Self {
+        Self {
+            kind,
+            name: name.into(),
+            arguments: Vec::default(),
+            per_object: false,
+            build,
+        }
+    }
+
+    /// Declares the arguments, e.g. to be filled with their defaults.
+    pub(crate) fn with_arguments(mut self, arguments: Vec<ArgSpec>) -> Self {
+        self.arguments = arguments;
+        self
+    }
+
+    /// Marks the func to map each object independently of the others.
+    pub(crate) fn per_object(mut self) -> Self {
+        self.per_object = true;
+        self
+    }
+}
+
+impl<F> fmt::Debug for NodeFactory<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NodeFactory")
+            .field("kind", &self.kind)
+            .field("name", &self.name)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl<F> PipeNodeFactory for NodeFactory<F>
+where
+    F: Send + Sync + Fn(&PlanArguments) -> Result<PipeNodeImpl>,
+{
+    fn kind(&self) -> PlanKind {
+        let name = self.name();
+        match self.kind {
+            PlanType::Batch => PlanKind::Batch { name },
+            PlanType::Func => PlanKind::Func {
+                model_name: "stream".into(),
+                func: name,
+            },
+            PlanType::Model => PlanKind::Model { name },
+            PlanType::Sink => PlanKind::Sink { name },
+            PlanType::Src => PlanKind::Src { name },
+            PlanType::Store => PlanKind::Store { name },
+            PlanType::Stream => PlanKind::Stream { name },
+        }
+    }
+
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn arguments(&self) -> Vec<ArgSpec> {
+        self.arguments.clone()
+    }
+
+    fn is_per_object(&self) -> bool {
+        self.per_object
+    }
+
+    async fn build(&self, args: &PlanArguments) -> Result<PipeNodeImpl> {
+        (self.build)(args)
+    }
+}